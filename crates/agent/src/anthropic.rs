@@ -1,5 +1,10 @@
+use crate::capabilities::prepare_request;
+use crate::context::fit_to_context;
+use crate::errors::response_body;
+use crate::telemetry::traced_chat;
 use crate::types::{
-    Agent, AgentError, AgentMessage, AgentResponse, StreamChunk, Usage,
+    extra_headers, request_timeout, health_check_messages, require_api_key, secret_header, warmup_head, Agent, AgentError, AgentMessage, AgentResponse, ImageInput,
+    MessageRole, StreamChunk, Usage,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -11,74 +16,141 @@ use tracing::{debug, trace};
 pub struct AnthropicAgent {
     config: clanker_config::AgentConfig,
     client: Client,
+    timeout: Duration,
 }
 
 impl AnthropicAgent {
+    /// Request timeout when `request_timeout_secs` is not configured
+    const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
     pub fn new(config: clanker_config::AgentConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_client(config, Client::new())
+    }
+
+    /// Create an agent sending requests through `client` (sharing its connection pool)
+    pub fn with_client(config: clanker_config::AgentConfig, client: Client) -> Self {
+        let timeout = request_timeout(&config, Self::DEFAULT_TIMEOUT_SECS);
+        Self { config, client, timeout }
+    }
 
-        Self { config, client }
+    /// HTTP request timeout in use
+    pub fn timeout(&self) -> Duration {
+        self.timeout
     }
 
     const API_URL: &'static str = "https://api.anthropic.com/v1/messages";
-}
 
-#[async_trait]
-impl Agent for AnthropicAgent {
-    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+    /// System prompt used when the conversation has no system message
+    const DEFAULT_SYSTEM: &'static str = "You are a helpful AI assistant.";
+
+    /// Beta flag enabling `cache_control` on request blocks
+    const PROMPT_CACHE_BETA: &'static str = "prompt-caching-2024-07-31";
+
+    /// Send a chat completion request with an explicit output token limit
+    async fn complete(
+        &self,
+        messages: Vec<AgentMessage>,
+        max_tokens: u32,
+    ) -> Result<AgentResponse, AgentError> {
         debug!("Sending chat request to Anthropic");
+        let (messages, max_tokens) = prepare_request(&self.config.model, messages, max_tokens);
 
-        let request = AnthropicRequest {
-            model: self.config.model.clone(),
-            max_tokens: self.config.max_tokens,
-            system: "You are a helpful AI assistant.".to_string(),
-            messages: messages_to_anthropic(messages),
-        };
+        // Anthropic requires the thinking budget to stay below max_tokens
+        let thinking_budget = self
+            .config
+            .reasoning_budget_tokens
+            .filter(|budget| supports_extended_thinking(&self.config.model) && *budget < max_tokens);
+        let request = build_request(
+            self.config.model.clone(),
+            max_tokens,
+            messages,
+            self.config.enable_prompt_cache,
+            thinking_budget,
+            &self.config.stop_sequences,
+        );
 
-        let response = self
+        let mut builder = self
             .client
             .post(Self::API_URL)
-            .header("x-api-key", self.config.api_key.as_ref().unwrap_or(&String::new()))
+            .timeout(self.timeout)
+            .header("x-api-key", secret_header(self.config.api_key.clone().unwrap_or_default())?)
             .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
+            .header("content-type", "application/json");
+        if self.config.enable_prompt_cache {
+            builder = builder.header("anthropic-beta", Self::PROMPT_CACHE_BETA);
+        }
+
+        let response = builder
+            .headers(extra_headers(&self.config))
             .json(&request)
             .send()
             .await
             .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
 
-        let status = response.status();
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| AgentError::HttpError(e.to_string()))?;
+        let response_text = response_body("Anthropic", response).await?;
 
-        if !status.is_success() {
-            return Err(AgentError::ProviderError(format!(
-                "Anthropic API error {}: {}",
-                status, response_text
-            )));
-        }
+        trace!("Received Anthropic response");
 
-        let anthropic_response: AnthropicResponse = serde_json::from_str(&response_text)
-            .map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
+        parse_response(&response_text, &self.config.model)
+    }
+}
 
-        trace!("Received Anthropic response");
+/// Model name prefixes accepting the `thinking` parameter
+const EXTENDED_THINKING_MODELS: &[&str] = &["claude-3-7-sonnet", "claude-sonnet-4", "claude-opus-4", "claude-haiku-4"];
 
-        Ok(AgentResponse {
-            content: anthropic_response.content[0].text.clone(),
-            finish_reason: anthropic_response.stop_reason.unwrap_or_else(|| "stop".to_string()),
-            usage: Usage {
-                prompt_tokens: anthropic_response.usage.input_tokens,
-                completion_tokens: anthropic_response.usage.output_tokens,
-                total_tokens: anthropic_response.usage.input_tokens
-                    + anthropic_response.usage.output_tokens,
-            },
-            model: self.config.model.clone(),
-            provider: "anthropic".to_string(),
-        })
+/// Check if the model supports extended thinking
+fn supports_extended_thinking(model: &str) -> bool {
+    EXTENDED_THINKING_MODELS.iter().any(|prefix| model.starts_with(prefix))
+}
+
+/// Parse a successful Anthropic response body
+fn parse_response(body: &str, model: &str) -> Result<AgentResponse, AgentError> {
+    let anthropic_response: AnthropicResponse =
+        serde_json::from_str(body).map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
+
+    // Tool-use-only or refusal responses may carry no text block at all;
+    // thinking blocks are never part of the reply
+    let text = anthropic_response
+        .content
+        .iter()
+        .filter(|block| block.kind.as_deref().is_none_or(|kind| kind == "text"))
+        .find_map(|block| block.text.clone());
+
+    // An empty "end_turn" is reported as "empty" so callers can tell it apart
+    let finish_reason = match anthropic_response.stop_reason {
+        Some(reason) if text.is_some() || reason != "end_turn" => reason,
+        _ if text.is_none() => "empty".to_string(),
+        _ => "stop".to_string(),
+    };
+
+    Ok(AgentResponse {
+        content: text.unwrap_or_default(),
+        finish_reason,
+        usage: Usage {
+            prompt_tokens: anthropic_response.usage.input_tokens,
+            completion_tokens: anthropic_response.usage.output_tokens,
+            total_tokens: anthropic_response.usage.input_tokens
+                + anthropic_response.usage.output_tokens,
+        },
+        model: model.to_string(),
+        provider: "anthropic".to_string(),
+    })
+}
+
+#[async_trait]
+impl Agent for AnthropicAgent {
+    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+        let messages = fit_to_context(messages, self.max_context(), self.config.max_tokens);
+        traced_chat(self.provider(), self.model(), self.complete(messages, self.config.max_tokens)).await
+    }
+
+    async fn health(&self) -> Result<(), AgentError> {
+        require_api_key(&self.config)?;
+        self.complete(health_check_messages(), 1).await.map(|_| ())
+    }
+
+    fn warmup_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(warmup_head(&self.client, Self::API_URL, self.timeout, &self.config))
     }
 
     async fn chat_stream(
@@ -99,6 +171,15 @@ impl Agent for AnthropicAgent {
     fn model(&self) -> &str {
         &self.config.model
     }
+
+    fn max_context(&self) -> u32 {
+        200_000
+    }
+
+    fn supports_vision(&self) -> bool {
+        // Every Claude 3+ model accepts images
+        !self.config.model.starts_with("claude-2") && !self.config.model.starts_with("claude-instant")
+    }
 }
 
 /// Anthropic API request
@@ -106,19 +187,86 @@ impl Agent for AnthropicAgent {
 struct AnthropicRequest {
     model: String,
     max_tokens: u32,
-    system: String,
+    system: AnthropicSystem,
     messages: Vec<AnthropicMessage>,
+    /// Extended thinking, when a reasoning budget is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ThinkingConfig>,
+    /// Sequences ending generation (`agent.stop_sequences`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ThinkingConfig {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    budget_tokens: u32,
+}
+
+/// Plain system prompt, or a single text block marked for prompt caching
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+enum AnthropicSystem {
+    Text(String),
+    Blocks(Vec<AnthropicSystemBlock>),
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct AnthropicSystemBlock {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+    cache_control: CacheControl,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    kind: &'static str,
 }
 
 #[derive(Debug, Serialize)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: AnthropicContent,
+}
+
+/// Plain text, or content blocks when the message carries images
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+enum AnthropicContent {
+    Text(String),
+    Blocks(Vec<AnthropicContentBlock>),
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContentBlock {
+    Text { text: String },
+    Image { source: AnthropicImageSource },
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicImageSource {
+    Url { url: String },
+    Base64 { media_type: String, data: String },
+}
+
+impl From<ImageInput> for AnthropicImageSource {
+    fn from(image: ImageInput) -> Self {
+        match image {
+            ImageInput::Url { url } => Self::Url { url },
+            ImageInput::Base64 { media_type, data } => Self::Base64 { media_type, data },
+        }
+    }
 }
 
 /// Anthropic API response
 #[derive(Debug, Deserialize)]
 struct AnthropicResponse {
+    #[serde(default)]
     content: Vec<ContentBlock>,
     stop_reason: Option<String>,
     usage: AnthropicUsage,
@@ -126,7 +274,12 @@ struct AnthropicResponse {
 
 #[derive(Debug, Deserialize)]
 struct ContentBlock {
-    text: String,
+    /// Block type (`text`, `thinking`, `tool_use`, ...)
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    /// Absent for non-text blocks such as `tool_use`
+    #[serde(default)]
+    text: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -135,6 +288,54 @@ struct AnthropicUsage {
     output_tokens: u32,
 }
 
+/// Build a request, moving system messages into the top-level `system` field
+/// (Anthropic rejects `system` roles inside `messages`). With `cache_system`
+/// the prompt is sent as a block marked `cache_control: ephemeral`; a
+/// `thinking_budget` enables extended thinking.
+fn build_request(
+    model: String,
+    max_tokens: u32,
+    messages: Vec<AgentMessage>,
+    cache_system: bool,
+    thinking_budget: Option<u32>,
+    stop_sequences: &[String],
+) -> AnthropicRequest {
+    let (system, turns): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|msg| matches!(msg.role, MessageRole::System));
+
+    let system = if system.is_empty() {
+        AnthropicAgent::DEFAULT_SYSTEM.to_string()
+    } else {
+        system
+            .into_iter()
+            .map(|msg| msg.content)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    };
+    let system = if cache_system {
+        AnthropicSystem::Blocks(vec![AnthropicSystemBlock {
+            kind: "text",
+            text: system,
+            cache_control: CacheControl { kind: "ephemeral" },
+        }])
+    } else {
+        AnthropicSystem::Text(system)
+    };
+
+    AnthropicRequest {
+        model,
+        max_tokens,
+        system,
+        messages: messages_to_anthropic(turns),
+        thinking: thinking_budget.map(|budget_tokens| ThinkingConfig {
+            kind: "enabled",
+            budget_tokens,
+        }),
+        stop_sequences: stop_sequences.to_vec(),
+    }
+}
+
 /// Convert agent messages to Anthropic format
 fn messages_to_anthropic(messages: Vec<AgentMessage>) -> Vec<AnthropicMessage> {
     messages
@@ -144,7 +345,18 @@ fn messages_to_anthropic(messages: Vec<AgentMessage>) -> Vec<AnthropicMessage> {
                 .unwrap_or_else(|_| "\"user\"".to_string())
                 .trim_matches('"')
                 .to_string(),
-            content: msg.content,
+            content: if msg.images.is_empty() {
+                AnthropicContent::Text(msg.content)
+            } else {
+                // Images first, then the question about them
+                AnthropicContent::Blocks(
+                    msg.images
+                        .into_iter()
+                        .map(|image| AnthropicContentBlock::Image { source: image.into() })
+                        .chain(std::iter::once(AnthropicContentBlock::Text { text: msg.content }))
+                        .collect(),
+                )
+            },
         })
         .collect()
 }
@@ -155,15 +367,16 @@ mod tests {
 
     #[test]
     fn test_messages_to_anthropic() {
-        use crate::types::MessageRole;
         let messages = vec![
             AgentMessage {
                 role: MessageRole::User,
                 content: "Hello".to_string(),
+                images: Vec::new(),
             },
             AgentMessage {
                 role: MessageRole::Assistant,
                 content: "Hi there!".to_string(),
+                images: Vec::new(),
             },
         ];
 
@@ -171,8 +384,175 @@ mod tests {
 
         assert_eq!(anthropic_messages.len(), 2);
         assert_eq!(anthropic_messages[0].role, "user");
-        assert_eq!(anthropic_messages[0].content, "Hello");
+        assert_eq!(anthropic_messages[0].content, AnthropicContent::Text("Hello".to_string()));
         assert_eq!(anthropic_messages[1].role, "assistant");
-        assert_eq!(anthropic_messages[1].content, "Hi there!");
+        assert_eq!(anthropic_messages[1].content, AnthropicContent::Text("Hi there!".to_string()));
+    }
+
+    #[test]
+    fn test_image_blocks() {
+        let messages = vec![AgentMessage {
+            role: MessageRole::User,
+            content: "Describe this".to_string(),
+            images: vec![ImageInput::Base64 {
+                media_type: "image/png".to_string(),
+                data: "QUJD".to_string(),
+            }],
+        }];
+
+        let json = serde_json::to_value(messages_to_anthropic(messages)).unwrap();
+        assert_eq!(
+            json[0]["content"],
+            serde_json::json!([
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "QUJD"}},
+                {"type": "text", "text": "Describe this"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_system_messages_move_to_system_field() {
+        let messages = vec![
+            AgentMessage {
+                role: MessageRole::System,
+                content: "You are Master_Clanker.".to_string(),
+                images: Vec::new(),
+            },
+            AgentMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                images: Vec::new(),
+            },
+            AgentMessage {
+                role: MessageRole::System,
+                content: "Answer briefly.".to_string(),
+                images: Vec::new(),
+            },
+        ];
+
+        let request = build_request("claude".to_string(), 100, messages, false, None, &[]);
+
+        assert_eq!(
+            request.system,
+            AnthropicSystem::Text("You are Master_Clanker.\n\nAnswer briefly.".to_string())
+        );
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].role, "user");
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|m| m["role"] != "system"));
+    }
+
+    #[test]
+    fn test_default_system_without_system_message() {
+        let messages = vec![AgentMessage {
+            role: MessageRole::User,
+            content: "Hello".to_string(),
+            images: Vec::new(),
+        }];
+
+        let request = build_request("claude".to_string(), 100, messages, false, None, &[]);
+        assert_eq!(request.system, AnthropicSystem::Text(AnthropicAgent::DEFAULT_SYSTEM.to_string()));
+    }
+
+    #[test]
+    fn test_prompt_cache_marks_system_block() {
+        let messages = || {
+            vec![
+                AgentMessage {
+                    role: MessageRole::System,
+                    content: "Long instructions".to_string(),
+                    images: Vec::new(),
+                },
+                AgentMessage {
+                    role: MessageRole::User,
+                    content: "Hello".to_string(),
+                    images: Vec::new(),
+                },
+            ]
+        };
+
+        let cached = serde_json::to_value(build_request("claude".to_string(), 100, messages(), true, None, &[])).unwrap();
+        assert_eq!(
+            cached["system"],
+            serde_json::json!([
+                {"type": "text", "text": "Long instructions", "cache_control": {"type": "ephemeral"}},
+            ])
+        );
+
+        let plain = serde_json::to_value(build_request("claude".to_string(), 100, messages(), false, None, &[])).unwrap();
+        assert_eq!(plain["system"], "Long instructions");
+        assert!(!plain.to_string().contains("cache_control"));
+    }
+
+    #[test]
+    fn test_stop_sequences_in_request() {
+        let stops = vec!["END".to_string(), "</answer>".to_string()];
+        let request = serde_json::to_value(build_request("claude".to_string(), 100, Vec::new(), false, None, &stops)).unwrap();
+        assert_eq!(request["stop_sequences"], serde_json::json!(["END", "</answer>"]));
+        assert!(request.get("stop").is_none());
+    }
+
+    #[test]
+    fn test_thinking_budget_in_request_and_stripped_from_reply() {
+        let messages = vec![AgentMessage {
+            role: MessageRole::User,
+            content: "Prove it".to_string(),
+            images: Vec::new(),
+        }];
+        let request = serde_json::to_value(build_request("claude".to_string(), 8000, messages, false, Some(2048), &[])).unwrap();
+        assert_eq!(request["thinking"], serde_json::json!({"type": "enabled", "budget_tokens": 2048}));
+
+        let plain = serde_json::to_value(build_request("claude".to_string(), 100, Vec::new(), false, None, &[])).unwrap();
+        assert!(plain.get("thinking").is_none());
+        assert!(plain.get("stop_sequences").is_none());
+
+        let body = r#"{"content":[{"type":"thinking","thinking":"Let me reason step by step...","signature":"sig"},{"type":"redacted_thinking","data":"abc"},{"type":"text","text":"QED"}],"stop_reason":"end_turn","usage":{"input_tokens":5,"output_tokens":40}}"#;
+        let response = parse_response(body, "claude-sonnet-4-20250514").unwrap();
+        assert_eq!(response.content, "QED");
+        assert!(!response.content.contains("step by step"));
+
+        assert!(supports_extended_thinking("claude-sonnet-4-20250514"));
+        assert!(!supports_extended_thinking("claude-3-5-sonnet-20241022"));
+    }
+
+    #[test]
+    fn test_request_timeout_from_config() {
+        let agent = AnthropicAgent::new(clanker_config::AgentConfig::default());
+        assert_eq!(agent.timeout(), Duration::from_secs(AnthropicAgent::DEFAULT_TIMEOUT_SECS));
+
+        let agent = AnthropicAgent::new(clanker_config::AgentConfig {
+            request_timeout_secs: Some(120),
+            ..Default::default()
+        });
+        assert_eq!(agent.timeout(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_parse_response_with_empty_content() {
+        let body = r#"{"content":[],"stop_reason":"end_turn","usage":{"input_tokens":12,"output_tokens":0}}"#;
+
+        let response = parse_response(body, "claude").unwrap();
+        assert_eq!(response.content, "");
+        assert_eq!(response.finish_reason, "empty");
+        assert_eq!(response.usage.prompt_tokens, 12);
+
+        let refusal = r#"{"content":[],"stop_reason":"refusal","usage":{"input_tokens":12,"output_tokens":0}}"#;
+        let response = parse_response(refusal, "claude").unwrap();
+        assert_eq!(response.finish_reason, "refusal");
+        assert!(response.is_filtered());
+    }
+
+    #[test]
+    fn test_parse_response_skips_non_text_blocks() {
+        let body = r#"{"content":[{"type":"tool_use","id":"t1","name":"x","input":{}},{"type":"text","text":"Done"}],"stop_reason":"end_turn","usage":{"input_tokens":1,"output_tokens":2}}"#;
+
+        let response = parse_response(body, "claude").unwrap();
+        assert_eq!(response.content, "Done");
+        assert_eq!(response.finish_reason, "end_turn");
     }
 }
@@ -0,0 +1,178 @@
+//! Per-model request limits consulted before a request is built.
+//!
+//! Some models reject a `max_tokens` above their output cap or refuse system
+//! messages. [`prepare_request`] clamps the token limit and folds system
+//! messages into the first user turn for those models. Models missing from
+//! the table are sent as-is.
+
+use crate::types::{AgentMessage, MessageRole};
+use tracing::debug;
+
+/// What a model accepts in a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    /// Largest `max_tokens` the model accepts (None = no known cap)
+    pub max_output_tokens: Option<u32>,
+    /// Accepts messages with the system role
+    pub supports_system: bool,
+    /// Accepts streaming requests
+    pub supports_streaming: bool,
+}
+
+impl Default for ModelCapabilities {
+    fn default() -> Self {
+        Self {
+            max_output_tokens: None,
+            supports_system: true,
+            supports_streaming: true,
+        }
+    }
+}
+
+const fn caps(max_output_tokens: u32, supports_system: bool, supports_streaming: bool) -> ModelCapabilities {
+    ModelCapabilities {
+        max_output_tokens: Some(max_output_tokens),
+        supports_system,
+        supports_streaming,
+    }
+}
+
+/// Model name prefixes and their capabilities; the first matching prefix wins,
+/// so more specific prefixes come first
+const MODEL_TABLE: &[(&str, ModelCapabilities)] = &[
+    // OpenAI
+    ("o1-preview", caps(32_768, false, false)),
+    ("o1-mini", caps(65_536, false, true)),
+    ("gpt-4o-mini", caps(16_384, true, true)),
+    ("gpt-4o", caps(16_384, true, true)),
+    ("gpt-4-turbo", caps(4_096, true, true)),
+    ("gpt-4.1", caps(32_768, true, true)),
+    ("gpt-4", caps(8_192, true, true)),
+    ("gpt-3.5-turbo", caps(4_096, true, true)),
+    // Anthropic
+    ("claude-3-5", caps(8_192, true, true)),
+    ("claude-3-haiku", caps(4_096, true, true)),
+    ("claude-3-opus", caps(4_096, true, true)),
+    ("claude-3-sonnet", caps(4_096, true, true)),
+    ("claude-sonnet-4", caps(64_000, true, true)),
+    ("claude-opus-4", caps(32_000, true, true)),
+    // Groq-hosted open models
+    ("gemma", caps(8_192, false, true)),
+    ("llama-3.1-8b-instant", caps(8_192, true, true)),
+    ("llama-3.3-70b-versatile", caps(32_768, true, true)),
+    // xAI
+    ("grok-2", caps(32_768, true, true)),
+];
+
+/// Capabilities for a model (defaults when the model is not in the table)
+pub fn capabilities(model: &str) -> ModelCapabilities {
+    let model = model.to_lowercase();
+    MODEL_TABLE
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, caps)| *caps)
+        .unwrap_or_default()
+}
+
+/// Clamp `max_tokens` to the model's output cap
+pub fn clamp_max_tokens(model: &str, max_tokens: u32) -> u32 {
+    match capabilities(model).max_output_tokens {
+        Some(cap) if max_tokens > cap => {
+            debug!("Clamping max_tokens {} to {} for model {}", max_tokens, cap, model);
+            cap
+        }
+        _ => max_tokens,
+    }
+}
+
+/// Fold system messages into the first user message (or turn them into user
+/// messages when there is none) for models without system-role support
+pub fn relocate_system_messages(model: &str, messages: Vec<AgentMessage>) -> Vec<AgentMessage> {
+    if capabilities(model).supports_system
+        || !messages.iter().any(|m| matches!(m.role, MessageRole::System))
+    {
+        return messages;
+    }
+
+    debug!("Model {} does not accept system messages; moving them into the user turn", model);
+    let (system, mut rest): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|m| matches!(m.role, MessageRole::System));
+    let instructions = system
+        .iter()
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    match rest.iter_mut().find(|m| matches!(m.role, MessageRole::User)) {
+        Some(first_user) => {
+            first_user.content = format!("{}\n\n{}", instructions, first_user.content);
+        }
+        None => rest.insert(
+            0,
+            AgentMessage {
+                role: MessageRole::User,
+                content: instructions,
+                images: Vec::new(),
+            },
+        ),
+    }
+    rest
+}
+
+/// Apply the model's limits to a request's messages and output token limit
+pub fn prepare_request(model: &str, messages: Vec<AgentMessage>, max_tokens: u32) -> (Vec<AgentMessage>, u32) {
+    (relocate_system_messages(model, messages), clamp_max_tokens(model, max_tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: MessageRole, content: &str) -> AgentMessage {
+        AgentMessage {
+            role,
+            content: content.to_string(),
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_max_tokens_clamped_to_model_cap() {
+        // gpt-4-turbo caps output at 4096 tokens
+        assert_eq!(clamp_max_tokens("gpt-4-turbo", 8192), 4096);
+        assert_eq!(clamp_max_tokens("gpt-4-turbo", 1024), 1024);
+        // The more specific prefix wins over "gpt-4"
+        assert_eq!(clamp_max_tokens("gpt-4o-2024-08-06", 100_000), 16_384);
+        // Unknown models are left alone
+        assert_eq!(clamp_max_tokens("some-new-model", 100_000), 100_000);
+    }
+
+    #[test]
+    fn test_system_messages_folded_into_user_turn() {
+        let messages = vec![
+            msg(MessageRole::System, "Be brief."),
+            msg(MessageRole::User, "hello"),
+            msg(MessageRole::Assistant, "hi"),
+        ];
+
+        let (prepared, max_tokens) = prepare_request("o1-preview", messages.clone(), 50_000);
+        assert_eq!(max_tokens, 32_768);
+        assert_eq!(prepared.len(), 2);
+        assert!(matches!(prepared[0].role, MessageRole::User));
+        assert_eq!(prepared[0].content, "Be brief.\n\nhello");
+
+        // Models with system support keep the messages untouched
+        let (kept, _) = prepare_request("gpt-4o", messages, 1024);
+        assert_eq!(kept.len(), 3);
+        assert!(matches!(kept[0].role, MessageRole::System));
+    }
+
+    #[test]
+    fn test_system_only_conversation_becomes_user_message() {
+        let prepared = relocate_system_messages("gemma2-9b-it", vec![msg(MessageRole::System, "Say hi.")]);
+        assert_eq!(prepared.len(), 1);
+        assert!(matches!(prepared[0].role, MessageRole::User));
+        assert_eq!(prepared[0].content, "Say hi.");
+    }
+}
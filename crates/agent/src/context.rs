@@ -0,0 +1,129 @@
+//! Context window budgeting: estimate prompt size and drop old history to fit.
+//!
+//! Token counts use a cheap heuristic (about four characters per token plus a small
+//! per-message overhead), which is close enough to keep requests under provider limits.
+
+use crate::types::{AgentMessage, MessageRole};
+
+/// Approximate characters per token
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Approximate per-message overhead (role, separators)
+const TOKENS_PER_MESSAGE: u32 = 4;
+
+/// Estimate tokens for a piece of text
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+/// Estimate tokens for a list of messages
+pub fn estimate_message_tokens(messages: &[AgentMessage]) -> u32 {
+    messages
+        .iter()
+        .map(|m| estimate_tokens(&m.content) + TOKENS_PER_MESSAGE)
+        .sum()
+}
+
+/// Drop the oldest non-system messages until the estimate fits `max_context`.
+///
+/// System messages and the most recent message are always kept, so the result
+/// may still exceed the budget when those alone are too large.
+pub fn truncate_to_tokens(mut messages: Vec<AgentMessage>, max_context: u32) -> Vec<AgentMessage> {
+    let mut total = estimate_message_tokens(&messages);
+
+    while total > max_context {
+        let last = messages.len().saturating_sub(1);
+        let Some(index) = messages
+            .iter()
+            .take(last)
+            .position(|m| !matches!(m.role, MessageRole::System))
+        else {
+            break;
+        };
+
+        let removed = messages.remove(index);
+        total -= estimate_tokens(&removed.content) + TOKENS_PER_MESSAGE;
+    }
+
+    messages
+}
+
+/// Truncate a provider request to its context window, leaving room for a
+/// completion of up to `max_tokens`
+pub(crate) fn fit_to_context(messages: Vec<AgentMessage>, max_context: u32, max_tokens: u32) -> Vec<AgentMessage> {
+    truncate_to_tokens(messages, max_context.saturating_sub(max_tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: MessageRole, content: &str) -> AgentMessage {
+        AgentMessage {
+            role,
+            content: content.to_string(),
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_truncate_drops_oldest_and_keeps_system() {
+        let messages = vec![
+            msg(MessageRole::System, "You are helpful."),
+            msg(MessageRole::User, &"a".repeat(400)),
+            msg(MessageRole::Assistant, &"b".repeat(400)),
+            msg(MessageRole::User, "latest question"),
+        ];
+
+        // System (~4+4) + latest (~4+4) fit; the two 100-token messages do not
+        let truncated = truncate_to_tokens(messages, 120);
+
+        assert_eq!(truncated.len(), 3);
+        assert!(matches!(truncated[0].role, MessageRole::System));
+        assert!(truncated[1].content.starts_with('b'));
+        assert_eq!(truncated[2].content, "latest question");
+
+        let truncated = truncate_to_tokens(truncated, 20);
+        assert_eq!(truncated.len(), 2);
+        assert!(matches!(truncated[0].role, MessageRole::System));
+        assert_eq!(truncated[1].content, "latest question");
+    }
+
+    #[test]
+    fn test_fit_to_context_reserves_completion_tokens() {
+        let messages = || vec![msg(MessageRole::User, &"a".repeat(400)), msg(MessageRole::User, "latest")];
+
+        assert_eq!(fit_to_context(messages(), 1000, 100).len(), 2);
+        // 1000 - 950 leaves 50 tokens: only the latest message fits
+        assert_eq!(fit_to_context(messages(), 1000, 950).len(), 1);
+    }
+
+    #[test]
+    fn test_truncate_within_budget_is_unchanged() {
+        let messages = vec![
+            msg(MessageRole::System, "sys"),
+            msg(MessageRole::User, "hello"),
+        ];
+
+        assert_eq!(truncate_to_tokens(messages, 1000).len(), 2);
+    }
+
+    #[test]
+    fn test_truncate_keeps_latest_message_even_if_too_large() {
+        let messages = vec![
+            msg(MessageRole::User, "old"),
+            msg(MessageRole::User, &"x".repeat(1000)),
+        ];
+
+        let truncated = truncate_to_tokens(messages, 10);
+        assert_eq!(truncated.len(), 1);
+        assert_eq!(truncated[0].content.len(), 1000);
+    }
+}
@@ -0,0 +1,170 @@
+//! Provider error responses: map non-2xx bodies to typed [`AgentError`]s.
+//!
+//! Handles the common `{"error": {"type", "message", "code"}}` shape used by OpenAI-compatible
+//! APIs, Anthropic's `{"type": "error", "error": {...}}` and bare `{"error": "..."}` strings.
+//! Unknown shapes fall back to [`AgentError::ProviderError`] with the raw body.
+
+use crate::types::AgentError;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ErrorBody {
+    Detailed {
+        #[serde(rename = "type", default)]
+        kind: Option<String>,
+        #[serde(default)]
+        code: Option<serde_json::Value>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    Message(String),
+}
+
+/// Map a non-2xx provider response to an [`AgentError`]
+///
+/// `retry_after` is the raw `Retry-After` header value, if any (seconds form only).
+pub fn error_from_response(
+    provider: &str,
+    status: u16,
+    retry_after: Option<&str>,
+    body: &str,
+) -> AgentError {
+    let (kind, message) = match serde_json::from_str::<ErrorEnvelope>(body).map(|e| e.error) {
+        Ok(ErrorBody::Detailed { kind, code, message }) => {
+            let code = code.map(|c| match c {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            });
+            (kind.or(code).unwrap_or_default(), message)
+        }
+        Ok(ErrorBody::Message(message)) => (String::new(), Some(message)),
+        Err(_) => (String::new(), None),
+    };
+
+    match (status, kind.as_str()) {
+        (401 | 403, _) | (_, "authentication_error" | "permission_error" | "invalid_api_key") => {
+            AgentError::AuthenticationFailed
+        }
+        (429, _) | (_, "rate_limit_error" | "rate_limit_exceeded") => {
+            AgentError::RateLimited(parse_retry_after(retry_after))
+        }
+        (400 | 404 | 413 | 422, _) | (_, "invalid_request_error" | "not_found_error") => {
+            AgentError::InvalidRequest(message.unwrap_or_else(|| body.to_string()))
+        }
+        _ => AgentError::ProviderError(format!(
+            "{} API error {}: {}",
+            provider,
+            status,
+            message.as_deref().unwrap_or(body)
+        )),
+    }
+}
+
+/// Pass a successful provider response through; a non-2xx one is read and
+/// mapped with [`error_from_response`]
+pub(crate) async fn error_for_status(provider: &str, response: reqwest::Response) -> Result<reqwest::Response, AgentError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| AgentError::HttpError(e.to_string()))?;
+    Err(error_from_response(provider, status.as_u16(), retry_after.as_deref(), &body))
+}
+
+/// Body of a successful provider response, or the typed error for a non-2xx one
+pub(crate) async fn response_body(provider: &str, response: reqwest::Response) -> Result<String, AgentError> {
+    error_for_status(provider, response)
+        .await?
+        .text()
+        .await
+        .map_err(|e| AgentError::HttpError(e.to_string()))
+}
+
+fn parse_retry_after(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authentication_errors() {
+        let anthropic = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        assert!(matches!(
+            error_from_response("Anthropic", 401, None, anthropic),
+            AgentError::AuthenticationFailed
+        ));
+
+        let openai = r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#;
+        assert!(matches!(
+            error_from_response("OpenAI", 401, None, openai),
+            AgentError::AuthenticationFailed
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_with_retry_after() {
+        let body = r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#;
+        match error_from_response("Groq", 429, Some("12"), body) {
+            AgentError::RateLimited(retry) => assert_eq!(retry, Some(Duration::from_secs(12))),
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+
+        // HTTP-date form isn't parsed
+        let anthropic = r#"{"type":"error","error":{"type":"rate_limit_error","message":"slow down"}}"#;
+        assert!(matches!(
+            error_from_response("Anthropic", 429, Some("Wed, 21 Oct 2026 07:28:00 GMT"), anthropic),
+            AgentError::RateLimited(None)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: must be positive"}}"#;
+        match error_from_response("Anthropic", 400, None, body) {
+            AgentError::InvalidRequest(msg) => assert_eq!(msg, "max_tokens: must be positive"),
+            other => panic!("expected InvalidRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_shapes_fall_back_to_provider_error() {
+        match error_from_response("Z.ai", 500, None, "<html>Bad Gateway</html>") {
+            AgentError::ProviderError(msg) => {
+                assert_eq!(msg, "Z.ai API error 500: <html>Bad Gateway</html>")
+            }
+            other => panic!("expected ProviderError, got {:?}", other),
+        }
+
+        let overloaded = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        match error_from_response("Anthropic", 529, None, overloaded) {
+            AgentError::ProviderError(msg) => assert_eq!(msg, "Anthropic API error 529: Overloaded"),
+            other => panic!("expected ProviderError, got {:?}", other),
+        }
+
+        let bare = r#"{"error":"model not available"}"#;
+        assert!(matches!(
+            error_from_response("Grok", 503, None, bare),
+            AgentError::ProviderError(msg) if msg.ends_with("model not available")
+        ));
+    }
+}
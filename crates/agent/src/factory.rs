@@ -5,6 +5,7 @@ use crate::openai::OpenAIAgent;
 use crate::types::Agent;
 use crate::zai::ZaiAgent;
 use clanker_config::AgentConfig;
+use reqwest::Client;
 use std::sync::Arc;
 use tracing::{debug, info};
 
@@ -50,12 +51,18 @@ impl AgentFactory {
 
     /// Create an arc-wrapped agent for shared ownership (e.g. gateway state)
     pub fn create_arc_from_config(config: AgentConfig) -> Arc<dyn Agent + Send + Sync> {
+        Self::create_arc_with_client(config, Client::new())
+    }
+
+    /// Create an arc-wrapped agent that sends requests through `client`, so
+    /// agents built from the same client reuse one connection pool
+    pub fn create_arc_with_client(config: AgentConfig, client: Client) -> Arc<dyn Agent + Send + Sync> {
         match config.provider.to_lowercase().as_str() {
-            "anthropic" => Arc::new(AnthropicAgent::new(config)),
-            "openai" => Arc::new(OpenAIAgent::new(config)),
-            "grok" => Arc::new(GrokAgent::new(config)),
-            "groq" => Arc::new(GroqAgent::new(config)),
-            "zai" => Arc::new(ZaiAgent::new(config)),
+            "anthropic" => Arc::new(AnthropicAgent::with_client(config, client)),
+            "openai" => Arc::new(OpenAIAgent::with_client(config, client)),
+            "grok" => Arc::new(GrokAgent::with_client(config, client)),
+            "groq" => Arc::new(GroqAgent::with_client(config, client)),
+            "zai" => Arc::new(ZaiAgent::with_client(config, client)),
             _ => Arc::new(crate::placeholder::PlaceholderAgent::new(config)),
         }
     }
@@ -97,4 +104,44 @@ mod tests {
         assert!(!AgentFactory::is_supported("unknown"));
         assert!(!AgentFactory::is_supported(""));
     }
+
+    #[tokio::test]
+    async fn test_agents_share_provided_client() {
+        let mut server = mockito::Server::new_async().await;
+        // Only requests sent through the shared client carry this header
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("x-clanker-pool", "shared")
+            .with_status(200)
+            .with_body(
+                r#"{"id":"1","choices":[{"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}]}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-clanker-pool", "shared".parse().unwrap());
+        let client = Client::builder().default_headers(headers).build().unwrap();
+        let config = AgentConfig {
+            provider: "zai".to_string(),
+            model: "glm-4.7".to_string(),
+            api_key: Some("test-key".to_string()),
+            api_base_url: Some(server.url()),
+            ..Default::default()
+        };
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| AgentFactory::create_arc_with_client(config.clone(), client.clone()))
+            .collect();
+        for worker in &workers {
+            let message = crate::types::AgentMessage {
+                role: crate::types::MessageRole::User,
+                content: "hi".to_string(),
+                images: Vec::new(),
+            };
+            assert_eq!(worker.chat(vec![message]).await.unwrap().content, "ok");
+        }
+        mock.assert_async().await;
+    }
 }
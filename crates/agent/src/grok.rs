@@ -1,5 +1,10 @@
+use crate::capabilities::prepare_request;
+use crate::context::fit_to_context;
+use crate::errors::response_body;
+use crate::sse::{self, ChunkStream};
+use crate::telemetry::traced_chat;
 use crate::types::{
-    Agent, AgentError, AgentMessage, AgentResponse, StreamChunk, Usage,
+    extra_headers, request_timeout, health_check_messages, require_api_key, secret_header, warmup_head, Agent, AgentError, AgentMessage, AgentResponse, Usage,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -11,58 +16,80 @@ use tracing::{debug, trace};
 pub struct GrokAgent {
     config: clanker_config::AgentConfig,
     client: Client,
+    timeout: Duration,
 }
 
 impl GrokAgent {
+    /// Request timeout when `request_timeout_secs` is not configured
+    const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
     pub fn new(config: clanker_config::AgentConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_client(config, Client::new())
+    }
 
-        Self { config, client }
+    /// Create an agent sending requests through `client` (sharing its connection pool)
+    pub fn with_client(config: clanker_config::AgentConfig, client: Client) -> Self {
+        let timeout = request_timeout(&config, Self::DEFAULT_TIMEOUT_SECS);
+        Self { config, client, timeout }
     }
 
-    const API_URL: &'static str = "https://api.x.ai/v1/chat/completions";
-}
+    /// HTTP request timeout in use
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
 
-#[async_trait]
-impl Agent for GrokAgent {
-    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
-        debug!("Sending chat request to Grok (xAI)");
+    /// xAI API base URL (overridden by `api_base_url`)
+    const API_BASE: &'static str = "https://api.x.ai/v1";
+
+    /// Chat completions URL under the configured or default base
+    fn chat_url(&self) -> String {
+        let base = self.config.api_base_url.as_deref().unwrap_or(Self::API_BASE);
+        format!("{}/chat/completions", base.trim_end_matches('/'))
+    }
+
+    /// POST to the chat completions endpoint with credentials and extra headers.
+    /// No timeout is set: plain requests add one, streams are bounded per chunk.
+    fn chat_request(&self) -> Result<reqwest::RequestBuilder, AgentError> {
+        let auth = secret_header(format!("Bearer {}", self.config.api_key.as_deref().unwrap_or_default()))?;
+        Ok(self
+            .client
+            .post(self.chat_url())
+            .header("Authorization", auth)
+            .header("content-type", "application/json")
+            .headers(extra_headers(&self.config)))
+    }
 
-        let request = GrokRequest {
+    /// Request body for `messages`
+    fn build_request(&self, messages: Vec<AgentMessage>, max_tokens: u32) -> GrokRequest {
+        GrokRequest {
             model: self.config.model.clone(),
             messages: messages_to_grok(messages),
-            max_tokens: Some(self.config.max_tokens),
+            max_tokens: Some(max_tokens),
             temperature: Some(0.7), // Default temperature
-        };
+            stop: self.config.stop_sequences.clone(),
+        }
+    }
+
+    /// Send a chat completion request with an explicit output token limit
+    async fn complete(
+        &self,
+        messages: Vec<AgentMessage>,
+        max_tokens: u32,
+    ) -> Result<AgentResponse, AgentError> {
+        debug!("Sending chat request to Grok (xAI)");
+        let (messages, max_tokens) = prepare_request(&self.config.model, messages, max_tokens);
+
+        let request = self.build_request(messages, max_tokens);
 
         let response = self
-            .client
-            .post(Self::API_URL)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.api_key.as_ref().unwrap_or(&String::new())),
-            )
-            .header("content-type", "application/json")
+            .chat_request()?
+            .timeout(self.timeout)
             .json(&request)
             .send()
             .await
             .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
 
-        let status = response.status();
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| AgentError::HttpError(e.to_string()))?;
-
-        if !status.is_success() {
-            return Err(AgentError::ProviderError(format!(
-                "Grok API error {}: {}",
-                status, response_text
-            )));
-        }
+        let response_text = response_body("Grok", response).await?;
 
         let grok_response: GrokResponse = serde_json::from_str(&response_text)
             .map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
@@ -72,8 +99,8 @@ impl Agent for GrokAgent {
         let content = grok_response
             .choices
             .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_else(|| "".to_string());
+            .and_then(|c| c.message.content.clone())
+            .unwrap_or_default();
 
         let finish_reason = grok_response
             .choices
@@ -93,16 +120,30 @@ impl Agent for GrokAgent {
             provider: "grok".to_string(),
         })
     }
+}
 
-    async fn chat_stream(
-        &self,
-        _messages: Vec<AgentMessage>,
-    ) -> Result<
-        Box<dyn futures::Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>,
-        AgentError,
-    > {
-        debug!("Streaming not yet implemented for Grok");
-        Err(AgentError::Unknown("Streaming not implemented".to_string()))
+#[async_trait]
+impl Agent for GrokAgent {
+    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+        let messages = fit_to_context(messages, self.max_context(), self.config.max_tokens);
+        traced_chat(self.provider(), self.model(), self.complete(messages, self.config.max_tokens)).await
+    }
+
+    async fn health(&self) -> Result<(), AgentError> {
+        require_api_key(&self.config)?;
+        self.complete(health_check_messages(), 1).await.map(|_| ())
+    }
+
+    fn warmup_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(warmup_head(&self.client, self.chat_url(), self.timeout, &self.config))
+    }
+
+    async fn chat_stream(&self, messages: Vec<AgentMessage>) -> Result<ChunkStream, AgentError> {
+        let request = self.chat_request()?;
+        sse::chat_stream("Grok", &self.config, self.max_context(), request, self.timeout, messages, |messages, max_tokens| {
+            self.build_request(messages, max_tokens)
+        })
+        .await
     }
 
     fn provider(&self) -> &str {
@@ -112,6 +153,10 @@ impl Agent for GrokAgent {
     fn model(&self) -> &str {
         &self.config.model
     }
+
+    fn max_context(&self) -> u32 {
+        131_072
+    }
 }
 
 /// Grok API request (OpenAI-compatible)
@@ -121,6 +166,9 @@ struct GrokRequest {
     messages: Vec<GrokMessage>,
     max_tokens: Option<u32>,
     temperature: Option<f32>,
+    /// Sequences ending generation (`agent.stop_sequences`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -144,7 +192,8 @@ struct Choice {
 
 #[derive(Debug, Deserialize)]
 struct Message {
-    content: String,
+    /// Null when the provider filtered or refused the reply
+    content: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -172,6 +221,49 @@ fn messages_to_grok(messages: Vec<AgentMessage>) -> Vec<GrokMessage> {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_api_base_url_changes_request_url() {
+        let agent = GrokAgent::new(clanker_config::AgentConfig::default());
+        assert_eq!(agent.chat_url(), "https://api.x.ai/v1/chat/completions");
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/proxy/v1/chat/completions")
+            .with_status(200)
+            .with_body(
+                r#"{"choices":[{"message":{"content":"ok"},"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}"#,
+            )
+            .create_async()
+            .await;
+
+        let agent = GrokAgent::new(clanker_config::AgentConfig {
+            provider: "grok".to_string(),
+            model: "grok-2".to_string(),
+            api_key: Some("test-key".to_string()),
+            api_base_url: Some(format!("{}/proxy/v1/", server.url())),
+            ..Default::default()
+        });
+        let message = AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "hi".to_string(),
+            images: Vec::new(),
+        };
+        assert_eq!(agent.chat(vec![message]).await.unwrap().content, "ok");
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_filtered_response_with_null_content() {
+        let body = r#"{
+            "choices": [{"message": {"role": "assistant", "content": null}, "finish_reason": "content_filter"}],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 0, "total_tokens": 5}
+        }"#;
+
+        let response: GrokResponse = serde_json::from_str(body).unwrap();
+        assert!(response.choices[0].message.content.is_none());
+        assert_eq!(response.choices[0].finish_reason, "content_filter");
+    }
+
     #[test]
     fn test_messages_to_grok() {
         use crate::types::MessageRole;
@@ -179,10 +271,12 @@ mod tests {
             AgentMessage {
                 role: MessageRole::User,
                 content: "Hello".to_string(),
+                images: Vec::new(),
             },
             AgentMessage {
                 role: MessageRole::Assistant,
                 content: "Hi there!".to_string(),
+                images: Vec::new(),
             },
         ];
 
@@ -203,9 +297,7 @@ mod tests {
             api_key_env: "GROK_API_KEY".to_string(),
             api_key: Some("test-key".to_string()),
             max_tokens: 100,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         };
 
         let agent = GrokAgent::new(config);
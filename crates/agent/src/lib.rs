@@ -20,9 +20,7 @@
 //!         api_key_env: "OPENCLAW_ANTHROPIC_API_KEY".to_string(),
 //!         api_key: Some("your-api-key".to_string()),
 //!         max_tokens: 4096,
-//!         api_base_url: None,
-//!         worker: None,
-//!         fallback: None,
+//!         ..Default::default()
 //!     };
 //!
 //!     let agent = AgentFactory::create_from_config(config);
@@ -31,6 +29,7 @@
 //!         AgentMessage {
 //!             role: MessageRole::User,
 //!             content: "Hello!".to_string(),
+//!             images: Vec::new(),
 //!         }
 //!     ];
 //!
@@ -42,20 +41,32 @@
 //! ```
 
 pub mod anthropic;
+pub mod capabilities;
+pub mod context;
+pub mod errors;
 pub mod factory;
 pub mod grok;
 pub mod groq;
+pub mod models;
 pub mod openai;
 pub mod orchestrator;
 pub mod placeholder;
+pub mod sse;
+pub mod telemetry;
 pub mod types;
 pub mod zai;
 
 // Re-exports for convenience
+pub use context::{estimate_tokens, truncate_to_tokens};
 pub use factory::AgentFactory;
-pub use orchestrator::{MasterClanker, MASTER_SYSTEM_PROMPT};
+pub use placeholder::PlaceholderAgent;
+pub use orchestrator::{
+    Delegation, MasterClanker, ProgressCallback, WorkerProgress, DELEGATION_LIMIT_PROMPT, DIRECT_ANSWER_PROMPT,
+    MASTER_SYSTEM_PROMPT,
+};
 pub use types::{
-    Agent, AgentError, AgentMessage, AgentResponse, MessageRole,
+    Agent, AgentError, AgentMessage, AgentResponse, ChatOptions, ImageInput, MessageRole,
     StreamChunk, SystemPrompt, Usage, WorkerResult, WorkerTask, system_prompts,
+    FILTERED_FINISH_REASONS,
 };
 pub use clanker_config::AgentConfig;
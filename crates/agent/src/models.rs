@@ -0,0 +1,146 @@
+//! Model discovery: list the model ids a provider accepts.
+//!
+//! OpenAI, Anthropic and Groq expose a models endpoint returning
+//! `{"data": [{"id": ...}, ...]}`; Grok and Z.ai fall back to a curated list.
+
+use crate::errors::response_body;
+use crate::types::{secret_header, AgentError};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Timeout for a models endpoint request
+const LIST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Curated model ids for providers without a usable list endpoint
+const GROK_MODELS: &[&str] = &["grok-4", "grok-3", "grok-3-mini", "grok-2", "grok-2-vision"];
+const ZAI_MODELS: &[&str] = &["glm-4.7", "glm-4.6", "glm-4.5", "glm-4.5-air", "glm-4.5v"];
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+/// Models endpoint for providers that have one
+pub fn models_endpoint(provider: &str) -> Option<&'static str> {
+    match provider.to_lowercase().as_str() {
+        "openai" => Some("https://api.openai.com/v1/models"),
+        "anthropic" => Some("https://api.anthropic.com/v1/models"),
+        "groq" => Some("https://api.groq.com/openai/v1/models"),
+        _ => None,
+    }
+}
+
+/// Curated model list for providers without a models endpoint
+pub fn static_models(provider: &str) -> Option<&'static [&'static str]> {
+    match provider.to_lowercase().as_str() {
+        "grok" => Some(GROK_MODELS),
+        "zai" => Some(ZAI_MODELS),
+        _ => None,
+    }
+}
+
+/// Conventional environment variable holding the provider's API key
+pub fn default_api_key_env(provider: &str) -> String {
+    format!("OPENCLAW_{}_API_KEY", provider.to_uppercase())
+}
+
+/// Parse an OpenAI-style `{"data": [{"id": ...}]}` list into sorted model ids
+pub fn parse_model_list(body: &str) -> Result<Vec<String>, AgentError> {
+    let list: ModelList =
+        serde_json::from_str(body).map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
+    let mut ids: Vec<String> = list.data.into_iter().map(|m| m.id).collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// List model ids for `provider`, querying its models endpoint when it has one
+pub async fn list_models(provider: &str, api_key: Option<&str>) -> Result<Vec<String>, AgentError> {
+    if let Some(models) = static_models(provider) {
+        return Ok(models.iter().map(|m| m.to_string()).collect());
+    }
+    let url = models_endpoint(provider)
+        .ok_or_else(|| AgentError::InvalidRequest(format!("Unknown provider: {}", provider)))?;
+    let api_key = api_key.filter(|k| !k.is_empty()).ok_or(AgentError::AuthenticationFailed)?;
+    fetch_models(provider, url, api_key).await
+}
+
+/// GET a models endpoint and parse the returned ids
+pub async fn fetch_models(provider: &str, url: &str, api_key: &str) -> Result<Vec<String>, AgentError> {
+    let client = Client::builder()
+        .timeout(LIST_TIMEOUT)
+        .build()
+        .map_err(|e| AgentError::HttpError(e.to_string()))?;
+
+    let mut request = client.get(url);
+    request = if provider.eq_ignore_ascii_case("anthropic") {
+        request
+            .header("x-api-key", secret_header(api_key.to_string())?)
+            .header("anthropic-version", "2023-06-01")
+    } else {
+        request.header("Authorization", secret_header(format!("Bearer {}", api_key))?)
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
+    let body = response_body(provider, response).await?;
+    parse_model_list(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_openai_model_list() {
+        let body = r#"{
+            "object": "list",
+            "data": [
+                {"id": "gpt-4o", "object": "model", "created": 1715367049, "owned_by": "system"},
+                {"id": "gpt-4.1-mini", "object": "model", "created": 1744318173, "owned_by": "system"},
+                {"id": "dall-e-3", "object": "model", "created": 1698785189, "owned_by": "system"}
+            ]
+        }"#;
+        let ids = parse_model_list(body).unwrap();
+        assert_eq!(ids, vec!["dall-e-3", "gpt-4.1-mini", "gpt-4o"]);
+
+        assert!(matches!(parse_model_list("{\"models\": []}"), Err(AgentError::InvalidResponse(_))));
+    }
+
+    #[test]
+    fn test_provider_sources() {
+        assert!(models_endpoint("OpenAI").is_some());
+        assert!(models_endpoint("groq").unwrap().contains("/openai/v1/models"));
+        assert!(models_endpoint("zai").is_none());
+        assert!(static_models("zai").unwrap().contains(&"glm-4.7"));
+        assert_eq!(default_api_key_env("groq"), "OPENCLAW_GROQ_API_KEY");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_models_sends_bearer_key() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v1/models")
+            .match_header("authorization", "Bearer test-key")
+            .with_status(200)
+            .with_body(r#"{"data": [{"id": "llama-3.3-70b-versatile"}]}"#)
+            .create_async()
+            .await;
+
+        let url = format!("{}/v1/models", server.url());
+        let ids = fetch_models("groq", &url, "test-key").await.unwrap();
+        assert_eq!(ids, vec!["llama-3.3-70b-versatile"]);
+        mock.assert_async().await;
+
+        let missing_key = list_models("openai", None).await;
+        assert!(matches!(missing_key, Err(AgentError::AuthenticationFailed)));
+    }
+}
@@ -1,5 +1,11 @@
+use crate::capabilities::prepare_request;
+use crate::context::fit_to_context;
+use crate::errors::response_body;
+use crate::sse::{self, ChunkStream};
+use crate::telemetry::traced_chat;
 use crate::types::{
-    Agent, AgentError, AgentMessage, AgentResponse, StreamChunk, Usage,
+    extra_headers, request_timeout, health_check_messages, require_api_key, secret_header, warmup_head, Agent, AgentError, AgentMessage, AgentResponse,
+    ChatOptions, ResponseFormat, Usage,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -11,75 +17,125 @@ use tracing::{debug, trace};
 pub struct OpenAIAgent {
     config: clanker_config::AgentConfig,
     client: Client,
+    timeout: Duration,
 }
 
 impl OpenAIAgent {
+    /// Request timeout when `request_timeout_secs` is not configured
+    const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
     pub fn new(config: clanker_config::AgentConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_client(config, Client::new())
+    }
 
-        Self { config, client }
+    /// Create an agent sending requests through `client` (sharing its connection pool)
+    pub fn with_client(config: clanker_config::AgentConfig, client: Client) -> Self {
+        let timeout = request_timeout(&config, Self::DEFAULT_TIMEOUT_SECS);
+        Self { config, client, timeout }
     }
 
-    const API_URL: &'static str = "https://api.openai.com/v1/chat/completions";
-}
+    /// HTTP request timeout in use
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
 
-#[async_trait]
-impl Agent for OpenAIAgent {
-    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
-        debug!("Sending chat request to OpenAI");
+    /// OpenAI API base URL
+    const API_BASE: &'static str = "https://api.openai.com/v1";
 
-        let request = OpenAIRequest {
+    /// Chat completions URL: `{base}/chat/completions`, or the deployment URL in Azure mode
+    fn chat_url(&self) -> String {
+        let base = self
+            .config
+            .api_base_url
+            .as_deref()
+            .unwrap_or(Self::API_BASE)
+            .trim_end_matches('/');
+        match &self.config.azure {
+            Some(azure) => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                base, azure.deployment, azure.api_version
+            ),
+            None => format!("{}/chat/completions", base),
+        }
+    }
+
+    /// Credential header: Azure takes the key as `api-key`, OpenAI as a bearer token
+    fn auth_header(&self) -> Result<(&'static str, reqwest::header::HeaderValue), AgentError> {
+        let api_key = self.config.api_key.as_deref().unwrap_or_default();
+        if self.config.azure.is_some() {
+            Ok(("api-key", secret_header(api_key.to_string())?))
+        } else {
+            Ok(("Authorization", secret_header(format!("Bearer {}", api_key))?))
+        }
+    }
+
+    /// POST to the chat completions endpoint with credentials and extra headers.
+    /// No timeout is set: plain requests add one, streams are bounded per chunk.
+    fn chat_request(&self) -> Result<reqwest::RequestBuilder, AgentError> {
+        let (auth_name, auth_value) = self.auth_header()?;
+        Ok(self
+            .client
+            .post(self.chat_url())
+            .header(auth_name, auth_value)
+            .header("content-type", "application/json")
+            .headers(extra_headers(&self.config)))
+    }
+
+    /// Request body for `messages`
+    fn build_request(&self, messages: Vec<AgentMessage>, max_tokens: u32, json_mode: bool) -> OpenAIRequest {
+        OpenAIRequest {
             model: self.config.model.clone(),
-            messages: messages_to_openai(messages),
-            max_tokens: Some(self.config.max_tokens),
+            messages: messages_to_openai(messages, self.supports_vision()),
+            max_tokens: Some(max_tokens),
             temperature: Some(0.7),  // Default temperature
-        };
+            response_format: ResponseFormat::json_mode(json_mode),
+            stop: self.config.stop_sequences.clone(),
+        }
+    }
+
+    /// Send a chat completion request with an explicit output token limit
+    async fn complete(
+        &self,
+        messages: Vec<AgentMessage>,
+        max_tokens: u32,
+        json_mode: bool,
+    ) -> Result<AgentResponse, AgentError> {
+        debug!("Sending chat request to OpenAI");
+        let (messages, max_tokens) = prepare_request(&self.config.model, messages, max_tokens);
+
+        let request = self.build_request(messages, max_tokens, json_mode);
 
         let response = self
-            .client
-            .post(Self::API_URL)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.api_key.as_ref().unwrap_or(&String::new())),
-            )
-            .header("content-type", "application/json")
+            .chat_request()?
+            .timeout(self.timeout)
             .json(&request)
             .send()
             .await
             .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
 
-        let status = response.status();
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| AgentError::HttpError(e.to_string()))?;
-
-        if !status.is_success() {
-            return Err(AgentError::ProviderError(format!(
-                "OpenAI API error {}: {}",
-                status, response_text
-            )));
-        }
+        let response_text = response_body("OpenAI", response).await?;
 
         let openai_response: OpenAIResponse = serde_json::from_str(&response_text)
             .map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
 
         trace!("Received OpenAI response");
 
-        let content = openai_response
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_else(|| "".to_string());
+        let choice = openai_response.choices.first();
+
+        // Structured refusals come back in `refusal` with a null `content`
+        let refusal = choice.and_then(|c| c.message.refusal.clone());
+
+        let content = choice
+            .and_then(|c| c.message.content.clone().or_else(|| refusal.clone()))
+            .unwrap_or_default();
 
-        let finish_reason = openai_response
-            .choices
-            .first()
-            .map(|c| c.finish_reason.clone())
-            .unwrap_or_else(|| "stop".to_string());
+        let finish_reason = if refusal.is_some() {
+            "refusal".to_string()
+        } else {
+            choice
+                .map(|c| c.finish_reason.clone())
+                .unwrap_or_else(|| "stop".to_string())
+        };
 
         Ok(AgentResponse {
             content,
@@ -93,16 +149,39 @@ impl Agent for OpenAIAgent {
             provider: "openai".to_string(),
         })
     }
+}
 
-    async fn chat_stream(
+#[async_trait]
+impl Agent for OpenAIAgent {
+    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+        self.chat_with_options(messages, &ChatOptions::default()).await
+    }
+
+    async fn chat_with_options(
         &self,
-        _messages: Vec<AgentMessage>,
-    ) -> Result<
-        Box<dyn futures::Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>,
-        AgentError,
-    > {
-        debug!("Streaming not yet implemented for OpenAI");
-        Err(AgentError::Unknown("Streaming not implemented".to_string()))
+        messages: Vec<AgentMessage>,
+        options: &ChatOptions,
+    ) -> Result<AgentResponse, AgentError> {
+        let messages = fit_to_context(messages, self.max_context(), self.config.max_tokens);
+        let json_mode = options.json_mode || self.config.json_mode;
+        traced_chat(self.provider(), self.model(), self.complete(messages, self.config.max_tokens, json_mode)).await
+    }
+
+    async fn health(&self) -> Result<(), AgentError> {
+        require_api_key(&self.config)?;
+        self.complete(health_check_messages(), 1, false).await.map(|_| ())
+    }
+
+    fn warmup_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(warmup_head(&self.client, self.chat_url(), self.timeout, &self.config))
+    }
+
+    async fn chat_stream(&self, messages: Vec<AgentMessage>) -> Result<ChunkStream, AgentError> {
+        let request = self.chat_request()?;
+        sse::chat_stream("OpenAI", &self.config, self.max_context(), request, self.timeout, messages, |messages, max_tokens| {
+            self.build_request(messages, max_tokens, self.config.json_mode)
+        })
+        .await
     }
 
     fn provider(&self) -> &str {
@@ -112,6 +191,18 @@ impl Agent for OpenAIAgent {
     fn model(&self) -> &str {
         &self.config.model
     }
+
+    fn supports_json_mode(&self) -> bool {
+        true
+    }
+
+    fn max_context(&self) -> u32 {
+        128_000
+    }
+
+    fn supports_vision(&self) -> bool {
+        model_supports_vision(&self.config.model)
+    }
 }
 
 /// OpenAI API request
@@ -121,12 +212,37 @@ struct OpenAIRequest {
     messages: Vec<OpenAIMessage>,
     max_tokens: Option<u32>,
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    /// Sequences ending generation (`agent.stop_sequences`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    content: OpenAIContent,
+}
+
+/// Plain text, or content parts when the message carries images
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAIImageUrl },
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct OpenAIImageUrl {
+    url: String,
 }
 
 /// OpenAI API response
@@ -144,7 +260,9 @@ struct Choice {
 
 #[derive(Debug, Deserialize)]
 struct Message {
-    content: String,
+    content: Option<String>,
+    #[serde(default)]
+    refusal: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -154,8 +272,8 @@ struct OpenAIUsage {
     total_tokens: u32,
 }
 
-/// Convert agent messages to OpenAI format
-fn messages_to_openai(messages: Vec<AgentMessage>) -> Vec<OpenAIMessage> {
+/// Convert agent messages to OpenAI format; images are sent as `image_url` parts when `vision` is set
+fn messages_to_openai(messages: Vec<AgentMessage>, vision: bool) -> Vec<OpenAIMessage> {
     messages
         .into_iter()
         .map(|msg| OpenAIMessage {
@@ -163,15 +281,169 @@ fn messages_to_openai(messages: Vec<AgentMessage>) -> Vec<OpenAIMessage> {
                 .unwrap_or_else(|_| "\"user\"".to_string())
                 .trim_matches('"')
                 .to_string(),
-            content: msg.content,
+            content: if vision && !msg.images.is_empty() {
+                let images = msg.images.iter().map(|image| OpenAIContentPart::ImageUrl {
+                    image_url: OpenAIImageUrl { url: image.to_url() },
+                });
+                OpenAIContent::Parts(
+                    std::iter::once(OpenAIContentPart::Text { text: msg.content })
+                        .chain(images)
+                        .collect(),
+                )
+            } else {
+                if !msg.images.is_empty() {
+                    debug!("Model is text-only, dropping {} image(s)", msg.images.len());
+                }
+                OpenAIContent::Text(msg.content)
+            },
         })
         .collect()
 }
 
+/// OpenAI models that accept image inputs
+fn model_supports_vision(model: &str) -> bool {
+    ["gpt-4o", "gpt-4.1", "gpt-4-turbo", "gpt-5", "o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn hello() -> Vec<AgentMessage> {
+        vec![AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "hi".to_string(),
+            images: Vec::new(),
+        }]
+    }
+
+    const OK_BODY: &str = r#"{"id":"1","model":"gpt-4o","choices":[{"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}"#;
+
+    #[test]
+    fn test_chat_url_defaults_to_openai() {
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig::default());
+        assert_eq!(agent.chat_url(), "https://api.openai.com/v1/chat/completions");
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            api_base_url: Some("https://proxy.internal/v1/".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(agent.chat_url(), "https://proxy.internal/v1/chat/completions");
+    }
+
+    #[tokio::test]
+    async fn test_openai_mode_sends_bearer_to_base_url() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("authorization", "Bearer sk-test")
+            .match_header("api-key", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body(OK_BODY)
+            .create_async()
+            .await;
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            api_key: Some("sk-test".to_string()),
+            api_base_url: Some(server.url()),
+            ..Default::default()
+        });
+        assert_eq!(agent.chat(hello()).await.unwrap().content, "ok");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_warmup_opens_connection_whatever_the_status() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("HEAD", "/chat/completions")
+            .with_status(405)
+            .create_async()
+            .await;
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            api_base_url: Some(server.url()),
+            ..Default::default()
+        });
+        agent.warmup().await.unwrap();
+        mock.assert_async().await;
+
+        // Nothing listening: the connection itself fails
+        let unreachable = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            api_base_url: Some("http://127.0.0.1:1".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(unreachable.warmup().await, Err(AgentError::RequestFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_sends_stream_flag_and_decodes_deltas() {
+        use futures::StreamExt;
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"stream": true})))
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"o\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"k\"}}]}\n\n",
+                "data: [DONE]\n\n",
+            ))
+            .create_async()
+            .await;
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            api_key: Some("sk-test".to_string()),
+            api_base_url: Some(server.url()),
+            ..Default::default()
+        });
+        let chunks: Vec<_> = agent.chat_stream(hello()).await.unwrap().collect().await;
+        let chunks: Vec<_> = chunks.into_iter().map(Result::unwrap).collect();
+        assert_eq!(chunks.iter().map(|c| c.content.as_str()).collect::<String>(), "ok");
+        assert!(chunks.last().unwrap().done);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_azure_mode_uses_deployment_url_and_api_key() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/openai/deployments/gpt-4o-prod/chat/completions")
+            .match_query(mockito::Matcher::UrlEncoded("api-version".into(), "2024-10-21".into()))
+            .match_header("api-key", "azure-key")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body(OK_BODY)
+            .create_async()
+            .await;
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            api_key: Some("azure-key".to_string()),
+            api_base_url: Some(server.url()),
+            azure: Some(clanker_config::AzureOpenAIConfig {
+                deployment: "gpt-4o-prod".to_string(),
+                api_version: "2024-10-21".to_string(),
+            }),
+            ..Default::default()
+        });
+        assert_eq!(agent.chat(hello()).await.unwrap().content, "ok");
+        mock.assert_async().await;
+    }
+
     #[test]
     fn test_messages_to_openai() {
         use crate::types::MessageRole;
@@ -179,20 +451,130 @@ mod tests {
             AgentMessage {
                 role: MessageRole::User,
                 content: "Hello".to_string(),
+                images: Vec::new(),
             },
             AgentMessage {
                 role: MessageRole::Assistant,
                 content: "Hi there!".to_string(),
+                images: Vec::new(),
             },
         ];
 
-        let openai_messages = messages_to_openai(messages);
+        let openai_messages = messages_to_openai(messages, false);
 
         assert_eq!(openai_messages.len(), 2);
         assert_eq!(openai_messages[0].role, "user");
-        assert_eq!(openai_messages[0].content, "Hello");
+        assert_eq!(openai_messages[0].content, OpenAIContent::Text("Hello".to_string()));
         assert_eq!(openai_messages[1].role, "assistant");
-        assert_eq!(openai_messages[1].content, "Hi there!");
+        assert_eq!(openai_messages[1].content, OpenAIContent::Text("Hi there!".to_string()));
+    }
+
+    #[test]
+    fn test_json_mode_sets_response_format() {
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig::default());
+        let messages = || {
+            vec![AgentMessage {
+                role: crate::types::MessageRole::User,
+                content: "List three colors as JSON".to_string(),
+                images: Vec::new(),
+            }]
+        };
+
+        let json = serde_json::to_value(agent.build_request(messages(), 100, true)).unwrap();
+        assert_eq!(json["response_format"], serde_json::json!({"type": "json_object"}));
+
+        let json = serde_json::to_value(agent.build_request(messages(), 100, false)).unwrap();
+        assert!(json.get("response_format").is_none());
+        assert!(agent.supports_json_mode());
+    }
+
+    #[test]
+    fn test_stop_sequences_sent_as_stop() {
+        let messages = || {
+            vec![AgentMessage {
+                role: crate::types::MessageRole::User,
+                content: "Answer, then write END".to_string(),
+                images: Vec::new(),
+            }]
+        };
+        let plain = OpenAIAgent::new(clanker_config::AgentConfig::default());
+        let json = serde_json::to_value(plain.build_request(messages(), 100, false)).unwrap();
+        assert!(json.get("stop").is_none());
+
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            stop_sequences: vec!["END".to_string(), "</answer>".to_string()],
+            ..Default::default()
+        });
+        let json = serde_json::to_value(agent.build_request(messages(), 100, false)).unwrap();
+        assert_eq!(json["stop"], serde_json::json!(["END", "</answer>"]));
+    }
+
+    #[test]
+    fn test_vision_request_body() {
+        use crate::types::{ImageInput, MessageRole};
+        let messages = vec![AgentMessage {
+            role: MessageRole::User,
+            content: "What is in this picture?".to_string(),
+            images: vec![
+                ImageInput::Url {
+                    url: "https://example.com/cat.png".to_string(),
+                },
+                ImageInput::Base64 {
+                    media_type: "image/jpeg".to_string(),
+                    data: "QUJD".to_string(),
+                },
+            ],
+        }];
+
+        let request = OpenAIRequest {
+            model: "gpt-4o".to_string(),
+            messages: messages_to_openai(messages.clone(), model_supports_vision("gpt-4o")),
+            max_tokens: Some(100),
+            temperature: None,
+            response_format: None,
+            stop: Vec::new(),
+        };
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                {"type": "text", "text": "What is in this picture?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}},
+                {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,QUJD"}},
+            ])
+        );
+
+        // Text-only models get plain content and no images
+        assert!(!model_supports_vision("gpt-3.5-turbo"));
+        let body = serde_json::to_value(messages_to_openai(messages, false)).unwrap();
+        assert_eq!(body[0]["content"], "What is in this picture?");
+    }
+
+    #[test]
+    fn test_openai_response_with_refusal() {
+        let body = r#"{
+            "choices": [{
+                "message": {"role": "assistant", "content": null, "refusal": "I can't help with that."},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 6, "total_tokens": 11}
+        }"#;
+
+        let response: OpenAIResponse = serde_json::from_str(body).unwrap();
+        let message = &response.choices[0].message;
+        assert!(message.content.is_none());
+        assert_eq!(message.refusal.as_deref(), Some("I can't help with that."));
+    }
+
+    #[tokio::test]
+    async fn test_openai_health_without_api_key() {
+        let agent = OpenAIAgent::new(clanker_config::AgentConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4".to_string(),
+            ..Default::default()
+        });
+
+        assert!(matches!(agent.health().await, Err(AgentError::AuthenticationFailed)));
     }
 
     #[test]
@@ -203,9 +585,7 @@ mod tests {
             api_key_env: "OPENAI_API_KEY".to_string(),
             api_key: Some("test-key".to_string()),
             max_tokens: 100,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         };
 
         let agent = OpenAIAgent::new(config);
@@ -4,9 +4,10 @@
 //! Worker_Clankers use Groq and execute tasks with assigned identities.
 
 use crate::factory::AgentFactory;
-use crate::types::{Agent, AgentMessage, AgentResponse, MessageRole, WorkerResult, WorkerTask};
+use crate::types::{Agent, AgentMessage, MessageRole, WorkerResult, WorkerTask};
 use clanker_config::{AgentConfig, WorkerAgentConfig};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, error, warn};
 
 /// Delegation marker in Master response - when present, we parse and spawn workers
@@ -23,11 +24,44 @@ You may spawn up to 5 workers. Each worker gets a distinct identity and a specif
 
 If you can answer the user's question directly without delegation, respond normally. Do NOT use [DELEGATE] for simple queries."#;
 
+/// Follow-up once `orchestration.max_delegation_depth` is reached and the master delegates again
+pub const DELEGATION_LIMIT_PROMPT: &str = "Delegation limit reached: no more Worker_Clankers can be spawned. Answer the user directly using the results above. Do not use [DELEGATE].";
+
+/// System prompt used to re-ask Master_Clanker after a malformed delegation
+pub const DIRECT_ANSWER_PROMPT: &str = "You are Master_Clanker. Answer the user's question directly and completely. Do not delegate and do not use [DELEGATE].";
+
+/// Result of parsing a Master response for delegation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delegation {
+    /// No delegation marker; the response is the final answer
+    None,
+    /// Valid worker assignments
+    Tasks(Vec<WorkerTask>),
+    /// Marker present but the assignments could not be parsed (reason)
+    Malformed(String),
+}
+
+/// Worker lifecycle event reported during delegation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerProgress {
+    /// Worker was spawned
+    Started { identity: String },
+    /// Worker finished (successfully, with an error, or timed out)
+    Completed { identity: String },
+}
+
+/// Callback receiving [`WorkerProgress`] events; called from worker tasks
+pub type ProgressCallback = Arc<dyn Fn(WorkerProgress) + Send + Sync>;
+
 /// Orchestrator that wraps the Master agent and spawns Worker_Clankers
 pub struct MasterClanker {
     master_agent: Arc<dyn Agent + Send + Sync>,
     worker_config: WorkerAgentConfig,
     max_workers: usize,
+    /// Agent used for workers instead of building a Groq agent from `worker_config`
+    worker_agent: Option<Arc<dyn Agent + Send + Sync>>,
+    /// HTTP client shared by spawned workers so delegations reuse connections
+    http_client: reqwest::Client,
 }
 
 impl MasterClanker {
@@ -41,106 +75,107 @@ impl MasterClanker {
             master_agent,
             worker_config,
             max_workers,
+            worker_agent: None,
+            http_client: reqwest::Client::new(),
         }
     }
 
-    /// Spawn a single Worker_Clanker with identity and task
-    pub async fn spawn_worker(
-        &self,
-        identity: &str,
-        task: &str,
-    ) -> Result<AgentResponse, crate::types::AgentError> {
-        let groq_config = self.worker_config_to_agent_config();
-        if groq_config.api_key.is_none() || groq_config.api_key.as_ref().unwrap().is_empty() {
-            return Err(crate::types::AgentError::AuthenticationFailed);
-        }
+    /// Send worker requests through `client` (e.g. one shared with the master)
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = client;
+        self
+    }
 
-        let worker = AgentFactory::create_arc_from_config(groq_config);
-        let system_prompt = format!(
-            "You are Worker_Clanker. Your identity: {}. Execute this task: {}",
-            identity, task
-        );
+    /// Use the given agent for all Worker_Clankers instead of Groq
+    pub fn with_worker_agent(mut self, agent: Arc<dyn Agent + Send + Sync>) -> Self {
+        self.worker_agent = Some(agent);
+        self
+    }
 
-        let messages = vec![
-            AgentMessage {
-                role: MessageRole::System,
-                content: system_prompt,
-            },
-            AgentMessage {
-                role: MessageRole::User,
-                content: task.to_string(),
-            },
-        ];
+    /// Check if workers can run: a worker agent was supplied or the worker API key is set
+    pub fn workers_available(&self) -> bool {
+        self.worker_agent.is_some() || self.worker_config.api_key.as_ref().is_some_and(|k| !k.is_empty())
+    }
+
+    /// Run a single Worker_Clanker with identity and task, giving up after
+    /// `worker_timeout_secs` like delegated workers
+    pub async fn spawn_worker(&self, identity: &str, task: &str) -> WorkerResult {
+        let (identity, task) = (identity.to_string(), task.to_string());
+        match self.worker() {
+            Some(worker) => run_worker(worker, identity, task, self.worker_timeout()).await,
+            None => WorkerResult::failed(identity, task, "Groq API key not configured"),
+        }
+    }
+
+    /// Agent workers run on: the supplied worker agent, or one built from the
+    /// worker config (None without a Groq API key)
+    fn worker(&self) -> Option<Arc<dyn Agent + Send + Sync>> {
+        if let Some(agent) = &self.worker_agent {
+            return Some(agent.clone());
+        }
+        let groq_config = worker_config_to_agent_config(&self.worker_config);
+        if groq_config.api_key.as_ref().is_none_or(|k| k.is_empty()) {
+            warn!("Worker_Clanker: Groq API key not set, skipping");
+            return None;
+        }
+        Some(AgentFactory::create_arc_with_client(groq_config, self.http_client.clone()))
+    }
 
-        debug!("Spawning Worker_Clanker: identity={}, task_len={}", identity, task.len());
-        worker.chat(messages).await
+    fn worker_timeout(&self) -> Duration {
+        Duration::from_secs(self.worker_config.worker_timeout_secs)
     }
 
     /// Delegate tasks to Worker_Clankers in parallel (up to max_workers)
     pub async fn delegate(&self, workers: Vec<WorkerTask>) -> Vec<WorkerResult> {
+        self.delegate_with_progress(workers, None).await
+    }
+
+    /// Delegate tasks, reporting each worker's start and completion to `progress`.
+    /// Dropping the returned future (e.g. an aborted request) aborts the workers.
+    pub async fn delegate_with_progress(
+        &self,
+        workers: Vec<WorkerTask>,
+        progress: Option<ProgressCallback>,
+    ) -> Vec<WorkerResult> {
         let workers: Vec<WorkerTask> = workers.into_iter().take(self.max_workers).collect();
 
-        let mut handles = Vec::with_capacity(workers.len());
-        for wt in workers {
-            let identity = wt.identity.clone();
-            let task = wt.task.clone();
-            let worker_config = self.worker_config.clone();
-            handles.push(tokio::spawn(async move {
-                let groq_config = worker_config_to_agent_config(&worker_config);
-                if groq_config.api_key.is_none() || groq_config.api_key.as_ref().unwrap().is_empty() {
-                    warn!("Worker_Clanker: Groq API key not set, skipping");
-                    return WorkerResult {
-                        identity: identity.clone(),
-                        task: task.clone(),
-                        content: format!("[Error: Groq API key not configured for worker {}]", identity),
-                    };
-                }
+        let timeout = self.worker_timeout();
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, WorkerTask { identity, task }) in workers.into_iter().enumerate() {
+            let worker = self.worker();
 
-                let worker = AgentFactory::create_arc_from_config(groq_config);
-                let system_prompt = format!(
-                    "You are Worker_Clanker. Your identity: {}. Execute this task: {}",
-                    identity, task
-                );
-
-                let messages = vec![
-                    AgentMessage {
-                        role: MessageRole::System,
-                        content: system_prompt,
-                    },
-                    AgentMessage {
-                        role: MessageRole::User,
-                        content: task.clone(),
-                    },
-                ];
-
-                match worker.chat(messages).await {
-                    Ok(resp) => WorkerResult {
-                        identity,
-                        task,
-                        content: resp.content,
-                    },
-                    Err(e) => {
-                        error!("Worker_Clanker {} failed: {}", identity, e);
-                        WorkerResult {
-                            identity,
-                            task,
-                            content: format!("[Worker error: {}]", e),
-                        }
-                    }
+            if let Some(report) = &progress {
+                report(WorkerProgress::Started {
+                    identity: identity.clone(),
+                });
+            }
+            let progress = progress.clone();
+            tasks.spawn(async move {
+                let result = match worker {
+                    Some(worker) => run_worker(worker, identity, task, timeout).await,
+                    None => WorkerResult::failed(identity, task, "Groq API key not configured"),
+                };
+                if let Some(report) = progress {
+                    report(WorkerProgress::Completed {
+                        identity: result.identity.clone(),
+                    });
                 }
-            }));
+                (index, result)
+            });
         }
 
-        let mut results = Vec::with_capacity(handles.len());
-        for h in handles {
-            match h.await {
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
                 Ok(r) => results.push(r),
                 Err(e) => {
                     error!("Worker join error: {}", e);
                 }
             }
         }
-        results
+        // Workers finish in any order; report them in the order they were assigned
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
     }
 
     /// Get the master agent for direct chat
@@ -149,31 +184,84 @@ impl MasterClanker {
     }
 
     /// Parse delegation from Master response; returns None if no delegation
+    /// (or if the delegation block is malformed, see [`Self::parse_delegation_block`])
     pub fn parse_delegation(response: &str) -> Option<Vec<WorkerTask>> {
+        match Self::parse_delegation_block(response) {
+            Delegation::Tasks(tasks) => Some(tasks),
+            Delegation::None | Delegation::Malformed(_) => None,
+        }
+    }
+
+    /// Parse delegation from Master response, distinguishing "no marker" from a
+    /// marker followed by unusable JSON. Repeated leading markers are tolerated.
+    pub fn parse_delegation_block(response: &str) -> Delegation {
         let trimmed = response.trim();
         if !trimmed.starts_with(DELEGATE_PREFIX) {
-            return None;
+            return Delegation::None;
         }
 
-        let json_start = trimmed
-            .strip_prefix(DELEGATE_PREFIX)
-            .map(|s| s.trim())
-            .unwrap_or("");
+        let mut json_start = trimmed;
+        while let Some(rest) = json_start.strip_prefix(DELEGATE_PREFIX) {
+            json_start = rest.trim();
+        }
         if json_start.is_empty() {
-            return None;
+            return Delegation::Malformed("no worker assignments after marker".to_string());
         }
 
         // Find the JSON array - it may be followed by more text
-        let json_str = extract_json_array(json_start)?;
-        let tasks: Vec<WorkerTask> = serde_json::from_str(&json_str).ok()?;
-        if tasks.is_empty() {
-            return None;
+        let Some(json_str) = extract_json_array(json_start) else {
+            return Delegation::Malformed("expected a complete JSON array".to_string());
+        };
+        match serde_json::from_str::<Vec<WorkerTask>>(&json_str) {
+            Ok(tasks) if tasks.is_empty() => Delegation::Malformed("empty worker array".to_string()),
+            Ok(tasks) => Delegation::Tasks(tasks),
+            Err(e) => Delegation::Malformed(e.to_string()),
         }
-        Some(tasks)
     }
+}
 
-    fn worker_config_to_agent_config(&self) -> AgentConfig {
-        worker_config_to_agent_config(&self.worker_config)
+/// Run one Worker_Clanker call, giving up after `timeout`
+async fn run_worker(
+    worker: Arc<dyn Agent + Send + Sync>,
+    identity: String,
+    task: String,
+    timeout: Duration,
+) -> WorkerResult {
+    let system_prompt = format!(
+        "You are Worker_Clanker. Your identity: {}. Execute this task: {}",
+        identity, task
+    );
+
+    let messages = vec![
+        AgentMessage {
+            role: MessageRole::System,
+            content: system_prompt,
+            images: Vec::new(),
+        },
+        AgentMessage {
+            role: MessageRole::User,
+            content: task.clone(),
+            images: Vec::new(),
+        },
+    ];
+
+    debug!("Spawning Worker_Clanker: identity={}, task_len={}", identity, task.len());
+    match tokio::time::timeout(timeout, worker.chat(messages)).await {
+        Ok(Ok(resp)) => WorkerResult {
+            identity,
+            task,
+            content: resp.content,
+            error: None,
+            usage: resp.usage,
+        },
+        Ok(Err(e)) => {
+            error!("Worker_Clanker {} failed: {}", identity, e);
+            WorkerResult::failed(identity, task, e.to_string())
+        }
+        Err(_) => {
+            error!("Worker_Clanker {} timed out after {:?}", identity, timeout);
+            WorkerResult::failed(identity, task, format!("timed out after {:?}", timeout))
+        }
     }
 }
 
@@ -184,9 +272,7 @@ fn worker_config_to_agent_config(worker: &WorkerAgentConfig) -> AgentConfig {
         api_key_env: worker.api_key_env.clone(),
         api_key: worker.api_key.clone(),
         max_tokens: worker.max_tokens,
-        api_base_url: None,
-        worker: None,
-        fallback: None,
+        ..Default::default()
     }
 }
 
@@ -200,7 +286,7 @@ fn extract_json_array(s: &str) -> Option<String> {
     let mut in_string = false;
     let mut escape = false;
     let mut quote_char = '"';
-    for (i, c) in s.chars().enumerate() {
+    for (i, c) in s.char_indices() {
         if escape {
             escape = false;
             continue;
@@ -234,6 +320,7 @@ fn extract_json_array(s: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::AgentResponse;
 
     #[test]
     fn test_parse_delegation_empty() {
@@ -241,6 +328,39 @@ mod tests {
         assert!(MasterClanker::parse_delegation("").is_none());
     }
 
+    #[test]
+    fn test_parse_delegation_block_truncated_array() {
+        let s = r#"[DELEGATE][{"identity":"Research Assistant","task":"Find"#;
+        assert!(matches!(MasterClanker::parse_delegation_block(s), Delegation::Malformed(_)));
+        assert!(MasterClanker::parse_delegation(s).is_none());
+    }
+
+    #[test]
+    fn test_parse_delegation_block_non_array() {
+        let s = r#"[DELEGATE]{"identity":"Research Assistant","task":"Find studies"}"#;
+        assert!(matches!(MasterClanker::parse_delegation_block(s), Delegation::Malformed(_)));
+
+        let s = r#"[DELEGATE]["just", "strings"]"#;
+        assert!(matches!(MasterClanker::parse_delegation_block(s), Delegation::Malformed(_)));
+
+        assert!(matches!(MasterClanker::parse_delegation_block("[DELEGATE]"), Delegation::Malformed(_)));
+        assert!(matches!(MasterClanker::parse_delegation_block("[DELEGATE][]"), Delegation::Malformed(_)));
+    }
+
+    #[test]
+    fn test_parse_delegation_block_duplicate_markers() {
+        let s = r#"[DELEGATE] [DELEGATE][{"identity":"A","task":"T1"}]"#;
+        match MasterClanker::parse_delegation_block(s) {
+            Delegation::Tasks(tasks) => assert_eq!(tasks[0].identity, "A"),
+            other => panic!("expected tasks, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_delegation_block_no_marker() {
+        assert_eq!(MasterClanker::parse_delegation_block("Just an answer"), Delegation::None);
+    }
+
     #[test]
     fn test_parse_delegation_valid() {
         let s = r#"[DELEGATE][{"identity":"Research Assistant","task":"Find studies"}]"#;
@@ -289,9 +409,7 @@ mod tests {
             api_key_env: "TEST".to_string(),
             api_key: None,
             max_tokens: 100,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         });
 
         let worker_config = WorkerAgentConfig {
@@ -299,6 +417,7 @@ mod tests {
             api_key_env: "GROQ_TEST".to_string(),
             api_key: None,
             max_tokens: 100,
+            worker_timeout_secs: 60,
         };
 
         let orchestrator = MasterClanker::new(master, worker_config, 2);
@@ -322,4 +441,205 @@ mod tests {
 
         assert_eq!(results.len(), 2, "delegate should cap at max_workers=2");
     }
+
+    #[tokio::test]
+    async fn test_scripted_master_drives_delegation() {
+        use crate::placeholder::PlaceholderAgent;
+
+        let master = Arc::new(PlaceholderAgent::scripted(vec![
+            r#"[DELEGATE][{"identity":"Researcher","task":"Find facts"},{"identity":"Writer","task":"Draft"}]"#
+                .to_string(),
+            "Synthesized answer".to_string(),
+        ]));
+        let worker = Arc::new(PlaceholderAgent::scripted(vec!["worker output".to_string()]));
+        let orchestrator =
+            MasterClanker::new(master, WorkerAgentConfig::default(), 5).with_worker_agent(worker);
+
+        let user = vec![AgentMessage {
+            role: MessageRole::User,
+            content: "Write a report".to_string(),
+            images: Vec::new(),
+        }];
+        let first = orchestrator.master_agent().chat(user.clone()).await.unwrap();
+        let tasks = MasterClanker::parse_delegation(&first.content).expect("scripted delegation");
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].identity, "Researcher");
+
+        let results = orchestrator.delegate(tasks).await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.content == "worker output"));
+
+        let synthesis = orchestrator.master_agent().chat(user).await.unwrap();
+        assert_eq!(synthesis.content, "Synthesized answer");
+    }
+
+    /// Worker that sleeps before answering
+    struct SlowAgent {
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for SlowAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, crate::types::AgentError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(AgentResponse {
+                content: "done".to_string(),
+                finish_reason: "stop".to_string(),
+                usage: crate::types::Usage {
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                },
+                model: "slow".to_string(),
+                provider: "slow".to_string(),
+            })
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<
+            Box<dyn futures::Stream<Item = Result<crate::types::StreamChunk, crate::types::AgentError>> + Send + Unpin>,
+            crate::types::AgentError,
+        > {
+            Err(crate::types::AgentError::Unknown("not supported".to_string()))
+        }
+
+        fn provider(&self) -> &str {
+            "slow"
+        }
+
+        fn model(&self) -> &str {
+            "slow"
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delegate_worker_timeout() {
+        let master = AgentFactory::create_arc_from_config(AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+        let worker_config = WorkerAgentConfig {
+            worker_timeout_secs: 1,
+            ..Default::default()
+        };
+
+        let orchestrator = MasterClanker::new(master, worker_config, 2).with_worker_agent(Arc::new(SlowAgent {
+            delay: Duration::from_secs(30),
+        }));
+
+        let results = orchestrator
+            .delegate(vec![WorkerTask {
+                identity: "Slow".to_string(),
+                task: "Take forever".to_string(),
+            }])
+            .await;
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "");
+        assert_eq!(results[0].error.as_deref(), Some("timed out after 1s"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spawn_worker_timeout() {
+        let master = AgentFactory::create_arc_from_config(AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+        let worker_config = WorkerAgentConfig {
+            worker_timeout_secs: 1,
+            ..Default::default()
+        };
+
+        let orchestrator = MasterClanker::new(master, worker_config, 2).with_worker_agent(Arc::new(SlowAgent {
+            delay: Duration::from_secs(30),
+        }));
+
+        let result = orchestrator.spawn_worker("Slow", "Take forever").await;
+
+        assert_eq!(result.identity, "Slow");
+        assert_eq!(result.error.as_deref(), Some("timed out after 1s"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_aborted_delegation_stops_workers() {
+        let master = AgentFactory::create_arc_from_config(AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+        let worker_config = WorkerAgentConfig {
+            worker_timeout_secs: 60,
+            ..Default::default()
+        };
+        let orchestrator = MasterClanker::new(master, worker_config, 2).with_worker_agent(Arc::new(SlowAgent {
+            delay: Duration::from_secs(30),
+        }));
+
+        let completed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = completed.clone();
+        let progress: ProgressCallback = Arc::new(move |event| {
+            if matches!(event, WorkerProgress::Completed { .. }) {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+        let delegation = tokio::spawn(async move {
+            let tasks = vec![WorkerTask {
+                identity: "Slow".to_string(),
+                task: "Take forever".to_string(),
+            }];
+            orchestrator.delegate_with_progress(tasks, Some(progress)).await
+        });
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        delegation.abort();
+        assert!(delegation.await.unwrap_err().is_cancelled());
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(completed.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delegate_worker_within_timeout() {
+        let master = AgentFactory::create_arc_from_config(AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+        let worker_config = WorkerAgentConfig {
+            worker_timeout_secs: 10,
+            ..Default::default()
+        };
+
+        let orchestrator = MasterClanker::new(master, worker_config, 2).with_worker_agent(Arc::new(SlowAgent {
+            delay: Duration::from_secs(1),
+        }));
+
+        let results = orchestrator
+            .delegate(vec![WorkerTask {
+                identity: "Quick".to_string(),
+                task: "Answer".to_string(),
+            }])
+            .await;
+
+        assert_eq!(results[0].content, "done");
+        assert!(results[0].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delegate_without_worker_key_reports_error() {
+        let master = AgentFactory::create_arc_from_config(AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+        let orchestrator = MasterClanker::new(master, WorkerAgentConfig::default(), 2);
+
+        let results = orchestrator
+            .delegate(vec![WorkerTask {
+                identity: "Researcher".to_string(),
+                task: "Find facts".to_string(),
+            }])
+            .await;
+
+        assert_eq!(results[0].content, "");
+        assert_eq!(results[0].error.as_deref(), Some("Groq API key not configured"));
+    }
 }
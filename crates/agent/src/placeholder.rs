@@ -1,30 +1,71 @@
 use crate::types::{Agent, AgentError, AgentMessage, AgentResponse, StreamChunk};
 use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 /// Placeholder agent for testing and development
+///
+/// By default it echoes the last message. A [`scripted`](Self::scripted) agent
+/// instead returns canned responses in order, repeating the last one.
 pub struct PlaceholderAgent {
     config: clanker_config::AgentConfig,
+    /// Artificial latency before each response
+    delay: Option<Duration>,
+    /// Canned responses (empty = echo mode)
+    script: Vec<String>,
+    /// Index of the next scripted response
+    next: AtomicUsize,
 }
 
 impl PlaceholderAgent {
     pub fn new(config: clanker_config::AgentConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            delay: None,
+            script: Vec::new(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Agent returning `responses` in order, then repeating the last one
+    /// (an empty script echoes like [`new`](Self::new))
+    pub fn scripted(responses: Vec<String>) -> Self {
+        let mut agent = Self::new(clanker_config::AgentConfig {
+            provider: "placeholder".to_string(),
+            model: "scripted".to_string(),
+            ..Default::default()
+        });
+        agent.script = responses;
+        agent
+    }
+
+    /// Wait `delay` before answering (simulates a slow provider)
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
     }
 }
 
 #[async_trait]
 impl Agent for PlaceholderAgent {
     async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
-        let last_message = messages
-            .last()
-            .map(|msg| msg.content.clone())
-            .unwrap_or_else(|| "Hello!".to_string());
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let content = if self.script.is_empty() {
+            let last_message = messages
+                .last()
+                .map(|msg| msg.content.clone())
+                .unwrap_or_else(|| "Hello!".to_string());
+            format!("Placeholder response from {}: {}", self.config.provider, last_message)
+        } else {
+            let index = self.next.fetch_add(1, Ordering::Relaxed).min(self.script.len() - 1);
+            self.script[index].clone()
+        };
 
         Ok(AgentResponse {
-            content: format!(
-                "Placeholder response from {}: {}",
-                self.config.provider, last_message
-            ),
+            content,
             finish_reason: "stop".to_string(),
             usage: crate::types::Usage {
                 prompt_tokens: messages.len() as u32,
@@ -58,7 +99,7 @@ impl Agent for PlaceholderAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clanker_config::AgentConfig;
+    use crate::types::ChatOptions;
 
     #[tokio::test]
     async fn test_placeholder_agent() {
@@ -68,9 +109,7 @@ mod tests {
             api_key_env: "PLACEHOLDER_API_KEY".to_string(),
             api_key: Some("test-key".to_string()),
             max_tokens: 100,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         };
 
         let agent = PlaceholderAgent::new(config);
@@ -78,6 +117,7 @@ mod tests {
         let messages = vec![AgentMessage {
             role: crate::types::MessageRole::User,
             content: "Hello!".to_string(),
+            images: Vec::new(),
         }];
 
         let response = agent.chat(messages).await.unwrap();
@@ -86,6 +126,86 @@ mod tests {
         assert_eq!(response.model, "test-model");
     }
 
+    #[tokio::test]
+    async fn test_chat_cancellable_aborts_slow_request() {
+        let agent = PlaceholderAgent::new(clanker_config::AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        })
+        .with_delay(Duration::from_secs(30));
+
+        let token = tokio_util::sync::CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            canceller.cancel();
+        });
+
+        let messages = vec![AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "Hello!".to_string(),
+            images: Vec::new(),
+        }];
+        let result = agent.chat_cancellable(messages, &ChatOptions::default(), token).await;
+        assert!(matches!(result, Err(AgentError::Unknown(ref reason)) if reason == "cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_cancellable_completes_when_not_cancelled() {
+        let agent = PlaceholderAgent::new(clanker_config::AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+
+        let messages = vec![AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "Hello!".to_string(),
+            images: Vec::new(),
+        }];
+        let response = agent
+            .chat_cancellable(messages, &ChatOptions::default(), tokio_util::sync::CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(response.content.contains("Hello!"));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_responses_repeat_last() {
+        let agent = PlaceholderAgent::scripted(vec!["first".to_string(), "second".to_string()]);
+        let ask = || async {
+            let messages = vec![AgentMessage {
+                role: crate::types::MessageRole::User,
+                content: "Hello!".to_string(),
+                images: Vec::new(),
+            }];
+            agent.chat(messages).await.unwrap().content
+        };
+
+        assert_eq!(ask().await, "first");
+        assert_eq!(ask().await, "second");
+        assert_eq!(ask().await, "second");
+        assert_eq!(agent.provider(), "placeholder");
+
+        // An empty script keeps the echo behavior
+        let echo = PlaceholderAgent::scripted(Vec::new());
+        let messages = vec![AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "ping".to_string(),
+            images: Vec::new(),
+        }];
+        assert!(echo.chat(messages).await.unwrap().content.ends_with("ping"));
+    }
+
+    #[tokio::test]
+    async fn test_placeholder_health() {
+        let agent = PlaceholderAgent::new(clanker_config::AgentConfig {
+            provider: "placeholder".to_string(),
+            ..Default::default()
+        });
+
+        assert!(agent.health().await.is_ok());
+    }
+
     #[test]
     fn test_placeholder_creation() {
         let config = clanker_config::AgentConfig {
@@ -94,9 +214,7 @@ mod tests {
             api_key_env: "PLACEHOLDER_API_KEY".to_string(),
             api_key: Some("test-key".to_string()),
             max_tokens: 100,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         };
 
         let agent = PlaceholderAgent::new(config);
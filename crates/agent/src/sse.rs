@@ -0,0 +1,292 @@
+//! Streaming for OpenAI-compatible providers (OpenAI, Groq, Grok, Z.ai).
+//!
+//! With `"stream": true` these APIs answer with server-sent events: one
+//! `data: {...}` line per delta carrying `choices[0].delta.content`, ended by
+//! `data: [DONE]`. [`decode_sse`] turns the raw body into [`StreamChunk`]s.
+//!
+//! A stream may run far longer than any single request should, so instead of a
+//! whole-response deadline the request timeout bounds the wait for the response
+//! headers and every gap between body chunks.
+
+use crate::capabilities::{capabilities, prepare_request};
+use crate::context::fit_to_context;
+use crate::errors::error_for_status;
+use crate::types::{AgentError, AgentMessage, StreamChunk, Usage};
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Boxed chunk stream returned by `Agent::chat_stream`
+pub type ChunkStream = Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>;
+
+/// Payload marking the end of the stream
+const DONE: &str = "[DONE]";
+
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Reject streaming for models known not to support it
+pub fn require_streaming(model: &str) -> Result<(), AgentError> {
+    if capabilities(model).supports_streaming {
+        Ok(())
+    } else {
+        Err(AgentError::InvalidRequest(format!("Model {} does not support streaming", model)))
+    }
+}
+
+/// `Agent::chat_stream` for an OpenAI-compatible provider: fit `messages` to the
+/// context, build the request body with `build` (messages, output token limit) and
+/// send it with `"stream": true` through `request`
+pub async fn chat_stream<B: Serialize>(
+    provider: &str,
+    config: &clanker_config::AgentConfig,
+    max_context: u32,
+    request: reqwest::RequestBuilder,
+    timeout: Duration,
+    messages: Vec<AgentMessage>,
+    build: impl FnOnce(Vec<AgentMessage>, u32) -> B,
+) -> Result<ChunkStream, AgentError> {
+    require_streaming(&config.model)?;
+    let messages = fit_to_context(messages, max_context, config.max_tokens);
+    let (messages, max_tokens) = prepare_request(&config.model, messages, config.max_tokens);
+    let mut body = serde_json::to_value(build(messages, max_tokens))
+        .map_err(|e| AgentError::SerializationError(e.to_string()))?;
+    body["stream"] = serde_json::Value::Bool(true);
+    stream_chat_completion(provider, request.json(&body), timeout).await
+}
+
+/// Send a streaming chat completion request and decode its event stream. `timeout`
+/// bounds the wait for the response headers and for each body chunk after that.
+pub async fn stream_chat_completion(
+    provider: &str,
+    request: reqwest::RequestBuilder,
+    timeout: Duration,
+) -> Result<ChunkStream, AgentError> {
+    let response = tokio::time::timeout(timeout, request.send())
+        .await
+        .map_err(|_| AgentError::RequestFailed(format!("No response within {:?}", timeout)))?
+        .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
+
+    let bytes = error_for_status(provider, response)
+        .await?
+        .bytes_stream()
+        .map_err(|e| AgentError::HttpError(e.to_string()));
+    Ok(Box::new(decode_sse(with_idle_timeout(bytes, timeout))))
+}
+
+/// End `bytes` with a `RequestFailed` error once no chunk arrives for `idle`
+fn with_idle_timeout<S, B>(bytes: S, idle: Duration) -> impl Stream<Item = Result<B, AgentError>> + Send + Unpin
+where
+    S: Stream<Item = Result<B, AgentError>> + Send + Unpin + 'static,
+    B: Send + 'static,
+{
+    futures::stream::unfold(Some(bytes), move |bytes| async move {
+        let mut bytes = bytes?;
+        match tokio::time::timeout(idle, bytes.next()).await {
+            Ok(Some(item)) => Some((item, Some(bytes))),
+            Ok(None) => None,
+            Err(_) => Some((Err(AgentError::RequestFailed(format!("Stream idle for {:?}", idle))), None)),
+        }
+    })
+    .boxed()
+}
+
+/// Line-buffering SSE parser; bytes may split lines (and UTF-8 sequences) anywhere
+#[derive(Debug, Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl SseDecoder {
+    /// Feed body bytes, returning the chunks of every completed line
+    fn push(&mut self, bytes: &[u8]) -> Vec<Result<StreamChunk, AgentError>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut chunks = Vec::new();
+        while !self.done {
+            let Some(end) = self.buffer.iter().position(|b| *b == b'\n') else {
+                break;
+            };
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(chunk) = parse_line(line.trim_end_matches(['\r', '\n'])) {
+                self.done = matches!(chunk, Ok(StreamChunk { done: true, .. }));
+                chunks.push(chunk);
+            }
+        }
+        chunks
+    }
+}
+
+/// Chunk for one SSE line; `None` for comments, other fields and empty deltas
+fn parse_line(line: &str) -> Option<Result<StreamChunk, AgentError>> {
+    let data = line.strip_prefix("data:")?.trim_start();
+    if data == DONE {
+        return Some(Ok(StreamChunk {
+            content: String::new(),
+            done: true,
+            usage: None,
+            finish_reason: None,
+        }));
+    }
+
+    let event: StreamEvent = match serde_json::from_str(data) {
+        Ok(event) => event,
+        Err(e) => return Some(Err(AgentError::InvalidResponse(e.to_string()))),
+    };
+    if let Some(error) = event.error {
+        return Some(Err(AgentError::ProviderError(error.to_string())));
+    }
+    let (content, finish_reason) = match event.choices.into_iter().next() {
+        Some(choice) => (choice.delta.content.unwrap_or_default(), choice.finish_reason),
+        None => (String::new(), None),
+    };
+    // Role-only deltas carry nothing to forward
+    if content.is_empty() && event.usage.is_none() && finish_reason.is_none() {
+        return None;
+    }
+    Some(Ok(StreamChunk {
+        content,
+        done: false,
+        usage: event.usage,
+        finish_reason,
+    }))
+}
+
+struct DecodeState<S> {
+    bytes: S,
+    decoder: SseDecoder,
+    pending: VecDeque<Result<StreamChunk, AgentError>>,
+    finished: bool,
+}
+
+/// Decode an SSE body into chunks, ending after the `[DONE]` chunk.
+/// A body that ends without `[DONE]` yields a final `InvalidResponse` error.
+pub fn decode_sse<S, B>(bytes: S) -> impl Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin
+where
+    S: Stream<Item = Result<B, AgentError>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+{
+    let state = DecodeState {
+        bytes,
+        decoder: SseDecoder::default(),
+        pending: VecDeque::new(),
+        finished: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(chunk) = state.pending.pop_front() {
+                return Some((chunk, state));
+            }
+            if state.finished || state.decoder.done {
+                return None;
+            }
+            match state.bytes.next().await {
+                Some(Ok(bytes)) => {
+                    let chunks = state.decoder.push(bytes.as_ref());
+                    state.pending.extend(chunks);
+                }
+                Some(Err(e)) => {
+                    state.finished = true;
+                    return Some((Err(e), state));
+                }
+                None => {
+                    state.finished = true;
+                    let truncated = AgentError::InvalidResponse("Stream ended before [DONE]".to_string());
+                    return Some((Err(truncated), state));
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Recorded OpenAI-style stream (role delta, content deltas, finish, usage, done)
+    const RECORDED: &str = concat!(
+        "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+        ": keep-alive\n\n",
+        "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo, wörld\"}}]}\r\n\r\n",
+        "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"id\":\"c1\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":4,\"total_tokens\":13}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    async fn decode_in_pieces(body: &str, piece: usize) -> Vec<Result<StreamChunk, AgentError>> {
+        let pieces: Vec<Result<Vec<u8>, AgentError>> =
+            body.as_bytes().chunks(piece).map(|bytes| Ok(bytes.to_vec())).collect();
+        decode_sse(futures::stream::iter(pieces)).collect().await
+    }
+
+    #[tokio::test]
+    async fn test_decode_recorded_stream() {
+        // 7-byte pieces split lines and the multi-byte "ö"
+        let chunks = decode_in_pieces(RECORDED, 7).await;
+        let chunks: Vec<StreamChunk> = chunks.into_iter().map(Result::unwrap).collect();
+
+        let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(content, "Hello, wörld");
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| !c.done));
+        let usage = chunks.iter().find_map(|c| c.usage.clone()).unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (9, 4));
+        let finish_reason = chunks.iter().find_map(|c| c.finish_reason.clone());
+        assert_eq!(finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_reports_truncated_and_error_streams() {
+        let chunks = decode_in_pieces("data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n", 64).await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap().content, "Hi");
+        assert!(matches!(chunks[1], Err(AgentError::InvalidResponse(_))));
+
+        let chunks = decode_in_pieces("data: {\"error\":{\"message\":\"overloaded\"}}\n\ndata: [DONE]\n\n", 64).await;
+        assert!(matches!(&chunks[0], Err(AgentError::ProviderError(m)) if m.contains("overloaded")));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_times_out() {
+        let first: Result<Vec<u8>, AgentError> = Ok(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n".to_vec());
+        let stalled = futures::stream::iter([first]).chain(futures::stream::pending());
+        let chunks: Vec<_> =
+            decode_sse(with_idle_timeout(Box::pin(stalled), Duration::from_millis(20))).collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap().content, "Hi");
+        assert!(matches!(&chunks[1], Err(AgentError::RequestFailed(m)) if m.contains("idle")));
+    }
+
+    #[test]
+    fn test_require_streaming() {
+        assert!(require_streaming("gpt-4o").is_ok());
+        assert!(matches!(require_streaming("o1-preview"), Err(AgentError::InvalidRequest(_))));
+    }
+}
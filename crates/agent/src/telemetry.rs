@@ -0,0 +1,110 @@
+//! Tracing spans around provider requests.
+//!
+//! Every `chat` call runs inside an `agent_chat` span tagged with the provider
+//! and model. When the request finishes the span records its latency and, on
+//! success, the prompt and completion token counts from the response usage.
+
+use crate::types::{AgentError, AgentResponse};
+use std::future::Future;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Instrument;
+
+/// Name of the span wrapping each provider chat request
+pub const CHAT_SPAN: &str = "agent_chat";
+
+/// Run a provider request inside an [`CHAT_SPAN`] span, recording usage and latency
+pub async fn traced_chat<F>(provider: &str, model: &str, request: F) -> Result<AgentResponse, AgentError>
+where
+    F: Future<Output = Result<AgentResponse, AgentError>>,
+{
+    let span = tracing::info_span!(
+        CHAT_SPAN,
+        provider,
+        model,
+        prompt_tokens = Empty,
+        completion_tokens = Empty,
+        latency_ms = Empty,
+        error = Empty,
+    );
+    let started = Instant::now();
+    let result = request.instrument(span.clone()).await;
+
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    match &result {
+        Ok(response) => {
+            span.record("prompt_tokens", response.usage.prompt_tokens);
+            span.record("completion_tokens", response.usage.completion_tokens);
+        }
+        Err(e) => {
+            span.record("error", tracing::field::display(e));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Usage;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    /// Fields recorded on chat spans, by field name
+    type Captured = Arc<Mutex<HashMap<String, String>>>;
+
+    struct CaptureLayer(Captured);
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for CaptureLayer {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == CHAT_SPAN {
+                attrs.record(&mut Fields(&mut self.0.lock().unwrap()));
+            }
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut Fields(&mut self.0.lock().unwrap()));
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_chat_span_records_token_counts() {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::registry().with(CaptureLayer(captured.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let response = AgentResponse {
+            content: "hi".to_string(),
+            finish_reason: "stop".to_string(),
+            usage: Usage {
+                prompt_tokens: 12,
+                completion_tokens: 3,
+                total_tokens: 15,
+            },
+            model: "llama-3.3-70b-versatile".to_string(),
+            provider: "groq".to_string(),
+        };
+        traced_chat("groq", "llama-3.3-70b-versatile", async { Ok(response) })
+            .await
+            .unwrap();
+
+        let fields = captured.lock().unwrap();
+        assert_eq!(fields["provider"], "\"groq\"");
+        assert_eq!(fields["model"], "\"llama-3.3-70b-versatile\"");
+        assert_eq!(fields["prompt_tokens"], "12");
+        assert_eq!(fields["completion_tokens"], "3");
+        assert!(fields.contains_key("latency_ms"));
+        assert!(!fields.contains_key("error"));
+    }
+}
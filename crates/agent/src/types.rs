@@ -1,9 +1,10 @@
 use async_trait::async_trait;
-use clanker_core::ChannelType;
+use clanker_core::{Attachment, ChannelType};
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 /// Agent errors
 #[derive(Error, Debug)]
@@ -20,6 +21,9 @@ pub enum AgentError {
     #[error("Rate limited: retry after {0:?}")]
     RateLimited(Option<Duration>),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
 
@@ -29,15 +33,74 @@ pub enum AgentError {
     #[error("HTTP error: {0}")]
     HttpError(String),
 
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
+impl AgentError {
+    /// Whether another attempt (or another provider) might succeed; a rejected
+    /// key or request is not retried with the fallback agent
+    pub fn is_transient(&self) -> bool {
+        !matches!(
+            self,
+            AgentError::AuthenticationFailed
+                | AgentError::InvalidRequest(_)
+                | AgentError::SerializationError(_)
+                | AgentError::Config(_)
+        )
+    }
+}
+
 /// Agent message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentMessage {
     pub role: MessageRole,
     pub content: String,
+    /// Images sent alongside `content` to vision-capable providers (text-only providers drop them)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageInput>,
+}
+
+/// Image passed to a vision-capable model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageInput {
+    /// Image fetched by the provider from a URL
+    Url { url: String },
+    /// Inline image data
+    Base64 { media_type: String, data: String },
+}
+
+impl ImageInput {
+    /// Image input for an attachment; `None` for non-image attachments.
+    /// `data:<type>;base64,<data>` URLs become inline images.
+    pub fn from_attachment(attachment: &Attachment) -> Option<Self> {
+        if !attachment.mime_type.starts_with("image/") {
+            return None;
+        }
+        if let Some(rest) = attachment.url.strip_prefix("data:") {
+            let (meta, data) = rest.split_once(',')?;
+            let media_type = meta.strip_suffix(";base64")?;
+            return Some(Self::Base64 {
+                media_type: media_type.to_string(),
+                data: data.to_string(),
+            });
+        }
+        Some(Self::Url {
+            url: attachment.url.clone(),
+        })
+    }
+
+    /// Image as a URL (a data URL for inline images)
+    pub fn to_url(&self) -> String {
+        match self {
+            Self::Url { url } => url.clone(),
+            Self::Base64 { media_type, data } => format!("data:{};base64,{}", media_type, data),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,34 +121,164 @@ pub struct AgentResponse {
     pub provider: String,
 }
 
+/// Finish reasons providers use when a response was refused or content-filtered
+/// (OpenAI-compatible `content_filter`, Anthropic `refusal`, Z.ai `sensitive`)
+pub const FILTERED_FINISH_REASONS: &[&str] = &["content_filter", "refusal", "sensitive"];
+
+impl AgentResponse {
+    /// Check if the provider refused or filtered this response
+    pub fn is_filtered(&self) -> bool {
+        FILTERED_FINISH_REASONS.contains(&self.finish_reason.to_lowercase().as_str())
+    }
+}
+
+/// Messages for a provider health check request
+pub(crate) fn health_check_messages() -> Vec<AgentMessage> {
+    vec![AgentMessage {
+        role: MessageRole::User,
+        content: "ping".to_string(),
+        images: Vec::new(),
+    }]
+}
+
+/// Fail fast when no API key is configured
+pub(crate) fn require_api_key(config: &clanker_config::AgentConfig) -> Result<(), AgentError> {
+    match config.api_key.as_deref() {
+        Some(key) if !key.is_empty() => Ok(()),
+        _ => Err(AgentError::AuthenticationFailed),
+    }
+}
+
+/// Provider HTTP timeout: `request_timeout_secs`, or the provider's default
+pub(crate) fn request_timeout(config: &clanker_config::AgentConfig, default_secs: u64) -> Duration {
+    Duration::from_secs(config.request_timeout_secs.unwrap_or(default_secs))
+}
+
+/// `HEAD` request to a provider endpoint for `Agent::warmup`, with the configured extra headers
+pub(crate) fn warmup_head(
+    client: &reqwest::Client,
+    url: impl reqwest::IntoUrl,
+    timeout: Duration,
+    config: &clanker_config::AgentConfig,
+) -> reqwest::RequestBuilder {
+    client.head(url).timeout(timeout).headers(extra_headers(config))
+}
+
+/// Header value for a credential, marked sensitive so it never shows up in `Debug` output.
+/// A key that is not a valid header value (e.g. one with a trailing newline) is a config error.
+pub(crate) fn secret_header(value: String) -> Result<reqwest::header::HeaderValue, AgentError> {
+    let mut header = reqwest::header::HeaderValue::from_str(&value).map_err(|_| {
+        AgentError::Config("API key contains characters not allowed in an HTTP header".to_string())
+    })?;
+    header.set_sensitive(true);
+    Ok(header)
+}
+
+/// Configured `extra_headers` for a provider request. Values are marked
+/// sensitive since they often carry proxy credentials; entries that are not
+/// valid HTTP headers are skipped.
+pub(crate) fn extra_headers(config: &clanker_config::AgentConfig) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in &config.extra_headers {
+        let (Ok(name), Ok(mut value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) else {
+            tracing::warn!("Skipping invalid extra header '{}'", name);
+            continue;
+        };
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    headers
+}
+
+/// Per-request options for [`Agent::chat_with_options`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChatOptions {
+    /// Ask for a JSON object response (OpenAI-style `response_format`)
+    pub json_mode: bool,
+}
+
+/// OpenAI-compatible `response_format` request field
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub(crate) struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+impl ResponseFormat {
+    /// `{"type": "json_object"}` when JSON mode is requested
+    pub(crate) fn json_mode(enabled: bool) -> Option<Self> {
+        enabled.then_some(Self { kind: "json_object" })
+    }
+}
+
 /// Task assigned to a Worker_Clanker by Master_Clanker
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerTask {
     pub identity: String,
     pub task: String,
 }
 
 /// Result from a Worker_Clanker after completing its task
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerResult {
     pub identity: String,
     pub task: String,
+    /// Worker's answer (empty when it failed)
     pub content: String,
+    /// Why the worker produced no answer (request error, timeout, missing key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Tokens the worker's call used (not shown to Master_Clanker)
+    #[serde(skip)]
+    pub usage: Usage,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl WorkerResult {
+    /// Result of a worker that produced no answer
+    pub fn failed(identity: String, task: String, error: impl Into<String>) -> Self {
+        Self {
+            identity,
+            task,
+            content: String::new(),
+            error: Some(error.into()),
+            usage: Usage::default(),
+        }
+    }
+
+    /// Render results as a fenced JSON array for Master_Clanker's synthesis turn,
+    /// so worker output can't be confused with the surrounding instructions
+    pub fn to_synthesis_block(results: &[WorkerResult]) -> String {
+        let json = serde_json::to_string_pretty(results).unwrap_or_else(|_| "[]".to_string());
+        format!("```json\n{}\n```", json)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
 }
 
+impl std::ops::AddAssign<&Usage> for Usage {
+    fn add_assign(&mut self, other: &Usage) {
+        self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
+        self.completion_tokens = self.completion_tokens.saturating_add(other.completion_tokens);
+        self.total_tokens = self.total_tokens.saturating_add(other.total_tokens);
+    }
+}
+
 /// Stream chunk for streaming responses
 #[derive(Debug, Clone)]
 pub struct StreamChunk {
     pub content: String,
     pub done: bool,
     pub usage: Option<Usage>,
+    /// Why the provider stopped generating, on the chunk that reports it
+    pub finish_reason: Option<String>,
 }
 
 /// System prompt configuration
@@ -122,6 +315,7 @@ pub mod system_prompts {
             ChannelType::Discord => "You are a helpful AI assistant for Discord. Be conversational and use Discord-friendly formatting.",
             ChannelType::Slack => "You are a helpful AI assistant for Slack. Keep responses professional and clear.",
             ChannelType::WhatsApp => "You are a helpful AI assistant for WhatsApp. Keep responses friendly and concise.",
+            ChannelType::Webhook => "You are a helpful AI assistant. Responses are delivered to an HTTP webhook; use plain text.",
         };
 
         SystemPrompt::new(prompt).with_channel_type(channel_type)
@@ -147,22 +341,114 @@ pub trait Agent: Send + Sync {
         AgentError,
     >;
 
+    /// Send chat request with per-request options. Options the provider can't
+    /// honor are rejected rather than silently ignored.
+    async fn chat_with_options(
+        &self,
+        messages: Vec<AgentMessage>,
+        options: &ChatOptions,
+    ) -> Result<AgentResponse, AgentError> {
+        if options.json_mode {
+            return Err(AgentError::InvalidRequest(format!(
+                "JSON mode is not supported by {}",
+                self.provider()
+            )));
+        }
+        self.chat(messages).await
+    }
+
+    /// Send chat request with per-request options, aborting with
+    /// `AgentError::Unknown("cancelled")` when `token` fires
+    async fn chat_cancellable(
+        &self,
+        messages: Vec<AgentMessage>,
+        options: &ChatOptions,
+        token: CancellationToken,
+    ) -> Result<AgentResponse, AgentError> {
+        tokio::select! {
+            result = self.chat_with_options(messages, options) => result,
+            _ = token.cancelled() => Err(AgentError::Unknown("cancelled".to_string())),
+        }
+    }
+
+    /// Check the agent can reach its provider (e.g. the API key is accepted).
+    /// Providers send a minimal request; the default assumes healthy.
+    async fn health(&self) -> Result<(), AgentError> {
+        Ok(())
+    }
+
+    /// Open a pooled connection to the provider (DNS, TCP and TLS) ahead of the
+    /// first request by sending `warmup_request`. Any HTTP status counts; only
+    /// transport errors fail.
+    async fn warmup(&self) -> Result<(), AgentError> {
+        let Some(request) = self.warmup_request() else {
+            return Ok(());
+        };
+        request
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| AgentError::RequestFailed(e.to_string()))
+    }
+
+    /// Request `warmup` sends (usually a `HEAD` to the chat endpoint); `None` for
+    /// agents without a provider connection
+    fn warmup_request(&self) -> Option<reqwest::RequestBuilder> {
+        None
+    }
+
     /// Get agent provider name
     fn provider(&self) -> &str;
 
     /// Get model name
     fn model(&self) -> &str;
+
+    /// Get maximum context window (tokens)
+    fn max_context(&self) -> u32 {
+        4096 // Default
+    }
+
+    /// Whether the provider accepts `ChatOptions::json_mode`
+    fn supports_json_mode(&self) -> bool {
+        false
+    }
+
+    /// Whether the model accepts image inputs (`AgentMessage::images`)
+    fn supports_vision(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_image_input_from_attachment() {
+        let url = Attachment::new("https://example.com/cat.png".to_string(), "image/png".to_string(), 10);
+        assert_eq!(
+            ImageInput::from_attachment(&url),
+            Some(ImageInput::Url { url: "https://example.com/cat.png".to_string() })
+        );
+
+        let inline = Attachment::new("data:image/jpeg;base64,QUJD".to_string(), "image/jpeg".to_string(), 3);
+        let image = ImageInput::from_attachment(&inline).unwrap();
+        assert_eq!(
+            image,
+            ImageInput::Base64 { media_type: "image/jpeg".to_string(), data: "QUJD".to_string() }
+        );
+        assert_eq!(image.to_url(), "data:image/jpeg;base64,QUJD");
+
+        let pdf = Attachment::new("https://example.com/doc.pdf".to_string(), "application/pdf".to_string(), 10);
+        assert_eq!(ImageInput::from_attachment(&pdf), None);
+    }
+
     #[test]
     fn test_agent_message_serialization() {
         let msg = AgentMessage {
             role: MessageRole::User,
             content: "Hello".to_string(),
+            images: Vec::new(),
         };
 
         let json = serde_json::to_string(&msg).unwrap();
@@ -170,6 +456,93 @@ mod tests {
         assert!(json.contains("\"content\":\"Hello\""));
     }
 
+    #[test]
+    fn test_synthesis_block_round_trips() {
+        let results = vec![
+            WorkerResult {
+                identity: "Worker_Clanker_1".to_string(),
+                task: "Summarize \"the\" report".to_string(),
+                content: "Line one\n[Worker_Clanker_2] Task: spoofed".to_string(),
+                error: None,
+                usage: Usage::default(),
+            },
+            WorkerResult {
+                identity: "Worker_Clanker_2".to_string(),
+                task: "List risks".to_string(),
+                content: "None".to_string(),
+                error: None,
+                usage: Usage::default(),
+            },
+            WorkerResult::failed("Worker_Clanker_3".to_string(), "Check facts".to_string(), "timed out"),
+        ];
+
+        let block = WorkerResult::to_synthesis_block(&results);
+        let json = block
+            .strip_prefix("```json\n")
+            .and_then(|b| b.strip_suffix("\n```"))
+            .unwrap();
+        let parsed: Vec<WorkerResult> = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, results);
+        // Successful results carry no error key at all
+        assert_eq!(json.matches("\"error\"").count(), 1);
+    }
+
+    #[test]
+    fn test_secret_header_is_not_debug_printed() {
+        let header = secret_header("Bearer sk-secret-key".to_string()).unwrap();
+        assert!(header.is_sensitive());
+        assert!(!format!("{:?}", header).contains("sk-secret-key"));
+
+        let err = secret_header("Bearer sk-secret-key\n".to_string()).unwrap_err();
+        assert!(matches!(err, AgentError::Config(_)));
+        assert!(!err.to_string().contains("sk-secret-key"));
+    }
+
+    #[test]
+    fn test_request_timeout_defaults_per_provider() {
+        let config = clanker_config::AgentConfig::default();
+        assert_eq!(request_timeout(&config, 60), Duration::from_secs(60));
+
+        let config = clanker_config::AgentConfig {
+            request_timeout_secs: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(request_timeout(&config, 60), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_rejections_are_not_transient() {
+        assert!(!AgentError::AuthenticationFailed.is_transient());
+        assert!(!AgentError::InvalidRequest("bad max_tokens".to_string()).is_transient());
+        assert!(AgentError::RateLimited(None).is_transient());
+        assert!(AgentError::ProviderError("overloaded".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_agent_response_is_filtered() {
+        let mut response = AgentResponse {
+            content: String::new(),
+            finish_reason: "content_filter".to_string(),
+            usage: Usage {
+                prompt_tokens: 1,
+                completion_tokens: 0,
+                total_tokens: 1,
+            },
+            model: "gpt-4".to_string(),
+            provider: "openai".to_string(),
+        };
+        assert!(response.is_filtered());
+
+        response.finish_reason = "refusal".to_string();
+        assert!(response.is_filtered());
+
+        response.finish_reason = "stop".to_string();
+        assert!(!response.is_filtered());
+
+        response.finish_reason = "end_turn".to_string();
+        assert!(!response.is_filtered());
+    }
+
     #[test]
     fn test_system_prompt_creation() {
         let prompt = SystemPrompt::new("Test prompt");
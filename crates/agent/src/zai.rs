@@ -4,8 +4,14 @@
 //! API: https://api.z.ai/api/paas/v4/chat/completions
 //! Docs: https://docs.z.ai/
 
+use crate::capabilities::prepare_request;
+use crate::context::fit_to_context;
+use crate::errors::response_body;
+use crate::sse::{self, ChunkStream};
+use crate::telemetry::traced_chat;
 use crate::types::{
-    Agent, AgentError, AgentMessage, AgentResponse, StreamChunk, Usage,
+    extra_headers, request_timeout, health_check_messages, require_api_key, secret_header, warmup_head, Agent, AgentError, AgentMessage, AgentResponse,
+    ChatOptions, ResponseFormat, Usage,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -17,66 +23,82 @@ use tracing::{debug, trace};
 pub struct ZaiAgent {
     config: clanker_config::AgentConfig,
     client: Client,
+    timeout: Duration,
 }
 
 impl ZaiAgent {
+    /// Request timeout when `request_timeout_secs` is not configured
+    const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
     pub fn new(config: clanker_config::AgentConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(60))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_client(config, Client::new())
+    }
+
+    /// Create an agent sending requests through `client` (sharing its connection pool)
+    pub fn with_client(config: clanker_config::AgentConfig, client: Client) -> Self {
+        let timeout = request_timeout(&config, Self::DEFAULT_TIMEOUT_SECS);
+        Self { config, client, timeout }
+    }
 
-        Self { config, client }
+    /// HTTP request timeout in use
+    pub fn timeout(&self) -> Duration {
+        self.timeout
     }
 
     /// Z.ai API base URL (OpenAI-compatible)
     const API_BASE: &'static str = "https://api.z.ai/api/paas/v4";
-}
 
-#[async_trait]
-impl Agent for ZaiAgent {
-    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
-        debug!("Sending chat request to Z.ai (GLM-4.7)");
+    /// Chat completions URL under the configured or default base
+    fn chat_url(&self) -> String {
+        let base = self.config.api_base_url.as_deref().unwrap_or(Self::API_BASE);
+        format!("{}/chat/completions", base.trim_end_matches('/'))
+    }
 
-        let api_url = self
-            .config
-            .api_base_url
-            .as_deref()
-            .unwrap_or(Self::API_BASE);
-        let url = format!("{}/chat/completions", api_url.trim_end_matches('/'));
+    /// POST to the chat completions endpoint with credentials and extra headers.
+    /// No timeout is set: plain requests add one, streams are bounded per chunk.
+    fn chat_request(&self) -> Result<reqwest::RequestBuilder, AgentError> {
+        let auth = secret_header(format!("Bearer {}", self.config.api_key.as_deref().unwrap_or_default()))?;
+        Ok(self
+            .client
+            .post(self.chat_url())
+            .header("Authorization", auth)
+            .header("content-type", "application/json")
+            .headers(extra_headers(&self.config)))
+    }
 
-        let request = ZaiRequest {
+    /// Request body for `messages`
+    fn build_request(&self, messages: Vec<AgentMessage>, max_tokens: u32, json_mode: bool) -> ZaiRequest {
+        ZaiRequest {
             model: self.config.model.clone(),
             messages: messages_to_zai(messages),
-            max_tokens: Some(self.config.max_tokens),
+            max_tokens: Some(max_tokens),
             temperature: Some(0.7),
-        };
+            response_format: ResponseFormat::json_mode(json_mode),
+            stop: self.config.stop_sequences.clone(),
+        }
+    }
+
+    /// Send a chat completion request with an explicit output token limit
+    async fn complete(
+        &self,
+        messages: Vec<AgentMessage>,
+        max_tokens: u32,
+        json_mode: bool,
+    ) -> Result<AgentResponse, AgentError> {
+        debug!("Sending chat request to Z.ai (GLM-4.7)");
+        let (messages, max_tokens) = prepare_request(&self.config.model, messages, max_tokens);
+
+        let request = self.build_request(messages, max_tokens, json_mode);
 
         let response = self
-            .client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.api_key.as_ref().unwrap_or(&String::new())),
-            )
-            .header("Content-Type", "application/json")
+            .chat_request()?
+            .timeout(self.timeout)
             .json(&request)
             .send()
             .await
             .map_err(|e| AgentError::RequestFailed(e.to_string()))?;
 
-        let status = response.status();
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| AgentError::HttpError(e.to_string()))?;
-
-        if !status.is_success() {
-            return Err(AgentError::ProviderError(format!(
-                "Z.ai API error {}: {}",
-                status, response_text
-            )));
-        }
+        let response_text = response_body("Z.ai", response).await?;
 
         let zai_response: ZaiResponse = serde_json::from_str(&response_text)
             .map_err(|e| AgentError::InvalidResponse(e.to_string()))?;
@@ -87,7 +109,7 @@ impl Agent for ZaiAgent {
             .choices
             .first()
             .and_then(|c| c.message.content.clone())
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
 
         let finish_reason = zai_response
             .choices
@@ -113,16 +135,39 @@ impl Agent for ZaiAgent {
             provider: "zai".to_string(),
         })
     }
+}
 
-    async fn chat_stream(
+#[async_trait]
+impl Agent for ZaiAgent {
+    async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+        self.chat_with_options(messages, &ChatOptions::default()).await
+    }
+
+    async fn chat_with_options(
         &self,
-        _messages: Vec<AgentMessage>,
-    ) -> Result<
-        Box<dyn futures::Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>,
-        AgentError,
-    > {
-        debug!("Streaming not yet implemented for Z.ai");
-        Err(AgentError::Unknown("Streaming not implemented".to_string()))
+        messages: Vec<AgentMessage>,
+        options: &ChatOptions,
+    ) -> Result<AgentResponse, AgentError> {
+        let messages = fit_to_context(messages, self.max_context(), self.config.max_tokens);
+        let json_mode = options.json_mode || self.config.json_mode;
+        traced_chat(self.provider(), self.model(), self.complete(messages, self.config.max_tokens, json_mode)).await
+    }
+
+    async fn health(&self) -> Result<(), AgentError> {
+        require_api_key(&self.config)?;
+        self.complete(health_check_messages(), 1, false).await.map(|_| ())
+    }
+
+    fn warmup_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(warmup_head(&self.client, self.chat_url(), self.timeout, &self.config))
+    }
+
+    async fn chat_stream(&self, messages: Vec<AgentMessage>) -> Result<ChunkStream, AgentError> {
+        let request = self.chat_request()?;
+        sse::chat_stream("Z.ai", &self.config, self.max_context(), request, self.timeout, messages, |messages, max_tokens| {
+            self.build_request(messages, max_tokens, self.config.json_mode)
+        })
+        .await
     }
 
     fn provider(&self) -> &str {
@@ -132,6 +177,14 @@ impl Agent for ZaiAgent {
     fn model(&self) -> &str {
         &self.config.model
     }
+
+    fn supports_json_mode(&self) -> bool {
+        true
+    }
+
+    fn max_context(&self) -> u32 {
+        128_000
+    }
 }
 
 /// Z.ai API request (OpenAI-compatible)
@@ -141,6 +194,11 @@ struct ZaiRequest {
     messages: Vec<ZaiMessage>,
     max_tokens: Option<u32>,
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    /// Sequences ending generation (`agent.stop_sequences`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -191,6 +249,57 @@ fn messages_to_zai(messages: Vec<AgentMessage>) -> Vec<ZaiMessage> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_request_timeout_defaults_to_sixty_seconds() {
+        let agent = ZaiAgent::new(clanker_config::AgentConfig::default());
+        assert_eq!(agent.timeout(), Duration::from_secs(60));
+
+        let agent = ZaiAgent::new(clanker_config::AgentConfig {
+            request_timeout_secs: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(agent.timeout(), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_sent_with_request() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("openai-organization", "org-123")
+            .match_header("x-proxy-auth", "proxy-secret")
+            // Configured headers replace built-in ones of the same name
+            .match_header("authorization", "Bearer proxy-token")
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"content":"ok"},"finish_reason":"stop"}]}"#)
+            .create_async()
+            .await;
+
+        let agent = ZaiAgent::new(clanker_config::AgentConfig {
+            provider: "zai".to_string(),
+            model: "glm-4.7".to_string(),
+            api_key: Some("test-key".to_string()),
+            api_base_url: Some(server.url()),
+            extra_headers: [
+                ("OpenAI-Organization", "org-123"),
+                ("X-Proxy-Auth", "proxy-secret"),
+                ("Authorization", "Bearer proxy-token"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            ..Default::default()
+        });
+        let message = AgentMessage {
+            role: crate::types::MessageRole::User,
+            content: "hi".to_string(),
+            images: Vec::new(),
+        };
+
+        assert_eq!(agent.chat(vec![message]).await.unwrap().content, "ok");
+        mock.assert_async().await;
+    }
+
     #[test]
     fn test_messages_to_zai() {
         use crate::types::MessageRole;
@@ -198,10 +307,12 @@ mod tests {
             AgentMessage {
                 role: MessageRole::User,
                 content: "Hello".to_string(),
+                images: Vec::new(),
             },
             AgentMessage {
                 role: MessageRole::Assistant,
                 content: "Hi there!".to_string(),
+                images: Vec::new(),
             },
         ];
 
@@ -222,9 +333,7 @@ mod tests {
             api_key_env: "ZAI_API_KEY".to_string(),
             api_key: Some("test-key".to_string()),
             max_tokens: 4096,
-            api_base_url: None,
-            worker: None,
-            fallback: None,
+            ..Default::default()
         };
 
         let agent = ZaiAgent::new(config);
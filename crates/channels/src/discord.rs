@@ -2,13 +2,99 @@ use crate::{Channel, Result};
 use crate::error::ChannelError;
 use async_trait::async_trait;
 use clanker_core::{ChannelType, Message};
+use serenity::builder::CreateMessage;
+use serenity::gateway::{ConnectionStage, ShardStageUpdateEvent};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::event::ResumedEvent;
+use serenity::model::gateway::Ready;
+use serenity::prelude::{Context, EventHandler, GatewayIntents};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::{debug, info};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+/// Maximum message length accepted by Discord
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// Which messages the bot answers (`channels.discord.command_prefix` and
+/// `respond_on_mention`); with neither set, every message is answered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageTrigger {
+    pub command_prefix: Option<String>,
+    pub respond_on_mention: bool,
+}
+
+impl MessageTrigger {
+    /// Text to forward to the agent, with the prefix or bot mention stripped;
+    /// `None` when the message does not trigger the bot. The prefix must be a whole
+    /// word (`!ask` does not match `!askme`). Mentions are only detected once the
+    /// bot's user id is known.
+    pub fn accept(&self, content: &str, bot_user_id: Option<&str>) -> Option<String> {
+        if self.command_prefix.is_none() && !self.respond_on_mention {
+            return Some(content.to_string());
+        }
+
+        let text = if let Some(rest) = self
+            .command_prefix
+            .as_deref()
+            .and_then(|prefix| content.trim_start().strip_prefix(prefix))
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            rest.to_string()
+        } else {
+            let bot_user_id = bot_user_id.filter(|id| self.respond_on_mention && mentions(content, id))?;
+            strip_mentions(content, bot_user_id)
+        };
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
+
+/// Whether `content` mentions the user, as `<@id>` or the nickname form `<@!id>`
+pub fn mentions(content: &str, user_id: &str) -> bool {
+    content.contains(&format!("<@{}>", user_id)) || content.contains(&format!("<@!{}>", user_id))
+}
+
+/// Remove every mention of the user from `content`
+fn strip_mentions(content: &str, user_id: &str) -> String {
+    content
+        .replace(&format!("<@{}>", user_id), "")
+        .replace(&format!("<@!{}>", user_id), "")
+}
+
+/// How the gateway connection is split into shards (`channels.discord.shard_count`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShardStrategy {
+    /// Run the shard count Discord recommends for the bot
+    #[default]
+    Autoshard,
+    /// Run exactly this many shards
+    Fixed(u32),
+}
+
+impl ShardStrategy {
+    /// Start the client's shard manager; returns once every shard has shut down.
+    /// Serenity reconnects and resumes dropped shards by itself.
+    async fn start(self, client: &mut serenity::Client) -> serenity::Result<()> {
+        match self {
+            Self::Autoshard => client.start_autosharded().await,
+            Self::Fixed(count) => client.start_shards(count).await,
+        }
+    }
+}
 
 /// Discord channel implementation
 pub struct DiscordChannel {
     token: String,
-    connected: AtomicBool,
+    /// REST client replies are sent with
+    http: Arc<Http>,
+    connected: Arc<AtomicBool>,
+    /// Which inbound messages are forwarded to the agent
+    trigger: MessageTrigger,
+    /// The bot's own user id, learned when the gateway session starts
+    bot_user_id: Arc<RwLock<Option<String>>>,
+    /// Gateway sharding
+    shards: ShardStrategy,
 }
 
 impl DiscordChannel {
@@ -17,16 +103,134 @@ impl DiscordChannel {
         debug!("Creating Discord channel");
 
         Ok(Self {
+            http: Arc::new(Http::new(&token)),
             token,
-            connected: AtomicBool::new(false),
+            connected: Arc::new(AtomicBool::new(false)),
+            trigger: MessageTrigger::default(),
+            bot_user_id: Arc::new(RwLock::new(None)),
+            shards: ShardStrategy::default(),
         })
     }
 
-    /// Convert clanker Message to Discord message
-    fn message_to_discord(msg: &Message) -> Result<(String, String)> {
-        let channel_id = msg.channel_id.clone();
-        let content = msg.text.clone();
-        Ok((channel_id, content))
+    /// Only forward messages matching `trigger`
+    pub fn with_trigger(mut self, trigger: MessageTrigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    /// Run `shard_count` gateway shards (autosharding when `None`)
+    pub fn with_shard_count(mut self, shard_count: Option<u32>) -> Self {
+        self.shards = shard_count.map_or(ShardStrategy::Autoshard, ShardStrategy::Fixed);
+        self
+    }
+
+    /// Sharding the listener starts the client with
+    pub fn shard_strategy(&self) -> ShardStrategy {
+        self.shards
+    }
+
+    /// Record the bot's user id (from the gateway `READY` event) for mention detection
+    pub fn set_bot_user_id(&self, user_id: impl Into<String>) {
+        *self.bot_user_id.write().unwrap_or_else(|e| e.into_inner()) = Some(user_id.into());
+    }
+
+    /// Apply the trigger to an inbound message: `None` when the bot should not
+    /// answer, otherwise the message with the prefix or mention stripped
+    pub fn filter_inbound(&self, message: Message) -> Option<Message> {
+        filter_message(&self.trigger, &self.bot_user_id, message)
+    }
+
+    /// Convert clanker Message to the Discord channel and the message to post there,
+    /// referencing `metadata.reply_to` so the reply is threaded (a reference that
+    /// is not a Discord message id is dropped and the reply sent unthreaded)
+    fn message_to_discord(msg: &Message) -> Result<(ChannelId, CreateMessage)> {
+        let channel_id = ChannelId::new(parse_id(&msg.channel_id, "channel")?);
+        let mut builder = CreateMessage::new().content(msg.text.clone());
+        if let Some(reply_to) = &msg.metadata.reply_to {
+            match parse_id(reply_to, "message") {
+                Ok(message_id) => builder = builder.reference_message((channel_id, MessageId::new(message_id))),
+                Err(e) => debug!("Sending reply unthreaded: {}", e),
+            }
+        }
+        Ok((channel_id, builder))
+    }
+}
+
+/// Discord snowflake id (a non-zero integer) from its string form
+fn parse_id(id: &str, kind: &str) -> Result<u64> {
+    id.parse::<u64>()
+        .ok()
+        .filter(|id| *id != 0)
+        .ok_or_else(|| ChannelError::SendFailed(format!("invalid Discord {} id '{}'", kind, id)))
+}
+
+fn filter_message(trigger: &MessageTrigger, bot_user_id: &RwLock<Option<String>>, mut message: Message) -> Option<Message> {
+    let bot_user_id = bot_user_id.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match trigger.accept(&message.text, bot_user_id.as_deref()) {
+        Some(text) => {
+            message.text = text;
+            Some(message)
+        }
+        None => {
+            debug!("Ignoring Discord message {} without prefix or mention", message.id);
+            None
+        }
+    }
+}
+
+/// Serenity event handler forwarding triggered messages to the gateway and
+/// logging shard connection changes
+struct InboundHandler {
+    trigger: MessageTrigger,
+    bot_user_id: Arc<RwLock<Option<String>>>,
+    connected: Arc<AtomicBool>,
+    tx: tokio::sync::mpsc::Sender<Message>,
+}
+
+#[serenity::async_trait]
+impl EventHandler for InboundHandler {
+    async fn message(&self, _ctx: Context, msg: serenity::model::channel::Message) {
+        if msg.author.bot || msg.content.is_empty() {
+            return;
+        }
+        let mut core_msg = Message::new(
+            ChannelType::Discord,
+            msg.channel_id.to_string(),
+            msg.author.id.to_string(),
+            msg.content.clone(),
+        );
+        // Keep the Discord message id so replies can reference it
+        core_msg.id = msg.id.to_string();
+        if let Some(core_msg) = filter_message(&self.trigger, &self.bot_user_id, core_msg) {
+            let _ = self.tx.send(core_msg).await;
+        }
+    }
+
+    async fn ready(&self, _ctx: Context, ready: Ready) {
+        let shard = ready.shard.map(|info| format!("{}/{}", info.id.0, info.total));
+        info!(
+            "Discord shard {} ready as {}",
+            shard.as_deref().unwrap_or("0/1"),
+            ready.user.name
+        );
+        *self.bot_user_id.write().unwrap_or_else(|e| e.into_inner()) = Some(ready.user.id.to_string());
+        self.connected.store(true, Ordering::SeqCst);
+    }
+
+    async fn resume(&self, _ctx: Context, _event: ResumedEvent) {
+        info!("Discord session resumed");
+        self.connected.store(true, Ordering::SeqCst);
+    }
+
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        match event.new {
+            ConnectionStage::Connected => info!("Discord shard {} connected (was {})", event.shard_id.0, event.old),
+            ConnectionStage::Disconnected => warn!("Discord shard {} disconnected", event.shard_id.0),
+            stage if stage.is_connecting() && event.old == ConnectionStage::Connected => {
+                warn!("Discord shard {} lost its connection, reconnecting ({})", event.shard_id.0, stage)
+            }
+            stage => debug!("Discord shard {} is {}", event.shard_id.0, stage),
+        }
     }
 }
 
@@ -35,37 +239,63 @@ impl Channel for DiscordChannel {
     async fn send(&self, message: Message) -> Result<()> {
         debug!("Sending message to Discord: {}", message.id);
 
+        message.validate(MAX_MESSAGE_LENGTH)?;
+
         if !self.is_connected() {
             return Err(ChannelError::ConnectionError(
                 "Discord bot is not connected".to_string(),
             ));
         }
 
-        let (_channel_id, _content) = Self::message_to_discord(&message)?;
-
-        // TODO: Implement actual Discord sending
-        // This requires the serenity client to be initialized
-        info!("Discord send not yet fully implemented - placeholder");
+        let (channel_id, builder) = Self::message_to_discord(&message)?;
+        let sent = channel_id
+            .send_message(self.http.as_ref(), builder)
+            .await
+            .map_err(|e| ChannelError::SendFailed(e.to_string()))?;
 
-        debug!("Message placeholder sent");
+        debug!("Message sent to Discord as {}", sent.id);
         Ok(())
     }
 
     async fn listen(&self) -> Result<()> {
-        info!("Starting Discord listener");
-        self.connected.store(true, Ordering::SeqCst);
-        info!("Discord listen not yet fully implemented - placeholder");
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        Ok(())
+        // Without a gateway nothing answers; just log what arrives
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Message>(32);
+        let log = async move {
+            while let Some(message) = rx.recv().await {
+                info!("Discord message {} from {} in {}", message.id, message.sender, message.channel_id);
+            }
+        };
+        let (result, ()) = tokio::join!(self.listen_with_tx(tx), log);
+        result
     }
 
     async fn listen_with_tx(
         &self,
-        _tx: tokio::sync::mpsc::Sender<Message>,
+        tx: tokio::sync::mpsc::Sender<Message>,
     ) -> Result<()> {
-        Err(ChannelError::UnsupportedChannel(
-            "Discord listen_with_tx not yet implemented".to_string(),
-        ))
+        info!("Starting Discord listener (forwarding to gateway, {:?})", self.shards);
+
+        // Inbound messages pass the trigger (`filter_inbound`) before reaching `tx`
+        let handler = InboundHandler {
+            trigger: self.trigger.clone(),
+            bot_user_id: self.bot_user_id.clone(),
+            connected: self.connected.clone(),
+            tx,
+        };
+        let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+        let mut client = serenity::Client::builder(&self.token, intents)
+            .event_handler(handler)
+            .await
+            .map_err(|e| ChannelError::ConnectionError(e.to_string()))?;
+
+        let result = self.shards.start(&mut client).await;
+
+        // The shard manager only returns once every shard has stopped; report it so the gateway reconnects
+        self.connected.store(false, Ordering::SeqCst);
+        Err(ChannelError::ListenError(match result {
+            Ok(()) => "Discord shards stopped".to_string(),
+            Err(e) => format!("Discord client stopped: {}", e),
+        }))
     }
 
     fn channel_type(&self) -> ChannelType {
@@ -88,6 +318,15 @@ mod tests {
         assert_eq!(channel.unwrap().channel_type(), ChannelType::Discord);
     }
 
+    #[test]
+    fn test_shard_count_reaches_client_start() {
+        let channel = DiscordChannel::new("test-token".to_string()).unwrap();
+        assert_eq!(channel.shard_strategy(), ShardStrategy::Autoshard);
+        let channel = channel.with_shard_count(Some(4));
+        assert_eq!(channel.shard_strategy(), ShardStrategy::Fixed(4));
+        assert_eq!(channel.with_shard_count(None).shard_strategy(), ShardStrategy::Autoshard);
+    }
+
     #[test]
     fn test_discord_channel_type() {
         let channel = DiscordChannel::new("test-token".to_string()).unwrap();
@@ -103,9 +342,53 @@ mod tests {
             "Hello".to_string(),
         );
 
-        let (channel_id, text) = DiscordChannel::message_to_discord(&msg).unwrap();
-        assert_eq!(channel_id, "123456789");
-        assert_eq!(text, "Hello");
+        let (channel_id, builder) = DiscordChannel::message_to_discord(&msg).unwrap();
+        assert_eq!(channel_id, ChannelId::new(123456789));
+        let request = serde_json::to_value(&builder).unwrap();
+        assert_eq!(request["content"], "Hello");
+        assert!(request.get("message_reference").is_none());
+
+        let reply = msg.clone().set_reply_to("987654321".to_string());
+        let (_, builder) = DiscordChannel::message_to_discord(&reply).unwrap();
+        let reference = &serde_json::to_value(&builder).unwrap()["message_reference"];
+        assert_eq!(reference["message_id"], "987654321");
+        assert_eq!(reference["channel_id"], "123456789");
+
+        let reply = msg.clone().set_reply_to("not-an-id".to_string());
+        let (_, builder) = DiscordChannel::message_to_discord(&reply).unwrap();
+        assert!(serde_json::to_value(&builder).unwrap().get("message_reference").is_none());
+
+        let mut bad = msg.clone();
+        bad.channel_id = "general".to_string();
+        assert!(matches!(DiscordChannel::message_to_discord(&bad), Err(ChannelError::SendFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_over_limit_message() {
+        let channel = DiscordChannel::new("test-token".to_string()).unwrap();
+        let msg = Message::new(
+            ChannelType::Discord,
+            "123456789".to_string(),
+            "user".to_string(),
+            "a".repeat(MAX_MESSAGE_LENGTH + 1),
+        );
+
+        let err = channel.send(msg).await.unwrap_err();
+        assert!(matches!(err, ChannelError::MessageTooLong(_, MAX_MESSAGE_LENGTH)));
+    }
+
+    #[tokio::test]
+    async fn test_send_accepts_message_under_limit() {
+        let channel = DiscordChannel::new("test-token".to_string()).unwrap();
+        let msg = Message::new(
+            ChannelType::Discord,
+            "123456789".to_string(),
+            "user".to_string(),
+            "a".repeat(MAX_MESSAGE_LENGTH - 1),
+        );
+
+        let err = channel.send(msg).await.unwrap_err();
+        assert!(matches!(err, ChannelError::ConnectionError(_)));
     }
 
     #[test]
@@ -117,8 +400,72 @@ mod tests {
             "Test message".to_string(),
         );
 
-        let (channel_id, text) = DiscordChannel::message_to_discord(&msg).unwrap();
-        assert_eq!(channel_id, "987654321");
-        assert_eq!(text, "Test message");
+        let (channel_id, builder) = DiscordChannel::message_to_discord(&msg).unwrap();
+        assert_eq!(channel_id, ChannelId::new(987654321));
+        assert_eq!(serde_json::to_value(&builder).unwrap()["content"], "Test message");
+    }
+
+    fn trigger(prefix: Option<&str>, respond_on_mention: bool) -> MessageTrigger {
+        MessageTrigger {
+            command_prefix: prefix.map(str::to_string),
+            respond_on_mention,
+        }
+    }
+
+    #[test]
+    fn test_trigger_strips_command_prefix() {
+        let trigger = trigger(Some("!ask"), false);
+        assert_eq!(trigger.accept("!ask what time is it?", None).as_deref(), Some("what time is it?"));
+        assert_eq!(trigger.accept("  !ask   hi  ", None).as_deref(), Some("hi"));
+        assert_eq!(trigger.accept("what time is it?", None), None);
+        assert_eq!(trigger.accept("please !ask later", None), None);
+        assert_eq!(trigger.accept("!askme later", None), None);
+        // Nothing left to answer
+        assert_eq!(trigger.accept("!ask", None), None);
+    }
+
+    #[test]
+    fn test_trigger_detects_mentions() {
+        assert!(mentions("hey <@42>", "42"));
+        assert!(mentions("<@!42> hi", "42"));
+        assert!(!mentions("hey <@420>", "42"));
+        assert!(!mentions("hey @42", "42"));
+
+        let trigger = trigger(None, true);
+        assert_eq!(trigger.accept("<@42> what's up?", Some("42")).as_deref(), Some("what's up?"));
+        assert_eq!(trigger.accept("thanks <@!42>", Some("42")).as_deref(), Some("thanks"));
+        assert_eq!(trigger.accept("<@7> what's up?", Some("42")), None);
+        // Mentions can't be matched before the bot's id is known
+        assert_eq!(trigger.accept("<@42> what's up?", None), None);
+    }
+
+    #[test]
+    fn test_trigger_prefix_or_mention() {
+        let trigger = trigger(Some("!"), true);
+        assert_eq!(trigger.accept("! help", Some("42")).as_deref(), Some("help"));
+        assert_eq!(trigger.accept("!help", Some("42")), None);
+        assert_eq!(trigger.accept("<@42> help", Some("42")).as_deref(), Some("help"));
+        assert_eq!(trigger.accept("help", Some("42")), None);
+
+        // No trigger configured: everything is answered unchanged
+        assert_eq!(MessageTrigger::default().accept(" hi ", None).as_deref(), Some(" hi "));
+    }
+
+    #[test]
+    fn test_filter_inbound_uses_bot_user_id() {
+        let channel = DiscordChannel::new("test-token".to_string())
+            .unwrap()
+            .with_trigger(trigger(None, true));
+        let msg = Message::new(
+            ChannelType::Discord,
+            "123456789".to_string(),
+            "user".to_string(),
+            "<@42> hello".to_string(),
+        );
+        assert!(channel.filter_inbound(msg.clone()).is_none());
+
+        channel.set_bot_user_id("42");
+        let forwarded = channel.filter_inbound(msg).unwrap();
+        assert_eq!(forwarded.text, "hello");
     }
 }
@@ -1,3 +1,4 @@
+use clanker_core::{ClankerError, Retryable};
 use thiserror::Error;
 
 /// Channel errors
@@ -40,6 +41,28 @@ pub enum ChannelError {
 /// Channel result type
 pub type Result<T> = std::result::Result<T, ChannelError>;
 
+impl From<ClankerError> for ChannelError {
+    fn from(err: ClankerError) -> Self {
+        match err {
+            ClankerError::MessageTooLong { length, max } => Self::MessageTooLong(length, max),
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl Retryable for ChannelError {
+    fn is_retryable(&self) -> bool {
+        matches!(self, Self::ConnectionError(_) | Self::RateLimited(_))
+    }
+
+    fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RateLimited(after) => *after,
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +75,20 @@ mod tests {
         let err = ChannelError::SendFailed("Network error".to_string());
         assert!(err.to_string().contains("Send failed"));
     }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(ChannelError::ConnectionError("reset".to_string()).is_retryable());
+        let limited = ChannelError::RateLimited(Some(std::time::Duration::from_secs(2)));
+        assert!(limited.is_retryable());
+        assert_eq!(limited.retry_after(), Some(std::time::Duration::from_secs(2)));
+        assert!(!ChannelError::AuthenticationFailed.is_retryable());
+        assert!(!ChannelError::SendFailed("bad request".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_from_message_too_long() {
+        let err: ChannelError = ClankerError::MessageTooLong { length: 5000, max: 4096 }.into();
+        assert!(matches!(err, ChannelError::MessageTooLong(5000, 4096)));
+    }
 }
@@ -3,6 +3,7 @@
 //! Provides implementations for messaging channels:
 //! - Telegram bot (teloxide)
 //! - Discord bot (serenity)
+//! - Generic HTTP webhook (send-only, reqwest)
 //!
 //! # Example
 //!
@@ -31,6 +32,8 @@ pub mod error;
 pub mod telegram;
 #[cfg(feature = "discord")]
 pub mod discord;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 use clanker_core::ChannelType;
 use std::sync::Arc;
@@ -38,6 +41,9 @@ use std::sync::Arc;
 // Re-exports
 pub use error::{ChannelError, Result};
 
+/// Default attempts per outgoing message when sends fail transiently
+pub const SEND_ATTEMPTS: u32 = 3;
+
 /// Channel trait for all messaging platforms
 #[async_trait::async_trait]
 pub trait Channel: Send + Sync {
@@ -49,11 +55,47 @@ pub trait Channel: Send + Sync {
 
     /// Listen for incoming messages and forward to sender (for gateway integration).
     /// Does not echo; messages are sent to `tx` for processing.
+    /// Return `Ok` only when there is nothing (more) to listen for; the gateway
+    /// reconnects listeners that return an error.
     async fn listen_with_tx(
         &self,
         tx: tokio::sync::mpsc::Sender<clanker_core::Message>,
     ) -> Result<()>;
 
+    /// Show a "typing" indicator in the chat while a reply is being prepared
+    /// (no-op for channels without one)
+    async fn send_typing(&self, _channel_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Send a message and return its channel-side id, for later `edit`/`delete`
+    /// (`None` for channels that do not report one)
+    async fn send_with_id(&self, message: clanker_core::Message) -> Result<Option<String>> {
+        self.send(message).await.map(|_| None)
+    }
+
+    /// Replace the text of a message sent earlier to `channel_id`
+    async fn edit(&self, _channel_id: &str, _message_id: &str, _new_text: &str) -> Result<()> {
+        Err(ChannelError::UnsupportedChannel(format!(
+            "{} does not support editing messages",
+            self.channel_type()
+        )))
+    }
+
+    /// Delete a message sent earlier to `channel_id`
+    async fn delete(&self, _channel_id: &str, _message_id: &str) -> Result<()> {
+        Err(ChannelError::UnsupportedChannel(format!(
+            "{} does not support deleting messages",
+            self.channel_type()
+        )))
+    }
+
+    /// Name chat commands address the bot by (Telegram's `/command@name`),
+    /// once the listener has looked it up
+    fn bot_username(&self) -> Option<String> {
+        None
+    }
+
     /// Get the channel type
     fn channel_type(&self) -> ChannelType;
 
@@ -75,7 +117,16 @@ impl ChannelFactory {
     /// Create an Arc-wrapped Telegram channel (for shared ownership in gateway)
     #[cfg(feature = "telegram")]
     pub fn create_arc_telegram(token: String) -> Result<Arc<dyn Channel + Send + Sync>> {
-        let ch = telegram::TelegramChannel::new(token)?;
+        Self::create_arc_telegram_with_attempts(token, SEND_ATTEMPTS)
+    }
+
+    /// Create an Arc-wrapped Telegram channel making up to `send_attempts` attempts per message
+    #[cfg(feature = "telegram")]
+    pub fn create_arc_telegram_with_attempts(
+        token: String,
+        send_attempts: u32,
+    ) -> Result<Arc<dyn Channel + Send + Sync>> {
+        let ch = telegram::TelegramChannel::new(token)?.with_send_attempts(send_attempts);
         Ok(Arc::new(ch) as Arc<dyn Channel + Send + Sync>)
     }
 
@@ -89,7 +140,30 @@ impl ChannelFactory {
     /// Create an Arc-wrapped Discord channel (for shared ownership in gateway)
     #[cfg(feature = "discord")]
     pub fn create_arc_discord(token: String) -> Result<Arc<dyn Channel + Send + Sync>> {
-        let ch = discord::DiscordChannel::new(token)?;
+        Self::create_arc_discord_with_trigger(token, discord::MessageTrigger::default(), None)
+    }
+
+    /// Create an Arc-wrapped Discord channel answering only messages matching `trigger`,
+    /// running `shard_count` gateway shards (autosharding when `None`)
+    #[cfg(feature = "discord")]
+    pub fn create_arc_discord_with_trigger(
+        token: String,
+        trigger: discord::MessageTrigger,
+        shard_count: Option<u32>,
+    ) -> Result<Arc<dyn Channel + Send + Sync>> {
+        let ch = discord::DiscordChannel::new(token)?
+            .with_trigger(trigger)
+            .with_shard_count(shard_count);
+        Ok(Arc::new(ch) as Arc<dyn Channel + Send + Sync>)
+    }
+
+    /// Create an Arc-wrapped webhook channel posting to `url`
+    #[cfg(feature = "webhook")]
+    pub fn create_arc_webhook(
+        url: String,
+        bearer_token: Option<String>,
+    ) -> Result<Arc<dyn Channel + Send + Sync>> {
+        let ch = webhook::WebhookChannel::new(url, bearer_token)?;
         Ok(Arc::new(ch) as Arc<dyn Channel + Send + Sync>)
     }
 
@@ -108,12 +182,15 @@ impl ChannelFactory {
     }
 
     /// Get supported channel types
+    #[allow(clippy::vec_init_then_push)]
     pub fn supported_channels() -> Vec<&'static str> {
         let mut channels = Vec::new();
         #[cfg(feature = "telegram")]
         channels.push("telegram");
         #[cfg(feature = "discord")]
         channels.push("discord");
+        #[cfg(feature = "webhook")]
+        channels.push("webhook");
         channels
     }
 }
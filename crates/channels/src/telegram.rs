@@ -1,19 +1,28 @@
-use crate::{Channel, Result};
+use crate::{Channel, Result, SEND_ATTEMPTS};
 use crate::error::ChannelError;
 use async_trait::async_trait;
-use clanker_core::{ChannelType, Message};
+use clanker_core::{retry_with_backoff, ChannelType, Message};
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 use teloxide::{
     prelude::*,
-    types::ChatId,
-    Bot,
+    types::{ChatAction, ChatId, MessageId},
+    ApiError, Bot, RequestError,
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Maximum message length accepted by the Telegram Bot API
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
 
 /// Telegram channel implementation
 pub struct TelegramChannel {
     bot: Bot,
     connected: AtomicBool,
+    /// Attempts per outgoing message
+    send_attempts: u32,
+    /// Bot username from getMe, looked up when the listener starts
+    username: OnceLock<String>,
 }
 
 impl TelegramChannel {
@@ -26,41 +35,125 @@ impl TelegramChannel {
         Ok(Self {
             bot,
             connected: AtomicBool::new(false),
+            send_attempts: SEND_ATTEMPTS,
+            username: OnceLock::new(),
         })
     }
 
-    /// Convert clanker Message to Telegram message
-    fn message_to_telegram(msg: &Message) -> Result<(ChatId, String)> {
-        let chat_id: i64 = msg.channel_id.parse()
-            .map_err(|_| ChannelError::InvalidConfig(format!(
-                "Invalid chat ID: {}",
-                msg.channel_id
-            )))?;
+    /// Set how many attempts each outgoing message gets (at least one)
+    pub fn with_send_attempts(mut self, send_attempts: u32) -> Self {
+        self.send_attempts = send_attempts.max(1);
+        self
+    }
 
-        Ok((ChatId(chat_id), msg.text.clone()))
+    /// Parse a clanker channel id into a Telegram chat id
+    fn chat_id(channel_id: &str) -> Result<ChatId> {
+        channel_id
+            .parse()
+            .map(ChatId)
+            .map_err(|_| ChannelError::InvalidConfig(format!("Invalid chat ID: {}", channel_id)))
     }
-}
 
-#[async_trait]
-impl Channel for TelegramChannel {
-    async fn send(&self, message: Message) -> Result<()> {
-        debug!("Sending message to Telegram: {}", message.id);
+    /// Parse the chat and message ids addressing an existing Telegram message
+    fn message_ref(channel_id: &str, message_id: &str) -> Result<(ChatId, MessageId)> {
+        let chat_id = Self::chat_id(channel_id)?;
+        let message_id = message_id
+            .parse()
+            .map(MessageId)
+            .map_err(|_| ChannelError::InvalidConfig(format!("Invalid message ID: {}", message_id)))?;
+        Ok((chat_id, message_id))
+    }
 
-        if !self.is_connected() {
-            return Err(ChannelError::ConnectionError(
+    /// Fail fast while the bot is not polling
+    fn ensure_connected(&self) -> Result<()> {
+        if self.is_connected() {
+            Ok(())
+        } else {
+            Err(ChannelError::ConnectionError(
                 "Telegram bot is not connected".to_string(),
-            ));
+            ))
         }
+    }
 
-        let (chat_id, text) = Self::message_to_telegram(&message)?;
+    /// Convert clanker Message to Telegram message, plus the Telegram message
+    /// to reply to when `metadata.reply_to` holds one (ids from other sources are ignored)
+    fn message_to_telegram(msg: &Message) -> Result<(ChatId, String, Option<MessageId>)> {
+        let chat_id = Self::chat_id(&msg.channel_id)?;
+        let reply_to = msg
+            .metadata
+            .reply_to
+            .as_deref()
+            .and_then(|id| id.parse().ok())
+            .map(MessageId);
 
-        self.bot
-            .send_message(chat_id, text)
-            .await
-            .map_err(|e| ChannelError::SendFailed(e.to_string()))?;
+        Ok((chat_id, msg.text.clone(), reply_to))
+    }
+}
+
+/// Map a Bot API error, keeping network/I/O failures and flood control retryable
+fn send_error(err: RequestError) -> ChannelError {
+    match err {
+        RequestError::Network(e) => ChannelError::ConnectionError(e.to_string()),
+        RequestError::Io(e) => ChannelError::ConnectionError(e.to_string()),
+        RequestError::RetryAfter(after) => ChannelError::RateLimited(Some(after)),
+        other => ChannelError::SendFailed(other.to_string()),
+    }
+}
+
+/// Run a Bot API request up to `attempts` times, retrying transient failures with backoff
+async fn send_with_retry<F, Fut, T>(mut request: F, attempts: u32) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, RequestError>>,
+{
+    retry_with_backoff(
+        || {
+            let response = request();
+            async move { response.await.map_err(send_error) }
+        },
+        attempts,
+    )
+    .await
+}
+
+impl TelegramChannel {
+    /// Send a message, returning the id Telegram assigned to it
+    async fn send_message(&self, message: Message) -> Result<MessageId> {
+        debug!("Sending message to Telegram: {}", message.id);
+
+        message.validate(MAX_MESSAGE_LENGTH)?;
+
+        self.ensure_connected()?;
+
+        let (chat_id, text, reply_to) = Self::message_to_telegram(&message)?;
+
+        let sent = send_with_retry(
+            || {
+                let mut request = self.bot.send_message(chat_id, text.clone());
+                if let Some(reply_to) = reply_to {
+                    // Still deliver the reply if the original was deleted meanwhile
+                    request = request.reply_to_message_id(reply_to).allow_sending_without_reply(true);
+                }
+                request.send()
+            },
+            self.send_attempts,
+        )
+        .await?;
 
         debug!("Message sent successfully");
-        Ok(())
+        Ok(sent.id)
+    }
+}
+
+#[async_trait]
+impl Channel for TelegramChannel {
+    async fn send(&self, message: Message) -> Result<()> {
+        self.send_message(message).await.map(|_| ())
+    }
+
+    async fn send_with_id(&self, message: Message) -> Result<Option<String>> {
+        let id = self.send_message(message).await?;
+        Ok(Some(id.0.to_string()))
     }
 
     async fn listen(&self) -> Result<()> {
@@ -85,6 +178,18 @@ impl Channel for TelegramChannel {
         info!("Starting Telegram listener (forwarding to gateway)");
         self.connected.store(true, Ordering::SeqCst);
 
+        if self.username.get().is_none() {
+            match self.bot.get_me().await {
+                Ok(me) => {
+                    if let Some(username) = me.user.username {
+                        let _ = self.username.set(username);
+                    }
+                }
+                // Commands addressed as /command@bot are then not recognized
+                Err(e) => warn!("Could not look up the Telegram bot username: {}", e),
+            }
+        }
+
         let bot = self.bot.clone();
         let handler = move |_bot: Bot, msg: teloxide::types::Message| {
             let tx = tx.clone();
@@ -97,12 +202,14 @@ impl Channel for TelegramChannel {
                     .from()
                     .map(|u| u.id.0.to_string())
                     .unwrap_or_else(|| "unknown".to_string());
-                let core_msg = Message::new(
+                let mut core_msg = Message::new(
                     ChannelType::Telegram,
                     msg.chat.id.0.to_string(),
                     sender,
                     text.to_string(),
                 );
+                // Keep the Telegram message id so replies can be threaded to it
+                core_msg.id = msg.id.0.to_string();
                 let _ = tx.send(core_msg).await;
                 Ok(())
             }
@@ -110,9 +217,63 @@ impl Channel for TelegramChannel {
 
         teloxide::repl(bot, handler).await;
 
+        // repl only returns once polling has stopped; report it so the gateway reconnects
+        self.connected.store(false, Ordering::SeqCst);
+        Err(ChannelError::ListenError("Telegram polling stopped".to_string()))
+    }
+
+    async fn send_typing(&self, channel_id: &str) -> Result<()> {
+        let chat_id = Self::chat_id(channel_id)?;
+        self.ensure_connected()?;
+
+        // Telegram shows the action for about 5 seconds or until the next message
+        self.bot
+            .send_chat_action(chat_id, ChatAction::Typing)
+            .await
+            .map_err(send_error)?;
         Ok(())
     }
 
+    async fn edit(&self, channel_id: &str, message_id: &str, new_text: &str) -> Result<()> {
+        let (chat_id, message_id) = Self::message_ref(channel_id, message_id)?;
+        let length = new_text.chars().count();
+        if length > MAX_MESSAGE_LENGTH {
+            return Err(ChannelError::MessageTooLong(length, MAX_MESSAGE_LENGTH));
+        }
+        self.ensure_connected()?;
+
+        send_with_retry(
+            || {
+                let request = self.bot.edit_message_text(chat_id, message_id, new_text).send();
+                async move {
+                    match request.await {
+                        // Editing to the current text is a no-op, not a failure
+                        Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+                        other => other.map(|_| ()),
+                    }
+                }
+            },
+            self.send_attempts,
+        )
+        .await
+    }
+
+    async fn delete(&self, channel_id: &str, message_id: &str) -> Result<()> {
+        let (chat_id, message_id) = Self::message_ref(channel_id, message_id)?;
+        self.ensure_connected()?;
+
+        send_with_retry(
+            || self.bot.delete_message(chat_id, message_id).send(),
+            self.send_attempts,
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn bot_username(&self) -> Option<String> {
+        self.username.get().cloned()
+    }
+
     fn channel_type(&self) -> ChannelType {
         ChannelType::Telegram
     }
@@ -151,9 +312,106 @@ mod tests {
         let result = TelegramChannel::message_to_telegram(&msg);
         assert!(result.is_ok());
 
-        let (chat_id, text) = result.unwrap();
+        let (chat_id, text, reply_to) = result.unwrap();
         assert_eq!(chat_id, ChatId(123456));
         assert_eq!(text, "Hello");
+        assert_eq!(reply_to, None);
+    }
+
+    #[test]
+    fn test_message_to_telegram_reply_id() {
+        let reply = Message::new(
+            ChannelType::Telegram,
+            "123456".to_string(),
+            "assistant".to_string(),
+            "Hi!".to_string(),
+        )
+        .set_reply_to("42".to_string());
+
+        let (_, _, reply_to) = TelegramChannel::message_to_telegram(&reply).unwrap();
+        assert_eq!(reply_to, Some(MessageId(42)));
+
+        // Ids that did not come from Telegram (e.g. WebSocket message UUIDs) are not replied to
+        let reply = reply.set_reply_to("9b2f6c1e-4a7d-4f3b-8c2e-1d5a6b7c8d9e".to_string());
+        let (_, _, reply_to) = TelegramChannel::message_to_telegram(&reply).unwrap();
+        assert_eq!(reply_to, None);
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_over_limit_message() {
+        let channel = TelegramChannel::new("test-token".to_string()).unwrap();
+        let msg = Message::new(
+            ChannelType::Telegram,
+            "123456".to_string(),
+            "user".to_string(),
+            "a".repeat(MAX_MESSAGE_LENGTH + 1),
+        );
+
+        let err = channel.send(msg).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ChannelError::MessageTooLong(len, MAX_MESSAGE_LENGTH) if len == MAX_MESSAGE_LENGTH + 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_accepts_message_at_limit() {
+        let channel = TelegramChannel::new("test-token".to_string()).unwrap();
+        let msg = Message::new(
+            ChannelType::Telegram,
+            "123456".to_string(),
+            "user".to_string(),
+            "a".repeat(MAX_MESSAGE_LENGTH),
+        );
+
+        // Passes length validation and fails on the (unconnected) bot instead
+        let err = channel.send(msg).await.unwrap_err();
+        assert!(matches!(err, ChannelError::ConnectionError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_send_typing_validates_chat_id() {
+        let channel = TelegramChannel::new("test-token".to_string()).unwrap();
+
+        let err = channel.send_typing("not-a-chat").await.unwrap_err();
+        assert!(matches!(err, ChannelError::InvalidConfig(_)));
+
+        let err = channel.send_typing("123456").await.unwrap_err();
+        assert!(matches!(err, ChannelError::ConnectionError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_transient_errors() {
+        use std::sync::atomic::AtomicU32;
+
+        let calls = AtomicU32::new(0);
+        let sent = send_with_retry(
+            || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(RequestError::Io(std::io::Error::from(std::io::ErrorKind::ConnectionReset)))
+                } else {
+                    Ok("sent")
+                }
+            },
+            3,
+        )
+        .await;
+        assert_eq!(sent.unwrap(), "sent");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // API errors (e.g. the bot was blocked) are not retried
+        let calls = AtomicU32::new(0);
+        let err = send_with_retry(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(RequestError::Api(teloxide::ApiError::BotBlocked))
+            },
+            3,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ChannelError::SendFailed(_)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
     #[test]
@@ -168,4 +426,66 @@ mod tests {
         let result = TelegramChannel::message_to_telegram(&msg);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_message_ref() {
+        assert_eq!(
+            TelegramChannel::message_ref("-100123", "42").unwrap(),
+            (ChatId(-100123), MessageId(42))
+        );
+        assert!(matches!(
+            TelegramChannel::message_ref("123", "not-a-message"),
+            Err(ChannelError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            TelegramChannel::message_ref("not-a-chat", "42"),
+            Err(ChannelError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_and_delete_requests() {
+        use teloxide::payloads::{DeleteMessage, EditMessageText};
+        use teloxide::requests::HasPayload;
+        use teloxide::types::Recipient;
+
+        let channel = TelegramChannel::new("test-token".to_string()).unwrap();
+        let (chat_id, message_id) = TelegramChannel::message_ref("123456", "42").unwrap();
+
+        let edit = channel.bot.edit_message_text(chat_id, message_id, "Updated");
+        let EditMessageText {
+            chat_id,
+            message_id,
+            text,
+            ..
+        } = edit.payload_ref();
+        assert_eq!(*chat_id, Recipient::Id(ChatId(123456)));
+        assert_eq!(*message_id, MessageId(42));
+        assert_eq!(text, "Updated");
+
+        let delete = channel.bot.delete_message(ChatId(123456), MessageId(42));
+        let DeleteMessage { chat_id, message_id } = delete.payload_ref();
+        assert_eq!(*chat_id, Recipient::Id(ChatId(123456)));
+        assert_eq!(*message_id, MessageId(42));
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_delete_validate_before_sending() {
+        let channel = TelegramChannel::new("test-token".to_string()).unwrap();
+
+        let err = channel
+            .edit("123456", "42", &"a".repeat(MAX_MESSAGE_LENGTH + 1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ChannelError::MessageTooLong(_, MAX_MESSAGE_LENGTH)));
+
+        let err = channel.edit("123456", "abc", "Updated").await.unwrap_err();
+        assert!(matches!(err, ChannelError::InvalidConfig(_)));
+
+        // Valid requests fail on the (unconnected) bot
+        let err = channel.edit("123456", "42", "Updated").await.unwrap_err();
+        assert!(matches!(err, ChannelError::ConnectionError(_)));
+        let err = channel.delete("123456", "42").await.unwrap_err();
+        assert!(matches!(err, ChannelError::ConnectionError(_)));
+    }
 }
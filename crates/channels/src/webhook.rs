@@ -0,0 +1,216 @@
+use crate::{Channel, Result, SEND_ATTEMPTS};
+use crate::error::ChannelError;
+use async_trait::async_trait;
+use clanker_core::{retry_with_backoff, ChannelType, Message};
+use std::time::Duration;
+use tracing::debug;
+
+/// Timeout for a single webhook POST
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outbound HTTP webhook channel
+///
+/// `send` POSTs the `Message` as JSON to the configured URL. Webhooks are
+/// send-only: there is nothing to listen on, so `listen`/`listen_with_tx`
+/// return immediately.
+pub struct WebhookChannel {
+    client: reqwest::Client,
+    url: String,
+    bearer_token: Option<String>,
+}
+
+impl WebhookChannel {
+    /// Create a new webhook channel posting to `url`
+    pub fn new(url: String, bearer_token: Option<String>) -> Result<Self> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ChannelError::InvalidConfig(format!(
+                "Webhook url must start with http:// or https://, got '{}'",
+                url
+            )));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| ChannelError::InvalidConfig(e.to_string()))?;
+
+        debug!("Creating webhook channel for {}", url);
+
+        Ok(Self {
+            client,
+            url,
+            bearer_token: bearer_token.filter(|t| !t.is_empty()),
+        })
+    }
+
+    /// Target URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// POST the message once
+    async fn post(&self, message: &Message) -> Result<()> {
+        let mut request = self.client.post(&self.url).json(message);
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ChannelError::ConnectionError(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+
+        Err(match status.as_u16() {
+            401 | 403 => ChannelError::AuthenticationFailed,
+            429 => ChannelError::RateLimited(retry_after),
+            code => ChannelError::SendFailed(format!("webhook returned {}: {}", code, body.trim())),
+        })
+    }
+}
+
+#[async_trait]
+impl Channel for WebhookChannel {
+    async fn send(&self, message: Message) -> Result<()> {
+        debug!("Posting message {} to webhook", message.id);
+
+        retry_with_backoff(|| self.post(&message), SEND_ATTEMPTS).await
+    }
+
+    async fn listen(&self) -> Result<()> {
+        debug!("Webhook channel is send-only; nothing to listen on");
+        Ok(())
+    }
+
+    async fn listen_with_tx(
+        &self,
+        _tx: tokio::sync::mpsc::Sender<Message>,
+    ) -> Result<()> {
+        self.listen().await
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Webhook
+    }
+
+    fn is_connected(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    fn message() -> Message {
+        Message::new(
+            ChannelType::Webhook,
+            "chat-1".to_string(),
+            "clanker".to_string(),
+            "Hello from the gateway".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_webhook_rejects_invalid_url() {
+        let err = WebhookChannel::new("example.com/hook".to_string(), None).err().unwrap();
+        assert!(matches!(err, ChannelError::InvalidConfig(_)));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_message_json() {
+        let mut server = mockito::Server::new_async().await;
+        let msg = message();
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("content-type", "application/json")
+            .match_header("authorization", "Bearer secret")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "id": msg.id,
+                "channel_type": "webhook",
+                "channel_id": "chat-1",
+                "sender": "clanker",
+                "text": "Hello from the gateway",
+            })))
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let channel =
+            WebhookChannel::new(format!("{}/hook", server.url()), Some("secret".to_string())).unwrap();
+        channel.send(msg).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_webhook_omits_auth_without_token() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("authorization", Matcher::Missing)
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let channel = WebhookChannel::new(format!("{}/hook", server.url()), None).unwrap();
+        channel.send(message()).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_webhook_maps_error_status() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/unauthorized").with_status(401).create_async().await;
+        server
+            .mock("POST", "/limited")
+            .with_status(429)
+            .with_header("retry-after", "7")
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/broken")
+            .with_status(500)
+            .with_body("boom")
+            .create_async()
+            .await;
+
+        let send = |path: &str| {
+            let channel = WebhookChannel::new(format!("{}{}", server.url(), path), None).unwrap();
+            async move { channel.send(message()).await.unwrap_err() }
+        };
+
+        assert!(matches!(send("/unauthorized").await, ChannelError::AuthenticationFailed));
+        assert!(matches!(
+            send("/limited").await,
+            ChannelError::RateLimited(Some(d)) if d == Duration::from_secs(7)
+        ));
+        let err = send("/broken").await;
+        assert!(matches!(err, ChannelError::SendFailed(_)));
+        assert!(err.to_string().contains("500: boom"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_does_not_support_edit_or_delete() {
+        let channel = WebhookChannel::new("https://example.com/hook".to_string(), None).unwrap();
+        assert!(matches!(
+            channel.edit("chat-1", "1", "changed").await,
+            Err(ChannelError::UnsupportedChannel(_))
+        ));
+        assert!(matches!(channel.delete("chat-1", "1").await, Err(ChannelError::UnsupportedChannel(_))));
+    }
+}
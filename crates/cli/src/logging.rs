@@ -0,0 +1,228 @@
+//! Logging setup: stdout plus an optional rotating log file, per `[logging]`.
+
+use clanker_config::{LogRotation, LoggingConfig};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Workspace crates whose logs follow the configured level (others stay at warn)
+const CRATES: &[&str] = &[
+    "open_clanker",
+    "clanker_core",
+    "clanker_config",
+    "clanker_agent",
+    "clanker_channels",
+    "clanker_gateway",
+];
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Parse a `logging.level` value (`error`, `warn`, `info`, `debug`, `trace`)
+pub fn parse_level(level: &str) -> Option<Level> {
+    Level::from_str(level.trim()).ok()
+}
+
+/// Subscriber settings resolved from `[logging]` and the CLI flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogSettings {
+    /// Most verbose level emitted by workspace crates
+    pub level: Level,
+    /// JSON lines instead of human-readable output
+    pub json: bool,
+}
+
+impl LogSettings {
+    /// Resolve settings; `--verbose`/`--debug` raise the level to at least debug
+    pub fn resolve(config: &LoggingConfig, debug: bool) -> Self {
+        let configured = parse_level(&config.level).unwrap_or(Level::INFO);
+        // Level ordering: TRACE > DEBUG > INFO > WARN > ERROR
+        let level = if debug { configured.max(Level::DEBUG) } else { configured };
+        Self {
+            level,
+            json: config.format == "json",
+        }
+    }
+}
+
+/// Install the global subscriber. `debug` raises the level to at least debug.
+/// Keep the returned guard alive so buffered file output is flushed on exit.
+pub fn init(config: &LoggingConfig, debug: bool) -> anyhow::Result<Option<WorkerGuard>> {
+    let LogSettings { level, json } = LogSettings::resolve(config, debug);
+
+    let mut layers = vec![fmt_layer(json, true, io::stdout)];
+    let guard = match &config.file {
+        Some(path) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_writer(Path::new(path), config)?);
+            layers.push(fmt_layer(json, false, writer));
+            Some(guard)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(env_filter(level))
+        .try_init()?;
+    Ok(guard)
+}
+
+fn env_filter(level: Level) -> EnvFilter {
+    let level = level.as_str().to_lowercase();
+    let directives: Vec<String> = CRATES.iter().map(|c| format!("{}={}", c, level)).collect();
+    EnvFilter::new(format!("warn,{}", directives.join(",")))
+}
+
+fn fmt_layer<W>(json: bool, ansi: bool, writer: W) -> BoxedLayer
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_ansi(ansi).with_writer(writer);
+    if json {
+        layer.json().boxed()
+    } else {
+        layer.boxed()
+    }
+}
+
+/// Open the log file writer for the configured rotation
+fn file_writer(path: &Path, config: &LoggingConfig) -> io::Result<Box<dyn Write + Send>> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let rotation = match config.rotation {
+        LogRotation::Size => {
+            let max_bytes = config.max_file_size_mb.saturating_mul(1024 * 1024);
+            return Ok(Box::new(SizeRotatingFile::open(path.to_path_buf(), max_bytes)?));
+        }
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "logging.file has no file name"))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(file_name.to_string_lossy())
+        .build(dir)
+        .map_err(io::Error::other)?;
+    Ok(Box::new(appender))
+}
+
+/// Log file that rolls over to `<path>.1` (replacing the previous backup) once it reaches `max_bytes`
+struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, max_bytes, file, written })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(".1");
+        std::fs::rename(&self.path, backup)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("error"), Some(Level::ERROR));
+        assert_eq!(parse_level("warn"), Some(Level::WARN));
+        assert_eq!(parse_level("info"), Some(Level::INFO));
+        assert_eq!(parse_level("DEBUG"), Some(Level::DEBUG));
+        assert_eq!(parse_level(" trace "), Some(Level::TRACE));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            format: "json".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            LogSettings::resolve(&config, false),
+            LogSettings { level: Level::WARN, json: true }
+        );
+        // --verbose/--debug override the configured level
+        assert_eq!(LogSettings::resolve(&config, true).level, Level::DEBUG);
+
+        let config = LoggingConfig {
+            level: "trace".to_string(),
+            format: "pretty".to_string(),
+            ..Default::default()
+        };
+        let settings = LogSettings::resolve(&config, true);
+        assert!(!settings.json);
+        // The flag never makes logging quieter
+        assert_eq!(settings.level, Level::TRACE);
+
+        let config = LoggingConfig {
+            level: "loud".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(LogSettings::resolve(&config, false).level, Level::INFO);
+    }
+
+    #[test]
+    fn test_env_filter_uses_level_for_workspace_crates() {
+        let filter = env_filter(Level::DEBUG).to_string();
+        assert!(filter.contains("clanker_gateway=debug"));
+        assert!(filter.contains("open_clanker=debug"));
+    }
+
+    #[test]
+    fn test_size_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clanker.log");
+        let mut file = SizeRotatingFile::open(path.clone(), 10).unwrap();
+
+        file.write_all(b"12345678").unwrap();
+        file.write_all(b"abcdef").unwrap();
+
+        assert_eq!(std::fs::read_to_string(dir.path().join("clanker.log.1")).unwrap(), "12345678");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "abcdef");
+    }
+}
@@ -0,0 +1,101 @@
+//! `logs` command: stream gateway WebSocket events to stdout, one line each.
+//!
+//! Lines are `<timestamp> <type> <data as compact JSON>`, so they can be
+//! grepped or piped. Connection notices go to stderr to keep stdout clean.
+
+use crate::tui::{ws_url, WS_RECONNECT_DELAY};
+use anyhow::Result;
+use futures_util::StreamExt;
+use std::io::Write;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// Event type of a frame that is not a gateway message
+const UNKNOWN_TYPE: &str = "unknown";
+
+/// Format a gateway frame as one output line; `None` when `filter` is set and
+/// does not list the frame's message type
+pub fn format_event(text: &str, filter: &[String], timestamp: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(text).ok();
+    let event_type = value
+        .as_ref()
+        .and_then(|v| v.get("type"))
+        .and_then(|t| t.as_str())
+        .unwrap_or(UNKNOWN_TYPE);
+    if !filter.is_empty() && !filter.iter().any(|f| f.eq_ignore_ascii_case(event_type)) {
+        return None;
+    }
+
+    let data = match value.as_ref().and_then(|v| v.get("data")) {
+        Some(data) => data.to_string(),
+        None if value.is_some() => String::new(),
+        // Keep non-JSON frames on one line
+        None => text.replace('\n', "\\n"),
+    };
+    let line = format!("{} {} {}", timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true), event_type, data);
+    Some(line.trim_end().to_string())
+}
+
+/// Print gateway events until interrupted, reconnecting when the connection drops
+pub async fn run_logs(host: &str, port: u16, filter: Vec<String>) -> Result<()> {
+    let url = ws_url(host, port);
+    loop {
+        match tokio_tungstenite::connect_async(&url).await {
+            Ok((ws_stream, _)) => {
+                eprintln!("Connected to {}", url);
+                let (_sink, mut stream) = ws_stream.split();
+                while let Some(frame) = stream.next().await {
+                    match frame {
+                        Ok(WsMessage::Text(text)) => {
+                            if let Some(line) = format_event(&text, &filter, chrono::Utc::now()) {
+                                let mut stdout = std::io::stdout().lock();
+                                // Stop quietly when the reader (e.g. `head`) goes away
+                                if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        Ok(WsMessage::Close(_)) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                }
+                eprintln!("Disconnected from {}", url);
+            }
+            Err(e) => eprintln!("WebSocket error: {}", e),
+        }
+        tokio::time::sleep(WS_RECONNECT_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_gateway::WsServerMessage;
+
+    fn at() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2026-10-14T09:30:00Z").unwrap().into()
+    }
+
+    #[test]
+    fn test_format_event_line() {
+        let pong = serde_json::to_string(&WsServerMessage::Pong { timestamp: 7 }).unwrap();
+        assert_eq!(
+            format_event(&pong, &[], at()).unwrap(),
+            r#"2026-10-14T09:30:00Z pong {"timestamp":7}"#
+        );
+
+        let error = serde_json::to_string(&WsServerMessage::error("BAD_REQUEST", "multi\nline")).unwrap();
+        let line = format_event(&error, &[], at()).unwrap();
+        assert!(line.starts_with("2026-10-14T09:30:00Z error {"), "{}", line);
+        assert_eq!(line.lines().count(), 1);
+
+        assert_eq!(format_event("not\njson", &[], at()).unwrap(), "2026-10-14T09:30:00Z unknown not\\njson");
+    }
+
+    #[test]
+    fn test_format_event_filter() {
+        let pong = serde_json::to_string(&WsServerMessage::Pong { timestamp: 7 }).unwrap();
+        let filter = vec!["error".to_string(), "Pong".to_string()];
+        assert!(format_event(&pong, &filter, at()).is_some());
+        assert!(format_event(&pong, &["error".to_string()], at()).is_none());
+    }
+}
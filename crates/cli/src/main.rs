@@ -1,13 +1,16 @@
 mod banner;
+mod logging;
+mod logs;
 mod onboard;
 mod tui;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use colored::Colorize;
 use std::path::PathBuf;
-use std::str::FromStr;
 
-use clanker_config::generate_default_config;
+use clanker_agent::{models, AgentError, AgentFactory, AgentMessage, MessageRole};
+use clanker_config::{generate_default_config, merge_default_config, AgentConfig};
 use clanker_gateway::GatewayServer;
 use tokio_util::sync::CancellationToken;
 
@@ -24,9 +27,11 @@ struct Cli {
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
 
+    /// Log at debug level or more (overrides logging.level)
     #[arg(short, long)]
     verbose: bool,
 
+    /// Same as --verbose
     #[arg(short, long)]
     debug: bool,
 
@@ -41,6 +46,9 @@ enum Commands {
         output: Option<PathBuf>,
         #[arg(short, long)]
         force: bool,
+        /// Add newly introduced options to an existing file, keeping its values
+        #[arg(short, long, conflicts_with = "force")]
+        merge: bool,
     },
     ConfigValidate {
         #[arg(short, long, value_name = "FILE")]
@@ -53,14 +61,31 @@ enum Commands {
         host: Option<String>,
         #[arg(short, long, value_name = "PORT")]
         port: Option<u16>,
+        /// Reply with the placeholder agent (echo) instead of calling AI providers
+        #[arg(long)]
+        dry_run: bool,
     },
     Send {
         message: String,
         #[arg(short, long, value_name = "CHANNEL")]
         channel: Option<String>,
-        #[arg(short, long, value_name = "ID")]
+        #[arg(short = 'i', long, value_name = "ID")]
         chat_id: Option<String>,
     },
+    /// Send one prompt straight to the configured agent (no gateway needed)
+    Chat {
+        prompt: String,
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+        #[arg(long, value_name = "PROVIDER")]
+        provider: Option<String>,
+        #[arg(long, value_name = "MODEL")]
+        model: Option<String>,
+    },
+    /// List model ids available from a provider (API key read from OPENCLAW_<PROVIDER>_API_KEY)
+    ListModels {
+        provider: String,
+    },
     Status {
         #[arg(short, long)]
         detailed: bool,
@@ -70,6 +95,23 @@ enum Commands {
         host: String,
         #[arg(short, long, value_name = "PORT", default_value = "18789")]
         port: u16,
+        #[arg(long, value_name = "CHANNEL", default_value = "telegram")]
+        channel: String,
+        /// Chat id messages are sent as (required for discord; telegram uses
+        /// chat 0, which is no real chat, when omitted)
+        #[arg(long, value_name = "ID")]
+        chat_id: Option<String>,
+    },
+    /// Print gateway WebSocket events to stdout, one line each
+    #[command(alias = "tail")]
+    Logs {
+        #[arg(long, value_name = "HOST", default_value = "127.0.0.1")]
+        host: String,
+        #[arg(short, long, value_name = "PORT", default_value = "18789")]
+        port: u16,
+        /// Only print these message types (e.g. send_response,error)
+        #[arg(short, long, value_name = "TYPE", value_delimiter = ',')]
+        filter: Vec<String>,
     },
     Onboard {
         #[arg(short, long, value_name = "FILE", default_value = "config.toml")]
@@ -78,6 +120,11 @@ enum Commands {
         env_file: PathBuf,
     },
     Version,
+    /// Print a shell completion script to stdout
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 #[tokio::main]
@@ -87,39 +134,65 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
-    // Skip logging setup for TUI - it takes over the terminal
-    let is_tui = matches!(cli.command, Some(Commands::Tui { .. }));
-    if !is_tui {
-        setup_logging(cli.verbose || cli.debug);
+    // Skip logging setup for TUI (it takes over the terminal), logs and completions (stdout is the output).
+    // The gateway sets up logging itself once its config (and [logging] section) is loaded.
+    let debug = cli.verbose || cli.debug;
+    let deferred = matches!(
+        cli.command,
+        Some(Commands::Tui { .. })
+            | Some(Commands::Logs { .. })
+            | Some(Commands::Completions { .. })
+            | Some(Commands::Gateway { .. })
+    );
+    if !deferred {
+        let console = clanker_config::LoggingConfig {
+            format: "pretty".to_string(),
+            ..Default::default()
+        };
+        logging::init(&console, debug)?;
     }
 
     match cli.command {
-        Some(Commands::ConfigGenerate { output, force }) => cmd_config_generate(output, force).await,
+        Some(Commands::ConfigGenerate { output, force, merge }) => {
+            cmd_config_generate(output, force, merge).await
+        }
         Some(Commands::ConfigValidate { config: config_path }) => cmd_config_validate(config_path.or(cli.config)).await,
-        Some(Commands::Gateway { config, host, port }) => cmd_gateway(config.or(cli.config), host, port).await,
+        Some(Commands::Gateway { config, host, port, dry_run }) => {
+            cmd_gateway(config.or(cli.config), host, port, dry_run, debug).await
+        }
         Some(Commands::Send { message, channel, chat_id }) => cmd_send(message, channel, chat_id).await,
+        Some(Commands::Chat { prompt, config, provider, model }) => {
+            cmd_chat(config.or(cli.config), prompt, provider, model).await
+        }
+        Some(Commands::ListModels { provider }) => cmd_list_models(provider).await,
         Some(Commands::Status { detailed }) => cmd_status(detailed).await,
-        Some(Commands::Tui { host, port }) => cmd_tui(host, port).await,
+        Some(Commands::Tui { host, port, channel, chat_id }) => cmd_tui(host, port, channel, chat_id).await,
+        Some(Commands::Logs { host, port, filter }) => crate::logs::run_logs(&host, port, filter).await,
         Some(Commands::Onboard { config, env_file }) => cmd_onboard(config, env_file).await,
         Some(Commands::Version) => cmd_version().await,
+        Some(Commands::Completions { shell }) => cmd_completions(shell, &mut std::io::stdout()),
         None => { print_welcome(); Ok(()) }
     }
 }
 
-fn setup_logging(debug: bool) {
-    let level = if debug { "debug" } else { "info" };
-    let log_level = tracing::Level::from_str(level).unwrap_or(tracing::Level::INFO);
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
-        .with_env_filter(format!("open_clanker={},clanker_core={}", level, level))
-        .with_max_level(log_level)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
-}
-
-async fn cmd_config_generate(output: Option<PathBuf>, force: bool) -> anyhow::Result<()> {
+async fn cmd_config_generate(output: Option<PathBuf>, force: bool, merge: bool) -> anyhow::Result<()> {
     let output_path = output.unwrap_or_else(|| PathBuf::from("config.toml"));
+    if output_path.exists() && merge {
+        let existing = std::fs::read_to_string(&output_path)?;
+        let (merged, added) = merge_default_config(&existing)?;
+        if added.is_empty() {
+            println!("Configuration is up to date: {}", output_path.display());
+            return Ok(());
+        }
+        std::fs::write(&output_path, merged)?;
+        println!("Added {} new option(s) to {}:", added.len(), output_path.display());
+        for key in added {
+            println!("  {}", key);
+        }
+        return Ok(());
+    }
     if output_path.exists() && !force {
-        eprintln!("Configuration file already exists. Use --force to overwrite.");
+        eprintln!("Configuration file already exists. Use --force to overwrite or --merge to add new options.");
         return Err(anyhow::anyhow!("File already exists"));
     }
     let config_content = generate_default_config();
@@ -146,39 +219,71 @@ async fn cmd_config_validate(config_path: Option<PathBuf>) -> anyhow::Result<()>
     Ok(())
 }
 
-async fn cmd_gateway(config_path: Option<PathBuf>, host: Option<String>, port: Option<u16>) -> anyhow::Result<()> {
-    let config_path = config_path.unwrap_or_else(|| PathBuf::from("config.toml"));
-    if !config_path.exists() {
-        eprintln!("Configuration file not found: {}", config_path.display());
-        eprintln!("Generate one with: open-clanker config-generate");
-        return Err(anyhow::anyhow!("Configuration file not found"));
-    }
-
-    let mut config = clanker_config::Config::load_from_path(&config_path)
+/// Load the gateway config file and environment, then apply CLI overrides
+fn load_gateway_config(
+    config_path: &std::path::Path,
+    host: Option<&str>,
+    port: Option<u16>,
+    dry_run: bool,
+) -> anyhow::Result<clanker_config::Config> {
+    let mut config = clanker_config::Config::load_from_path(config_path)
         .map_err(|e| anyhow::anyhow!("Failed to load config: {}", e))?;
 
     config.load_env().map_err(|e| anyhow::anyhow!("Failed to load env: {}", e))?;
 
     // Apply CLI overrides for host/port
     if let Some(h) = host {
-        config.server.host = h;
+        config.server.host = h.to_string();
     }
     if let Some(p) = port {
         config.server.port = p;
     }
+    if dry_run {
+        config.server.dry_run = true;
+    }
+    Ok(config)
+}
 
+async fn cmd_gateway(
+    config_path: Option<PathBuf>,
+    host: Option<String>,
+    port: Option<u16>,
+    dry_run: bool,
+    debug: bool,
+) -> anyhow::Result<()> {
+    let config_path = config_path.unwrap_or_else(|| PathBuf::from("config.toml"));
+    if !config_path.exists() {
+        eprintln!("Configuration file not found: {}", config_path.display());
+        eprintln!("Generate one with: open-clanker config-generate");
+        return Err(anyhow::anyhow!("Configuration file not found"));
+    }
+
+    let config = load_gateway_config(&config_path, host.as_deref(), port, dry_run)?;
     config.validate().map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
+    let _log_guard = logging::init(&config.logging, debug)?;
 
+    let dry_run = config.server.dry_run;
     let shutdown_token = CancellationToken::new();
-    let server = GatewayServer::new(config, shutdown_token.clone());
+    // SIGHUP re-reads the same file with the same overrides
+    let loader: clanker_gateway::server::ConfigLoader =
+        std::sync::Arc::new(move || load_gateway_config(&config_path, host.as_deref(), port, dry_run));
+    let server = GatewayServer::new(config, shutdown_token.clone()).with_config_loader(loader);
 
-    let addr = server.address();
     println!("{}", banner::gateway_banner());
     println!();
     println!("{}", "Gateway ready:".bold());
-    println!("  HTTP:       http://{}", addr);
-    println!("  WebSocket:  ws://{}/ws", addr);
-    println!("  Health:    http://{}/health", addr);
+    for addr in server.addresses() {
+        println!("  HTTP:       http://{}", addr);
+        println!("  WebSocket:  ws://{}/ws", addr);
+        println!("  Health:    http://{}/health", addr);
+    }
+    if dry_run {
+        println!();
+        println!(
+            "{}",
+            "DRY RUN: replies echo the input via the placeholder agent; no AI provider is called.".yellow().bold()
+        );
+    }
     println!();
     println!("Press Ctrl+C to stop.");
 
@@ -192,6 +297,144 @@ async fn cmd_send(_message: String, _channel: Option<String>, _chat_id: Option<S
     Ok(())
 }
 
+async fn cmd_chat(
+    config_path: Option<PathBuf>,
+    prompt: String,
+    provider: Option<String>,
+    model: Option<String>,
+) -> anyhow::Result<()> {
+    let config_path = config_path.unwrap_or_else(|| PathBuf::from("config.toml"));
+    if !config_path.exists() {
+        eprintln!("Configuration file not found: {}", config_path.display());
+        eprintln!("Generate one with: open-clanker config-generate");
+        return Err(anyhow::anyhow!("Configuration file not found"));
+    }
+
+    let mut config = clanker_config::Config::load_from_path(&config_path)
+        .map_err(|e| anyhow::anyhow!("Failed to load config: {}", e))?;
+    apply_agent_overrides(&mut config.agent, provider, model)?;
+    config.load_env().map_err(|e| anyhow::anyhow!("Failed to load env: {}", e))?;
+
+    let agent_config = config.agent.clone();
+    let agent = AgentFactory::create_from_config(config.agent);
+    let messages = vec![AgentMessage {
+        role: MessageRole::User,
+        content: prompt,
+        images: Vec::new(),
+    }];
+
+    let response = agent
+        .chat(messages)
+        .await
+        .map_err(|e| anyhow::anyhow!(describe_agent_error(&e, &agent_config)))?;
+
+    println!("{}", response.content);
+    println!();
+
+    let usage = clanker_core::UsageStats::new(response.usage.prompt_tokens, response.usage.completion_tokens);
+    let cost = usage.calculate_cost(&response.provider, &response.model);
+    println!(
+        "{}",
+        format!(
+            "{} / {} | tokens: {} prompt + {} completion = {} | est. cost: ${:.6}",
+            response.provider,
+            response.model,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens,
+            cost
+        )
+        .dimmed()
+    );
+    Ok(())
+}
+
+/// Apply --provider/--model overrides; a new provider reads its key from `OPENCLAW_<PROVIDER>_API_KEY`
+fn apply_agent_overrides(
+    agent: &mut AgentConfig,
+    provider: Option<String>,
+    model: Option<String>,
+) -> anyhow::Result<()> {
+    if let Some(provider) = provider {
+        let provider = provider.to_lowercase();
+        if !AgentFactory::is_supported(&provider) {
+            return Err(anyhow::anyhow!(
+                "Unsupported provider: {} (supported: {})",
+                provider,
+                AgentFactory::supported_providers().join(", ")
+            ));
+        }
+        if provider != agent.provider.to_lowercase() {
+            agent.api_key_env = models::default_api_key_env(&provider);
+            agent.api_key = None;
+            agent.api_base_url = None;
+        }
+        agent.provider = provider;
+    }
+    if let Some(model) = model {
+        agent.model = model;
+    }
+    Ok(())
+}
+
+/// Explain an agent failure in terms of what the user can fix
+fn describe_agent_error(error: &AgentError, config: &AgentConfig) -> String {
+    match error {
+        AgentError::AuthenticationFailed => format!(
+            "Authentication with {} failed: check that {} is set to a valid API key",
+            config.provider, config.api_key_env
+        ),
+        AgentError::RateLimited(Some(retry)) => format!(
+            "Rate limited by {}: retry after {}s",
+            config.provider,
+            retry.as_secs()
+        ),
+        AgentError::RateLimited(None) => format!("Rate limited by {}: try again later", config.provider),
+        AgentError::ProviderError(msg) => format!("{} returned an error: {}", config.provider, msg),
+        AgentError::InvalidRequest(msg) => format!("{} rejected the request: {}", config.provider, msg),
+        AgentError::RequestFailed(msg) | AgentError::HttpError(msg) => {
+            format!("Could not reach {}: {}", config.provider, msg)
+        }
+        AgentError::InvalidResponse(msg) | AgentError::SerializationError(msg) => {
+            format!("Unexpected response from {}: {}", config.provider, msg)
+        }
+        AgentError::Config(msg) => format!("{}: check the value of {}", msg, config.api_key_env),
+        AgentError::Unknown(msg) => format!("Agent error: {}", msg),
+    }
+}
+
+async fn cmd_list_models(provider: String) -> anyhow::Result<()> {
+    let provider = provider.to_lowercase();
+    if !AgentFactory::is_supported(&provider) {
+        return Err(anyhow::anyhow!(
+            "Unsupported provider: {} (supported: {})",
+            provider,
+            AgentFactory::supported_providers().join(", ")
+        ));
+    }
+    let agent_config = AgentConfig {
+        provider: provider.clone(),
+        api_key_env: models::default_api_key_env(&provider),
+        ..Default::default()
+    };
+
+    if let Some(curated) = models::static_models(&provider) {
+        println!("{} has no models endpoint; known models:", provider);
+        for model in curated {
+            println!("  {}", model);
+        }
+        return Ok(());
+    }
+    let api_key = std::env::var(&agent_config.api_key_env).ok();
+    let ids = models::list_models(&provider, api_key.as_deref())
+        .await
+        .map_err(|e| anyhow::anyhow!(describe_agent_error(&e, &agent_config)))?;
+    for id in ids {
+        println!("{}", id);
+    }
+    Ok(())
+}
+
 async fn cmd_status(_detailed: bool) -> anyhow::Result<()> {
     println!("Open Clanker Status");
     println!("Version: open-clanker {}", env!("CARGO_PKG_VERSION"));
@@ -223,19 +466,44 @@ async fn cmd_onboard(config: PathBuf, env_file: PathBuf) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn cmd_tui(host: String, port: u16) -> anyhow::Result<()> {
+async fn cmd_tui(host: String, port: u16, channel: String, chat_id: Option<String>) -> anyhow::Result<()> {
+    let channel_type = clanker_core::ChannelType::from_str(&channel)
+        .ok_or_else(|| anyhow::anyhow!("Unknown channel: {}", channel))?;
+    let chat_id = tui_chat_id(channel_type, chat_id)?;
+
     println!("Connecting to gateway at {}:{}...", host, port);
-    println!("Press 'q' or Esc to quit.");
+    println!("Messages go to {} chat {}. Press Esc to quit.", channel_type, chat_id);
 
-    crate::tui::run_tui(&host, port).await?;
+    let target = crate::tui::SendTarget { channel_type, chat_id };
+    crate::tui::run_tui(&host, port, target).await?;
     Ok(())
 }
 
+/// Chat id the TUI sends as; discord has no placeholder id the gateway accepts
+fn tui_chat_id(channel_type: clanker_core::ChannelType, chat_id: Option<String>) -> anyhow::Result<String> {
+    let chat_id = match chat_id {
+        Some(id) => id,
+        None if channel_type == clanker_core::ChannelType::Discord => {
+            anyhow::bail!("--chat-id is required for the discord channel")
+        }
+        None => "0".to_string(),
+    };
+    channel_type.validate_channel_id(&chat_id)?;
+    Ok(chat_id)
+}
+
 async fn cmd_version() -> anyhow::Result<()> {
     println!("open-clanker {}", env!("CARGO_PKG_VERSION"));
     Ok(())
 }
 
+fn cmd_completions(shell: Shell, out: &mut dyn std::io::Write) -> anyhow::Result<()> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+    Ok(())
+}
+
 fn print_welcome() {
     println!("{}", banner::welcome_banner());
     println!();
@@ -245,11 +513,78 @@ fn print_welcome() {
     println!("  config-validate  - Validate configuration");
     println!("  gateway          - Start gateway server");
     println!("  send             - Send message (when implemented)");
+    println!("  chat             - Send one prompt to the agent (verifies your API key)");
+    println!("  list-models      - List models available from a provider");
     println!("  status           - Show status");
     println!("  tui              - Launch TUI client (requires gateway running)");
     println!("  version          - Show version");
+    println!("  completions      - Print shell completion script (bash, zsh, fish, powershell)");
     println!();
     println!("{}", "Quick start:".bold());
     println!("  ./open-clanker onboard   # or: cargo run -p clanker-cli -- onboard");
     println!("  source .env && ./open-clanker gateway");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_agent_overrides() {
+        let mut agent = AgentConfig::default();
+        let model = agent.model.clone();
+
+        apply_agent_overrides(&mut agent, Some("Groq".to_string()), None).unwrap();
+        assert_eq!(agent.provider, "groq");
+        assert_eq!(agent.api_key_env, "OPENCLAW_GROQ_API_KEY");
+        assert_eq!(agent.model, model);
+
+        apply_agent_overrides(&mut agent, None, Some("llama-3.3-70b-versatile".to_string())).unwrap();
+        assert_eq!(agent.model, "llama-3.3-70b-versatile");
+
+        assert!(apply_agent_overrides(&mut agent, Some("nope".to_string()), None).is_err());
+    }
+
+    #[test]
+    fn test_describe_agent_error() {
+        let config = AgentConfig::default();
+
+        let auth = describe_agent_error(&AgentError::AuthenticationFailed, &config);
+        assert!(auth.contains(&config.api_key_env));
+
+        let limited = describe_agent_error(
+            &AgentError::RateLimited(Some(std::time::Duration::from_secs(30))),
+            &config,
+        );
+        assert!(limited.contains("retry after 30s"));
+    }
+
+    #[test]
+    fn test_tui_chat_id() {
+        use clanker_core::ChannelType;
+        assert_eq!(tui_chat_id(ChannelType::Telegram, None).unwrap(), "0");
+        assert!(tui_chat_id(ChannelType::Discord, None).is_err());
+        assert!(tui_chat_id(ChannelType::Discord, Some("abc".to_string())).is_err());
+        assert_eq!(
+            tui_chat_id(ChannelType::Discord, Some("123456789".to_string())).unwrap(),
+            "123456789"
+        );
+    }
+
+    #[test]
+    fn test_completions_for_each_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = Vec::new();
+            cmd_completions(shell, &mut out).unwrap();
+
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("open-clanker"), "{shell} script missing binary name");
+            assert!(script.contains("completions"), "{shell} script missing subcommands");
+        }
+    }
+}
@@ -109,7 +109,7 @@ pub fn run_onboard(config_path: &Path, env_path: &Path) -> Result<()> {
     {
         let choices: Vec<String> = other_providers
             .iter()
-            .map(|(_, _, name)| format!("{}", name))
+            .map(|(_, _, name)| name.to_string())
             .collect();
         let selected = MultiSelect::new()
             .with_prompt("Select providers to add keys for")
@@ -279,12 +279,14 @@ pub fn run_onboard(config_path: &Path, env_path: &Path) -> Result<()> {
     let mut channels = ChannelsConfig {
         telegram: None,
         discord: None,
+        webhook: None,
     };
 
     if enable_telegram {
         channels.telegram = Some(TelegramConfig {
             bot_token: "from-env".to_string(), // Placeholder; real value from .env
             allowed_chats: None,
+            ..Default::default()
         });
     }
 
@@ -292,6 +294,7 @@ pub fn run_onboard(config_path: &Path, env_path: &Path) -> Result<()> {
         channels.discord = Some(DiscordConfig {
             bot_token: "from-env".to_string(),
             guild_id: None,
+            ..Default::default()
         });
     }
 
@@ -311,6 +314,7 @@ pub fn run_onboard(config_path: &Path, env_path: &Path) -> Result<()> {
             host: "0.0.0.0".to_string(),
             port,
             tls: None,
+            ..Default::default()
         },
         channels,
         agent: AgentConfig {
@@ -319,12 +323,12 @@ pub fn run_onboard(config_path: &Path, env_path: &Path) -> Result<()> {
             api_key_env: provider_info.api_key_env.to_string(),
             api_key: None, // Always from env
             max_tokens: 4096,
-            api_base_url: None,
-            worker: None,
             fallback: agent_fallback,
+            ..Default::default()
         },
         orchestration: clanker_config::OrchestrationConfig::default(),
         logging: LoggingConfig::default(),
+        ..Default::default()
     };
 
     // Write config.toml (no secrets)
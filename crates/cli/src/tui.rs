@@ -1,12 +1,15 @@
 //! TUI client for Open Clanker Gateway
 //!
-//! Connects to a running gateway via HTTP (health) and WebSocket (events).
+//! Connects to a running gateway via HTTP (health) and WebSocket (events), and
+//! sends typed messages to a channel over the same WebSocket.
 
 use anyhow::Result;
-use futures_util::StreamExt; // needed for WebSocketStream::next()
+use clanker_core::ChannelType;
+use clanker_gateway::{WsClientMessage, WsServerMessage};
+use futures_util::{SinkExt, StreamExt};
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -17,8 +20,15 @@ use ratatui::{
 use serde::Deserialize;
 use std::io::{self, Stdout};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, RwLock};
+
+/// Channel the TUI sends typed messages to
+#[derive(Debug, Clone)]
+pub struct SendTarget {
+    pub channel_type: ChannelType,
+    pub chat_id: String,
+}
 
 /// Health response from gateway /health endpoint
 #[derive(Debug, Clone, Deserialize)]
@@ -37,14 +47,131 @@ pub struct HealthResponse {
     pub timestamp: Option<String>,
 }
 
+/// Maximum number of events kept in the events pane
+const MAX_EVENTS: usize = 100;
+
+/// How many recent events are checked for duplicates
+const DEDUP_WINDOW: usize = 10;
+
+/// Health poll interval while the gateway is reachable
+const HEALTH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest wait between health checks while the gateway is down
+const MAX_HEALTH_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Delay before the next health check after `failures` consecutive failures
+/// (2s on success, doubling per failure up to 30s)
+fn health_backoff(failures: u32) -> Duration {
+    let factor = 1u32 << failures.saturating_sub(1).min(4);
+    (HEALTH_INTERVAL * factor).min(MAX_HEALTH_BACKOFF)
+}
+
+/// Wait before reconnecting a dropped gateway WebSocket
+pub const WS_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Gateway WebSocket endpoint
+pub fn ws_url(host: &str, port: u16) -> String {
+    format!("ws://{}:{}/ws", host, port)
+}
+
+/// Events scrolled per PageUp/PageDown
+const PAGE_SIZE: usize = 10;
+
+/// Which events the events pane shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventFilter {
+    #[default]
+    All,
+    /// WebSocket traffic and connection changes
+    Ws,
+    /// Errors and failures only
+    Errors,
+}
+
+impl EventFilter {
+    /// Next filter in the cycle
+    pub fn next(self) -> Self {
+        match self {
+            EventFilter::All => EventFilter::Ws,
+            EventFilter::Ws => EventFilter::Errors,
+            EventFilter::Errors => EventFilter::All,
+        }
+    }
+
+    /// Short label for the pane title
+    pub fn label(self) -> &'static str {
+        match self {
+            EventFilter::All => "all",
+            EventFilter::Ws => "ws",
+            EventFilter::Errors => "errors",
+        }
+    }
+
+    /// Check if an event passes the filter (separators always do)
+    pub fn matches(self, event: &TuiEvent) -> bool {
+        if event.separator {
+            return true;
+        }
+        match self {
+            EventFilter::All => true,
+            EventFilter::Ws => ["WS:", "WebSocket", "Agent", "You:", "Send failed"]
+                .iter()
+                .any(|prefix| event.text.starts_with(prefix)),
+            EventFilter::Errors => {
+                let text = event.text.to_lowercase();
+                text.contains("error") || text.contains("failed")
+            }
+        }
+    }
+}
+
+/// Event shown in the events pane
+#[derive(Debug, Clone)]
+pub struct TuiEvent {
+    /// Time of the latest occurrence
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    /// Event text
+    pub text: String,
+    /// Consecutive identical occurrences folded into this event
+    pub count: u32,
+    /// Reconnect separator rather than a regular event
+    pub separator: bool,
+}
+
+impl TuiEvent {
+    /// Render the event as a single list line
+    pub fn display(&self) -> String {
+        if self.separator {
+            return format!("──── {} {} ────", self.timestamp.format("%H:%M:%S"), self.text);
+        }
+        if self.count > 1 {
+            format!("{} {} (x{})", self.timestamp.format("%H:%M:%S"), self.text, self.count)
+        } else {
+            format!("{} {}", self.timestamp.format("%H:%M:%S"), self.text)
+        }
+    }
+}
+
 /// Shared application state for TUI
 #[derive(Debug, Clone, Default)]
 pub struct TuiState {
     pub gateway_url: String,
     pub health: Option<HealthResponse>,
-    pub events: Vec<String>,
+    pub events: Vec<TuiEvent>,
     pub connection_status: String,
     pub error: Option<String>,
+    /// Text typed into the input box
+    pub input: String,
+    /// Events pane filter
+    pub filter: EventFilter,
+    /// Number of newest (filtered) events scrolled past
+    pub scroll: usize,
+    /// Consecutive failed health checks
+    pub health_failures: u32,
+    /// When the next health check runs (set while backing off)
+    pub next_health_check: Option<Instant>,
+    /// Number of successful WebSocket connections so far
+    ws_connects: u64,
 }
 
 impl TuiState {
@@ -55,25 +182,154 @@ impl TuiState {
             events: Vec::new(),
             connection_status: "Connecting...".to_string(),
             error: None,
+            input: String::new(),
+            filter: EventFilter::All,
+            scroll: 0,
+            health_failures: 0,
+            next_health_check: None,
+            ws_connects: 0,
         }
     }
 
+    /// Add an event; a recent identical event (since the last separator) is moved
+    /// to the end with its count bumped instead of adding a new line
     pub fn add_event(&mut self, msg: String) {
-        self.events.push(msg);
-        // Keep last 100 events
-        if self.events.len() > 100 {
+        let duplicate = self
+            .events
+            .iter()
+            .rev()
+            .take(DEDUP_WINDOW)
+            .take_while(|e| !e.separator)
+            .position(|e| e.text == msg)
+            .map(|pos| self.events.len() - 1 - pos);
+
+        let count = match duplicate {
+            Some(index) => self.events.remove(index).count + 1,
+            None => 1,
+        };
+        self.push_event(msg, false, count);
+    }
+
+    fn push_event(&mut self, text: String, separator: bool, count: u32) {
+        let event = TuiEvent {
+            timestamp: chrono::Local::now(),
+            text,
+            count,
+            separator,
+        };
+        // Keep a scrolled view on the same events as new ones arrive
+        if self.scroll > 0 && self.filter.matches(&event) {
+            self.scroll += 1;
+        }
+        self.events.push(event);
+        if self.events.len() > MAX_EVENTS {
             self.events.remove(0);
         }
+        self.clamp_scroll();
+    }
+
+    /// Events passing the current filter, newest first (`events` is kept in arrival order, under the state lock)
+    pub fn filtered_events(&self) -> Vec<&TuiEvent> {
+        self.events
+            .iter()
+            .rev()
+            .filter(|e| self.filter.matches(e))
+            .collect()
+    }
+
+    /// Scroll towards older events
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_add(lines);
+        self.clamp_scroll();
+    }
+
+    /// Scroll towards newer events
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Switch to the next filter and jump back to the newest events
+    pub fn cycle_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.scroll = 0;
+    }
+
+    fn clamp_scroll(&mut self) {
+        let total = self.filtered_events().len();
+        self.scroll = self.scroll.min(total.saturating_sub(1));
+    }
+
+    /// Record a WebSocket connection, with a separator and cleared error on reconnect
+    pub fn ws_connected(&mut self) {
+        if self.ws_connects > 0 {
+            self.error = None;
+            self.push_event("reconnected".to_string(), true, 1);
+        }
+        self.ws_connects += 1;
+        self.add_event("WebSocket connected".to_string());
+    }
+
+    /// Take the typed input for sending, leaving the box empty (None if blank)
+    pub fn take_input(&mut self) -> Option<String> {
+        let input = std::mem::take(&mut self.input);
+        let trimmed = input.trim();
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
     }
 
     pub fn set_connected(&mut self) {
+        if self.error.is_some() {
+            self.add_event("Gateway reachable".to_string());
+        }
         self.connection_status = "Connected".to_string();
         self.error = None;
+        self.health_failures = 0;
+        self.next_health_check = None;
     }
 
-    pub fn set_disconnected(&mut self, reason: String) {
+    /// Record a failed health check; returns how long to wait before the next one
+    pub fn set_disconnected(&mut self, reason: String) -> Duration {
+        self.add_event(format!("Health check failed: {}", reason));
         self.connection_status = format!("Disconnected: {}", reason);
         self.error = Some(reason);
+
+        self.health_failures = self.health_failures.saturating_add(1);
+        let delay = health_backoff(self.health_failures);
+        self.next_health_check = Some(Instant::now() + delay);
+        delay
+    }
+
+    /// Status text for the help bar, with the retry countdown while backing off
+    pub fn status_text(&self) -> String {
+        match self.next_health_check {
+            Some(at) => {
+                let secs = at.saturating_duration_since(Instant::now()).as_secs_f32().ceil() as u64;
+                format!("{} | retry in {}s", self.connection_status, secs)
+            }
+            None => self.connection_status.clone(),
+        }
+    }
+}
+
+/// Summarize a gateway WebSocket frame for the events pane
+fn describe_server_message(text: &str) -> String {
+    match serde_json::from_str::<WsServerMessage>(text) {
+        Ok(WsServerMessage::SendResponse {
+            success: true,
+            content: Some(content),
+            filtered,
+            ..
+        }) => {
+            let prefix = if filtered { "Agent (filtered)" } else { "Agent" };
+            format!("{}: {}", prefix, content)
+        }
+        Ok(WsServerMessage::SendResponse {
+            success: false,
+            error,
+            ..
+        }) => format!("Send failed: {}", error.unwrap_or_else(|| "unknown error".to_string())),
+        Ok(WsServerMessage::Error { code, message }) => format!("Error {}: {}", code, message),
+        Ok(WsServerMessage::Notice { level, message }) => format!("Notice ({:?}): {}", level, message),
+        _ => format!("WS: {}", text.chars().take(80).collect::<String>()),
     }
 }
 
@@ -81,6 +337,7 @@ impl TuiState {
 async fn fetch_health(base_url: &str) -> Result<HealthResponse> {
     let url = format!("{}/health", base_url.trim_end_matches('/'));
     let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(2))
         .timeout(Duration::from_secs(5))
         .build()?;
     let resp = client.get(&url).send().await?;
@@ -90,9 +347,9 @@ async fn fetch_health(base_url: &str) -> Result<HealthResponse> {
 }
 
 /// Run the TUI
-pub async fn run_tui(host: &str, port: u16) -> Result<()> {
+pub async fn run_tui(host: &str, port: u16, target: SendTarget) -> Result<()> {
     let base_url = format!("http://{}:{}", host, port);
-    let ws_url = format!("ws://{}:{}/ws", host, port);
+    let ws_url = ws_url(host, port);
 
     let state = Arc::new(RwLock::new(TuiState::new(base_url.clone())));
 
@@ -100,44 +357,66 @@ pub async fn run_tui(host: &str, port: u16) -> Result<()> {
     let state_health = state.clone();
     let base_url_health = base_url.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(2));
         loop {
-            interval.tick().await;
-            match fetch_health(&base_url_health).await {
+            let delay = match fetch_health(&base_url_health).await {
                 Ok(health) => {
                     let mut s = state_health.write().await;
                     s.health = Some(health);
                     s.set_connected();
+                    HEALTH_INTERVAL
                 }
                 Err(e) => {
                     let mut s = state_health.write().await;
-                    s.set_disconnected(e.to_string());
+                    s.set_disconnected(e.to_string())
                 }
-            }
+            };
+            tokio::time::sleep(delay).await;
         }
     });
 
-    // Spawn WebSocket task for events
+    // Messages typed in the input box, sent by the WebSocket task
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+
+    // Spawn WebSocket task for events and outgoing messages
     let state_ws = state.clone();
     let ws_url_clone = ws_url.clone();
     tokio::spawn(async move {
         loop {
             match tokio_tungstenite::connect_async(&ws_url_clone).await {
-                Ok((mut ws_stream, _)) => {
+                Ok((ws_stream, _)) => {
                     {
                         let mut s = state_ws.write().await;
-                        s.add_event("WebSocket connected".to_string());
+                        s.ws_connected();
                     }
-                    while let Some(msg) = ws_stream.next().await {
-                        match msg {
-                            Ok(WsMessage::Text(text)) => {
-                                let mut s = state_ws.write().await;
-                                s.add_event(format!("WS: {}", text.chars().take(80).collect::<String>()));
+                    let (mut sink, mut stream) = ws_stream.split();
+                    loop {
+                        tokio::select! {
+                            msg = stream.next() => match msg {
+                                Some(Ok(WsMessage::Text(text))) => {
+                                    let mut s = state_ws.write().await;
+                                    s.add_event(describe_server_message(&text));
+                                }
+                                Some(Ok(WsMessage::Ping(_))) | Some(Ok(WsMessage::Pong(_))) => {}
+                                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                                Some(Ok(_)) => {}
+                            },
+                            Some(message) = outgoing_rx.recv() => {
+                                let frame = WsClientMessage::SendMessage {
+                                    channel_id: target.chat_id.clone(),
+                                    channel_type: target.channel_type,
+                                    message,
+                                    no_fallback: false,
+                                    json_mode: false,
+                                    provider: None,
+                                    model: None,
+                                };
+                                let json = serde_json::to_string(&frame).expect("client message serializes");
+                                if let Err(e) = sink.send(WsMessage::Text(json.into())).await {
+                                    let mut s = state_ws.write().await;
+                                    s.add_event(format!("Send failed: {}", e));
+                                    break;
+                                }
                             }
-                            Ok(WsMessage::Ping(_)) | Ok(WsMessage::Pong(_)) => {}
-                            Ok(WsMessage::Close(_)) => break,
-                            Err(_) => break,
-                            _ => {}
                         }
                     }
                 }
@@ -146,13 +425,10 @@ pub async fn run_tui(host: &str, port: u16) -> Result<()> {
                     s.add_event(format!("WebSocket error: {}", e));
                 }
             }
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            tokio::time::sleep(WS_RECONNECT_DELAY).await;
         }
     });
 
-    // Need to use futures_util::StreamExt for next()
-    use futures_util::StreamExt;
-
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -160,7 +436,7 @@ pub async fn run_tui(host: &str, port: u16) -> Result<()> {
 
     let mut terminal = ratatui::Terminal::new(CrosstermBackend::new(stdout))?;
 
-    let result = run_ui_loop(&mut terminal, state).await;
+    let result = run_ui_loop(&mut terminal, state, outgoing_tx).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -173,18 +449,40 @@ pub async fn run_tui(host: &str, port: u16) -> Result<()> {
 async fn run_ui_loop(
     terminal: &mut ratatui::Terminal<CrosstermBackend<Stdout>>,
     state: Arc<RwLock<TuiState>>,
+    outgoing: mpsc::UnboundedSender<String>,
 ) -> Result<()> {
     loop {
         let state_read = state.read().await;
-        draw_ui(terminal, &*state_read)?;
+        draw_ui(terminal, &state_read)?;
         drop(state_read);
 
         if event::poll(Duration::from_millis(250))? {
             if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                        break;
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Esc => break,
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Char(c) => state.write().await.input.push(c),
+                    KeyCode::Up => state.write().await.scroll_up(1),
+                    KeyCode::Down => state.write().await.scroll_down(1),
+                    KeyCode::PageUp => state.write().await.scroll_up(PAGE_SIZE),
+                    KeyCode::PageDown => state.write().await.scroll_down(PAGE_SIZE),
+                    KeyCode::Tab => state.write().await.cycle_filter(),
+                    KeyCode::Backspace => {
+                        state.write().await.input.pop();
                     }
+                    KeyCode::Enter => {
+                        let mut s = state.write().await;
+                        if let Some(message) = s.take_input() {
+                            s.add_event(format!("You: {}", message));
+                            if outgoing.send(message).is_err() {
+                                s.add_event("Send failed: WebSocket task stopped".to_string());
+                            }
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
@@ -204,6 +502,7 @@ fn draw_ui(
                 Constraint::Length(3),
                 Constraint::Min(10),
                 Constraint::Length(3),
+                Constraint::Length(3),
             ])
             .split(frame.area());
 
@@ -233,43 +532,223 @@ fn draw_ui(
         frame.render_widget(status, chunks[0]);
 
         // Events pane
-        let events_block = Block::default()
-            .title(" Events ")
-            .borders(Borders::ALL);
-
-        let events: Vec<ListItem> = state
-            .events
+        let filtered = state.filtered_events();
+        let height = chunks[1].height.saturating_sub(2) as usize;
+        let events: Vec<ListItem> = filtered
             .iter()
-            .rev()
-            .take(20)
-            .map(|e| ListItem::new(e.as_str()))
+            .skip(state.scroll)
+            .take(height)
+            .map(|e| ListItem::new(e.display()))
             .collect();
 
+        let events_block = Block::default()
+            .title(events_title(state.filter, state.scroll, events.len(), filtered.len()))
+            .borders(Borders::ALL);
+
         let events_list = List::new(events).block(events_block);
         frame.render_widget(events_list, chunks[1]);
 
+        // Input pane
+        let input_block = Block::default()
+            .title(" Message (Enter to send) ")
+            .borders(Borders::ALL);
+
+        let input = Paragraph::new(state.input.as_str()).block(input_block);
+        frame.render_widget(input, chunks[2]);
+
+        let cursor_x = chunks[2].x + 1 + state.input.chars().count() as u16;
+        frame.set_cursor_position((cursor_x.min(chunks[2].right().saturating_sub(2)), chunks[2].y + 1));
+
         // Instructions pane
         let help_block = Block::default()
             .title(" Help ")
             .borders(Borders::ALL);
 
-        let help_text = if state.error.is_some() {
-            format!(
-                "Gateway: {} | {} | Press 'q' or Esc to quit",
-                state.gateway_url, state.connection_status
-            )
-        } else {
-            format!(
-                "Gateway: {} | {} | Press 'q' or Esc to quit",
-                state.gateway_url, state.connection_status
-            )
-        };
+        let help_text = format!(
+            "Gateway: {} | {} | Up/Down/PgUp/PgDn scroll, Tab filter | Esc or Ctrl+C to quit",
+            state.gateway_url,
+            state.status_text()
+        );
 
         let help = Paragraph::new(help_text)
             .block(help_block)
             .wrap(Wrap { trim: true });
-        frame.render_widget(help, chunks[2]);
+        frame.render_widget(help, chunks[3]);
     })?;
 
     Ok(())
 }
+
+/// Events pane title with filter and scroll position, e.g. ` Events [all] 11-30/57 `
+fn events_title(filter: EventFilter, scroll: usize, shown: usize, total: usize) -> String {
+    if total == 0 {
+        return format!(" Events [{}] ", filter.label());
+    }
+    let more = if scroll + shown < total { " ↓" } else { "" };
+    let newer = if scroll > 0 { " ↑" } else { "" };
+    format!(
+        " Events [{}] {}-{}/{}{}{} ",
+        filter.label(),
+        scroll + 1,
+        scroll + shown,
+        total,
+        newer,
+        more
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_events_are_folded() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        state.add_event("WebSocket error: refused".to_string());
+        state.add_event("WebSocket error: refused".to_string());
+        state.add_event("WebSocket error: refused".to_string());
+
+        assert_eq!(state.events.len(), 1);
+        assert_eq!(state.events[0].count, 3);
+        assert!(state.events[0].display().ends_with("(x3)"));
+
+        state.add_event("Health check failed: refused".to_string());
+        state.add_event("WebSocket error: refused".to_string());
+        assert_eq!(state.events.len(), 2);
+        assert_eq!(state.events[1].text, "WebSocket error: refused");
+        assert_eq!(state.events[1].count, 4);
+    }
+
+    #[test]
+    fn test_events_are_listed_newest_first() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        state.add_event("a".to_string());
+        state.add_event("b".to_string());
+        state.add_event("c".to_string());
+
+        // A repeat moves to the newest position
+        state.add_event("a".to_string());
+
+        let texts: Vec<&str> = state.filtered_events().iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn test_reconnect_adds_separator_and_clears_error() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        state.ws_connected();
+        assert!(!state.events.iter().any(|e| e.separator));
+
+        state.set_disconnected("connection refused".to_string());
+        state.ws_connected();
+
+        assert!(state.error.is_none());
+        let separators = state.events.iter().filter(|e| e.separator).count();
+        assert_eq!(separators, 1);
+        assert_eq!(state.events.last().unwrap().text, "WebSocket connected");
+    }
+
+    #[test]
+    fn test_scroll_is_clamped_and_stable() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        for i in 0..30 {
+            state.add_event(format!("event {}", i));
+        }
+
+        state.scroll_down(PAGE_SIZE);
+        assert_eq!(state.scroll, 10);
+        assert_eq!(state.filtered_events()[state.scroll].text, "event 19");
+
+        // New events don't move a scrolled view
+        state.add_event("event 30".to_string());
+        assert_eq!(state.filtered_events()[state.scroll].text, "event 19");
+
+        state.scroll_down(1000);
+        assert_eq!(state.scroll, 30);
+        state.scroll_up(1000);
+        assert_eq!(state.scroll, 0);
+    }
+
+    #[test]
+    fn test_filter_cycle() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        state.add_event("WS: {\"type\":\"pong\"}".to_string());
+        state.add_event("Gateway reachable".to_string());
+        state.add_event("Health check failed: refused".to_string());
+        state.scroll_down(1);
+
+        state.cycle_filter();
+        assert_eq!(state.filter, EventFilter::Ws);
+        assert_eq!(state.scroll, 0);
+        assert_eq!(state.filtered_events().len(), 1);
+
+        state.cycle_filter();
+        assert_eq!(state.filter, EventFilter::Errors);
+        assert_eq!(state.filtered_events()[0].text, "Health check failed: refused");
+
+        state.cycle_filter();
+        assert_eq!(state.filter, EventFilter::All);
+        assert_eq!(state.filtered_events().len(), 3);
+    }
+
+    #[test]
+    fn test_events_title() {
+        assert_eq!(events_title(EventFilter::All, 0, 0, 0), " Events [all] ");
+        assert_eq!(events_title(EventFilter::Ws, 0, 20, 57), " Events [ws] 1-20/57 ↓ ");
+        assert_eq!(events_title(EventFilter::All, 37, 20, 57), " Events [all] 38-57/57 ↑ ");
+    }
+
+    #[test]
+    fn test_health_backoff_grows_and_resets() {
+        assert_eq!(health_backoff(1), Duration::from_secs(2));
+        assert_eq!(health_backoff(2), Duration::from_secs(4));
+        assert_eq!(health_backoff(4), Duration::from_secs(16));
+        assert_eq!(health_backoff(5), Duration::from_secs(30));
+        assert_eq!(health_backoff(50), Duration::from_secs(30));
+
+        let mut state = TuiState::new("http://localhost".to_string());
+        assert_eq!(state.set_disconnected("refused".to_string()), Duration::from_secs(2));
+        assert_eq!(state.set_disconnected("refused".to_string()), Duration::from_secs(4));
+        assert!(state.status_text().contains("retry in"));
+
+        state.set_connected();
+        assert_eq!(state.health_failures, 0);
+        assert_eq!(state.status_text(), "Connected");
+        assert_eq!(state.set_disconnected("refused".to_string()), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_take_input_trims_and_clears() {
+        let mut state = TuiState::new("http://localhost".to_string());
+        state.input = "  hello gateway ".to_string();
+        assert_eq!(state.take_input(), Some("hello gateway".to_string()));
+        assert!(state.input.is_empty());
+
+        state.input = "   ".to_string();
+        assert_eq!(state.take_input(), None);
+        assert!(state.input.is_empty());
+    }
+
+    #[test]
+    fn test_describe_send_response() {
+        let ok = serde_json::to_string(&WsServerMessage::send_response(
+            true,
+            Some("msg-1".to_string()),
+            None,
+            Some("Hi there".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(describe_server_message(&ok), "Agent: Hi there");
+
+        let failed = serde_json::to_string(&WsServerMessage::send_response(
+            false,
+            None,
+            Some("agent down".to_string()),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(describe_server_message(&failed), "Send failed: agent down");
+
+        assert_eq!(describe_server_message("not json"), "WS: not json");
+    }
+}
@@ -1,17 +1,35 @@
-use clanker_core::{ClankerError, Result};
+use clanker_core::{ChannelType, ClankerError, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use tracing::warn;
 
 /// Main configuration structure for Open Clanker
 #[derive(Debug, Deserialize, Serialize)]
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Config {
     pub server: ServerConfig,
     pub channels: ChannelsConfig,
     pub agent: AgentConfig,
+    /// Extra agents sharing direct replies with `[agent]` round-robin (`[[agents]]`),
+    /// e.g. several API keys for the same provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<AgentConfig>,
     #[serde(default)]
     pub orchestration: OrchestrationConfig,
     pub logging: LoggingConfig,
+    /// Static responses answered without calling the agent
+    #[serde(default, skip_serializing_if = "FaqConfig::is_empty")]
+    pub faq: FaqConfig,
+    /// Gateway-side handling of agent responses
+    #[serde(default)]
+    pub processing: ProcessingConfig,
+    /// JSONL audit trail of agent interactions (disabled without a path)
+    #[serde(default, skip_serializing_if = "AuditConfig::is_disabled")]
+    pub audit: AuditConfig,
+    /// Spend caps on estimated agent cost (unlimited without caps)
+    #[serde(default, skip_serializing_if = "BudgetConfig::is_disabled")]
+    pub budget: BudgetConfig,
 }
 
 impl Config {
@@ -45,11 +63,24 @@ impl Config {
             }
         }
 
+        // Load webhook bearer token
+        if let Some(webhook) = &mut self.channels.webhook {
+            if let Ok(token) = std::env::var("OPENCLAW_WEBHOOK_BEARER_TOKEN") {
+                webhook.bearer_token = Some(token);
+            }
+        }
+
         // Load agent API key based on provider
         if let Ok(api_key) = std::env::var(&self.agent.api_key_env) {
             self.agent.api_key = Some(api_key);
         }
 
+        for agent in &mut self.agents {
+            if let Ok(api_key) = std::env::var(&agent.api_key_env) {
+                agent.api_key = Some(api_key);
+            }
+        }
+
         // Load worker API key when orchestration is enabled
         if self.orchestration.enabled {
             let worker = self.agent.worker.get_or_insert_with(WorkerAgentConfig::default);
@@ -65,7 +96,17 @@ impl Config {
             }
         }
 
+        for model_override in &mut self.agent.model_overrides {
+            if let Some(api_key) = model_override.api_key_env.as_ref().and_then(|env| std::env::var(env).ok()) {
+                model_override.api_key = Some(api_key);
+            }
+        }
+
         // Override server config from environment
+        if let Ok(token) = std::env::var("OPENCLAW_ADMIN_TOKEN") {
+            self.server.admin_token = Some(token).filter(|t| !t.is_empty());
+        }
+
         if let Ok(host) = std::env::var("OPENCLAW_HOST") {
             self.server.host = host;
         }
@@ -82,13 +123,40 @@ impl Config {
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
         // Validate server config
-        if self.server.port == 0 || self.server.port > 65535 {
+        if self.server.port == 0 {
             return Err(ClankerError::Config(format!(
                 "Invalid port: {}. Must be between 1 and 65535",
                 self.server.port
             )));
         }
 
+        for addr in &self.server.listen {
+            if addr.parse::<std::net::SocketAddr>().is_err() {
+                return Err(ClankerError::Config(format!(
+                    "Invalid server.listen address: {}. Expected ip:port (e.g. 127.0.0.1:18790 or [::]:18789)",
+                    addr
+                )));
+            }
+        }
+
+        if self.server.max_connections == 0 {
+            return Err(ClankerError::Config(
+                "Server max_connections must be at least 1".to_string(),
+            ));
+        }
+
+        if self.server.max_subscriptions_per_connection == 0 {
+            return Err(ClankerError::Config(
+                "Server max_subscriptions_per_connection must be at least 1".to_string(),
+            ));
+        }
+
+        if self.server.broadcast_capacity == 0 {
+            return Err(ClankerError::Config(
+                "Server broadcast_capacity must be at least 1".to_string(),
+            ));
+        }
+
         // Validate channel configurations (channels optional for WebSocket-only mode)
         if let Some(telegram) = &self.channels.telegram {
             if telegram.bot_token.is_empty() {
@@ -96,6 +164,11 @@ impl Config {
                     "Telegram bot token cannot be empty".to_string(),
                 ));
             }
+            if telegram.send_attempts == 0 {
+                return Err(ClankerError::Config(
+                    "channels.telegram.send_attempts must be at least 1".to_string(),
+                ));
+            }
         }
 
         if let Some(discord) = &self.channels.discord {
@@ -104,6 +177,25 @@ impl Config {
                     "Discord bot token cannot be empty".to_string(),
                 ));
             }
+            if discord.command_prefix.as_deref().is_some_and(|prefix| prefix.trim().is_empty()) {
+                return Err(ClankerError::Config(
+                    "channels.discord.command_prefix cannot be empty".to_string(),
+                ));
+            }
+            if discord.shard_count == Some(0) {
+                return Err(ClankerError::Config(
+                    "channels.discord.shard_count must be at least 1".to_string(),
+                ));
+            }
+        }
+
+        if let Some(webhook) = &self.channels.webhook {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(ClankerError::Config(format!(
+                    "Webhook url must start with http:// or https://, got '{}'",
+                    webhook.url
+                )));
+            }
         }
 
         // Validate agent configuration
@@ -121,8 +213,53 @@ impl Config {
             ));
         }
 
-        // Validate that API key is set
-        if self.agent.api_key.is_none() || self.agent.api_key.as_ref().unwrap().is_empty() {
+        validate_max_tokens("agent", &self.agent.provider, self.agent.max_tokens)?;
+
+        if self.agent.json_mode && !supports_json_mode(&self.agent.provider) {
+            return Err(ClankerError::Config(format!(
+                "agent.json_mode is not supported by provider '{}' (use openai, groq or zai)",
+                self.agent.provider
+            )));
+        }
+
+        validate_extra_headers("agent", &self.agent.extra_headers)?;
+        validate_azure("agent", &self.agent)?;
+        validate_reasoning_budget("agent", &self.agent)?;
+        validate_stop_sequences("agent", &self.agent)?;
+        validate_model_overrides(&self.agent, &valid_providers, self.server.dry_run)?;
+
+        if self.agent.request_timeout_secs == Some(0) {
+            return Err(ClankerError::Config(
+                "Agent request_timeout_secs must be at least 1".to_string(),
+            ));
+        }
+
+        for (channel, prompt) in &self.agent.system_prompts {
+            if ChannelType::from_str(channel).is_none() {
+                return Err(ClankerError::Config(format!(
+                    "agent.system_prompts: unknown channel type '{}'",
+                    channel
+                )));
+            }
+            if prompt.trim().is_empty() {
+                return Err(ClankerError::Config(format!(
+                    "agent.system_prompts.{} cannot be empty",
+                    channel
+                )));
+            }
+        }
+
+        if !model_matches_provider(&self.agent.provider, &self.agent.model) {
+            warn!(
+                "Agent model '{}' does not look like a known {} model",
+                self.agent.model, self.agent.provider
+            );
+        }
+
+        // Validate that API key is set (dry-run never calls a provider)
+        if !self.server.dry_run
+            && (self.agent.api_key.is_none() || self.agent.api_key.as_ref().unwrap().is_empty())
+        {
             return Err(ClankerError::Config(
                 format!(
                     "Agent API key must be set via environment variable: {}",
@@ -131,6 +268,36 @@ impl Config {
                 ));
         }
 
+        for (index, agent) in self.agents.iter().enumerate() {
+            let section = format!("agents[{}]", index);
+            if !valid_providers.contains(&agent.provider.as_str()) {
+                return Err(ClankerError::Config(format!(
+                    "Invalid {} provider: {}. Must be one of: {:?}",
+                    section, agent.provider, valid_providers
+                )));
+            }
+            if agent.model.is_empty() {
+                return Err(ClankerError::Config(format!("{} model cannot be empty", section)));
+            }
+            validate_max_tokens(&section, &agent.provider, agent.max_tokens)?;
+            if agent.json_mode && !supports_json_mode(&agent.provider) {
+                return Err(ClankerError::Config(format!(
+                    "{}.json_mode is not supported by provider '{}'",
+                    section, agent.provider
+                )));
+            }
+            validate_extra_headers(&section, &agent.extra_headers)?;
+            validate_azure(&section, agent)?;
+            validate_reasoning_budget(&section, agent)?;
+            validate_stop_sequences(&section, agent)?;
+            if !self.server.dry_run && agent.api_key.as_deref().unwrap_or_default().is_empty() {
+                return Err(ClankerError::Config(format!(
+                    "{} API key must be set via environment variable: {}",
+                    section, agent.api_key_env
+                )));
+            }
+        }
+
         // Validate orchestration config
         if self.orchestration.max_workers == 0 || self.orchestration.max_workers > 5 {
             return Err(ClankerError::Config(
@@ -138,6 +305,14 @@ impl Config {
             ));
         }
 
+        if let Some(prompt) = &self.orchestration.master_prompt {
+            if prompt.trim().is_empty() {
+                return Err(ClankerError::Config(
+                    "orchestration.master_prompt cannot be empty".to_string(),
+                ));
+            }
+        }
+
         // When orchestration enabled with explicit worker config, validate worker model
         if self.orchestration.enabled {
             if let Some(worker) = &self.agent.worker {
@@ -146,8 +321,61 @@ impl Config {
                         "Worker agent model cannot be empty".to_string(),
                     ));
                 }
+
+                validate_max_tokens("worker", "groq", worker.max_tokens)?;
+
+                if worker.worker_timeout_secs == 0 {
+                    return Err(ClankerError::Config(
+                        "Worker worker_timeout_secs must be at least 1".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if self.processing.cache_enabled
+            && (self.processing.cache_capacity == 0 || self.processing.cache_ttl_secs == 0)
+        {
+            return Err(ClankerError::Config(
+                "processing.cache_capacity and processing.cache_ttl_secs must be at least 1 when caching is enabled".to_string(),
+            ));
+        }
+
+        if self.audit.path.as_deref().is_some_and(|path| path.trim().is_empty()) {
+            return Err(ClankerError::Config("audit.path cannot be empty".to_string()));
+        }
+
+        for (key, cap) in [("daily_usd", self.budget.daily_usd), ("monthly_usd", self.budget.monthly_usd)] {
+            if cap.is_some_and(|cap| !cap.is_finite() || cap <= 0.0) {
+                return Err(ClankerError::Config(format!("budget.{} must be a positive amount", key)));
             }
         }
+        if self.budget.state_file.as_deref().is_some_and(|path| path.trim().is_empty()) {
+            return Err(ClankerError::Config("budget.state_file cannot be empty".to_string()));
+        }
+
+        if self.processing.max_inbound_chars == Some(0) {
+            return Err(ClankerError::Config(
+                "processing.max_inbound_chars must be at least 1".to_string(),
+            ));
+        }
+
+        if self.processing.max_concurrent_requests == 0 {
+            return Err(ClankerError::Config(
+                "processing.max_concurrent_requests must be at least 1".to_string(),
+            ));
+        }
+
+        if self.processing.degraded_after_failures == 0 {
+            return Err(ClankerError::Config(
+                "processing.degraded_after_failures must be at least 1".to_string(),
+            ));
+        }
+
+        if self.processing.history_turns > 0 && self.processing.history_max_chats == 0 {
+            return Err(ClankerError::Config(
+                "processing.history_max_chats must be at least 1 when history is enabled".to_string(),
+            ));
+        }
 
         // Validate logging config
         let valid_log_levels = ["error", "warn", "info", "debug", "trace"];
@@ -166,6 +394,19 @@ impl Config {
             )));
         }
 
+        if let Some(file) = &self.logging.file {
+            if file.trim().is_empty() {
+                return Err(ClankerError::Config(
+                    "logging.file cannot be empty".to_string(),
+                ));
+            }
+            if self.logging.rotation == LogRotation::Size && self.logging.max_file_size_mb == 0 {
+                return Err(ClankerError::Config(
+                    "logging.max_file_size_mb must be at least 1 for size rotation".to_string(),
+                ));
+            }
+        }
+
         Ok(())
     }
 
@@ -183,12 +424,247 @@ impl Config {
     }
 }
 
+/// Maximum `max_tokens` accepted for a provider's completions
+pub fn max_tokens_ceiling(provider: &str) -> u32 {
+    match provider.to_lowercase().as_str() {
+        "anthropic" => 64_000,
+        _ => 8192,
+    }
+}
+
+/// Ensure `max_tokens` is within 1..=provider ceiling
+fn validate_max_tokens(section: &str, provider: &str, max_tokens: u32) -> Result<()> {
+    let ceiling = max_tokens_ceiling(provider);
+    if max_tokens == 0 || max_tokens > ceiling {
+        return Err(ClankerError::Config(format!(
+            "Invalid {} max_tokens: {}. Must be between 1 and {} for provider {}",
+            section, max_tokens, ceiling, provider
+        )));
+    }
+    Ok(())
+}
+
+/// Check that extra request headers have HTTP token names and single-line values
+fn validate_extra_headers(section: &str, headers: &HashMap<String, String>) -> Result<()> {
+    for (name, value) in headers {
+        let valid_name = !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+        if !valid_name {
+            return Err(ClankerError::Config(format!(
+                "{}.extra_headers: invalid header name '{}'",
+                section, name
+            )));
+        }
+        if value.bytes().any(|b| b == b'\r' || b == b'\n' || b == 0) {
+            return Err(ClankerError::Config(format!(
+                "{}.extra_headers.{}: value must be a single line",
+                section, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Smallest extended-thinking budget Anthropic accepts
+const MIN_REASONING_BUDGET_TOKENS: u32 = 1024;
+
+/// Check that a thinking budget targets Anthropic and fits the API's bounds
+fn validate_reasoning_budget(section: &str, agent: &AgentConfig) -> Result<()> {
+    let Some(budget) = agent.reasoning_budget_tokens else {
+        return Ok(());
+    };
+    if !agent.provider.eq_ignore_ascii_case("anthropic") {
+        return Err(ClankerError::Config(format!(
+            "{}.reasoning_budget_tokens is only supported by provider 'anthropic'",
+            section
+        )));
+    }
+    if budget < MIN_REASONING_BUDGET_TOKENS || budget >= agent.max_tokens {
+        return Err(ClankerError::Config(format!(
+            "Invalid {}.reasoning_budget_tokens: {}. Must be at least {} and below max_tokens ({})",
+            section, budget, MIN_REASONING_BUDGET_TOKENS, agent.max_tokens
+        )));
+    }
+    Ok(())
+}
+
+/// Most stop sequences providers accept per request
+const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Check the stop sequences fit the common provider limit and none is empty
+fn validate_stop_sequences(section: &str, agent: &AgentConfig) -> Result<()> {
+    if agent.stop_sequences.len() > MAX_STOP_SEQUENCES {
+        return Err(ClankerError::Config(format!(
+            "{}.stop_sequences allows at most {} entries, got {}",
+            section,
+            MAX_STOP_SEQUENCES,
+            agent.stop_sequences.len()
+        )));
+    }
+    if agent.stop_sequences.iter().any(String::is_empty) {
+        return Err(ClankerError::Config(format!(
+            "{}.stop_sequences cannot contain an empty string",
+            section
+        )));
+    }
+    Ok(())
+}
+
+/// Check each `agent.model_overrides` entry names a provider, a model and a usable API key
+/// (keys are not checked in dry-run mode)
+fn validate_model_overrides(agent: &AgentConfig, valid_providers: &[&str], dry_run: bool) -> Result<()> {
+    for (index, model_override) in agent.model_overrides.iter().enumerate() {
+        let section = format!("agent.model_overrides[{}]", index);
+        if !valid_providers.contains(&model_override.provider.as_str()) {
+            return Err(ClankerError::Config(format!(
+                "Invalid {} provider: {}. Must be one of: {:?}",
+                section, model_override.provider, valid_providers
+            )));
+        }
+        if model_override.model.is_empty() {
+            return Err(ClankerError::Config(format!("{} model cannot be empty", section)));
+        }
+        if dry_run {
+            continue;
+        }
+        match &model_override.api_key_env {
+            Some(env) if model_override.api_key.as_deref().unwrap_or_default().is_empty() => {
+                return Err(ClankerError::Config(format!(
+                    "{} API key must be set via environment variable: {}",
+                    section, env
+                )));
+            }
+            None if model_override.provider != agent.provider => {
+                return Err(ClankerError::Config(format!(
+                    "{}.api_key_env is required for provider '{}' (the agent's key is only reused for '{}')",
+                    section, model_override.provider, agent.provider
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check that Azure mode is used with the OpenAI provider and a resource endpoint
+fn validate_azure(section: &str, agent: &AgentConfig) -> Result<()> {
+    let Some(azure) = &agent.azure else {
+        return Ok(());
+    };
+    if !agent.provider.eq_ignore_ascii_case("openai") {
+        return Err(ClankerError::Config(format!(
+            "{}.azure requires provider 'openai', not '{}'",
+            section, agent.provider
+        )));
+    }
+    if agent.api_base_url.as_deref().is_none_or(|url| url.trim().is_empty()) {
+        return Err(ClankerError::Config(format!(
+            "{}.azure requires api_base_url (e.g. https://my-resource.openai.azure.com)",
+            section
+        )));
+    }
+    if azure.deployment.trim().is_empty() || azure.api_version.trim().is_empty() {
+        return Err(ClankerError::Config(format!(
+            "{}.azure deployment and api_version cannot be empty",
+            section
+        )));
+    }
+    Ok(())
+}
+
+/// Check if a model name looks like it belongs to the given provider
+fn model_matches_provider(provider: &str, model: &str) -> bool {
+    let model = model.to_lowercase();
+    let prefixes: &[&str] = match provider.to_lowercase().as_str() {
+        "anthropic" => &["claude"],
+        "openai" => &["gpt", "o1", "o3", "o4", "chatgpt"],
+        "grok" => &["grok"],
+        "groq" => &["llama", "meta-llama", "mixtral", "gemma", "qwen", "deepseek", "openai/", "moonshotai/"],
+        "zai" => &["glm"],
+        _ => return true,
+    };
+    prefixes.iter().any(|p| model.starts_with(p))
+}
+
 /// Server configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Addresses to bind (`ip:port`), e.g. a loopback admin port plus a public one.
+    /// When empty the server binds `host:port` only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listen: Vec<String>,
     pub tls: Option<TlsConfig>,
+    /// Maximum concurrent WebSocket connections
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+    /// Maximum channel subscriptions per WebSocket connection
+    #[serde(default = "default_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: usize,
+    /// Consecutive malformed frames a WebSocket client is answered with an
+    /// error for; the next one closes the connection
+    #[serde(default = "default_max_invalid_messages")]
+    pub max_invalid_messages: u32,
+    /// What to do when a WebSocket client falls behind the broadcast channel
+    #[serde(default)]
+    pub broadcast_lag_policy: LagPolicy,
+    /// Broadcast messages buffered for the slowest WebSocket client before it lags
+    #[serde(default = "default_broadcast_capacity")]
+    pub broadcast_capacity: usize,
+    /// Broadcast messages larger than this many bytes (text plus attachment URLs)
+    /// are sent as a summary without attachment URLs and with the text cut to this
+    /// size; unset broadcasts them whole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_summary_bytes: Option<usize>,
+    /// Bearer token for admin endpoints such as `/connections` and `/channels` (env: OPENCLAW_ADMIN_TOKEN).
+    /// Admin endpoints are disabled when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<String>,
+    /// Milliseconds a `/health` response is reused before it is recomputed (0 disables caching)
+    #[serde(default = "default_health_cache_ms")]
+    pub health_cache_ms: u64,
+    /// Answer with the placeholder agent (echoing input) instead of the configured
+    /// providers; channels, WebSocket and broadcasting stay real
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Open connections to the configured providers at boot, so the first
+    /// message doesn't pay for DNS and the TLS handshake
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warmup_on_start: bool,
+}
+
+/// Per-client behavior when a WebSocket consumer lags the broadcast channel
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LagPolicy {
+    /// Drop the missed messages and continue from the latest
+    SkipToLatest,
+    /// Close the client's connection
+    #[default]
+    Disconnect,
+}
+
+fn default_max_connections() -> usize {
+    1024
+}
+
+fn default_max_subscriptions_per_connection() -> usize {
+    64
+}
+
+fn default_max_invalid_messages() -> u32 {
+    10
+}
+
+fn default_broadcast_capacity() -> usize {
+    1000
+}
+
+fn default_health_cache_ms() -> u64 {
+    500
 }
 
 impl Default for ServerConfig {
@@ -196,7 +672,18 @@ impl Default for ServerConfig {
         Self {
             host: "0.0.0.0".to_string(),
             port: 18789,
+            listen: Vec::new(),
             tls: None,
+            max_connections: default_max_connections(),
+            max_subscriptions_per_connection: default_max_subscriptions_per_connection(),
+            max_invalid_messages: default_max_invalid_messages(),
+            broadcast_lag_policy: LagPolicy::default(),
+            broadcast_capacity: default_broadcast_capacity(),
+            broadcast_summary_bytes: None,
+            admin_token: None,
+            health_cache_ms: default_health_cache_ms(),
+            dry_run: false,
+            warmup_on_start: false,
         }
     }
 }
@@ -213,6 +700,8 @@ pub struct TlsConfig {
 pub struct ChannelsConfig {
     pub telegram: Option<TelegramConfig>,
     pub discord: Option<DiscordConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<WebhookConfig>,
 }
 
 impl Default for ChannelsConfig {
@@ -220,6 +709,7 @@ impl Default for ChannelsConfig {
         Self {
             telegram: Some(TelegramConfig::default()),
             discord: Some(DiscordConfig::default()),
+            webhook: None,
         }
     }
 }
@@ -228,7 +718,18 @@ impl Default for ChannelsConfig {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TelegramConfig {
     pub bot_token: String,
+    /// Chat ids the bot answers; messages from other chats are ignored (all when unset)
     pub allowed_chats: Option<Vec<String>>,
+    /// Attempts per outgoing message; network errors and flood control are retried with backoff
+    #[serde(default = "default_send_attempts")]
+    pub send_attempts: u32,
+    /// Send replies as a placeholder and edit it as the agent's answer streams in
+    #[serde(default)]
+    pub stream_to_channel: bool,
+}
+
+fn default_send_attempts() -> u32 {
+    3
 }
 
 impl Default for TelegramConfig {
@@ -236,15 +737,38 @@ impl Default for TelegramConfig {
         Self {
             bot_token: "your-telegram-bot-token".to_string(),
             allowed_chats: None,
+            send_attempts: default_send_attempts(),
+            stream_to_channel: false,
         }
     }
 }
 
+/// Outbound HTTP webhook configuration
+///
+/// Replies are POSTed as JSON-encoded `Message`s to `url`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Sent as `Authorization: Bearer <token>` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<String>,
+}
+
 /// Discord bot configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DiscordConfig {
     pub bot_token: String,
     pub guild_id: Option<String>,
+    /// Only answer messages starting with this prefix (e.g. `!ask`), which is
+    /// stripped before the message reaches the agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_prefix: Option<String>,
+    /// Answer messages that mention the bot (the mention is stripped)
+    #[serde(default)]
+    pub respond_on_mention: bool,
+    /// Number of gateway shards; unset uses the count Discord recommends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard_count: Option<u32>,
 }
 
 impl Default for DiscordConfig {
@@ -252,6 +776,9 @@ impl Default for DiscordConfig {
         Self {
             bot_token: "your-discord-bot-token".to_string(),
             guild_id: None,
+            command_prefix: None,
+            respond_on_mention: false,
+            shard_count: None,
         }
     }
 }
@@ -283,17 +810,79 @@ pub struct AgentConfig {
     pub provider: String,
     pub model: String,
     pub api_key_env: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,  // Loaded from environment, not saved to file
     pub max_tokens: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub api_base_url: Option<String>,  // Optional: Custom API endpoint
     /// Worker agent config (Groq) for orchestration - optional, uses defaults if absent
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub worker: Option<WorkerAgentConfig>,
     /// Fallback agent (e.g. Z.ai) when primary (e.g. Claude) fails
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fallback: Option<FallbackAgentConfig>,
+    /// HTTP request timeout in seconds (provider default when unset). Streams are
+    /// not cut off by it; it bounds the wait for the response and for each chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// System prompt overrides keyed by channel type (`telegram`, `discord`, ...);
+    /// channels without an entry use the built-in prompt for their type
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub system_prompts: HashMap<String, String>,
+    /// Mark the system prompt as cacheable (Anthropic prompt caching)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub enable_prompt_cache: bool,
+    /// Ask for JSON object responses on every request (OpenAI, Groq and Z.ai only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub json_mode: bool,
+    /// Extra HTTP headers sent with every provider request (e.g. `OpenAI-Organization`,
+    /// proxy auth); they replace built-in headers of the same name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_headers: HashMap<String, String>,
+    /// Extended thinking budget for Claude models that support it (Anthropic only;
+    /// at least 1024 and below `max_tokens`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_budget_tokens: Option<u32>,
+    /// Markers at which the model stops generating (at most 4);
+    /// sent as `stop` to OpenAI-compatible APIs and `stop_sequences` to Anthropic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// Send OpenAI requests to an Azure OpenAI deployment (`api_base_url` is the resource endpoint)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure: Option<AzureOpenAIConfig>,
+    /// Models WebSocket clients may pick for a single `send_message` (`provider` / `model`);
+    /// requests naming any other model are rejected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub model_overrides: Vec<ModelOverrideConfig>,
+}
+
+/// Model allowed as a per-request override of the agent. An override of another
+/// provider uses that provider's defaults: the agent's `api_base_url` and
+/// `extra_headers` do not apply to it.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ModelOverrideConfig {
+    pub provider: String,
+    pub model: String,
+    /// Environment variable holding the provider's API key; when unset the
+    /// agent's own key is used (same provider only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,  // Loaded from environment, not saved to file
+}
+
+/// Azure OpenAI deployment addressed by an `openai` agent
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct AzureOpenAIConfig {
+    /// Deployment name (the model is chosen by the deployment)
+    pub deployment: String,
+    /// `api-version` query parameter
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+}
+
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
 }
 
 /// Worker agent configuration (Groq-only, used by Master_Clanker for subagents)
@@ -305,12 +894,24 @@ pub struct WorkerAgentConfig {
     pub api_key: Option<String>,  // Loaded from environment, not saved to file
     #[serde(default = "default_worker_max_tokens")]
     pub max_tokens: u32,
+    /// Seconds a single worker call may take before it is abandoned
+    #[serde(default = "default_worker_timeout_secs")]
+    pub worker_timeout_secs: u64,
+}
+
+/// Providers accepting OpenAI-style `response_format` JSON mode
+pub fn supports_json_mode(provider: &str) -> bool {
+    matches!(provider.to_lowercase().as_str(), "openai" | "groq" | "zai")
 }
 
 fn default_worker_max_tokens() -> u32 {
     2048
 }
 
+fn default_worker_timeout_secs() -> u64 {
+    60
+}
+
 impl Default for WorkerAgentConfig {
     fn default() -> Self {
         Self {
@@ -318,6 +919,7 @@ impl Default for WorkerAgentConfig {
             api_key_env: "OPENCLAW_GROQ_API_KEY".to_string(),
             api_key: None,
             max_tokens: 2048,
+            worker_timeout_secs: default_worker_timeout_secs(),
         }
     }
 }
@@ -329,6 +931,20 @@ pub struct OrchestrationConfig {
     pub enabled: bool,
     #[serde(default = "default_max_workers")]
     pub max_workers: usize,
+    /// Custom Master_Clanker system prompt (defaults to the built-in delegation prompt)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_prompt: Option<String>,
+    /// Broadcast worker started/completed events to WebSocket clients
+    #[serde(default)]
+    pub progress_events: bool,
+    /// Rounds of delegation per message; past it Master_Clanker must answer
+    /// from the results it has (0 never spawns workers)
+    #[serde(default = "default_max_delegation_depth")]
+    pub max_delegation_depth: usize,
+}
+
+fn default_max_delegation_depth() -> usize {
+    1
 }
 
 fn default_orchestration_enabled() -> bool {
@@ -344,6 +960,9 @@ impl Default for OrchestrationConfig {
         Self {
             enabled: true,
             max_workers: 5,
+            master_prompt: None,
+            progress_events: false,
+            max_delegation_depth: default_max_delegation_depth(),
         }
     }
 }
@@ -359,6 +978,15 @@ impl Default for AgentConfig {
             api_base_url: None,
             worker: None,
             fallback: None,
+            request_timeout_secs: None,
+            system_prompts: HashMap::new(),
+            enable_prompt_cache: false,
+            json_mode: false,
+            extra_headers: HashMap::new(),
+            reasoning_budget_tokens: None,
+            stop_sequences: Vec::new(),
+            azure: None,
+            model_overrides: Vec::new(),
         }
     }
 }
@@ -368,6 +996,34 @@ impl Default for AgentConfig {
 pub struct LoggingConfig {
     pub level: String,
     pub format: String,
+    /// Also write logs to this file (in addition to stdout)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// When the log file is rotated
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// Size threshold for `rotation = "size"`, in megabytes
+    #[serde(default = "default_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+}
+
+/// Log file rotation policy
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    /// New file per day (`<file>.YYYY-MM-DD`)
+    #[default]
+    Daily,
+    /// New file per hour (`<file>.YYYY-MM-DD-HH`)
+    Hourly,
+    /// Roll over to `<file>.1` once the file reaches `max_file_size_mb`
+    Size,
+    /// Single file, never rotated
+    Never,
+}
+
+fn default_max_file_size_mb() -> u64 {
+    10
 }
 
 impl Default for LoggingConfig {
@@ -375,23 +1031,447 @@ impl Default for LoggingConfig {
         Self {
             level: "info".to_string(),
             format: "json".to_string(),
+            file: None,
+            rotation: LogRotation::default(),
+            max_file_size_mb: default_max_file_size_mb(),
         }
     }
 }
 
-/// Generate a default configuration
+/// Gateway-side handling of agent responses
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProcessingConfig {
+    /// Message sent instead of a response the provider refused or filtered.
+    /// When unset, the provider's own text is forwarded (or a generic notice if empty).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refusal_message: Option<String>,
+    /// Message sent instead of an empty or whitespace-only agent response
+    #[serde(default = "default_empty_response_message")]
+    pub empty_response_message: String,
+    /// Cache direct agent responses for identical prompts (opt-in)
+    #[serde(default)]
+    pub cache_enabled: bool,
+    /// Seconds a cached response stays valid
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// Maximum cached responses (least recently used are evicted)
+    #[serde(default = "default_cache_capacity")]
+    pub cache_capacity: usize,
+    /// Seconds within which a repeated channel message (same chat, sender and
+    /// text) is treated as a redelivery and skipped; 0 disables deduplication
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
+    /// Maximum agent requests in flight at once; further messages wait for a free slot
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Consecutive primary agent failures after which the gateway reports itself
+    /// degraded (while the fallback agent answers)
+    #[serde(default = "default_degraded_after_failures")]
+    pub degraded_after_failures: u32,
+    /// Earlier exchanges per chat sent with each direct agent request; 0 disables history
+    #[serde(default)]
+    pub history_turns: usize,
+    /// Chats whose history is kept; the least recently active one is forgotten beyond this
+    #[serde(default = "default_history_max_chats")]
+    pub history_max_chats: usize,
+    /// Longest inbound message (in characters) passed to the agent; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_inbound_chars: Option<usize>,
+    /// What to do with inbound messages over `max_inbound_chars`
+    #[serde(default)]
+    pub inbound_limit_mode: InboundLimitMode,
+    /// Cleanup applied to agent responses before they are sent
+    #[serde(default)]
+    pub post_process: PostProcessConfig,
+}
+
+/// Opt-in cleanup of agent responses (all disabled by default)
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct PostProcessConfig {
+    /// Strip leading and trailing whitespace
+    #[serde(default)]
+    pub trim_whitespace: bool,
+    /// Unwrap a response that is entirely one fenced code block
+    #[serde(default)]
+    pub strip_code_fence: bool,
+    /// Collapse runs of blank lines into one
+    #[serde(default)]
+    pub collapse_blank_lines: bool,
+}
+
+/// Handling of inbound messages longer than `processing.max_inbound_chars`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InboundLimitMode {
+    /// Refuse the message (WebSocket/HTTP clients get an error, chat users a short notice)
+    #[default]
+    Reject,
+    /// Cut the message down to the limit and process the rest
+    Truncate,
+}
+
+fn default_empty_response_message() -> String {
+    "I couldn't generate a response.".to_string()
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_cache_capacity() -> usize {
+    256
+}
+
+fn default_dedup_window_secs() -> u64 {
+    10
+}
+
+fn default_max_concurrent_requests() -> usize {
+    32
+}
+
+fn default_degraded_after_failures() -> u32 {
+    3
+}
+
+fn default_history_max_chats() -> usize {
+    1000
+}
+
+impl Default for ProcessingConfig {
+    fn default() -> Self {
+        Self {
+            refusal_message: None,
+            empty_response_message: default_empty_response_message(),
+            cache_enabled: false,
+            cache_ttl_secs: default_cache_ttl_secs(),
+            cache_capacity: default_cache_capacity(),
+            dedup_window_secs: default_dedup_window_secs(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            degraded_after_failures: default_degraded_after_failures(),
+            history_turns: 0,
+            history_max_chats: default_history_max_chats(),
+            max_inbound_chars: None,
+            inbound_limit_mode: InboundLimitMode::default(),
+            post_process: PostProcessConfig::default(),
+        }
+    }
+}
+
+/// Audit log of agent interactions, kept apart from the debug logs
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// JSONL file appended with one line per answered or failed message (unset disables auditing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Also record the message text and the reply (lengths and usage only by default)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_content: bool,
+}
+
+impl AuditConfig {
+    /// Check if no audit file is configured
+    pub fn is_disabled(&self) -> bool {
+        self.path.is_none()
+    }
+}
+
+/// Caps on the estimated cost of agent requests, in US dollars. Windows are
+/// UTC calendar days and months; once a cap is reached, agent requests are
+/// refused until the window resets.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct BudgetConfig {
+    /// Spend allowed per UTC day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_usd: Option<f64>,
+    /// Spend allowed per UTC month
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_usd: Option<f64>,
+    /// JSON file keeping the running totals across restarts (in memory only when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_file: Option<String>,
+}
+
+impl BudgetConfig {
+    /// Check if no spend cap is configured
+    pub fn is_disabled(&self) -> bool {
+        self.daily_usd.is_none() && self.monthly_usd.is_none()
+    }
+}
+
+/// Static FAQ configuration: canned responses for common inputs
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct FaqConfig {
+    /// Inline FAQ entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<FaqEntry>,
+    /// Optional TOML file with additional `[[entries]]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl FaqConfig {
+    /// Check if no FAQ entries or file are configured
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.file.is_none()
+    }
+
+    /// Collect inline entries plus any entries loaded from `file`
+    pub fn load_entries(&self) -> Result<Vec<FaqEntry>> {
+        let mut entries = self.entries.clone();
+
+        if let Some(path) = &self.file {
+            let content = std::fs::read_to_string(path).map_err(|e| {
+                ClankerError::config_file(format!("Failed to read FAQ file {}: {}", path, e))
+            })?;
+            let file: FaqFile = toml::from_str(&content).map_err(|e| {
+                ClankerError::TomlParse(format!("Failed to parse FAQ file {}: {}", path, e))
+            })?;
+            entries.extend(file.entries);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// A single FAQ entry
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FaqEntry {
+    /// Input to match (exact, case-insensitive) or a regex when `regex = true`
+    pub pattern: String,
+    /// Response returned when the pattern matches
+    pub response: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+/// FAQ file layout
+#[derive(Debug, Deserialize)]
+struct FaqFile {
+    #[serde(default)]
+    entries: Vec<FaqEntry>,
+}
+
+/// Header written at the top of generated config files
+const CONFIG_HEADER: &str = "\
+# Open Clanker configuration
+#
+# Secrets are best supplied through the environment instead of this file:
+#   OPENCLAW_TELEGRAM_BOT_TOKEN, OPENCLAW_DISCORD_BOT_TOKEN, OPENCLAW_WEBHOOK_BEARER_TOKEN
+#   and the variable named by agent.api_key_env (e.g. OPENCLAW_ANTHROPIC_API_KEY).
+# OPENCLAW_HOST and OPENCLAW_PORT override [server] host and port; OPENCLAW_ADMIN_TOKEN
+# enables the admin endpoints (e.g. GET /connections).
+#
+# Regenerate with `config-generate --merge` to add new options without touching existing values.
+";
+
+/// Comments placed above sections and keys of the generated config (keyed by dotted path)
+const CONFIG_FIELD_DOCS: &[(&str, &str)] = &[
+    ("server", "Gateway HTTP/WebSocket server"),
+    ("server.host", "Address to bind (env: OPENCLAW_HOST)"),
+    ("server.port", "Port to listen on (env: OPENCLAW_PORT)"),
+    ("server.listen", "Bind these ip:port addresses instead of host:port"),
+    ("server.max_connections", "Maximum concurrent WebSocket connections"),
+    ("server.max_subscriptions_per_connection", "Maximum channel subscriptions per WebSocket connection"),
+    (
+        "server.max_invalid_messages",
+        "Malformed WebSocket frames in a row before the connection is closed",
+    ),
+    (
+        "server.broadcast_lag_policy",
+        "When a WebSocket client falls behind: \"disconnect\" or \"skip_to_latest\"",
+    ),
+    ("server.broadcast_capacity", "Broadcast messages buffered before a slow WebSocket client lags"),
+    (
+        "server.broadcast_summary_bytes",
+        "Broadcast larger messages without attachment URLs, text cut to size (marked truncated)",
+    ),
+    ("server.health_cache_ms", "Reuse a /health response for this many milliseconds (0 disables)"),
+    ("server.dry_run", "Echo messages with the placeholder agent instead of calling providers"),
+    ("server.warmup_on_start", "Open connections to the providers at boot to speed up the first message"),
+    ("channels.telegram", "Telegram bot (remove this section to disable)"),
+    ("channels.telegram.bot_token", "Bot token from @BotFather (env: OPENCLAW_TELEGRAM_BOT_TOKEN)"),
+    ("channels.telegram.allowed_chats", "Only answer these chat ids (unset answers every chat; applied on reload)"),
+    ("channels.telegram.send_attempts", "Attempts per reply when sending fails transiently"),
+    (
+        "channels.telegram.stream_to_channel",
+        "Type replies out by editing a placeholder as the answer streams (at most one edit per second)",
+    ),
+    ("channels.discord", "Discord bot (remove this section to disable)"),
+    ("channels.discord.bot_token", "Bot token from the developer portal (env: OPENCLAW_DISCORD_BOT_TOKEN)"),
+    (
+        "channels.discord.command_prefix",
+        "Only answer messages starting with this word (e.g. \"!ask\"); it is stripped before the agent sees the message",
+    ),
+    (
+        "channels.discord.respond_on_mention",
+        "Answer messages mentioning the bot; with command_prefix, other messages are ignored",
+    ),
+    (
+        "channels.discord.shard_count",
+        "Gateway shards to run; leave unset to use the count Discord recommends",
+    ),
+    ("agent", "AI agent answering channel messages"),
+    ("agent.provider", "One of: anthropic, openai, grok, groq, zai"),
+    ("agent.model", "Model name for the provider (e.g. claude-sonnet-4-20250514, gpt-4o, llama-3.3-70b-versatile)"),
+    (
+        "agent.api_key_env",
+        "Environment variable holding the API key, by provider:\n  \
+         OPENCLAW_ANTHROPIC_API_KEY, OPENCLAW_OPENAI_API_KEY, OPENCLAW_GROK_API_KEY,\n  \
+         OPENCLAW_GROQ_API_KEY, OPENCLAW_ZAI_API_KEY",
+    ),
+    ("agent.max_tokens", "Maximum tokens per response"),
+    ("agent.api_base_url", "Custom API endpoint (OpenAI-compatible proxy, Azure resource endpoint)"),
+    ("agent.azure", "Azure OpenAI deployment for the openai provider (deployment, api_version)"),
+    ("agent.reasoning_budget_tokens", "Extended thinking budget for Claude (>= 1024, below max_tokens)"),
+    ("agent.stop_sequences", "Up to 4 markers at which the model stops generating"),
+    ("agent.extra_headers", "Extra HTTP headers for every provider request (proxies, organization ids)"),
+    (
+        "agent.model_overrides",
+        "Models clients may pick per send_message; other providers' entries ignore api_base_url and extra_headers",
+    ),
+    (
+        "agent.model_overrides",
+        "Models WebSocket clients may pick per send_message (provider, model, api_key_env)",
+    ),
+    ("orchestration", "Master_Clanker / Worker_Clanker delegation"),
+    ("orchestration.enabled", "Let the master agent delegate tasks to workers ([agent.worker] configures them)"),
+    ("orchestration.max_workers", "Maximum workers running in parallel"),
+    ("orchestration.progress_events", "Broadcast worker started/completed events to WebSocket clients"),
+    (
+        "orchestration.max_delegation_depth",
+        "Rounds of delegation per message before the master must answer directly",
+    ),
+    ("logging", "Gateway logging"),
+    ("logging.level", "One of: error, warn, info, debug, trace"),
+    ("logging.format", "\"json\" or \"pretty\""),
+    ("logging.rotation", "Log file rotation when logging.file is set: daily, hourly, size or never"),
+    ("logging.max_file_size_mb", "File size that triggers rotation when rotation = \"size\""),
+    ("audit", "Audit trail of agent interactions (remove this section to disable)"),
+    ("audit.path", "JSONL file receiving one line per answered or failed message"),
+    ("audit.include_content", "Also record message and reply text (only lengths by default)"),
+    ("budget", "Spend caps on estimated agent cost (remove this section for no limit)"),
+    ("budget.daily_usd", "US dollars allowed per UTC day; agent requests are refused once reached"),
+    ("budget.monthly_usd", "US dollars allowed per UTC month; agent requests are refused once reached"),
+    (
+        "budget.state_file",
+        "JSON file keeping the running totals across restarts (the gateway won't start if it is unreadable)",
+    ),
+    ("processing", "Gateway-side handling of agent responses"),
+    ("processing.empty_response_message", "Sent instead of an empty agent response"),
+    ("processing.cache_enabled", "Cache direct agent responses for identical prompts"),
+    ("processing.cache_ttl_secs", "Seconds a cached response stays valid"),
+    ("processing.cache_capacity", "Maximum cached responses"),
+    ("processing.dedup_window_secs", "Ignore repeated messages within this many seconds (0 disables)"),
+    ("processing.max_concurrent_requests", "Maximum agent requests in flight at once"),
+    (
+        "processing.degraded_after_failures",
+        "Consecutive primary failures before health reports degraded",
+    ),
+    (
+        "processing.history_turns",
+        "Earlier exchanges per chat sent with each direct request (0 disables; /reset clears)",
+    ),
+    (
+        "processing.history_max_chats",
+        "Chats whose history is kept; the least recently active is forgotten first",
+    ),
+    (
+        "processing.inbound_limit_mode",
+        "Messages over max_inbound_chars: \"reject\" or \"truncate\"",
+    ),
+];
+
+/// Generate a default configuration with comments documenting each field
 pub fn generate_default_config() -> String {
-    let config = Config {
-        server: ServerConfig::default(),
-        channels: ChannelsConfig::default(),
-        agent: AgentConfig::default(),
-        orchestration: OrchestrationConfig::default(),
-        logging: LoggingConfig::default(),
-    };
+    match default_config_document() {
+        Ok(doc) => format!("{}{}", CONFIG_HEADER, doc),
+        Err(e) => format!("Error generating config: {}", e),
+    }
+}
+
+/// Add keys and sections introduced since an existing config file was written.
+///
+/// Values and comments already in `existing` are kept as-is; only missing keys
+/// (with their documentation comments) are appended. Optional nested sections
+/// such as `[channels.discord]` are not added to files that omit them. Returns
+/// the merged file and the dotted paths of the added entries.
+pub fn merge_default_config(existing: &str) -> Result<(String, Vec<String>)> {
+    let mut doc: toml_edit::DocumentMut = existing.parse().map_err(|e| {
+        ClankerError::TomlParse(format!("Failed to parse TOML: {}", e))
+    })?;
+    let defaults = default_config_document()?;
+
+    let mut added = Vec::new();
+    merge_table(doc.as_table_mut(), defaults.as_table(), "", &mut added);
+    Ok((doc.to_string(), added))
+}
+
+/// Default config as an editable document with field comments attached
+fn default_config_document() -> Result<toml_edit::DocumentMut> {
+    let rendered = toml::to_string_pretty(&Config::default()).map_err(|e| {
+        ClankerError::Config(format!("Failed to serialize config: {}", e))
+    })?;
+    let mut doc: toml_edit::DocumentMut = rendered.parse().map_err(|e| {
+        ClankerError::TomlParse(format!("Failed to parse TOML: {}", e))
+    })?;
+    annotate_table(doc.as_table_mut(), "");
+    Ok(doc)
+}
+
+fn annotate_table(table: &mut toml_edit::Table, path: &str) {
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    for key in keys {
+        let full = join_path(path, &key);
+        let doc = CONFIG_FIELD_DOCS.iter().find(|(p, _)| *p == full).map(|(_, d)| *d);
+        match table.get_mut(&key) {
+            Some(toml_edit::Item::Table(child)) => {
+                if let Some(doc) = doc {
+                    child.decor_mut().set_prefix(format!("\n{}", comment_lines(doc)));
+                }
+                annotate_table(child, &full);
+            }
+            Some(_) => {
+                if let (Some(doc), Some(mut k)) = (doc, table.key_mut(&key)) {
+                    k.leaf_decor_mut().set_prefix(comment_lines(doc));
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+fn merge_table(target: &mut toml_edit::Table, defaults: &toml_edit::Table, path: &str, added: &mut Vec<String>) {
+    for (key, item) in defaults.iter() {
+        let full = join_path(path, key);
+        match target.get_mut(key) {
+            Some(existing) => {
+                if let (Some(existing), Some(default)) = (existing.as_table_mut(), item.as_table()) {
+                    merge_table(existing, default, &full, added);
+                }
+            }
+            // Nested sections are optional (channels, worker agents); only fill in top-level ones
+            None if item.is_table() && !path.is_empty() => {}
+            None => {
+                if let Some((default_key, _)) = defaults.get_key_value(key) {
+                    target.insert_formatted(default_key, item.clone());
+                    added.push(full);
+                }
+            }
+        }
+    }
+}
 
-    toml::to_string_pretty(&config).unwrap_or_else(|e| {
-        format!("Error generating config: {}", e)
-    })
+fn comment_lines(doc: &str) -> String {
+    doc.lines().map(|line| format!("# {}\n", line)).collect()
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
 }
 
 #[cfg(test)]
@@ -415,6 +1495,68 @@ mod tests {
         assert!(config_str.contains("model = \"claude-sonnet-4-20250514\""));
     }
 
+    #[test]
+    fn test_generated_config_is_commented_and_parses() {
+        let config_str = generate_default_config();
+
+        assert!(config_str.starts_with("# Open Clanker configuration"));
+        assert!(config_str.contains("# One of: anthropic, openai, grok, groq, zai\nprovider = \"anthropic\""));
+        assert!(config_str.contains("OPENCLAW_TELEGRAM_BOT_TOKEN"));
+
+        let mut config: Config = toml::from_str(&config_str).unwrap();
+        config.agent.api_key = Some("test-key".to_string());
+        assert!(config.validate().is_ok());
+        assert_eq!(config.server.port, 18789);
+        assert_eq!(config.processing.dedup_window_secs, 10);
+        assert_eq!(config.processing.degraded_after_failures, 3);
+        assert_eq!(config.processing.history_max_chats, 1000);
+    }
+
+    #[test]
+    fn test_merge_default_config_keeps_user_values() {
+        let existing = r#"# my gateway
+[server]
+host = "127.0.0.1" # local only
+port = 9000
+
+[channels.telegram]
+bot_token = "real-token"
+
+[agent]
+provider = "groq"
+model = "llama-3.3-70b-versatile"
+api_key_env = "OPENCLAW_GROQ_API_KEY"
+max_tokens = 2048
+
+[logging]
+level = "debug"
+format = "pretty"
+"#;
+        let (merged, added) = merge_default_config(existing).unwrap();
+
+        // User values and comments survive
+        assert!(merged.contains("# my gateway"));
+        assert!(merged.contains("host = \"127.0.0.1\" # local only"));
+        assert!(merged.contains("port = 9000"));
+        assert!(merged.contains("provider = \"groq\""));
+        // Missing keys and top-level sections are added, optional channels are not
+        assert!(added.contains(&"server.max_connections".to_string()));
+        assert!(added.contains(&"logging.rotation".to_string()));
+        assert!(added.contains(&"processing".to_string()));
+        assert!(!added.iter().any(|k| k.starts_with("channels.discord")));
+        assert!(!merged.contains("[channels.discord]"));
+
+        let config: Config = toml::from_str(&merged).unwrap();
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.agent.max_tokens, 2048);
+        assert_eq!(config.server.max_connections, 1024);
+
+        // Merging again is a no-op
+        let (again, added) = merge_default_config(&merged).unwrap();
+        assert!(added.is_empty());
+        assert_eq!(again, merged);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config {
@@ -423,6 +1565,7 @@ mod tests {
             agent: AgentConfig::default(),
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         let serialized = toml::to_string_pretty(&config).unwrap();
@@ -441,13 +1584,17 @@ mod tests {
                 host: "0.0.0.0".to_string(),
                 port: 18789,
                 tls: None,
+                ..Default::default()
             },
             channels: ChannelsConfig {
                 telegram: Some(TelegramConfig {
                     bot_token: "test-token".to_string(),
                     allowed_chats: None,
+                    send_attempts: 3,
+                    stream_to_channel: false,
                 }),
                 discord: None,
+                webhook: None,
             },
             agent: AgentConfig {
                 provider: "anthropic".to_string(),
@@ -455,37 +1602,105 @@ mod tests {
                 api_key_env: "OPENCLAW_ANTHROPIC_API_KEY".to_string(),
                 api_key: Some("test-key".to_string()),
                 max_tokens: 4096,
-                api_base_url: None,
-                worker: None,
-                fallback: None,
+                ..Default::default()
             },
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig {
                 level: "info".to_string(),
                 format: "json".to_string(),
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn test_config_validation_invalid_port() {
+    fn test_logging_file_config() {
+        let logging: LoggingConfig = toml::from_str(
+            "level = \"debug\"\nformat = \"pretty\"\nfile = \"logs/clanker.log\"\nrotation = \"size\"\nmax_file_size_mb = 5",
+        )
+        .unwrap();
+        assert_eq!(logging.file.as_deref(), Some("logs/clanker.log"));
+        assert_eq!(logging.rotation, LogRotation::Size);
+        assert_eq!(logging.max_file_size_mb, 5);
+
+        // Existing configs without the new keys keep working
+        let logging: LoggingConfig = toml::from_str("level = \"info\"\nformat = \"json\"").unwrap();
+        assert!(logging.file.is_none());
+        assert_eq!(logging.rotation, LogRotation::Daily);
+
         let mut config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.logging.file = Some("clanker.log".to_string());
+        config.logging.rotation = LogRotation::Size;
+        config.logging.max_file_size_mb = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_invalid_port() {
+        let config = Config {
             server: ServerConfig {
                 host: "0.0.0.0".to_string(),
                 port: 0,  // Invalid port (0 is invalid)  // Invalid - should compile time error  // Invalid port
                 tls: None,
+                ..Default::default()
             },
             channels: ChannelsConfig::default(),
             agent: AgentConfig::default(),
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_server_connection_limits() {
+        let server: ServerConfig = toml::from_str("host = \"0.0.0.0\"\nport = 18789").unwrap();
+        assert_eq!(server.max_connections, 1024);
+        assert_eq!(server.max_subscriptions_per_connection, 64);
+        assert_eq!(server.max_invalid_messages, 10);
+        assert_eq!(server.broadcast_lag_policy, LagPolicy::Disconnect);
+        assert_eq!(server.broadcast_capacity, 1000);
+
+        let server: ServerConfig =
+            toml::from_str("host = \"0.0.0.0\"\nport = 18789\nbroadcast_lag_policy = \"skip_to_latest\"").unwrap();
+        assert_eq!(server.broadcast_lag_policy, LagPolicy::SkipToLatest);
+
+        let mut config = Config {
+            channels: ChannelsConfig {
+                telegram: None,
+                discord: None,
+                webhook: None,
+            },
+            ..Default::default()
+        };
+        config.server.max_connections = 0;
+        assert!(config.validate().is_err());
+
+        config.server.max_connections = 10;
+        config.server.max_subscriptions_per_connection = 0;
+        assert!(config.validate().is_err());
+
+        config.server.max_subscriptions_per_connection = 64;
+        config.server.broadcast_capacity = 0;
+        assert!(config.validate().is_err());
+
+        config.server.broadcast_capacity = 1000;
+        config.server.listen = vec!["127.0.0.1:18790".to_string(), "[::]:18789".to_string(), "localhost".to_string()];
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("server.listen address: localhost"), "{}", err);
+    }
+
     #[test]
     fn test_config_validation_no_channel() {
         // No channels is valid (WebSocket-only mode)
@@ -494,6 +1709,7 @@ mod tests {
             channels: ChannelsConfig {
                 telegram: None,
                 discord: None,
+                webhook: None,
             },
             agent: AgentConfig {
                 api_key: Some("test".to_string()),
@@ -501,11 +1717,58 @@ mod tests {
             },
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_agent_system_prompt_overrides() {
+        let agent: AgentConfig = toml::from_str(
+            "provider = \"anthropic\"\nmodel = \"claude-sonnet-4-20250514\"\napi_key_env = \"K\"\nmax_tokens = 100\n\
+             [system_prompts]\ntelegram = \"Be brief.\"",
+        )
+        .unwrap();
+        assert_eq!(agent.system_prompts.get("telegram").map(String::as_str), Some("Be brief."));
+
+        let mut config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..agent
+            },
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.agent.system_prompts.insert("irc".to_string(), "Hi".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_config() {
+        let channels: ChannelsConfig = toml::from_str(
+            "[webhook]\nurl = \"https://example.com/hook\"\nbearer_token = \"secret\"",
+        )
+        .unwrap();
+        let webhook = channels.webhook.as_ref().unwrap();
+        assert_eq!(webhook.url, "https://example.com/hook");
+        assert_eq!(webhook.bearer_token.as_deref(), Some("secret"));
+
+        let mut config = Config {
+            channels,
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.channels.webhook.as_mut().unwrap().url = "example.com/hook".to_string();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_config_validation_invalid_provider() {
         let config = Config {
@@ -518,6 +1781,7 @@ mod tests {
             },
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         assert!(config.validate().is_err());
@@ -535,8 +1799,10 @@ mod tests {
             orchestration: OrchestrationConfig {
                 enabled: true,
                 max_workers: 0,
+                ..Default::default()
             },
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         assert!(config.validate().is_err());
@@ -551,11 +1817,390 @@ mod tests {
             orchestration: OrchestrationConfig {
                 enabled: true,
                 max_workers: 6,
+                ..Default::default()
             },
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         assert!(config_max.validate().is_err());
+
+        let config_prompt = Config {
+            orchestration: OrchestrationConfig {
+                master_prompt: Some("   ".to_string()),
+                ..Default::default()
+            },
+            ..config_max
+        };
+
+        assert!(config_prompt.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_max_tokens_zero() {
+        let config = Config {
+            server: ServerConfig::default(),
+            channels: ChannelsConfig::default(),
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                max_tokens: 0,
+                ..Default::default()
+            },
+            orchestration: OrchestrationConfig::default(),
+            logging: LoggingConfig::default(),
+            ..Default::default()
+        };
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("max_tokens"));
+    }
+
+    #[test]
+    fn test_config_validation_telegram_send_attempts_zero() {
+        let mut config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.channels.telegram.as_mut().unwrap().send_attempts = 0;
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("send_attempts"));
+    }
+
+    #[test]
+    fn test_config_validation_discord_empty_command_prefix() {
+        let mut config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.channels.discord.as_mut().unwrap().command_prefix = Some(" ".to_string());
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("command_prefix"));
+    }
+
+    #[test]
+    fn test_config_validation_discord_zero_shards() {
+        let mut config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.channels.discord.as_mut().unwrap().shard_count = Some(0);
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("shard_count"));
+
+        config.channels.discord.as_mut().unwrap().shard_count = Some(2);
+        config.validate().unwrap();
+
+        // Counts beyond what the gateway client takes are rejected when parsing
+        let parsed = toml::from_str::<DiscordConfig>("bot_token = \"t\"\nshard_count = 4294967296");
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_agent_pool_parses_and_validates() {
+        let mut config: Config = toml::from_str(&format!(
+            "{}\n{}",
+            include_str!("../../../config-examples/config.toml"),
+            r#"
+[[agents]]
+provider = "anthropic"
+model = "claude-sonnet-4-20250514"
+api_key_env = "OPENCLAW_ANTHROPIC_API_KEY_2"
+max_tokens = 4096
+"#
+        ))
+        .unwrap();
+        config.agent.api_key = Some("key-1".to_string());
+        assert_eq!(config.agents.len(), 1);
+        assert_eq!(config.agents[0].api_key_env, "OPENCLAW_ANTHROPIC_API_KEY_2");
+
+        // Each pool member needs its own key
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agents[0] API key"));
+
+        config.agents[0].api_key = Some("key-2".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_dry_run_needs_no_api_keys() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agents.push(config.agent.clone());
+        config.agent.model_overrides = vec![ModelOverrideConfig {
+            provider: "groq".to_string(),
+            model: "llama-3.3-70b-versatile".to_string(),
+            api_key_env: None,
+            api_key: None,
+        }];
+        assert!(config.validate().is_err());
+
+        config.server.dry_run = true;
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_azure_requires_openai_and_base_url() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.api_key = Some("key".to_string());
+        config.agent.azure = Some(AzureOpenAIConfig {
+            deployment: "gpt-4o-prod".to_string(),
+            api_version: default_azure_api_version(),
+        });
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agent.azure requires provider 'openai'"), "{}", err);
+
+        config.agent.provider = "openai".to_string();
+        config.agent.model = "gpt-4o".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agent.azure requires api_base_url"), "{}", err);
+
+        config.agent.api_base_url = Some("https://my-resource.openai.azure.com".to_string());
+        config.channels = ChannelsConfig::default();
+        config.validate().unwrap();
+
+        let azure: AzureOpenAIConfig = toml::from_str("deployment = \"gpt-4o-prod\"").unwrap();
+        assert_eq!(azure.api_version, "2024-10-21");
+    }
+
+    #[test]
+    fn test_stop_sequences_validated() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.api_key = Some("key".to_string());
+        config.channels = ChannelsConfig::default();
+        config.agent.stop_sequences = vec!["END".to_string(); MAX_STOP_SEQUENCES];
+        config.validate().unwrap();
+
+        config.agent.stop_sequences.push("END".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("at most 4"), "{}", err);
+
+        config.agent.stop_sequences = vec![String::new()];
+        assert!(config.validate().unwrap_err().to_string().contains("empty string"));
+    }
+
+    #[test]
+    fn test_reasoning_budget_validated() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.api_key = Some("key".to_string());
+        config.channels = ChannelsConfig::default();
+        config.agent.reasoning_budget_tokens = Some(2048);
+        config.validate().unwrap();
+
+        config.agent.reasoning_budget_tokens = Some(512);
+        assert!(config.validate().unwrap_err().to_string().contains("at least 1024"));
+        config.agent.reasoning_budget_tokens = Some(config.agent.max_tokens);
+        assert!(config.validate().is_err());
+
+        config.agent.reasoning_budget_tokens = Some(2048);
+        config.agent.provider = "openai".to_string();
+        config.agent.model = "gpt-4o".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("only supported by provider 'anthropic'"), "{}", err);
+    }
+
+    #[test]
+    fn test_extra_headers_validated() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.extra_headers.insert("OpenAI-Organization".to_string(), "org-123".to_string());
+        config.agent.extra_headers.insert("Bad Header".to_string(), "x".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agent.extra_headers: invalid header name 'Bad Header'"), "{}", err);
+
+        config.agent.extra_headers.remove("Bad Header");
+        config.agent.extra_headers.insert("X-Proxy".to_string(), "a\r\nInjected: 1".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agent.extra_headers.X-Proxy"), "{}", err);
+
+        let agent: AgentConfig = toml::from_str(
+            "provider = \"openai\"\nmodel = \"gpt-4o\"\napi_key_env = \"K\"\nmax_tokens = 1\n\n[extra_headers]\nOpenAI-Organization = \"org-123\"",
+        )
+        .unwrap();
+        assert_eq!(agent.extra_headers["OpenAI-Organization"], "org-123");
+    }
+
+    #[test]
+    fn test_json_mode_requires_supporting_provider() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.api_key = Some("key".to_string());
+        config.agent.json_mode = true;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("agent.json_mode is not supported by provider 'anthropic'"));
+
+        config.agent.provider = "openai".to_string();
+        config.agent.model = "gpt-4o".to_string();
+        assert!(supports_json_mode("Groq"));
+        assert!(!supports_json_mode("grok"));
+        assert!(config.validate().is_ok(), "{:?}", config.validate());
+    }
+
+    #[test]
+    fn test_config_validation_max_tokens_over_ceiling() {
+        let config = Config {
+            server: ServerConfig::default(),
+            channels: ChannelsConfig::default(),
+            agent: AgentConfig {
+                provider: "openai".to_string(),
+                model: "gpt-4".to_string(),
+                api_key: Some("test".to_string()),
+                max_tokens: 100_000,
+                ..Default::default()
+            },
+            orchestration: OrchestrationConfig::default(),
+            logging: LoggingConfig::default(),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+
+        // Claude allows a higher ceiling than the other providers
+        let config = Config {
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                max_tokens: 32_000,
+                ..Default::default()
+            },
+            ..config
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_worker_max_tokens() {
+        let config = Config {
+            server: ServerConfig::default(),
+            channels: ChannelsConfig::default(),
+            agent: AgentConfig {
+                api_key: Some("test".to_string()),
+                worker: Some(WorkerAgentConfig {
+                    max_tokens: 0,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            orchestration: OrchestrationConfig::default(),
+            logging: LoggingConfig::default(),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_agent_config_optional_sections_default() {
+        let agent: AgentConfig = toml::from_str(
+            r#"
+            provider = "groq"
+            model = "llama-3.3-70b-versatile"
+            api_key_env = "OPENCLAW_GROQ_API_KEY"
+            max_tokens = 2048
+            "#,
+        )
+        .unwrap();
+
+        assert!(agent.api_key.is_none());
+        assert!(agent.api_base_url.is_none());
+        assert!(agent.worker.is_none());
+        assert!(agent.fallback.is_none());
+        assert!(agent.model_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_model_overrides_validated() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.agent.api_key = Some("key".to_string());
+        config.channels = ChannelsConfig::default();
+        config.agent.model_overrides = vec![
+            ModelOverrideConfig {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-haiku-20241022".to_string(),
+                api_key_env: None,
+                api_key: None,
+            },
+            ModelOverrideConfig {
+                provider: "groq".to_string(),
+                model: "llama-3.1-8b-instant".to_string(),
+                api_key_env: Some("OPENCLAW_GROQ_API_KEY".to_string()),
+                api_key: Some("groq-key".to_string()),
+            },
+        ];
+        config.validate().unwrap();
+
+        config.agent.model_overrides[1].api_key = None;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("OPENCLAW_GROQ_API_KEY"), "{}", err);
+
+        config.agent.model_overrides[1].api_key_env = None;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("api_key_env is required"), "{}", err);
+
+        config.agent.model_overrides[1].provider = "mistral".to_string();
+        assert!(config.validate().unwrap_err().to_string().contains("Invalid agent.model_overrides[1]"));
+    }
+
+    #[test]
+    fn test_model_matches_provider() {
+        assert!(model_matches_provider("anthropic", "claude-sonnet-4-20250514"));
+        assert!(model_matches_provider("groq", "llama-3.3-70b-versatile"));
+        assert!(model_matches_provider("zai", "GLM-4.7"));
+        assert!(!model_matches_provider("anthropic", "gpt-4"));
+        assert!(!model_matches_provider("openai", "claude-sonnet-4"));
+    }
+
+    #[test]
+    fn test_faq_config_load_entries() {
+        let faq_file = NamedTempFile::new().unwrap();
+        std::fs::write(
+            faq_file.path(),
+            r#"
+[[entries]]
+pattern = "help"
+response = "Help menu"
+
+[[entries]]
+pattern = "^/price\\b"
+response = "Pricing info"
+regex = true
+"#,
+        )
+        .unwrap();
+
+        let faq = FaqConfig {
+            entries: vec![FaqEntry {
+                pattern: "/start".to_string(),
+                response: "Welcome!".to_string(),
+                regex: false,
+            }],
+            file: Some(faq_file.path().to_string_lossy().to_string()),
+        };
+
+        let entries = faq.load_entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].pattern, "/start");
+        assert_eq!(entries[1].response, "Help menu");
+        assert!(entries[2].regex);
+    }
+
+    #[test]
+    fn test_faq_config_parsed_from_toml() {
+        let mut config_str = generate_default_config();
+        assert!(!config_str.contains("[faq]"));
+
+        config_str.push_str("\n[[faq.entries]]\npattern = \"/start\"\nresponse = \"Welcome!\"\n");
+        let config: Config = toml::from_str(&config_str).unwrap();
+        assert_eq!(config.faq.entries.len(), 1);
+        assert!(!config.faq.entries[0].regex);
     }
 
     #[test]
@@ -578,6 +2223,7 @@ mod tests {
             agent: AgentConfig::default(),
             orchestration: OrchestrationConfig::default(),
             logging: LoggingConfig::default(),
+            ..Default::default()
         };
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -592,14 +2238,60 @@ mod tests {
         let server_config = ServerConfig::default();
         assert_eq!(server_config.host, "0.0.0.0");
         assert_eq!(server_config.port, 18789);
+        assert!(!server_config.warmup_on_start);
 
         let telegram_config = TelegramConfig::default();
         assert_eq!(telegram_config.bot_token, "your-telegram-bot-token");
         assert!(telegram_config.allowed_chats.is_none());
+        assert_eq!(telegram_config.send_attempts, 3);
+        assert!(!telegram_config.stream_to_channel);
+
+        let discord_config = DiscordConfig::default();
+        assert!(discord_config.command_prefix.is_none());
+        assert!(!discord_config.respond_on_mention);
+        assert!(discord_config.shard_count.is_none());
 
         let agent_config = AgentConfig::default();
         assert_eq!(agent_config.provider, "anthropic");
         assert_eq!(agent_config.model, "claude-sonnet-4-20250514");
         assert_eq!(agent_config.max_tokens, 4096);
+
+        assert!(AuditConfig::default().is_disabled());
+        assert!(BudgetConfig::default().is_disabled());
+    }
+
+    #[test]
+    fn test_audit_config_parsed_and_validated() {
+        let mut config: Config = toml::from_str(&format!(
+            "{}\n[audit]\npath = \"logs/audit.jsonl\"\n",
+            include_str!("../../../config-examples/config.toml")
+        ))
+        .unwrap();
+        config.agent.api_key = Some("key".to_string());
+        assert_eq!(config.audit.path.as_deref(), Some("logs/audit.jsonl"));
+        assert!(!config.audit.include_content);
+        config.validate().unwrap();
+
+        config.audit.path = Some(" ".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("audit.path"));
+    }
+
+    #[test]
+    fn test_budget_caps_validated() {
+        let mut config: Config = toml::from_str(&format!(
+            "{}\n[budget]\ndaily_usd = 5\nmonthly_usd = 100.5\n",
+            include_str!("../../../config-examples/config.toml")
+        ))
+        .unwrap();
+        config.agent.api_key = Some("key".to_string());
+        assert_eq!((config.budget.daily_usd, config.budget.monthly_usd), (Some(5.0), Some(100.5)));
+        assert!(config.budget.state_file.is_none());
+        config.validate().unwrap();
+
+        config.budget.daily_usd = Some(0.0);
+        assert!(config.validate().unwrap_err().to_string().contains("budget.daily_usd"));
+        config.budget.daily_usd = None;
+        config.budget.monthly_usd = Some(f64::NAN);
+        assert!(config.validate().unwrap_err().to_string().contains("budget.monthly_usd"));
     }
 }
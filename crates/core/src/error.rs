@@ -51,6 +51,10 @@ pub enum ClankerError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Message text exceeds a channel's length limit
+    #[error("Message too long: {length} characters, max {max}")]
+    MessageTooLong { length: usize, max: usize },
+
     /// Not found errors
     #[error("Not found: {0}")]
     NotFound(String),
@@ -110,14 +114,14 @@ impl ClankerError {
 
     /// Check if error is retryable
     pub fn is_retryable(&self) -> bool {
-        matches!(
-            self,
+        match self {
             Self::Network(_) |
             Self::Io(_) |  // IO errors can be retryable (transient)
             Self::Timeout |
-            Self::RateLimit |
-            Self::Agent { provider: _, message: _ } if self.is_transient_agent_error()
-        )
+            Self::RateLimit => true,
+            Self::Agent { .. } => self.is_transient_agent_error(),
+            _ => false,
+        }
     }
 
     /// Check if agent error is transient (retryable)
@@ -149,6 +153,7 @@ impl ClankerError {
             Self::Authentication => "AUTHENTICATION_ERROR",
             Self::RateLimit => "RATE_LIMIT_ERROR",
             Self::InvalidInput(_) => "INVALID_INPUT_ERROR",
+            Self::MessageTooLong { .. } => "MESSAGE_TOO_LONG_ERROR",
             Self::NotFound(_) => "NOT_FOUND_ERROR",
             Self::Timeout => "TIMEOUT_ERROR",
             Self::Bot(_) => "BOT_ERROR",
@@ -4,11 +4,15 @@
 //! for the Open Clanker AI assistant gateway.
 
 pub mod error;
+pub mod retry;
+pub mod secret;
 pub mod traits;
 pub mod types;
 
 // Re-export common types
 pub use error::{ClankerError, Result};
+pub use retry::{retry_with_backoff, Retryable};
+pub use secret::mask_secret;
 pub use traits::{
     Agent, Channel, Configurable, HealthCheck, HealthStatus, Metrics, Storage,
 };
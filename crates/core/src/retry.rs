@@ -0,0 +1,167 @@
+//! Retry with jittered exponential backoff for transient failures.
+
+use crate::error::ClankerError;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Delay before the first retry (doubled for each further attempt)
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Upper bound on a single backoff delay
+pub const MAX_BACKOFF_DELAY: Duration = Duration::from_secs(5);
+
+/// Errors that can tell whether repeating the operation may succeed
+pub trait Retryable {
+    /// Check if the failed operation is worth retrying
+    fn is_retryable(&self) -> bool;
+
+    /// Delay requested by the remote side (e.g. a rate limit's retry-after)
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Retryable for ClankerError {
+    fn is_retryable(&self) -> bool {
+        ClankerError::is_retryable(self)
+    }
+}
+
+/// Run `op` up to `max_attempts` times, retrying only retryable errors.
+///
+/// Waits a jittered exponential delay between attempts, or the error's
+/// `retry_after` when given. A requested delay above [`MAX_BACKOFF_DELAY`]
+/// is not waited out; the error is returned instead.
+pub async fn retry_with_backoff<F, Fut, T, E>(op: F, max_attempts: u32) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Retryable,
+{
+    retry_with_base_delay(op, max_attempts, DEFAULT_BASE_DELAY).await
+}
+
+/// [`retry_with_backoff`] with a custom first-retry delay
+pub async fn retry_with_base_delay<F, Fut, T, E>(
+    mut op: F,
+    max_attempts: u32,
+    base_delay: Duration,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Retryable,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        if attempt >= max_attempts || !err.is_retryable() {
+            return Err(err);
+        }
+
+        let delay = match err.retry_after() {
+            Some(requested) if requested > MAX_BACKOFF_DELAY => return Err(err),
+            Some(requested) => requested,
+            None => backoff_delay(base_delay, attempt),
+        };
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Delay after the given failed attempt (1-based): `base * 2^(attempt-1)`,
+/// capped at [`MAX_BACKOFF_DELAY`], with the upper half randomized
+pub fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let ceiling = base.saturating_mul(1 << exponent).min(MAX_BACKOFF_DELAY);
+    let half = ceiling / 2;
+    let jitter_nanos = half.as_nanos() as u64;
+    if jitter_nanos == 0 {
+        return ceiling;
+    }
+    half + Duration::from_nanos(random_u64() % (jitter_nanos + 1))
+}
+
+fn random_u64() -> u64 {
+    // RandomState is seeded randomly per instance; good enough for jitter
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const TEST_DELAY: Duration = Duration::from_millis(1);
+
+    async fn failing_with(
+        calls: &AtomicU32,
+        make_err: fn() -> ClankerError,
+        max_attempts: u32,
+    ) -> crate::Result<()> {
+        retry_with_base_delay(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(make_err())
+            },
+            max_attempts,
+            TEST_DELAY,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_is_not_retried() {
+        let calls = AtomicU32::new(0);
+        let err = failing_with(&calls, || ClankerError::config("bad"), 5).await.unwrap_err();
+
+        assert!(matches!(err, ClankerError::Config(_)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retryable_error_is_retried_up_to_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let err = failing_with(&calls, || ClankerError::Timeout, 3).await.unwrap_err();
+
+        assert!(matches!(err, ClankerError::Timeout));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_success() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with_base_delay(
+            || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(ClankerError::Timeout)
+                } else {
+                    Ok("done")
+                }
+            },
+            5,
+            TEST_DELAY,
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_and_caps() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=4 {
+            let ceiling = base * (1 << (attempt - 1));
+            let delay = backoff_delay(base, attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "attempt {}: {:?}", attempt, delay);
+        }
+        assert!(backoff_delay(base, 30) <= MAX_BACKOFF_DELAY);
+    }
+}
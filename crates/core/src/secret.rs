@@ -0,0 +1,37 @@
+//! Helpers for keeping API keys and tokens out of logs.
+
+/// Characters shown at each end of a masked secret
+const VISIBLE_CHARS: usize = 4;
+
+/// Mask a secret for display, keeping only the first and last four characters
+///
+/// Secrets too short to reveal anything safely are fully masked.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= VISIBLE_CHARS * 3 {
+        return "*".repeat(chars.len().min(8));
+    }
+
+    let head: String = chars[..VISIBLE_CHARS].iter().collect();
+    let tail: String = chars[chars.len() - VISIBLE_CHARS..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_long_key() {
+        let key = "sk-ant-REDACTED";
+        assert_eq!(key.len(), 40);
+        assert_eq!(mask_secret(key), "sk-a...xyz1");
+    }
+
+    #[test]
+    fn test_mask_short_secret_fully() {
+        assert_eq!(mask_secret(""), "");
+        assert_eq!(mask_secret("abc"), "***");
+        assert_eq!(mask_secret("123456789012"), "********");
+    }
+}
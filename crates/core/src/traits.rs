@@ -1,6 +1,7 @@
 use crate::types::{AgentResponse, ChannelType, Message};
 use crate::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 /// Trait for message channels (Telegram, Discord, etc.)
 #[async_trait]
@@ -140,7 +141,8 @@ pub trait HealthCheck: Send + Sync {
 }
 
 /// Health status for components
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
 pub enum HealthStatus {
     /// Component is healthy
     Healthy,
@@ -175,6 +177,19 @@ impl HealthStatus {
     pub fn unhealthy(reason: impl Into<String>) -> Self {
         Self::Unhealthy(reason.into())
     }
+
+    /// Combine component statuses: unhealthy if any is unhealthy, degraded if any is degraded
+    pub fn aggregate<'a>(statuses: impl IntoIterator<Item = &'a HealthStatus>) -> Self {
+        let mut overall = Self::Healthy;
+        for status in statuses {
+            match status {
+                Self::Unhealthy(_) => return Self::unhealthy("one or more components are unhealthy"),
+                Self::Degraded(_) => overall = Self::degraded("one or more components are degraded"),
+                Self::Healthy => {}
+            }
+        }
+        overall
+    }
 }
 
 #[cfg(test)]
@@ -191,6 +206,33 @@ mod tests {
         assert!(!HealthStatus::Unhealthy("down".to_string()).is_functional());
     }
 
+    #[test]
+    fn test_health_status_aggregate() {
+        let healthy = HealthStatus::healthy();
+        let degraded = HealthStatus::degraded("slow");
+        let unhealthy = HealthStatus::unhealthy("down");
+
+        assert_eq!(HealthStatus::aggregate([&healthy, &healthy]), HealthStatus::Healthy);
+        assert!(matches!(
+            HealthStatus::aggregate([&healthy, &degraded]),
+            HealthStatus::Degraded(_)
+        ));
+        assert!(matches!(
+            HealthStatus::aggregate([&degraded, &unhealthy, &healthy]),
+            HealthStatus::Unhealthy(_)
+        ));
+        assert_eq!(HealthStatus::aggregate([]), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_health_status_serialization() {
+        let json = serde_json::to_string(&HealthStatus::degraded("slow")).unwrap();
+        assert_eq!(json, r#"{"status":"degraded","reason":"slow"}"#);
+
+        let json = serde_json::to_string(&HealthStatus::Healthy).unwrap();
+        assert_eq!(json, r#"{"status":"healthy"}"#);
+    }
+
     #[test]
     fn test_health_status_creation() {
         let healthy = HealthStatus::healthy();
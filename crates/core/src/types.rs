@@ -1,3 +1,4 @@
+use crate::error::{ClankerError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -70,11 +71,29 @@ impl Message {
         self
     }
 
+    /// Set the sender of the message being replied to
+    pub fn set_in_reply_to_sender(mut self, sender: UserId) -> Self {
+        self.metadata.in_reply_to_sender = Some(sender);
+        self
+    }
+
     /// Add a mention
     pub fn add_mention(mut self, user_id: UserId) -> Self {
         self.metadata.mentions.push(user_id);
         self
     }
+
+    /// Validate message text length (in characters) against `max_len`
+    pub fn validate(&self, max_len: usize) -> Result<()> {
+        let length = self.text.chars().count();
+        if length > max_len {
+            return Err(ClankerError::MessageTooLong {
+                length,
+                max: max_len,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -82,6 +101,13 @@ pub struct MessageMetadata {
     pub attachments: Vec<Attachment>,
     pub reply_to: Option<MessageId>,
     pub mentions: Vec<UserId>,
+    /// Sender of the message this one replies to (set on agent responses)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to_sender: Option<UserId>,
+    /// Attachment bodies (URLs) or part of the text were left out of this copy, e.g. in
+    /// a broadcast summary
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -105,6 +131,10 @@ impl Attachment {
 }
 
 /// Supported channel types
+///
+/// This is a closed set on purpose: channel-specific behavior (system prompts,
+/// message limits, routing) matches on it exhaustively, so a new sink gets a
+/// variant rather than a free-form name.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum ChannelType {
@@ -112,9 +142,20 @@ pub enum ChannelType {
     Discord,
     Slack,
     WhatsApp,
+    /// Generic outbound HTTP webhook (send only)
+    Webhook,
 }
 
 impl ChannelType {
+    /// All supported channel types
+    pub const ALL: [ChannelType; 5] = [
+        ChannelType::Telegram,
+        ChannelType::Discord,
+        ChannelType::Slack,
+        ChannelType::WhatsApp,
+        ChannelType::Webhook,
+    ];
+
     /// Get channel type as string
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -122,17 +163,44 @@ impl ChannelType {
             ChannelType::Discord => "discord",
             ChannelType::Slack => "slack",
             ChannelType::WhatsApp => "whatsapp",
+            ChannelType::Webhook => "webhook",
         }
     }
 
     /// Parse channel type from string
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "telegram" => Some(ChannelType::Telegram),
             "discord" => Some(ChannelType::Discord),
+            "slack" => Some(ChannelType::Slack),
+            "whatsapp" => Some(ChannelType::WhatsApp),
+            "webhook" => Some(ChannelType::Webhook),
             _ => None,
         }
     }
+
+    /// Check that `channel_id` has the shape this channel type addresses chats by:
+    /// a signed integer for Telegram, a non-zero snowflake for Discord. Other
+    /// types only require a non-empty id.
+    pub fn validate_channel_id(&self, channel_id: &str) -> Result<()> {
+        if channel_id.trim().is_empty() {
+            return Err(ClankerError::InvalidInput(format!("{} channel_id cannot be empty", self)));
+        }
+        match self {
+            ChannelType::Telegram if channel_id.parse::<i64>().is_err() => Err(ClankerError::InvalidInput(format!(
+                "telegram channel_id '{}' must be a numeric chat id",
+                channel_id
+            ))),
+            ChannelType::Discord if !matches!(channel_id.parse::<u64>(), Ok(id) if id != 0) => {
+                Err(ClankerError::InvalidInput(format!(
+                    "discord channel_id '{}' must be a numeric channel id",
+                    channel_id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 impl std::fmt::Display for ChannelType {
@@ -271,14 +339,67 @@ mod tests {
         assert_eq!(msg.metadata.reply_to, Some("message-123".to_string()));
     }
 
+    #[test]
+    fn test_message_validate_length() {
+        let msg = Message::new(
+            ChannelType::Telegram,
+            "12345".to_string(),
+            "user".to_string(),
+            "héllo".to_string(),
+        );
+
+        assert!(msg.validate(5).is_ok());
+        assert!(msg.validate(100).is_ok());
+
+        let err = msg.validate(4).unwrap_err();
+        assert!(matches!(
+            err,
+            ClankerError::MessageTooLong { length: 5, max: 4 }
+        ));
+        assert_eq!(err.error_code(), "MESSAGE_TOO_LONG_ERROR");
+    }
+
     #[test]
     fn test_channel_type_from_str() {
         assert_eq!(ChannelType::from_str("telegram"), Some(ChannelType::Telegram));
         assert_eq!(ChannelType::from_str("TELEGRAM"), Some(ChannelType::Telegram));
         assert_eq!(ChannelType::from_str("discord"), Some(ChannelType::Discord));
+        assert_eq!(ChannelType::from_str("slack"), Some(ChannelType::Slack));
+        assert_eq!(ChannelType::from_str("WhatsApp"), Some(ChannelType::WhatsApp));
+        assert_eq!(ChannelType::from_str("webhook"), Some(ChannelType::Webhook));
         assert_eq!(ChannelType::from_str("unknown"), None);
     }
 
+    #[test]
+    fn test_validate_channel_id() {
+        assert!(ChannelType::Telegram.validate_channel_id("123456789").is_ok());
+        assert!(ChannelType::Telegram.validate_channel_id("-1001234567890").is_ok());
+        let err = ChannelType::Telegram.validate_channel_id("general").unwrap_err();
+        assert!(matches!(err, ClankerError::InvalidInput(_)));
+        assert!(err.to_string().contains("numeric chat id"), "{}", err);
+
+        assert!(ChannelType::Discord.validate_channel_id("1234567890123456789").is_ok());
+        assert!(ChannelType::Discord.validate_channel_id("-5").is_err());
+        assert!(ChannelType::Discord.validate_channel_id("0").is_err());
+        assert!(ChannelType::Discord.validate_channel_id("#general").is_err());
+
+        assert!(ChannelType::Slack.validate_channel_id("C024BE91L").is_ok());
+        assert!(ChannelType::Webhook.validate_channel_id("alerts").is_ok());
+        assert!(ChannelType::Webhook.validate_channel_id("  ").is_err());
+    }
+
+    #[test]
+    fn test_channel_type_round_trip() {
+        for channel_type in ChannelType::ALL {
+            assert_eq!(
+                ChannelType::from_str(channel_type.as_str()),
+                Some(channel_type),
+                "from_str(as_str()) should round-trip for {:?}",
+                channel_type
+            );
+        }
+    }
+
     #[test]
     fn test_usage_stats_calculation() {
         let stats = UsageStats::new(1000, 500);
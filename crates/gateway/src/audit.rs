@@ -0,0 +1,211 @@
+//! Audit trail of agent interactions, kept apart from the debug logs.
+//!
+//! With `[audit] path` set, every message answered by an agent appends one
+//! JSON line: who sent it where, which provider and model answered, token
+//! usage, estimated cost and latency. Requests that get no answer (agent
+//! errors, budget refusals, rejected model overrides) append a line with
+//! `error` set. Message text and the reply are only recorded with
+//! `include_content`.
+
+use crate::budget::estimated_cost;
+use clanker_config::AuditConfig;
+use clanker_core::{ChannelType, Message};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+/// One answered (or failed) message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub channel_type: ChannelType,
+    pub channel_id: String,
+    pub sender: String,
+    /// Length of the message sent to the agent, in characters
+    pub prompt_chars: usize,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    /// Estimated cost in US dollars
+    pub cost_usd: f64,
+    /// Time the agent took to answer
+    pub latency_ms: u64,
+    /// Provider refused or content-filtered the response
+    pub filtered: bool,
+    /// Message text (`include_content` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Reply sent back (`include_content` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply: Option<String>,
+    /// Why the request got no answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Answer given by the agent, as recorded in the audit log
+#[derive(Debug, Clone, Copy)]
+pub struct AuditedAnswer<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub usage: &'a clanker_agent::Usage,
+    pub latency: Duration,
+    pub filtered: bool,
+    pub reply: &'a str,
+}
+
+/// Append-only JSONL audit file
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Arc<Mutex<File>>,
+    include_content: bool,
+}
+
+impl AuditLog {
+    /// Open (or create) the audit file for appending
+    pub fn open(path: impl AsRef<Path>, include_content: bool) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            include_content,
+        })
+    }
+
+    /// Build the audit log from config (None without a path, or when the file cannot be opened)
+    pub fn from_config(config: &AuditConfig) -> Option<Self> {
+        let path = config.path.as_ref()?;
+        match Self::open(path, config.include_content) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                error!("Audit log {} could not be opened, auditing is disabled: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Record for an answered message; text is kept only with `include_content`
+    pub fn record_for(&self, incoming: &Message, prompt: &str, answer: AuditedAnswer<'_>) -> AuditRecord {
+        AuditRecord {
+            timestamp: chrono::Utc::now(),
+            channel_type: incoming.channel_type,
+            channel_id: incoming.channel_id.clone(),
+            sender: incoming.sender.clone(),
+            prompt_chars: prompt.chars().count(),
+            provider: answer.provider.to_string(),
+            model: answer.model.to_string(),
+            prompt_tokens: answer.usage.prompt_tokens,
+            completion_tokens: answer.usage.completion_tokens,
+            cost_usd: estimated_cost(answer.provider, answer.model, answer.usage),
+            latency_ms: answer.latency.as_millis() as u64,
+            filtered: answer.filtered,
+            prompt: self.include_content.then(|| prompt.to_string()),
+            reply: self.include_content.then(|| answer.reply.to_string()),
+            error: None,
+        }
+    }
+
+    /// Record for a message that got no answer from `provider`/`model`
+    pub fn failure_for(
+        &self,
+        incoming: &Message,
+        prompt: &str,
+        provider: &str,
+        model: &str,
+        latency: Duration,
+        error: &str,
+    ) -> AuditRecord {
+        AuditRecord {
+            timestamp: chrono::Utc::now(),
+            channel_type: incoming.channel_type,
+            channel_id: incoming.channel_id.clone(),
+            sender: incoming.sender.clone(),
+            prompt_chars: prompt.chars().count(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost_usd: 0.0,
+            latency_ms: latency.as_millis() as u64,
+            filtered: false,
+            prompt: self.include_content.then(|| prompt.to_string()),
+            reply: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// Append a record as one line on the blocking pool, so a slow disk never
+    /// stalls the runtime; write failures are logged, not returned
+    pub async fn append(&self, record: &AuditRecord) {
+        let mut line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Audit record could not be serialized: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+        let file = self.file.clone();
+        let written = tokio::task::spawn_blocking(move || {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            file.write_all(line.as_bytes()).and_then(|_| file.flush())
+        })
+        .await;
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Audit record could not be written: {}", e),
+            Err(e) => error!("Audit writer failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(usage: &clanker_agent::Usage) -> AuditedAnswer<'_> {
+        AuditedAnswer {
+            provider: "anthropic",
+            model: "claude-sonnet-4-20250514",
+            usage,
+            latency: Duration::from_millis(420),
+            filtered: false,
+            reply: "Hello there",
+        }
+    }
+
+    #[test]
+    fn test_record_leaves_out_content_unless_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let usage = clanker_agent::Usage {
+            prompt_tokens: 1_000,
+            completion_tokens: 100,
+            total_tokens: 1_100,
+        };
+        let incoming = Message::new(ChannelType::Telegram, "42".to_string(), "alice".to_string(), "Hi, bot".to_string());
+
+        let log = AuditLog::open(dir.path().join("audit.jsonl"), false).unwrap();
+        let record = log.record_for(&incoming, &incoming.text, answer(&usage));
+        assert_eq!((record.sender.as_str(), record.prompt_chars, record.latency_ms), ("alice", 7, 420));
+        assert!((record.cost_usd - 0.0045).abs() < 1e-9);
+        assert!(record.prompt.is_none() && record.reply.is_none());
+
+        let log = AuditLog::open(dir.path().join("nested/audit.jsonl"), true).unwrap();
+        let record = log.record_for(&incoming, &incoming.text, answer(&usage));
+        assert_eq!(record.prompt.as_deref(), Some("Hi, bot"));
+        assert_eq!(record.reply.as_deref(), Some("Hello there"));
+        assert!(record.error.is_none());
+
+        let record = log.failure_for(&incoming, &incoming.text, "groq", "llama", Duration::ZERO, "rate limited");
+        assert_eq!((record.cost_usd, record.reply), (0.0, None));
+        assert_eq!(record.error.as_deref(), Some("rate limited"));
+    }
+}
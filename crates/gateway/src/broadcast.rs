@@ -1,9 +1,8 @@
-use crate::types::{WsServerMessage, ConnectionId};
+use crate::types::{ConnectionId, NoticeLevel, WsServerMessage};
 use clanker_core::{ChannelType, Message};
 use tokio::sync::broadcast;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
-use uuid::Uuid;
+use tracing::{debug, info, warn};
 
 /// Gateway-wide message broadcaster
 #[derive(Debug, Clone)]
@@ -12,19 +11,30 @@ pub struct MessageBroadcaster {
     tx: broadcast::Sender<WsServerMessage>,
     /// Token for graceful shutdown
     shutdown_token: CancellationToken,
+    /// Messages larger than this (see [`payload_size`]) are broadcast as summaries
+    summary_threshold: Option<usize>,
 }
 
 impl MessageBroadcaster {
-    /// Create new message broadcaster
-    pub fn new(shutdown_token: CancellationToken) -> Self {
-        let (tx, _rx) = broadcast::channel(1000);
+    /// Create new message broadcaster buffering up to `capacity` messages
+    /// for the slowest subscriber (`server.broadcast_capacity`; tokio rounds
+    /// it up to a power of two)
+    pub fn new(shutdown_token: CancellationToken, capacity: usize) -> Self {
+        let (tx, _rx) = broadcast::channel(capacity);
 
         Self {
             tx,
             shutdown_token,
+            summary_threshold: None,
         }
     }
 
+    /// Broadcast messages over `threshold` bytes as summaries (`server.broadcast_summary_bytes`)
+    pub fn with_summary_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.summary_threshold = threshold;
+        self
+    }
+
     /// Subscribe to message broadcasts
     pub fn subscribe(&self) -> broadcast::Receiver<WsServerMessage> {
         self.tx.subscribe()
@@ -32,21 +42,37 @@ impl MessageBroadcaster {
 
     /// Broadcast message to all subscribers
     pub async fn broadcast(&self, message: WsServerMessage) -> Result<(), broadcast::error::SendError<WsServerMessage>> {
-        let _ = self.tx.send(message);
+        self.publish(message);
         Ok(())
     }
 
+    /// Broadcast message without awaiting (for use from sync callbacks)
+    pub fn publish(&self, message: WsServerMessage) {
+        let _ = self.tx.send(message);
+    }
+
     /// Send message to specific channel subscribers
     pub async fn send_to_channel(
         &self,
         message: &Message,
     ) -> Result<(), broadcast::error::SendError<WsServerMessage>> {
-        let ws_message = WsServerMessage::MessageReceived(message.clone());
-
-        debug!(
-            "Broadcasting message to channel {}",
-            message.channel_id
-        );
+        let size = payload_size(message);
+        let ws_message = match self.summary_threshold {
+            Some(threshold) if size > threshold => {
+                debug!(
+                    "Broadcasting summary of {}-byte message to channel {}",
+                    size, message.channel_id
+                );
+                WsServerMessage::MessageReceived(summarize(message, threshold))
+            }
+            _ => {
+                debug!(
+                    "Broadcasting message to channel {}",
+                    message.channel_id
+                );
+                WsServerMessage::MessageReceived(message.clone())
+            }
+        };
 
         self.broadcast(ws_message).await
     }
@@ -67,6 +93,13 @@ impl MessageBroadcaster {
         self.broadcast(ws_message).await
     }
 
+    /// Send an operator notice to all subscribers; returns how many received it
+    pub fn send_notice(&self, level: NoticeLevel, message: impl Into<String>) -> usize {
+        let message = message.into();
+        info!("Broadcasting {:?} notice: {}", level, message);
+        self.tx.send(WsServerMessage::Notice { level, message }).unwrap_or(0)
+    }
+
     /// Check if broadcaster is still active
     pub fn is_active(&self) -> bool {
         !self.shutdown_token.is_cancelled()
@@ -78,6 +111,38 @@ impl MessageBroadcaster {
     }
 }
 
+/// Approximate broadcast size: message text plus attachment URLs (which may be data URLs)
+pub fn payload_size(message: &Message) -> usize {
+    message.text.len()
+        + message
+            .metadata
+            .attachments
+            .iter()
+            .map(|attachment| attachment.url.len())
+            .sum::<usize>()
+}
+
+/// Copy of a message without attachment URLs (keeping their id, type and size) and
+/// with its text cut to `max_bytes`; `truncated` is set only if something was left out
+fn summarize(message: &Message, max_bytes: usize) -> Message {
+    let mut summary = message.clone();
+    let mut truncated = false;
+    for attachment in &mut summary.metadata.attachments {
+        truncated |= !attachment.url.is_empty();
+        attachment.url.clear();
+    }
+    if summary.text.len() > max_bytes {
+        let mut end = max_bytes;
+        while !summary.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        summary.text.truncate(end);
+        truncated = true;
+    }
+    summary.metadata.truncated = truncated;
+    summary
+}
+
 /// Message sender for specific connection
 #[derive(Debug)]
 pub struct ConnectionSender {
@@ -156,11 +221,14 @@ impl MessageFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
+
+    const TEST_CAPACITY: usize = 1000;
 
     #[tokio::test]
     async fn test_broadcaster_creation() {
         let shutdown_token = CancellationToken::new();
-        let broadcaster = MessageBroadcaster::new(shutdown_token);
+        let broadcaster = MessageBroadcaster::new(shutdown_token, TEST_CAPACITY);
 
         assert!(broadcaster.is_active());
         assert_eq!(broadcaster.receiver_count(), 0);
@@ -169,7 +237,7 @@ mod tests {
     #[tokio::test]
     async fn test_broadcaster_subscribe() {
         let shutdown_token = CancellationToken::new();
-        let broadcaster = MessageBroadcaster::new(shutdown_token);
+        let broadcaster = MessageBroadcaster::new(shutdown_token, TEST_CAPACITY);
 
         let _rx = broadcaster.subscribe();
         assert_eq!(broadcaster.receiver_count(), 1);
@@ -181,7 +249,7 @@ mod tests {
     #[tokio::test]
     async fn test_broadcaster_broadcast() {
         let shutdown_token = CancellationToken::new();
-        let broadcaster = MessageBroadcaster::new(shutdown_token);
+        let broadcaster = MessageBroadcaster::new(shutdown_token, TEST_CAPACITY);
 
         let mut rx = broadcaster.subscribe();
 
@@ -198,6 +266,61 @@ mod tests {
         assert!(matches!(received, WsServerMessage::MessageReceived(_)));
     }
 
+    #[tokio::test]
+    async fn test_oversized_message_broadcast_as_summary() {
+        let broadcaster = MessageBroadcaster::new(CancellationToken::new(), TEST_CAPACITY).with_summary_threshold(Some(1024));
+        let mut rx = broadcaster.subscribe();
+
+        let mut message = Message::new(
+            ChannelType::Telegram,
+            "test-channel".to_string(),
+            "user123".to_string(),
+            "Look at this".to_string(),
+        );
+        let image = format!("data:image/png;base64,{}", "A".repeat(4096));
+        message.metadata.attachments.push(clanker_core::Attachment::new(image, "image/png".to_string(), 3072));
+        broadcaster.send_to_channel(&message).await.unwrap();
+
+        let WsServerMessage::MessageReceived(summary) = rx.recv().await.unwrap() else {
+            panic!("expected MessageReceived");
+        };
+        assert!(summary.metadata.truncated);
+        assert_eq!(summary.text, "Look at this");
+        let attachment = &summary.metadata.attachments[0];
+        assert!(attachment.url.is_empty());
+        assert_eq!(attachment.id, message.metadata.attachments[0].id);
+        assert_eq!((attachment.mime_type.as_str(), attachment.size_bytes), ("image/png", 3072));
+
+        // Messages within the threshold go out whole
+        message.metadata.attachments.clear();
+        broadcaster.send_to_channel(&message).await.unwrap();
+        let WsServerMessage::MessageReceived(whole) = rx.recv().await.unwrap() else {
+            panic!("expected MessageReceived");
+        };
+        assert!(!whole.metadata.truncated);
+        assert_eq!(whole, message);
+    }
+
+    #[test]
+    fn test_summary_cuts_long_text() {
+        let mut message = Message::new(
+            ChannelType::Telegram,
+            "test-channel".to_string(),
+            "user123".to_string(),
+            "héllo wörld".to_string(),
+        );
+        // Cut inside the two-byte "é" falls back to the character boundary
+        let summary = summarize(&message, 2);
+        assert_eq!(summary.text, "h");
+        assert!(summary.metadata.truncated);
+
+        // Nothing left out, nothing flagged
+        message.metadata.attachments.push(clanker_core::Attachment::new(String::new(), "image/png".to_string(), 0));
+        let summary = summarize(&message, 1024);
+        assert_eq!(summary.text, message.text);
+        assert!(!summary.metadata.truncated);
+    }
+
     #[tokio::test]
     async fn test_message_filter() {
         let filter = MessageFilter::new(
@@ -240,10 +363,30 @@ mod tests {
         assert!(filter.matches(&ping_msg));
     }
 
+    #[tokio::test]
+    async fn test_custom_capacity_buffers_before_lagging() {
+        let broadcaster = MessageBroadcaster::new(CancellationToken::new(), 4);
+        let mut slow = broadcaster.subscribe();
+
+        // Exactly `capacity` queued messages are all still delivered
+        for timestamp in 0..4 {
+            broadcaster.publish(WsServerMessage::Pong { timestamp });
+        }
+        for timestamp in 0..4 {
+            assert!(matches!(slow.recv().await.unwrap(), WsServerMessage::Pong { timestamp: t } if t == timestamp));
+        }
+
+        // One more than capacity pushes the slowest subscriber into lag
+        for timestamp in 0..5 {
+            broadcaster.publish(WsServerMessage::Pong { timestamp });
+        }
+        assert!(matches!(slow.recv().await, Err(broadcast::error::RecvError::Lagged(1))));
+    }
+
     #[tokio::test]
     async fn test_connection_sender() {
         let shutdown_token = CancellationToken::new();
-        let broadcaster = MessageBroadcaster::new(shutdown_token);
+        let broadcaster = MessageBroadcaster::new(shutdown_token, TEST_CAPACITY);
 
         let mut rx = ConnectionSender::new(Uuid::new_v4(), broadcaster.subscribe());
 
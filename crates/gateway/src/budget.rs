@@ -0,0 +1,315 @@
+//! Spend caps on the estimated cost of agent requests.
+//!
+//! With `budget.daily_usd` and/or `budget.monthly_usd` set, the cost of every
+//! answered request is added to running totals for the current UTC day and
+//! month. Once a total reaches its cap, new agent requests are refused until
+//! the window rolls over. With `budget.state_file` the totals are written to a
+//! small JSON file after each request and reloaded at boot; a file that exists
+//! but cannot be read keeps the gateway from starting instead of resetting the
+//! totals to zero.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use clanker_config::BudgetConfig;
+use clanker_core::UsageStats;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, warn};
+
+/// Budget window a cap applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetWindow {
+    Daily,
+    Monthly,
+}
+
+impl BudgetWindow {
+    fn label(self) -> &'static str {
+        match self {
+            BudgetWindow::Daily => "Daily",
+            BudgetWindow::Monthly => "Monthly",
+        }
+    }
+}
+
+/// Agent request refused because a spend cap is reached
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "{} spend budget of ${cap:.2} reached (${spent:.2} spent); agent requests resume at {}",
+    window.label(),
+    resets_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+)]
+pub struct BudgetExceeded {
+    pub window: BudgetWindow,
+    pub cap: f64,
+    pub spent: f64,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Running totals, as kept in `budget.state_file`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendTotals {
+    /// UTC day the totals were last updated on
+    pub day: NaiveDate,
+    /// Spent on `day`
+    pub daily_usd: f64,
+    /// Spent in the month of `day`
+    pub monthly_usd: f64,
+}
+
+impl SpendTotals {
+    /// Zero the totals whose window ended before `today`
+    fn roll_to(&mut self, today: NaiveDate) {
+        if self.day == today {
+            return;
+        }
+        if (self.day.year(), self.day.month()) != (today.year(), today.month()) {
+            self.monthly_usd = 0.0;
+        }
+        self.daily_usd = 0.0;
+        self.day = today;
+    }
+}
+
+/// Estimated spend against the configured caps
+#[derive(Debug)]
+pub struct SpendBudget {
+    daily_usd: Option<f64>,
+    monthly_usd: Option<f64>,
+    state_file: Option<PathBuf>,
+    totals: Arc<Mutex<SpendTotals>>,
+    /// Held while writing `state_file`, so the last write leaves the latest totals
+    write_lock: Arc<Mutex<()>>,
+    /// Why `state_file` could not be loaded; the file is then never overwritten
+    load_error: Option<String>,
+}
+
+impl SpendBudget {
+    /// Create a budget with the given caps and totals, persisting to `state_file` when set
+    pub fn new(config: &BudgetConfig, totals: SpendTotals) -> Self {
+        Self {
+            daily_usd: config.daily_usd,
+            monthly_usd: config.monthly_usd,
+            state_file: config.state_file.as_ref().map(PathBuf::from),
+            totals: Arc::new(Mutex::new(totals)),
+            write_lock: Arc::new(Mutex::new(())),
+            load_error: None,
+        }
+    }
+
+    /// Build the budget from config (None without caps), loading totals from
+    /// `state_file`; a missing file starts from zero, an unreadable one sets `load_error`
+    pub fn from_config(config: &BudgetConfig) -> Option<Self> {
+        if config.is_disabled() {
+            return None;
+        }
+        let loaded = config.state_file.as_deref().map(|path| load_totals(Path::new(path))).transpose();
+        Some(match loaded {
+            Ok(totals) => Self::new(config, totals.flatten().unwrap_or_default()),
+            Err(e) => {
+                let path = config.state_file.as_deref().unwrap_or_default();
+                error!("Budget state {} is unreadable: {}", path, e);
+                Self {
+                    load_error: Some(format!("budget state {} is unreadable: {}", path, e)),
+                    ..Self::new(config, SpendTotals::default())
+                }
+            }
+        })
+    }
+
+    /// Why `state_file` could not be loaded at boot (the gateway refuses to start)
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// Refuse when a cap is reached at `now`
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), BudgetExceeded> {
+        let today = now.date_naive();
+        let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        totals.roll_to(today);
+
+        if let Some(cap) = self.daily_usd.filter(|cap| totals.daily_usd >= *cap) {
+            return Err(BudgetExceeded {
+                window: BudgetWindow::Daily,
+                cap,
+                spent: totals.daily_usd,
+                resets_at: start_of(today + Duration::days(1)),
+            });
+        }
+        if let Some(cap) = self.monthly_usd.filter(|cap| totals.monthly_usd >= *cap) {
+            return Err(BudgetExceeded {
+                window: BudgetWindow::Monthly,
+                cap,
+                spent: totals.monthly_usd,
+                resets_at: start_of(first_of_next_month(today)),
+            });
+        }
+        Ok(())
+    }
+
+    /// Add the cost of an answered request made at `now`, saving the totals to
+    /// `state_file` on the blocking pool
+    pub async fn record(&self, cost_usd: f64, now: DateTime<Utc>) {
+        {
+            let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+            totals.roll_to(now.date_naive());
+            totals.daily_usd += cost_usd;
+            totals.monthly_usd += cost_usd;
+        }
+
+        let Some(path) = self.state_file.clone().filter(|_| self.load_error.is_none()) else {
+            return;
+        };
+        let (totals, write_lock) = (self.totals.clone(), self.write_lock.clone());
+        let written = tokio::task::spawn_blocking(move || {
+            let _writing = write_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let snapshot = totals.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            write_totals(&path, &snapshot).map_err(|e| format!("{}: {}", path.display(), e))
+        })
+        .await;
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Could not save budget state {}", e),
+            Err(e) => warn!("Budget state writer failed: {}", e),
+        }
+    }
+
+    /// Current totals (not yet rolled over to today)
+    pub fn totals(&self) -> SpendTotals {
+        self.totals.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Estimated cost in US dollars of a response's token usage
+pub fn estimated_cost(provider: &str, model: &str, usage: &clanker_agent::Usage) -> f64 {
+    UsageStats::new(usage.prompt_tokens, usage.completion_tokens).calculate_cost(provider, model)
+}
+
+/// Totals saved in `path`; `None` when the file does not exist yet
+fn load_totals(path: &Path) -> Result<Option<SpendTotals>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map(Some).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Replace `path` with the totals through a temporary file, so a crash mid-write
+/// never leaves a truncated state file behind
+fn write_totals(path: &Path, totals: &SpendTotals) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, serde_json::to_vec(totals)?)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn start_of(day: NaiveDate) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+fn first_of_next_month(day: NaiveDate) -> NaiveDate {
+    let (year, month) = if day.month() == 12 {
+        (day.year() + 1, 1)
+    } else {
+        (day.year(), day.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    fn budget(daily_usd: Option<f64>, monthly_usd: Option<f64>) -> SpendBudget {
+        let config = BudgetConfig {
+            daily_usd,
+            monthly_usd,
+            state_file: None,
+        };
+        SpendBudget::from_config(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_refused_once_daily_cap_is_crossed() {
+        let budget = budget(Some(1.0), None);
+        let now = at("2026-10-14T09:30:00Z");
+        budget.record(0.6, now).await;
+        budget.check(now).unwrap();
+
+        budget.record(0.6, now).await;
+        let err = budget.check(now).unwrap_err();
+        assert_eq!(err.window, BudgetWindow::Daily);
+        assert_eq!(err.resets_at, at("2026-10-15T00:00:00Z"));
+        assert_eq!(
+            err.to_string(),
+            "Daily spend budget of $1.00 reached ($1.20 spent); agent requests resume at 2026-10-15T00:00:00Z"
+        );
+
+        // The next UTC day starts from zero
+        budget.check(at("2026-10-15T00:00:01Z")).unwrap();
+        assert_eq!(budget.totals().daily_usd, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_monthly_cap_resets_with_the_month() {
+        let budget = budget(Some(10.0), Some(2.0));
+        budget.record(1.5, at("2026-12-30T12:00:00Z")).await;
+        budget.record(1.0, at("2026-12-31T12:00:00Z")).await;
+
+        let err = budget.check(at("2026-12-31T23:59:59Z")).unwrap_err();
+        assert_eq!(err.window, BudgetWindow::Monthly);
+        assert_eq!(err.resets_at, at("2027-01-01T00:00:00Z"));
+
+        budget.check(at("2027-01-01T00:00:00Z")).unwrap();
+        assert_eq!(budget.totals().monthly_usd, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_totals_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BudgetConfig {
+            daily_usd: Some(1.0),
+            monthly_usd: None,
+            state_file: Some(dir.path().join("state/budget.json").to_string_lossy().into_owned()),
+        };
+        let now = at("2026-10-14T09:30:00Z");
+        SpendBudget::from_config(&config).unwrap().record(1.25, now).await;
+
+        let restarted = SpendBudget::from_config(&config).unwrap();
+        assert_eq!(restarted.totals().daily_usd, 1.25);
+        assert!(restarted.check(now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_state_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("budget.json");
+        std::fs::write(&path, "{\"day\": \"2026-10-").unwrap();
+        let config = BudgetConfig {
+            daily_usd: Some(1.0),
+            monthly_usd: None,
+            state_file: Some(path.to_string_lossy().into_owned()),
+        };
+
+        let budget = SpendBudget::from_config(&config).unwrap();
+        assert!(budget.load_error().unwrap().contains("unreadable"));
+        // The damaged file is left for inspection rather than overwritten with fresh totals
+        budget.record(0.25, at("2026-10-14T09:30:00Z")).await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"day\": \"2026-10-");
+
+        // A good file is replaced whole, leaving no temporary copy
+        std::fs::remove_file(&path).unwrap();
+        let budget = SpendBudget::from_config(&config).unwrap();
+        assert!(budget.load_error().is_none());
+        budget.record(0.25, at("2026-10-14T09:30:00Z")).await;
+        assert_eq!(load_totals(&path).unwrap().unwrap().daily_usd, 0.25);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+//! Response cache: reuse agent answers for repeated identical prompts.
+//!
+//! Keyed on `(provider, model, system prompt, normalized user content)`, so
+//! channels with different system prompts never share answers. Entries expire after
+//! the configured TTL; when full, the least recently used entry is evicted.
+
+use clanker_config::ProcessingConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Cache key: provider, model, system prompt hash, normalized prompt
+type CacheKey = (String, String, u64, String);
+
+#[derive(Debug)]
+struct CacheEntry {
+    content: String,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Monotonic use counter for LRU ordering
+    tick: u64,
+}
+
+/// LRU cache of agent responses with a TTL
+#[derive(Debug)]
+pub struct ResponseCache {
+    capacity: usize,
+    ttl: Duration,
+    inner: Mutex<CacheInner>,
+}
+
+impl ResponseCache {
+    /// Create cache holding up to `capacity` entries for `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// Build cache from config (None when caching is disabled)
+    pub fn from_config(config: &ProcessingConfig) -> Option<Self> {
+        config.cache_enabled.then(|| {
+            Self::new(
+                config.cache_capacity,
+                Duration::from_secs(config.cache_ttl_secs),
+            )
+        })
+    }
+
+    /// Look up a cached response; expired entries are removed
+    pub fn get(&self, provider: &str, model: &str, system_prompt: &str, content: &str) -> Option<String> {
+        let key = cache_key(provider, model, system_prompt, content);
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let expired = match inner.entries.get_mut(&key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.ttl => {
+                entry.last_used = tick;
+                return Some(entry.content.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            inner.entries.remove(&key);
+        }
+        None
+    }
+
+    /// Store a response, evicting the least recently used entry when full
+    pub fn insert(&self, provider: &str, model: &str, system_prompt: &str, content: &str, response: String) {
+        let key = cache_key(provider, model, system_prompt, content);
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+
+        inner.entries.insert(
+            key,
+            CacheEntry {
+                content: response,
+                inserted_at: Instant::now(),
+                last_used: tick,
+            },
+        );
+    }
+
+    /// Number of cached entries (including not-yet-evicted expired ones)
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Normalize prompt text: trim, collapse whitespace, lowercase
+fn normalize(content: &str) -> String {
+    content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn cache_key(provider: &str, model: &str, system_prompt: &str, content: &str) -> CacheKey {
+    let mut hasher = DefaultHasher::new();
+    system_prompt.hash(&mut hasher);
+    (provider.to_string(), model.to_string(), hasher.finish(), normalize(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_on_normalized_content() {
+        let cache = ResponseCache::new(10, Duration::from_secs(60));
+        cache.insert("anthropic", "claude", "sys", "What is  Rust?", "A language".to_string());
+
+        assert_eq!(
+            cache.get("anthropic", "claude", "sys", "  what is rust? "),
+            Some("A language".to_string())
+        );
+        // Different model or system prompt is a different key
+        assert_eq!(cache.get("anthropic", "other", "sys", "what is rust?"), None);
+        assert_eq!(cache.get("anthropic", "claude", "other sys", "what is rust?"), None);
+    }
+
+    #[test]
+    fn test_cache_ttl_expiry() {
+        let cache = ResponseCache::new(10, Duration::from_millis(10));
+        cache.insert("p", "m", "sys", "hi", "hello".to_string());
+        assert!(cache.get("p", "m", "sys", "hi").is_some());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get("p", "m", "sys", "hi").is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = ResponseCache::new(2, Duration::from_secs(60));
+        cache.insert("p", "m", "sys", "a", "A".to_string());
+        cache.insert("p", "m", "sys", "b", "B".to_string());
+
+        // Touch "a" so "b" becomes least recently used
+        assert!(cache.get("p", "m", "sys", "a").is_some());
+        cache.insert("p", "m", "sys", "c", "C".to_string());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("p", "m", "sys", "a").is_some());
+        assert!(cache.get("p", "m", "sys", "b").is_none());
+        assert!(cache.get("p", "m", "sys", "c").is_some());
+    }
+
+    #[test]
+    fn test_cache_disabled_by_default() {
+        assert!(ResponseCache::from_config(&ProcessingConfig::default()).is_none());
+    }
+}
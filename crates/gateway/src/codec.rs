@@ -0,0 +1,223 @@
+//! WebSocket frame encoding: JSON text frames (default) or MessagePack binary frames.
+//!
+//! Clients pick the initial encoding with `?format=msgpack` on the `/ws` upgrade; after
+//! that the gateway answers in whichever encoding the client last used, so a connection
+//! may mix both.
+
+use crate::types::{WsClientMessage, WsServerMessage};
+use axum::extract::ws::{Message as WsMessage, Utf8Bytes};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Encoding of WebSocket data frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    /// JSON in text frames
+    #[default]
+    Json,
+    /// MessagePack in binary frames
+    Msgpack,
+}
+
+/// Frame encoding/decoding errors
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("invalid JSON frame: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to encode MessagePack frame: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("invalid MessagePack frame: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("unknown message type '{type_name}'")]
+    UnknownType { type_name: String, format: WireFormat },
+}
+
+impl CodecError {
+    /// Whether the client sent a frame that could not be decoded (including an
+    /// unknown message type)
+    pub fn is_malformed_frame(&self) -> bool {
+        matches!(self, Self::Json(_) | Self::Decode(_) | Self::UnknownType { .. })
+    }
+}
+
+/// `type` tags of [`WsClientMessage`] variants
+const CLIENT_MESSAGE_TYPES: &[&str] = &[
+    "subscribe",
+    "unsubscribe",
+    "subscribe_all",
+    "unsubscribe_all",
+    "send_message",
+    "reset_conversation",
+    "ping",
+];
+
+/// Unrecognized `type` tag of a frame that failed to decode, read leniently
+/// so that frames with a known type but bad fields keep their original error
+fn unknown_type(value: Option<serde_json::Value>) -> Option<String> {
+    let type_name = value?.get("type")?.as_str()?.to_string();
+    (!CLIENT_MESSAGE_TYPES.contains(&type_name.as_str())).then_some(type_name)
+}
+
+/// Encode a server message as a frame in the given format
+pub fn encode(message: &WsServerMessage, format: WireFormat) -> Result<WsMessage, CodecError> {
+    Ok(match format {
+        WireFormat::Json => WsMessage::Text(Utf8Bytes::from(serde_json::to_string(message)?)),
+        // Named (map) encoding so clients see the same field names as in JSON
+        WireFormat::Msgpack => WsMessage::Binary(rmp_serde::to_vec_named(message)?.into()),
+    })
+}
+
+/// Decode a client data frame, returning the message and the format it used
+///
+/// Returns `None` for control frames (ping, pong, close).
+pub fn decode(frame: &WsMessage) -> Option<Result<(WsClientMessage, WireFormat), CodecError>> {
+    match frame {
+        WsMessage::Text(text) => Some(
+            serde_json::from_str(text)
+                .map(|msg| (msg, WireFormat::Json))
+                .map_err(|e| match unknown_type(serde_json::from_str(text).ok()) {
+                    Some(type_name) => CodecError::UnknownType { type_name, format: WireFormat::Json },
+                    None => e.into(),
+                }),
+        ),
+        WsMessage::Binary(bytes) => Some(
+            rmp_serde::from_slice(bytes)
+                .map(|msg| (msg, WireFormat::Msgpack))
+                .map_err(|e| match unknown_type(rmp_serde::from_slice(bytes).ok()) {
+                    Some(type_name) => CodecError::UnknownType { type_name, format: WireFormat::Msgpack },
+                    None => e.into(),
+                }),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_core::ChannelType;
+
+    fn send_message() -> WsClientMessage {
+        WsClientMessage::SendMessage {
+            channel_id: "123".to_string(),
+            channel_type: ChannelType::Telegram,
+            message: "hello".to_string(),
+            no_fallback: true,
+            json_mode: false,
+            provider: None,
+            model: None,
+        }
+    }
+
+    #[test]
+    fn test_client_message_round_trip_both_formats() {
+        let json = WsMessage::Text(serde_json::to_string(&send_message()).unwrap().into());
+        let (msg, format) = decode(&json).unwrap().unwrap();
+        assert_eq!(format, WireFormat::Json);
+        assert!(matches!(msg, WsClientMessage::SendMessage { no_fallback: true, .. }));
+
+        let msgpack = WsMessage::Binary(rmp_serde::to_vec_named(&send_message()).unwrap().into());
+        let (msg, format) = decode(&msgpack).unwrap().unwrap();
+        assert_eq!(format, WireFormat::Msgpack);
+        assert!(matches!(
+            msg,
+            WsClientMessage::SendMessage { ref channel_id, .. } if channel_id == "123"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_type_reported_by_name() {
+        let frame = WsMessage::Text(r#"{"type":"bogus"}"#.into());
+        match decode(&frame).unwrap() {
+            Err(CodecError::UnknownType { type_name, format }) => {
+                assert_eq!(type_name, "bogus");
+                assert_eq!(format, WireFormat::Json);
+            }
+            other => panic!("expected UnknownType, got {:?}", other),
+        }
+
+        // A known type with bad fields keeps the underlying decode error
+        let frame = WsMessage::Text(r#"{"type":"ping","data":{}}"#.into());
+        assert!(matches!(decode(&frame).unwrap(), Err(CodecError::Json(_))));
+
+        let bytes = rmp_serde::to_vec_named(&serde_json::json!({"type": "bogus"})).unwrap();
+        assert!(matches!(
+            decode(&WsMessage::Binary(bytes.into())).unwrap(),
+            Err(CodecError::UnknownType { format: WireFormat::Msgpack, .. })
+        ));
+    }
+
+    #[test]
+    fn test_client_message_types_cover_every_variant() {
+        let variants = [
+            WsClientMessage::Subscribe { channel_id: "c".to_string(), channel_type: ChannelType::Telegram },
+            WsClientMessage::Unsubscribe { channel_id: "c".to_string() },
+            WsClientMessage::SubscribeAll,
+            WsClientMessage::UnsubscribeAll,
+            send_message(),
+            WsClientMessage::ResetConversation { channel_id: "c".to_string() },
+            WsClientMessage::Ping { timestamp: 0 },
+        ];
+        for variant in variants {
+            let json = serde_json::to_value(&variant).unwrap();
+            assert!(CLIENT_MESSAGE_TYPES.contains(&json["type"].as_str().unwrap()), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_server_message_round_trip_both_formats() {
+        let response = WsServerMessage::send_response(true, Some("id".to_string()), None, Some("hi".to_string()));
+
+        let WsMessage::Text(text) = encode(&response, WireFormat::Json).unwrap() else {
+            panic!("expected text frame");
+        };
+        let decoded: WsServerMessage = serde_json::from_str(&text).unwrap();
+        assert!(matches!(decoded, WsServerMessage::SendResponse { content: Some(ref c), .. } if c == "hi"));
+
+        let WsMessage::Binary(bytes) = encode(&response, WireFormat::Msgpack).unwrap() else {
+            panic!("expected binary frame");
+        };
+        let decoded: WsServerMessage = rmp_serde::from_slice(&bytes).unwrap();
+        assert!(matches!(decoded, WsServerMessage::SendResponse { content: Some(ref c), .. } if c == "hi"));
+    }
+
+    #[test]
+    fn test_message_received_round_trips_as_msgpack() {
+        let message = clanker_core::Message::new(
+            ChannelType::Discord,
+            "chan".to_string(),
+            "user".to_string(),
+            "text".to_string(),
+        );
+        let frame = encode(&WsServerMessage::MessageReceived(message.clone()), WireFormat::Msgpack).unwrap();
+        let WsMessage::Binary(bytes) = frame else {
+            panic!("expected binary frame");
+        };
+
+        match rmp_serde::from_slice::<WsServerMessage>(&bytes).unwrap() {
+            WsServerMessage::MessageReceived(decoded) => {
+                assert_eq!(decoded.id, message.id);
+                assert_eq!(decoded.text, "text");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_frames_and_control_frames() {
+        assert!(decode(&WsMessage::Text("not json".into())).unwrap().is_err());
+        assert!(decode(&WsMessage::Binary(vec![0xc1].into())).unwrap().is_err());
+        assert!(decode(&WsMessage::Ping(Vec::new().into())).is_none());
+    }
+
+    #[test]
+    fn test_format_query_values() {
+        #[derive(Deserialize)]
+        struct Params {
+            format: WireFormat,
+        }
+        let params: Params = serde_json::from_str(r#"{"format":"msgpack"}"#).unwrap();
+        assert_eq!(params.format, WireFormat::Msgpack);
+    }
+}
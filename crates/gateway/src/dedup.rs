@@ -0,0 +1,143 @@
+//! Inbound message deduplication.
+//!
+//! Channels can redeliver updates after a reconnect. A message is a duplicate
+//! when the same sender posted the same text to the same chat within the
+//! configured window (compared on second-resolution message timestamps).
+
+use clanker_config::ProcessingConfig;
+use clanker_core::Message;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Maximum fingerprints remembered regardless of window
+pub const MAX_TRACKED: usize = 4096;
+
+#[derive(Debug, Default)]
+struct DedupInner {
+    /// Fingerprint -> timestamp (unix seconds) of the last accepted message
+    seen: HashMap<u64, i64>,
+    /// Insertion order for pruning
+    order: VecDeque<(u64, i64)>,
+}
+
+/// Bounded record of recently seen inbound messages
+#[derive(Debug)]
+pub struct MessageDeduplicator {
+    window_secs: i64,
+    inner: Mutex<DedupInner>,
+}
+
+impl MessageDeduplicator {
+    /// Create deduplicator treating repeats within `window_secs` as duplicates
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs: window_secs.min(i64::MAX as u64) as i64,
+            inner: Mutex::new(DedupInner::default()),
+        }
+    }
+
+    /// Build deduplicator from config (None when `dedup_window_secs` is 0)
+    pub fn from_config(config: &ProcessingConfig) -> Option<Self> {
+        (config.dedup_window_secs > 0).then(|| Self::new(config.dedup_window_secs))
+    }
+
+    /// Record a message; returns `true` if it was already seen within the window
+    pub fn check_and_record(&self, message: &Message) -> bool {
+        let key = fingerprint(message);
+        let timestamp = message.timestamp.timestamp();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Drop entries that fell out of the window or exceed the bound
+        while let Some(&(old_key, old_ts)) = inner.order.front() {
+            if inner.order.len() < MAX_TRACKED && timestamp - old_ts <= self.window_secs {
+                break;
+            }
+            inner.order.pop_front();
+            if inner.seen.get(&old_key) == Some(&old_ts) {
+                inner.seen.remove(&old_key);
+            }
+        }
+
+        if let Some(&previous) = inner.seen.get(&key) {
+            if (timestamp - previous).abs() <= self.window_secs {
+                return true;
+            }
+        }
+
+        inner.seen.insert(key, timestamp);
+        inner.order.push_back((key, timestamp));
+        false
+    }
+
+    /// Number of fingerprints currently remembered
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).seen.len()
+    }
+
+    /// Check if no fingerprints are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hash of channel, chat, sender and text
+fn fingerprint(message: &Message) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    message.channel_type.hash(&mut hasher);
+    message.channel_id.hash(&mut hasher);
+    message.sender.hash(&mut hasher);
+    message.text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_core::ChannelType;
+
+    fn message(chat: &str, text: &str) -> Message {
+        Message::new(
+            ChannelType::Telegram,
+            chat.to_string(),
+            "user".to_string(),
+            text.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_duplicate_within_window() {
+        let dedup = MessageDeduplicator::new(10);
+        let msg = message("1", "hello");
+
+        assert!(!dedup.check_and_record(&msg));
+        assert!(dedup.check_and_record(&msg.clone()));
+        // Different chat or text is not a duplicate
+        assert!(!dedup.check_and_record(&message("2", "hello")));
+        assert!(!dedup.check_and_record(&message("1", "hello again")));
+        assert_eq!(dedup.len(), 3);
+    }
+
+    #[test]
+    fn test_repeat_outside_window_is_accepted() {
+        let dedup = MessageDeduplicator::new(10);
+        let mut msg = message("1", "hello");
+        assert!(!dedup.check_and_record(&msg));
+
+        msg.timestamp += chrono::Duration::seconds(11);
+        assert!(!dedup.check_and_record(&msg));
+        // The old entry was pruned, only the latest is tracked
+        assert_eq!(dedup.len(), 1);
+    }
+
+    #[test]
+    fn test_disabled_with_zero_window() {
+        let config = ProcessingConfig {
+            dedup_window_secs: 0,
+            ..Default::default()
+        };
+        assert!(MessageDeduplicator::from_config(&config).is_none());
+        assert!(MessageDeduplicator::from_config(&ProcessingConfig::default()).is_some());
+    }
+}
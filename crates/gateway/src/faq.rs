@@ -0,0 +1,154 @@
+//! Static FAQ responses: answer predictable inputs without calling the agent.
+
+use clanker_config::FaqConfig;
+use regex::Regex;
+use tracing::{info, warn};
+
+/// How an FAQ entry matches inbound text
+#[derive(Debug)]
+enum FaqPattern {
+    /// Exact match after trimming, case-insensitive
+    Exact(String),
+    /// Regular expression matched against the trimmed text
+    Regex(Regex),
+}
+
+/// A compiled FAQ entry
+#[derive(Debug)]
+struct FaqRule {
+    pattern: FaqPattern,
+    response: String,
+}
+
+/// Matcher over the configured FAQ entries (first match wins)
+#[derive(Debug, Default)]
+pub struct FaqMatcher {
+    rules: Vec<FaqRule>,
+}
+
+impl FaqMatcher {
+    /// Build matcher from config. Invalid entries are logged and skipped.
+    pub fn from_config(config: &FaqConfig) -> Self {
+        let entries = match config.load_entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to load FAQ entries: {}", e);
+                config.entries.clone()
+            }
+        };
+
+        let mut rules = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let pattern = if entry.regex {
+                match Regex::new(&entry.pattern) {
+                    Ok(re) => FaqPattern::Regex(re),
+                    Err(e) => {
+                        warn!("Skipping FAQ entry with invalid regex '{}': {}", entry.pattern, e);
+                        continue;
+                    }
+                }
+            } else {
+                FaqPattern::Exact(entry.pattern.trim().to_lowercase())
+            };
+            rules.push(FaqRule {
+                pattern,
+                response: entry.response,
+            });
+        }
+
+        if !rules.is_empty() {
+            info!("Loaded {} FAQ entries", rules.len());
+        }
+
+        Self { rules }
+    }
+
+    /// Find the canned response for `text`, if any entry matches
+    pub fn lookup(&self, text: &str) -> Option<&str> {
+        let trimmed = text.trim();
+        let lowered = trimmed.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| match &rule.pattern {
+                FaqPattern::Exact(p) => *p == lowered,
+                FaqPattern::Regex(re) => re.is_match(trimmed),
+            })
+            .map(|rule| rule.response.as_str())
+    }
+
+    /// Number of active FAQ entries
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Check if no FAQ entries are active
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_config::FaqEntry;
+
+    fn entry(pattern: &str, response: &str, regex: bool) -> FaqEntry {
+        FaqEntry {
+            pattern: pattern.to_string(),
+            response: response.to_string(),
+            regex,
+        }
+    }
+
+    #[test]
+    fn test_exact_match_is_trimmed_and_case_insensitive() {
+        let matcher = FaqMatcher::from_config(&FaqConfig {
+            entries: vec![entry("Help", "Help menu", false)],
+            file: None,
+        });
+
+        assert_eq!(matcher.lookup("  help "), Some("Help menu"));
+        assert_eq!(matcher.lookup("HELP"), Some("Help menu"));
+        assert_eq!(matcher.lookup("help me"), None);
+    }
+
+    #[test]
+    fn test_regex_match() {
+        let matcher = FaqMatcher::from_config(&FaqConfig {
+            entries: vec![entry(r"^/start(\s|$)", "Welcome!", true)],
+            file: None,
+        });
+
+        assert_eq!(matcher.lookup("/start"), Some("Welcome!"));
+        assert_eq!(matcher.lookup("/start now"), Some("Welcome!"));
+        assert_eq!(matcher.lookup("/started"), None);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_skipped() {
+        let matcher = FaqMatcher::from_config(&FaqConfig {
+            entries: vec![entry("(unclosed", "never", true), entry("ping", "pong", false)],
+            file: None,
+        });
+
+        assert_eq!(matcher.len(), 1);
+        assert_eq!(matcher.lookup("ping"), Some("pong"));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let matcher = FaqMatcher::from_config(&FaqConfig {
+            entries: vec![entry("hi", "first", false), entry("^hi$", "second", true)],
+            file: None,
+        });
+
+        assert_eq!(matcher.lookup("hi"), Some("first"));
+    }
+
+    #[test]
+    fn test_empty_matcher() {
+        let matcher = FaqMatcher::from_config(&FaqConfig::default());
+        assert!(matcher.is_empty());
+        assert_eq!(matcher.lookup("anything"), None);
+    }
+}
@@ -1,23 +1,41 @@
+use crate::codec::{self, CodecError, WireFormat};
 use crate::processor;
 use crate::state::AppState;
 use anyhow::Result;
-use clanker_core::Message;
-use crate::types::{HealthResponse, WsClientMessage, WsServerMessage};
+use clanker_core::{ChannelType, HealthStatus, Message};
+use crate::state::AgentOutcome;
+use crate::types::{
+    close_frame, ApiError, BroadcastRequest, BroadcastResult, ChannelStatus, ChannelsResponse, ConnectionInfo, ConnectionsResponse, DetailedHealthResponse, HealthResponse, SendRequest,
+    SendResult, StatsResponse, VersionResponse, WsClientMessage, WsServerMessage, WILDCARD_CHANNEL,
+};
 use axum::{
     extract::{
+        rejection::JsonRejection,
+        ConnectInfo,
+        Query,
         State,
         WebSocketUpgrade,
     },
     response::{IntoResponse, Json},
+    Extension,
 };
-use axum::extract::ws::{Message as WsMessage, WebSocket, Utf8Bytes};
+use axum::extract::ws::{close_code, Message as WsMessage, WebSocket};
+use axum::http::{HeaderMap, StatusCode};
+use std::net::SocketAddr;
 use futures_util::{SinkExt, StreamExt};
+use std::collections::BTreeMap;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Health check handler
 #[axum::debug_handler]
 pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
+    // Frequent liveness probes reuse a recent response instead of taking the connections lock
+    if let Some(health) = state.cached_health() {
+        return Json(health);
+    }
+
     let uptime = state.uptime_seconds() as u64;
     let active_connections = state.connection_count().await;
     let total_messages = state.total_message_count();
@@ -39,9 +57,111 @@ pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
         active_connections, total_messages, active_workers
     );
 
+    state.store_health(health.clone());
     Json(health)
 }
 
+/// Detailed health handler: per-component status, 503 when overall is unhealthy
+#[axum::debug_handler]
+pub async fn health_detailed(State(state): State<AppState>) -> impl IntoResponse {
+    let mut components = BTreeMap::new();
+    components.insert(
+        "agent".to_string(),
+        agent_health(
+            state.last_agent_outcome(),
+            state.fallback_agent().is_some(),
+            state.consecutive_primary_failures(),
+            state.degraded_provider(),
+        ),
+    );
+    components.insert(
+        "broadcaster".to_string(),
+        if state.broadcaster().is_active() {
+            HealthStatus::healthy()
+        } else {
+            HealthStatus::unhealthy("shutting down")
+        },
+    );
+    for channel in state.channels() {
+        let status = if channel.is_connected() {
+            HealthStatus::healthy()
+        } else {
+            HealthStatus::degraded("not connected")
+        };
+        components.insert(format!("channel:{}", channel.channel_type()), status);
+    }
+
+    let health = DetailedHealthResponse::from_components(components);
+    let code = match health.overall {
+        HealthStatus::Unhealthy(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+
+    (code, Json(health))
+}
+
+/// Agent status from the most recent primary call. With a fallback, failures
+/// degrade health; once `degraded_provider` is set (see `AppState::degraded_provider`)
+/// the reason says how long the fallback has been answering.
+fn agent_health(
+    outcome: Option<AgentOutcome>,
+    has_fallback: bool,
+    failures: u32,
+    degraded_provider: Option<String>,
+) -> HealthStatus {
+    match (outcome, degraded_provider) {
+        (Some(AgentOutcome::Failure(_, error)), Some(provider)) => HealthStatus::degraded(format!(
+            "primary failed {} times in a row, using fallback {}: {}",
+            failures, provider, error
+        )),
+        (Some(AgentOutcome::Failure(_, error)), None) if has_fallback => {
+            HealthStatus::degraded(format!("primary failing, using fallback: {}", error))
+        }
+        (Some(AgentOutcome::Failure(_, error)), None) => HealthStatus::unhealthy(error),
+        (Some(AgentOutcome::Success(_)) | None, _) => HealthStatus::healthy(),
+    }
+}
+
+/// Collect a snapshot of runtime statistics
+async fn collect_stats(state: &AppState) -> StatsResponse {
+    let (broadcast_lag_events, broadcast_lagged_messages, broadcast_lag_disconnects) =
+        state.broadcast_lag_counts();
+    StatsResponse {
+        uptime_seconds: state.uptime_seconds() as u64,
+        active_connections: state.connection_count().await,
+        peak_connections: state.peak_connection_count(),
+        total_messages: state.total_message_count(),
+        active_workers: state.worker_count(),
+        max_workers: state.worker_max(),
+        broadcast_lag_events,
+        broadcast_lagged_messages,
+        broadcast_lag_disconnects,
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+/// Stats handler (JSON)
+#[axum::debug_handler]
+pub async fn stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(collect_stats(&state).await)
+}
+
+/// Metrics handler (Prometheus text format)
+#[axum::debug_handler]
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = collect_stats(&state).await.to_prometheus();
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
+
+/// Build info handler
+#[axum::debug_handler]
+pub async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse::current())
+}
+
 /// Root handler
 #[axum::debug_handler]
 pub async fn root() -> impl IntoResponse {
@@ -51,35 +171,76 @@ pub async fn root() -> impl IntoResponse {
         "description": "AI Assistant Gateway with WebSocket support",
         "endpoints": {
             "health": "/health",
+            "health_detailed": "/health/detailed",
+            "stats": "/stats",
+            "metrics": "/metrics",
+            "version": "/version",
+            "send": "/send",
+            "connections": "/connections",
+            "channels": "/channels",
+            "broadcast": "/admin/broadcast",
             "ws": "/ws"
         }
     }))
 }
 
+/// Query parameters for the WebSocket upgrade
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct WsParams {
+    /// Initial frame encoding (`json` or `msgpack`)
+    #[serde(default)]
+    pub format: WireFormat,
+}
+
 /// WebSocket handler
 #[axum::debug_handler]
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    Query(params): Query<WsParams>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
 ) -> axum::response::Response {
-    info!("New WebSocket connection requested");
-    ws.on_upgrade(|socket| handle_websocket(socket, state))
+    info!("New WebSocket connection requested ({:?})", params.format);
+    let addr = connect_info
+        .map(|Extension(ConnectInfo(addr))| addr)
+        .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let conn_state = crate::types::ConnectionState::new(addr).with_user_agent(user_agent);
+    ws.on_upgrade(move |socket| handle_websocket(socket, state, params.format, conn_state))
 }
 
 /// Handle WebSocket connection
-async fn handle_websocket(socket: WebSocket, state: AppState) {
-    // Split WebSocket into sender and receiver
-    let (mut sender, mut receiver) = socket.split();
-    let connection_id = Uuid::new_v4();
-    let mut conn_state = crate::types::ConnectionState::new(
-        "0.0.0.0:0".parse().unwrap(),
-    );
-    conn_state.id = connection_id;
+async fn handle_websocket(
+    socket: WebSocket,
+    state: AppState,
+    mut format: WireFormat,
+    conn_state: crate::types::ConnectionState,
+) {
+    // Split WebSocket into sender and receiver; data frames are counted both ways
+    let (sender, mut receiver) = socket.split();
+    let connection_id = conn_state.id;
+    let counters = conn_state.counters.clone();
+    let sent_counters = counters.clone();
+    let mut sender = sender.with(move |msg: WsMessage| {
+        if is_data_frame(&msg) {
+            sent_counters.record_sent();
+        }
+        futures_util::future::ready(Ok::<_, axum::Error>(msg))
+    });
 
     info!("WebSocket connection {} established", connection_id);
 
-    // Add connection to state
-    state.add_connection(connection_id, conn_state.clone()).await;
+    // Add connection to state (rejected and closed when over the connection cap)
+    if let Err(e) = state.add_connection(connection_id, conn_state).await {
+        let error_msg = WsServerMessage::error(e.code(), e.to_string());
+        let _ = sender.send(codec::encode(&error_msg, format).unwrap()).await;
+        let _ = sender.send(ApiError::from(e).close_frame()).await;
+        return;
+    }
 
     // Subscribe to broadcasts
     let mut broadcast_rx = state.broadcaster().subscribe();
@@ -90,11 +251,14 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
         uptime_seconds: state.uptime_seconds() as u64,
     };
 
-    if let Err(e) = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&welcome).unwrap()))).await {
+    if let Err(e) = sender.send(codec::encode(&welcome, format).unwrap()).await {
         error!("Failed to send welcome message: {}", e);
         return;
     }
 
+    // Malformed frames in a row (`server.max_invalid_messages`); any valid frame resets it
+    let mut invalid_frames: u32 = 0;
+
     // Main event loop
     loop {
         tokio::select! {
@@ -102,12 +266,31 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
             Some(result) = receiver.next() => {
                 match result {
                     Ok(msg) => {
-                        if let Err(e) = handle_client_message(msg, &state, &mut sender, &connection_id).await {
-                            error!("Error handling client message: {}", e);
+                        let data_frame = is_data_frame(&msg);
+                        if data_frame {
+                            counters.record_received();
+                        }
+                        match handle_client_message(msg, &state, &mut sender, &connection_id, &mut format).await {
+                            Ok(()) if data_frame => invalid_frames = 0,
+                            Ok(()) => {}
+                            Err(e) => {
+                                if is_malformed_frame(&e) {
+                                    invalid_frames += 1;
+                                    if invalid_frames > state.config().server.max_invalid_messages {
+                                        warn!("Closing connection {} after {} malformed frames", connection_id, invalid_frames);
+                                        let reason = format!("{} malformed messages in a row", invalid_frames);
+                                        let _ = sender.send(close_frame(close_code::POLICY, "TOO_MANY_ERRORS", &reason)).await;
+                                        break;
+                                    }
+                                    debug!("Connection {} sent a malformed frame: {}", connection_id, e);
+                                } else {
+                                    error!("Error handling client message: {}", e);
+                                }
 
-                            // Send error to client
-                            let error_msg = WsServerMessage::error("MESSAGE_ERROR", e.to_string());
-                            let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&error_msg).unwrap()))).await;
+                                // Send error to client
+                                let error_msg = client_error(&e);
+                                let _ = sender.send(codec::encode(&error_msg, format).unwrap()).await;
+                            }
                         }
                     }
                     Err(e) => {
@@ -119,26 +302,17 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
 
             // Handle broadcast messages
             result = broadcast_rx.recv() => {
-                match result {
-                    Ok(broadcast_msg) => {
-                        // Filter messages for this connection's subscriptions
-                        if should_send_to_message(&broadcast_msg, &conn_state) {
-                            if let Err(e) = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&broadcast_msg).unwrap()))).await {
-                                error!("Failed to send broadcast message: {}", e);
-                                break;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Broadcast receive error: {}", e);
-                        break;
-                    }
+                if !forward_broadcast(result, &state, &mut sender, &connection_id, format).await {
+                    break;
                 }
             }
 
             // Handle shutdown signal
             _ = state.shutdown_token().cancelled() => {
                 info!("Shutdown signal received, closing connection {}", connection_id);
+                let _ = sender
+                    .send(close_frame(close_code::AWAY, "SHUTTING_DOWN", "Server is shutting down"))
+                    .await;
                 break;
             }
         }
@@ -149,26 +323,122 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
     info!("WebSocket connection {} closed", connection_id);
 }
 
+/// Relay one broadcast receive result to the client.
+///
+/// A lagged receiver has already skipped ahead to the oldest retained message;
+/// under `skip_to_latest` the client is told how many messages it missed and
+/// the connection carries on. Returns false when the connection should close;
+/// the client is sent a close frame first when the server is the one ending it.
+async fn forward_broadcast<S>(
+    result: Result<WsServerMessage, RecvError>,
+    state: &AppState,
+    sender: &mut S,
+    connection_id: &Uuid,
+    format: WireFormat,
+) -> bool
+where
+    S: futures_util::Sink<WsMessage, Error = axum::Error> + Unpin,
+{
+    match result {
+        Ok(broadcast_msg) => {
+            // Filter messages for this connection's subscriptions
+            let subscribed = match state.get_connection(connection_id).await {
+                Some(current) => should_send_to_message(&broadcast_msg, &current),
+                None => false,
+            };
+            if subscribed {
+                if let Err(e) = sender.send(codec::encode(&broadcast_msg, format).unwrap()).await {
+                    error!("Failed to send broadcast message: {}", e);
+                    return false;
+                }
+            }
+            true
+        }
+        Err(RecvError::Lagged(skipped)) => {
+            warn!("Connection {} lagged broadcast by {} messages", connection_id, skipped);
+            if !state.record_broadcast_lag(skipped) {
+                info!("Disconnecting lagging connection {}", connection_id);
+                let reason = format!("Missed {} broadcast messages", skipped);
+                let _ = sender.send(close_frame(close_code::POLICY, "BROADCAST_LAGGED", &reason)).await;
+                return false;
+            }
+            let notice = WsServerMessage::error(
+                "BROADCAST_LAGGED",
+                format!("Missed {} broadcast messages; continuing from the oldest retained one", skipped),
+            );
+            sender.send(codec::encode(&notice, format).unwrap()).await.is_ok()
+        }
+        Err(RecvError::Closed) => {
+            error!("Broadcast channel closed");
+            let _ = sender.send(ApiError::internal("Broadcast channel closed").close_frame()).await;
+            false
+        }
+    }
+}
+
+/// Whether handling failed because the client's frame could not be decoded
+fn is_malformed_frame(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<CodecError>()
+        .is_some_and(CodecError::is_malformed_frame)
+}
+
+/// Error frame answering a client message that failed
+fn client_error(error: &anyhow::Error) -> WsServerMessage {
+    match error.downcast_ref::<CodecError>() {
+        Some(CodecError::UnknownType { type_name, .. }) => WsServerMessage::error(
+            "UNKNOWN_MESSAGE_TYPE",
+            format!("Unknown message type '{}'", type_name),
+        ),
+        _ => WsServerMessage::error("MESSAGE_ERROR", error.to_string()),
+    }
+}
+
+/// Text and binary frames carry protocol messages; control frames don't
+fn is_data_frame(msg: &WsMessage) -> bool {
+    matches!(msg, WsMessage::Text(_) | WsMessage::Binary(_))
+}
+
 /// Handle client message
-async fn handle_client_message(
+async fn handle_client_message<S>(
     msg: WsMessage,
     state: &AppState,
-    sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
+    sender: &mut S,
     connection_id: &Uuid,
-) -> Result<(), anyhow::Error> {
+    format: &mut WireFormat,
+) -> Result<(), anyhow::Error>
+where
+    S: futures_util::Sink<WsMessage, Error = axum::Error> + Unpin,
+{
     match msg {
-        WsMessage::Text(text) => {
-            // Parse JSON message
-            let client_msg: WsClientMessage = serde_json::from_str(&text)?;
+        WsMessage::Text(_) | WsMessage::Binary(_) => {
+            // Parse JSON or MessagePack; reply in the encoding the client just used
+            let (client_msg, used) = match codec::decode(&msg).expect("text and binary frames always decode") {
+                Ok(decoded) => decoded,
+                Err(e @ CodecError::UnknownType { format: used, .. }) => {
+                    // Answered in the encoding the client used; counts as a malformed frame
+                    *format = used;
+                    return Err(e.into());
+                }
+                Err(e) => return Err(e.into()),
+            };
+            *format = used;
+            let format = *format;
 
             match client_msg {
                 WsClientMessage::Ping { timestamp } => {
                     // Respond with pong
                     let pong = WsServerMessage::Pong { timestamp };
-                    let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&pong)?))).await;
+                    let _ = sender.send(codec::encode(&pong, format)?).await;
                 }
 
                 WsClientMessage::Subscribe { channel_id, channel_type } => {
+                    if let Err(e) = state.subscribe(connection_id, channel_id.clone(), channel_type).await {
+                        warn!("Connection {} subscribe to {} rejected: {}", connection_id, channel_id, e);
+                        let error_msg = WsServerMessage::error(e.code(), e.to_string());
+                        let _ = sender.send(codec::encode(&error_msg, format)?).await;
+                        return Ok(());
+                    }
                     debug!("Connection {} subscribed to {} ({})", connection_id, channel_id, channel_type);
 
                     // Send confirmation
@@ -176,20 +446,58 @@ async fn handle_client_message(
                         channel_id: channel_id.clone(),
                         connection_id: *connection_id,
                     };
-                    let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&sub_msg)?))).await;
+                    let _ = sender.send(codec::encode(&sub_msg, format)?).await;
                 }
 
                 WsClientMessage::Unsubscribe { channel_id } => {
+                    state.unsubscribe(connection_id, &channel_id).await;
                     debug!("Connection {} unsubscribed from {}", connection_id, channel_id);
 
                     // Send confirmation
                     let unsub_msg = WsServerMessage::Unsubscribed {
                         channel_id: channel_id.clone(),
                     };
-                    let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&unsub_msg)?))).await;
+                    let _ = sender.send(codec::encode(&unsub_msg, format)?).await;
+                }
+
+                WsClientMessage::SubscribeAll => {
+                    state.set_subscribe_all(connection_id, true).await;
+                    debug!("Connection {} subscribed to all channels", connection_id);
+
+                    let sub_msg = WsServerMessage::Subscribed {
+                        channel_id: WILDCARD_CHANNEL.to_string(),
+                        connection_id: *connection_id,
+                    };
+                    let _ = sender.send(codec::encode(&sub_msg, format)?).await;
+                }
+
+                WsClientMessage::UnsubscribeAll => {
+                    state.set_subscribe_all(connection_id, false).await;
+                    debug!("Connection {} unsubscribed from all channels", connection_id);
+
+                    let unsub_msg = WsServerMessage::Unsubscribed {
+                        channel_id: WILDCARD_CHANNEL.to_string(),
+                    };
+                    let _ = sender.send(codec::encode(&unsub_msg, format)?).await;
                 }
 
-                WsClientMessage::SendMessage { channel_id, channel_type, message } => {
+                WsClientMessage::ResetConversation { channel_id } => {
+                    let dropped = state.reset_conversation(&channel_id);
+                    debug!(
+                        "Connection {} reset conversation in {} ({} exchanges dropped)",
+                        connection_id, channel_id, dropped
+                    );
+                    let reset_msg = WsServerMessage::ConversationReset { channel_id };
+                    let _ = sender.send(codec::encode(&reset_msg, format)?).await;
+                }
+
+                WsClientMessage::SendMessage { channel_id, channel_type, message, no_fallback, json_mode, provider, model } => {
+                    if let Err(e) = channel_type.validate_channel_id(&channel_id) {
+                        debug!("Connection {} sent an invalid channel id: {}", connection_id, e);
+                        let error_msg = WsServerMessage::error("BAD_REQUEST", e.to_string());
+                        let _ = sender.send(codec::encode(&error_msg, format)?).await;
+                        return Ok(());
+                    }
                     debug!("Sending message to channel {} ({}): {}", channel_id, channel_type, message);
 
                     // Increment message count
@@ -203,15 +511,37 @@ async fn handle_client_message(
                         message,
                     );
 
-                    match processor::process_message(&state, &incoming).await {
-                        Ok(response_msg) => {
-                            let response = WsServerMessage::send_response(
+                    // Acknowledge right away; the agent may take a while to answer
+                    let processing = WsServerMessage::Processing { message_id: incoming.id.clone() };
+                    let _ = sender.send(codec::encode(&processing, format)?).await;
+
+                    let options = processor::ProcessOptions {
+                        no_fallback,
+                        json_mode,
+                        provider,
+                        model,
+                        ..Default::default()
+                    };
+                    let requested_provider = options.provider.clone();
+                    match processor::process_message_with(state, &incoming, &options).await {
+                        Ok(processed) => {
+                            let mut response = WsServerMessage::send_response(
                                 true,
-                                Some(response_msg.id.clone()),
+                                Some(processed.message.id.clone()),
                                 None,
-                                Some(response_msg.text),
+                                Some(processed.message.text.clone()),
                             );
-                            let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&response)?))).await;
+                            if processed.filtered {
+                                response = response.filtered();
+                            }
+                            if let Some(provider) = fallback_answered(state, requested_provider.as_deref(), &processed) {
+                                response = response.degraded(provider);
+                            }
+                            let _ = sender.send(codec::encode(&response, format)?).await;
+
+                            if let Some(error) = channel_unavailable(state, processed.message.channel_type) {
+                                let _ = sender.send(codec::encode(&error, format)?).await;
+                            }
                         }
                         Err(e) => {
                             let response = WsServerMessage::send_response(
@@ -220,7 +550,7 @@ async fn handle_client_message(
                                 Some(e),
                                 None,
                             );
-                            let _ = sender.send(WsMessage::Text(Utf8Bytes::from(serde_json::to_string(&response)?))).await;
+                            let _ = sender.send(codec::encode(&response, format)?).await;
                         }
                     }
                 }
@@ -239,21 +569,143 @@ async fn handle_client_message(
         WsMessage::Pong(_) => {
             // Pong received, ignore
         }
-
-        _ => {
-            debug!("Received unhandled message type from {}", connection_id);
-        }
     }
 
     Ok(())
 }
 
+/// List active WebSocket connections (admin)
+pub async fn list_connections(State(state): State<AppState>) -> Json<ConnectionsResponse> {
+    let now = chrono::Utc::now();
+    let mut connections: Vec<ConnectionInfo> = state
+        .get_all_connections()
+        .await
+        .iter()
+        .map(|(_, conn)| ConnectionInfo::from_state(conn, now))
+        .collect();
+    connections.sort_by_key(|c| c.connected_at);
+
+    Json(ConnectionsResponse {
+        count: connections.len(),
+        connections,
+    })
+}
+
+/// Configured channels: whether each was instantiated and is connected (admin only)
+pub async fn list_channels(State(state): State<AppState>) -> Json<ChannelsResponse> {
+    let channels = state
+        .configured_channels()
+        .iter()
+        .map(|configured| {
+            let channel = state
+                .channel_for(configured.channel_type)
+                .filter(|_| configured.skipped_reason.is_none());
+            ChannelStatus {
+                channel_type: configured.channel_type,
+                instantiated: channel.is_some(),
+                connected: channel.is_some_and(|channel| channel.is_connected()),
+                skipped_reason: configured.skipped_reason.clone(),
+            }
+        })
+        .collect();
+    Json(ChannelsResponse { channels })
+}
+
+/// Announce a notice to every WebSocket connection (admin)
+pub async fn broadcast_notice(
+    State(state): State<AppState>,
+    body: Result<Json<BroadcastRequest>, JsonRejection>,
+) -> Result<Json<BroadcastResult>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::bad_request(e.body_text()))?;
+    if request.message.trim().is_empty() {
+        return Err(ApiError::bad_request("Notice message cannot be empty"));
+    }
+    let recipients = state.broadcaster().send_notice(request.level, request.message);
+    Ok(Json(BroadcastResult { recipients }))
+}
+
+/// Send a message to the agent over plain HTTP (for clients that can't hold a WebSocket)
+pub async fn send_message(
+    State(state): State<AppState>,
+    body: Result<Json<SendRequest>, JsonRejection>,
+) -> Result<Json<SendResult>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::bad_request(e.body_text()))?;
+    if request.message.trim().is_empty() {
+        return Err(ApiError::bad_request("Message text cannot be empty"));
+    }
+    request
+        .channel_type
+        .validate_channel_id(&request.channel_id)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    debug!("HTTP send to channel {} ({})", request.channel_id, request.channel_type);
+
+    state.increment_message_count();
+
+    let incoming = Message::new(
+        request.channel_type,
+        request.channel_id,
+        "user".to_string(),
+        request.message,
+    );
+    let options = processor::ProcessOptions {
+        no_fallback: request.no_fallback,
+        json_mode: request.json_mode,
+        ..Default::default()
+    };
+    let processed = processor::process_message_with(&state, &incoming, &options)
+        .await
+        .map_err(|e| ApiError::new("AGENT_ERROR", e, StatusCode::BAD_GATEWAY))?;
+
+    let message_id = processed.message.id.clone();
+    let content = processed.message.text.clone();
+    let provider = fallback_answered(&state, None, &processed);
+    let delivery_error = match channel_unavailable(&state, processed.message.channel_type) {
+        Some(WsServerMessage::Error { message, .. }) => Some(message),
+        _ => None,
+    };
+
+    Ok(Json(SendResult {
+        message_id,
+        content,
+        filtered: processed.filtered,
+        delivery_error,
+        degraded: provider.is_some(),
+        provider,
+    }))
+}
+
+/// Provider of a reply the fallback agent wrote in place of the primary (or of the
+/// client-picked `requested` provider), if it did
+fn fallback_answered(
+    state: &AppState,
+    requested: Option<&str>,
+    processed: &processor::ProcessedResponse,
+) -> Option<String> {
+    let provider = processed.provider.as_deref()?;
+    if requested == Some(provider) || !state.is_fallback_provider(provider) {
+        return None;
+    }
+    Some(provider.to_string())
+}
+
+/// Error frame for a client sending to a channel type no channel instance backs
+fn channel_unavailable(state: &AppState, channel_type: ChannelType) -> Option<WsServerMessage> {
+    if state.channel_for(channel_type).is_some() {
+        return None;
+    }
+    debug!("No {} channel active for a client send", channel_type);
+    Some(WsServerMessage::error(
+        "CHANNEL_UNAVAILABLE",
+        format!("No {} channel is active", channel_type),
+    ))
+}
+
 /// Check if message should be sent to connection based on subscriptions
 fn should_send_to_message(message: &WsServerMessage, conn_state: &crate::types::ConnectionState) -> bool {
     match message {
         WsServerMessage::MessageReceived(msg) => {
-            // Send if connection is subscribed to this channel
-            conn_state.is_subscribed(&msg.channel_id)
+            // Send if connection is subscribed to this channel, or to all of them
+            conn_state.receives_channel(&msg.channel_id)
         }
         // Send all other message types
         _ => true,
@@ -264,6 +716,507 @@ fn should_send_to_message(message: &WsServerMessage, conn_state: &crate::types::
 mod tests {
     use super::*;
 
+    type WsClient = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+    /// Serve `websocket_handler` on an ephemeral port and connect a client to it
+    async fn spawn_ws_server(state: AppState) -> WsClient {
+        spawn_ws_server_at(state, "/ws").await
+    }
+
+    async fn spawn_ws_server_at(state: AppState, path: &str) -> WsClient {
+        let app = axum::Router::new()
+            .route("/ws", axum::routing::any(websocket_handler))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (ws, _) = tokio_tungstenite::connect_async(format!("ws://{}{}", addr, path)).await.unwrap();
+        ws
+    }
+
+    #[test]
+    fn test_agent_health_from_outcome() {
+        assert_eq!(agent_health(None, false, 0, None), HealthStatus::Healthy);
+        assert_eq!(
+            agent_health(Some(AgentOutcome::Success(chrono::Utc::now())), false, 0, None),
+            HealthStatus::Healthy
+        );
+
+        let failure = AgentOutcome::Failure(chrono::Utc::now(), "rate limited".to_string());
+        assert_eq!(
+            agent_health(Some(failure.clone()), false, 1, None),
+            HealthStatus::unhealthy("rate limited")
+        );
+        assert!(matches!(agent_health(Some(failure.clone()), true, 1, None), HealthStatus::Degraded(_)));
+        assert_eq!(
+            agent_health(Some(failure), true, 3, Some("zai".to_string())),
+            HealthStatus::degraded("primary failed 3 times in a row, using fallback zai: rate limited")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_mixed_encodings() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let mut ws = spawn_ws_server_at(state, "/ws?format=msgpack").await;
+
+        // Welcome uses the negotiated encoding
+        let Frame::Binary(welcome) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected binary welcome");
+        };
+        assert!(matches!(
+            rmp_serde::from_slice::<WsServerMessage>(&welcome).unwrap(),
+            WsServerMessage::Health { .. }
+        ));
+
+        // A JSON frame gets a JSON reply on the same connection
+        ws.send(Frame::Text(r#"{"type":"ping","data":{"timestamp":1}}"#.into()))
+            .await
+            .unwrap();
+        let Frame::Text(pong) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text pong");
+        };
+        assert!(matches!(
+            serde_json::from_str::<WsServerMessage>(&pong).unwrap(),
+            WsServerMessage::Pong { timestamp: 1 }
+        ));
+
+        // ...and switching back to MessagePack still works
+        let ping = rmp_serde::to_vec_named(&WsClientMessage::Ping { timestamp: 2 }).unwrap();
+        ws.send(Frame::Binary(ping.into())).await.unwrap();
+        let Frame::Binary(pong) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected binary pong");
+        };
+        assert!(matches!(
+            rmp_serde::from_slice::<WsServerMessage>(&pong).unwrap(),
+            WsServerMessage::Pong { timestamp: 2 }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_processing_frame_precedes_send_response() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = false;
+        let agent = std::sync::Arc::new(clanker_agent::PlaceholderAgent::new(config.agent.clone()));
+        let state = AppState::with_agent(config, tokio_util::sync::CancellationToken::new(), agent);
+        let mut ws = spawn_ws_server(state).await;
+        // Welcome frame
+        ws.next().await.unwrap().unwrap();
+
+        ws.send(Frame::Text(
+            r#"{"type":"send_message","data":{"channel_id":"123","channel_type":"telegram","message":"hi"}}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let mut frames = Vec::new();
+        for _ in 0..2 {
+            let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+                panic!("expected text frame");
+            };
+            frames.push(serde_json::from_str::<WsServerMessage>(&text).unwrap());
+        }
+        assert!(matches!(frames[0], WsServerMessage::Processing { .. }), "{:?}", frames);
+        assert!(matches!(frames[1], WsServerMessage::SendResponse { success: true, .. }), "{:?}", frames);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_message_type_gets_specific_error() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let mut ws = spawn_ws_server(state).await;
+        // Welcome frame
+        ws.next().await.unwrap().unwrap();
+
+        ws.send(Frame::Text(r#"{"type":"bogus"}"#.into())).await.unwrap();
+        let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text frame");
+        };
+        match serde_json::from_str::<WsServerMessage>(&text).unwrap() {
+            WsServerMessage::Error { code, message } => {
+                assert_eq!(code, "UNKNOWN_MESSAGE_TYPE");
+                assert!(message.contains("bogus"));
+            }
+            other => panic!("expected error frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_malformed_frames_close_connection() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.server.max_invalid_messages = 2;
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let mut ws = spawn_ws_server(state).await;
+        // Welcome frame
+        ws.next().await.unwrap().unwrap();
+
+        // Two bad frames are answered; a valid one resets the count. Unknown types count too.
+        for frame in ["not json", r#"{"type":"bogus"}"#, r#"{"type":"ping","data":{"timestamp":1}}"#, "nope", "{]"] {
+            ws.send(Frame::Text(frame.into())).await.unwrap();
+            let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+                panic!("expected text frame after {:?}", frame);
+            };
+            match serde_json::from_str::<WsServerMessage>(&text).unwrap() {
+                WsServerMessage::Error { code, .. } if frame.contains("bogus") => assert_eq!(code, "UNKNOWN_MESSAGE_TYPE"),
+                WsServerMessage::Error { code, .. } => assert_eq!(code, "MESSAGE_ERROR"),
+                WsServerMessage::Pong { timestamp } => assert_eq!(timestamp, 1),
+                other => panic!("unexpected frame {:?}", other),
+            }
+        }
+
+        // The third in a row closes the connection
+        ws.send(Frame::Text("still not json".into())).await.unwrap();
+        let Frame::Close(Some(close)) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected close frame");
+        };
+        assert_eq!(u16::from(close.code), close_code::POLICY);
+        let reason: serde_json::Value = serde_json::from_str(close.reason.as_str()).unwrap();
+        assert_eq!(reason["code"], "TOO_MANY_ERRORS");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_rejects_invalid_channel_id() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let mut ws = spawn_ws_server(state.clone()).await;
+        // Welcome frame
+        ws.next().await.unwrap().unwrap();
+
+        ws.send(Frame::Text(
+            r#"{"type":"send_message","data":{"channel_id":"tui","channel_type":"telegram","message":"hi"}}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text frame");
+        };
+        match serde_json::from_str::<WsServerMessage>(&text).unwrap() {
+            WsServerMessage::Error { code, message } => {
+                assert_eq!(code, "BAD_REQUEST");
+                assert!(message.contains("numeric chat id"), "{}", message);
+            }
+            other => panic!("expected error frame, got {:?}", other),
+        }
+        // Rejected before reaching the agent
+        assert_eq!(state.total_message_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_connection_counts_messages() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let mut ws = spawn_ws_server(state.clone()).await;
+        // Welcome frame
+        ws.next().await.unwrap().unwrap();
+
+        for timestamp in 1..=2 {
+            let ping = format!(r#"{{"type":"ping","data":{{"timestamp":{}}}}}"#, timestamp);
+            ws.send(Frame::Text(ping.into())).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+        }
+
+        let connections = state.get_all_connections().await;
+        assert_eq!(connections.len(), 1);
+        let info = ConnectionInfo::from_state(&connections[0].1, chrono::Utc::now());
+        assert_eq!(info.messages_received, 2);
+        // Welcome plus two pongs
+        assert_eq!(info.messages_sent, 3);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_connection_gets_policy_close_frame() {
+        use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.server.max_connections = 1;
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let existing = crate::types::ConnectionState::new("127.0.0.1:40000".parse().unwrap());
+        state.add_connection(existing.id, existing).await.unwrap();
+        let mut ws = spawn_ws_server(state).await;
+        let Frame::Text(error) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text error frame");
+        };
+        assert!(error.contains("CONNECTION_LIMIT"), "{}", error);
+
+        let Frame::Close(Some(close)) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected close frame");
+        };
+        assert_eq!(close.code, CloseCode::Policy);
+        let reason: serde_json::Value = serde_json::from_str(&close.reason).unwrap();
+        assert_eq!(reason["code"], "CONNECTION_LIMIT");
+        assert!(reason["message"].as_str().unwrap().contains("limit"));
+    }
+
+    #[tokio::test]
+    async fn test_lagged_receiver_recovers() {
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.server.broadcast_lag_policy = clanker_config::LagPolicy::SkipToLatest;
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let conn = crate::types::ConnectionState::new("127.0.0.1:40000".parse().unwrap());
+        let id = conn.id;
+        state.add_connection(id, conn).await.unwrap();
+
+        // Overflow a small channel so the receiver lags
+        let (tx, mut rx) = tokio::sync::broadcast::channel(2);
+        for timestamp in 0..5 {
+            tx.send(WsServerMessage::Pong { timestamp }).unwrap();
+        }
+        let mut sink = Vec::new().sink_map_err(|never: std::convert::Infallible| -> axum::Error { match never {} });
+
+        let lagged = rx.recv().await;
+        assert!(matches!(lagged, Err(RecvError::Lagged(3))));
+        assert!(forward_broadcast(lagged, &state, &mut sink, &id, WireFormat::Json).await);
+        assert!(forward_broadcast(rx.recv().await, &state, &mut sink, &id, WireFormat::Json).await);
+
+        let frames: Vec<WsServerMessage> = sink
+            .get_ref()
+            .iter()
+            .map(|frame| match frame {
+                WsMessage::Text(text) => serde_json::from_str(text).unwrap(),
+                other => panic!("expected text frame, got {:?}", other),
+            })
+            .collect();
+        assert!(
+            matches!(&frames[0], WsServerMessage::Error { code, message } if code == "BROADCAST_LAGGED" && message.contains('3')),
+            "{:?}",
+            frames
+        );
+        assert!(matches!(frames[1], WsServerMessage::Pong { timestamp: 3 }), "{:?}", frames);
+        assert_eq!(state.broadcast_lag_counts(), (1, 3, 0));
+    }
+
+    #[tokio::test]
+    async fn test_send_without_channel_returns_error() {
+        let config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+
+        match channel_unavailable(&state, clanker_core::ChannelType::Telegram) {
+            Some(WsServerMessage::Error { code, message }) => {
+                assert_eq!(code, "CHANNEL_UNAVAILABLE");
+                assert!(message.contains("telegram"));
+            }
+            other => panic!("expected CHANNEL_UNAVAILABLE error, got {:?}", other),
+        }
+    }
+
+    fn send_router() -> axum::Router {
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = false;
+        let agent = std::sync::Arc::new(clanker_agent::PlaceholderAgent::new(config.agent.clone()));
+        let state = AppState::with_agent(config, tokio_util::sync::CancellationToken::new(), agent);
+        axum::Router::new()
+            .route("/send", axum::routing::post(send_message))
+            .with_state(state)
+    }
+
+    fn post_json(body: &str) -> axum::http::Request<axum::body::Body> {
+        axum::http::Request::post("/send")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_send_endpoint_returns_content() {
+        use tower::ServiceExt;
+
+        let response = send_router()
+            .oneshot(post_json(r#"{"channel_type":"telegram","channel_id":"123","message":"hello"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(result["content"].as_str().unwrap().contains("hello"));
+        assert!(result["message_id"].is_string());
+        // No Telegram channel is active with the example config
+        assert!(result["delivery_error"].as_str().unwrap().contains("telegram"));
+    }
+
+    #[tokio::test]
+    async fn test_send_endpoint_rejects_bad_body() {
+        use tower::ServiceExt;
+
+        let response = send_router()
+            .oneshot(post_json(r#"{"channel_type":"telegram","channel_id":"123","message":"  "}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = send_router().oneshot(post_json("not json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "BAD_REQUEST");
+
+        let response = send_router()
+            .oneshot(post_json(r#"{"channel_type":"discord","channel_id":"general","message":"hi"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(error["message"].as_str().unwrap().contains("discord channel_id"));
+    }
+
+    fn connections_router(admin_token: Option<&str>) -> (axum::Router, AppState) {
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.server.admin_token = admin_token.map(str::to_string);
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let router = axum::Router::new()
+            .route(
+                "/connections",
+                axum::routing::get(list_connections).layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    crate::middleware::require_admin_token,
+                )),
+            )
+            .with_state(state.clone());
+        (router, state)
+    }
+
+    fn get_connections(token: Option<&str>) -> axum::http::Request<axum::body::Body> {
+        let mut request = axum::http::Request::get("/connections");
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        request.body(axum::body::Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connections_lists_connection_details() {
+        use tower::ServiceExt;
+
+        let (router, state) = connections_router(Some("admin-secret"));
+        let conn = crate::types::ConnectionState::new("10.0.0.5:50123".parse().unwrap())
+            .with_user_agent(Some("clanker-tui/1.0".to_string()));
+        let id = conn.id;
+        state.add_connection(id, conn).await.unwrap();
+        state.subscribe(&id, "123".to_string(), clanker_core::ChannelType::Telegram).await.unwrap();
+
+        let response = router.oneshot(get_connections(Some("admin-secret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listing: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(listing["count"], 1);
+        let entry = &listing["connections"][0];
+        assert_eq!(entry["id"], id.to_string());
+        assert_eq!(entry["addr"], "10.0.0.5:50123");
+        assert_eq!(entry["user_agent"], "clanker-tui/1.0");
+        assert!(entry["connected_at"].is_string());
+        assert!(entry["uptime_seconds"].is_u64());
+        assert_eq!(entry["messages_received"], 0);
+        assert_eq!(
+            entry["subscriptions"],
+            serde_json::json!([{"channel_id": "123", "channel_type": "telegram"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_reports_build_info() {
+        let Json(info) = version().await;
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        for (field, value) in [
+            ("git_sha", &info.git_sha),
+            ("build_timestamp", &info.build_timestamp),
+            ("rustc_version", &info.rustc_version),
+        ] {
+            assert!(!value.is_empty(), "{} is empty", field);
+        }
+        assert!(info.rustc_version.starts_with("rustc"), "{}", info.rustc_version);
+        assert!(chrono::DateTime::parse_from_rfc3339(&info.build_timestamp).is_ok(), "{}", info.build_timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_connections_requires_admin_token() {
+        use tower::ServiceExt;
+
+        let (router, _) = connections_router(Some("admin-secret"));
+        let response = router.clone().oneshot(get_connections(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = router.oneshot(get_connections(Some("wrong"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Without a configured token the endpoint is disabled
+        let (router, _) = connections_router(None);
+        let response = router.oneshot(get_connections(Some("anything"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_worker_counts() {
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.max_workers = 4;
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+
+        let _permits = state.worker_semaphore().try_acquire_many_owned(2).unwrap();
+        let workers = state.track_workers(2);
+
+        let response = health_check(State(state.clone())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(health["active_workers"], 2);
+        assert_eq!(health["max_workers"], 4);
+        assert_eq!(state.worker_semaphore().available_permits(), 4 - 2);
+
+        drop(workers);
+        assert_eq!(state.worker_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_health_response_cached_within_interval() {
+        async fn timestamp(state: &AppState) -> serde_json::Value {
+            let response = health_check(State(state.clone())).await.into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()["timestamp"].clone()
+        }
+
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.server.health_cache_ms = 60_000;
+        let state = AppState::new(config.clone(), tokio_util::sync::CancellationToken::new());
+
+        let first = timestamp(&state).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        assert_eq!(timestamp(&state).await, first);
+
+        // With caching disabled every probe is recomputed
+        config.server.health_cache_ms = 0;
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let first = timestamp(&state).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        assert_ne!(timestamp(&state).await, first);
+    }
+
     #[test]
     fn test_health_response_serialization() {
         let health = HealthResponse::new("1.0.0".to_string(), 100, 5, 1000, 2, 5);
@@ -278,4 +1231,193 @@ mod tests {
         assert!(json.contains("\"active_workers\":2"));
         assert!(json.contains("\"max_workers\":5"));
     }
+
+    fn broadcast_router(state: &AppState) -> axum::Router {
+        axum::Router::new()
+            .route(
+                "/admin/broadcast",
+                axum::routing::post(broadcast_notice).layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    crate::middleware::require_admin_token,
+                )),
+            )
+            .with_state(state.clone())
+    }
+
+    fn post_broadcast(token: Option<&str>, body: &str) -> axum::http::Request<axum::body::Body> {
+        let mut request = axum::http::Request::post("/admin/broadcast").header("content-type", "application/json");
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        request.body(axum::body::Body::from(body.to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_subscribed_connection() {
+        use crate::types::NoticeLevel;
+        use tokio_tungstenite::tungstenite::Message as Frame;
+        use tower::ServiceExt;
+
+        let (_, state) = connections_router(Some("admin-secret"));
+        let app = broadcast_router(&state);
+        let mut ws = spawn_ws_server(state.clone()).await;
+        // Welcome frame (sent after the connection subscribes to broadcasts)
+        ws.next().await.unwrap().unwrap();
+
+        let response = app
+            .oneshot(post_broadcast(
+                Some("admin-secret"),
+                r#"{"level":"warning","message":"Restarting in 5 minutes"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: BroadcastResult = serde_json::from_slice(&body).unwrap();
+        assert_eq!(result.recipients, 1);
+
+        let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text frame");
+        };
+        match serde_json::from_str::<WsServerMessage>(&text).unwrap() {
+            WsServerMessage::Notice { level, message } => {
+                assert_eq!(level, NoticeLevel::Warning);
+                assert_eq!(message, "Restarting in 5 minutes");
+            }
+            other => panic!("expected notice frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_requires_admin_token_and_message() {
+        use tower::ServiceExt;
+
+        let (_, state) = connections_router(Some("admin-secret"));
+        let app = broadcast_router(&state);
+        let mut rx = state.broadcaster().subscribe();
+
+        let response = app.clone().oneshot(post_broadcast(None, r#"{"message":"hi"}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app
+            .clone()
+            .oneshot(post_broadcast(Some("admin-secret"), r#"{"message":"  "}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(rx.try_recv().is_err());
+
+        // `code` is accepted for the level and it defaults to info
+        let response = app
+            .clone()
+            .oneshot(post_broadcast(Some("admin-secret"), r#"{"code":"critical","message":"Disk full"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            WsServerMessage::Notice { level: crate::types::NoticeLevel::Critical, .. }
+        ));
+        app.oneshot(post_broadcast(Some("admin-secret"), r#"{"message":"Back"}"#)).await.unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            WsServerMessage::Notice { level: crate::types::NoticeLevel::Info, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_all_receives_unsubscribed_channels() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let (_, state) = connections_router(None);
+        let mut ws = spawn_ws_server(state.clone()).await;
+        ws.next().await.unwrap().unwrap();
+
+        ws.send(Frame::Text(r#"{"type":"subscribe_all"}"#.into())).await.unwrap();
+        let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text frame");
+        };
+        assert!(matches!(
+            serde_json::from_str::<WsServerMessage>(&text).unwrap(),
+            WsServerMessage::Subscribed { channel_id, .. } if channel_id == WILDCARD_CHANNEL
+        ));
+
+        let message = Message::new(
+            clanker_core::ChannelType::Telegram,
+            "999".to_string(),
+            "alice".to_string(),
+            "hi".to_string(),
+        );
+        state.broadcaster().publish(WsServerMessage::MessageReceived(message));
+        let Frame::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("expected text frame");
+        };
+        match serde_json::from_str::<WsServerMessage>(&text).unwrap() {
+            WsServerMessage::MessageReceived(received) => assert_eq!(received.channel_id, "999"),
+            other => panic!("expected message frame, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_should_send_to_message_honors_wildcard() {
+        let mut conn = crate::types::ConnectionState::new("127.0.0.1:1".parse().unwrap());
+        conn.subscribe("123".to_string(), clanker_core::ChannelType::Telegram);
+        let message = |channel_id: &str| {
+            WsServerMessage::MessageReceived(Message::new(
+                clanker_core::ChannelType::Telegram,
+                channel_id.to_string(),
+                "alice".to_string(),
+                "hi".to_string(),
+            ))
+        };
+
+        assert!(should_send_to_message(&message("123"), &conn));
+        assert!(!should_send_to_message(&message("999"), &conn));
+        conn.set_subscribe_all(true);
+        assert!(should_send_to_message(&message("999"), &conn));
+        conn.set_subscribe_all(false);
+        assert!(!should_send_to_message(&message("999"), &conn));
+        assert!(should_send_to_message(&message("123"), &conn));
+    }
+
+    #[tokio::test]
+    async fn test_list_channels_reports_active_and_skipped() {
+        use tower::ServiceExt;
+
+        let mut config: clanker_config::Config =
+            toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        // The example Telegram token is a placeholder; the webhook needs no credentials
+        config.channels.discord = None;
+        config.channels.webhook = Some(clanker_config::WebhookConfig {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            bearer_token: None,
+        });
+        let state = AppState::new(config, tokio_util::sync::CancellationToken::new());
+        let app = axum::Router::new()
+            .route("/channels", axum::routing::get(list_channels))
+            .with_state(state);
+
+        let request = axum::http::Request::get("/channels").body(axum::body::Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: ChannelsResponse = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            listed.channels,
+            vec![
+                ChannelStatus {
+                    channel_type: clanker_core::ChannelType::Telegram,
+                    instantiated: false,
+                    connected: false,
+                    skipped_reason: Some("placeholder token".to_string()),
+                },
+                ChannelStatus {
+                    channel_type: clanker_core::ChannelType::Webhook,
+                    instantiated: true,
+                    connected: true,
+                    skipped_reason: None,
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,182 @@
+//! Per-chat conversation history for direct agent replies.
+//!
+//! With `processing.history_turns` set, the most recent exchanges in a chat
+//! are sent to the agent ahead of the new message. A `/reset` message from the
+//! chat or a `reset_conversation` WebSocket message clears them. At most
+//! `processing.history_max_chats` chats are remembered; the least recently
+//! active one is dropped first.
+
+use clanker_agent::{AgentMessage, MessageRole};
+use clanker_config::ProcessingConfig;
+use clanker_core::ChannelType;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Chat key: channel type and channel id
+type ChatKey = (ChannelType, String);
+
+/// One user message and the reply sent for it
+#[derive(Debug, Clone)]
+struct Exchange {
+    user: String,
+    assistant: String,
+}
+
+/// Exchanges of one chat and when it was last recorded to
+#[derive(Debug, Default)]
+struct Chat {
+    exchanges: VecDeque<Exchange>,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct HistoryInner {
+    chats: HashMap<ChatKey, Chat>,
+    /// Monotonic use counter for LRU ordering
+    tick: u64,
+}
+
+/// Bounded history of recent exchanges per chat
+#[derive(Debug)]
+pub struct ConversationHistory {
+    max_turns: usize,
+    max_chats: usize,
+    inner: Mutex<HistoryInner>,
+}
+
+impl ConversationHistory {
+    /// Create history keeping the last `max_turns` exchanges of up to `max_chats` chats
+    pub fn new(max_turns: usize, max_chats: usize) -> Self {
+        Self {
+            max_turns: max_turns.max(1),
+            max_chats: max_chats.max(1),
+            inner: Mutex::new(HistoryInner::default()),
+        }
+    }
+
+    /// Build history from config (None when `history_turns` is 0)
+    pub fn from_config(config: &ProcessingConfig) -> Option<Self> {
+        (config.history_turns > 0).then(|| Self::new(config.history_turns, config.history_max_chats))
+    }
+
+    /// Earlier exchanges of a chat as alternating user/assistant messages, oldest first
+    pub fn messages(&self, channel_type: ChannelType, channel_id: &str) -> Vec<AgentMessage> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(chat) = inner.chats.get(&(channel_type, channel_id.to_string())) else {
+            return Vec::new();
+        };
+        chat.exchanges
+            .iter()
+            .flat_map(|exchange| {
+                [
+                    message(MessageRole::User, &exchange.user),
+                    message(MessageRole::Assistant, &exchange.assistant),
+                ]
+            })
+            .collect()
+    }
+
+    /// Remember an exchange, dropping the oldest one beyond the turn limit and
+    /// the least recently active chat beyond the chat limit
+    pub fn record(&self, channel_type: ChannelType, channel_id: &str, user: &str, assistant: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+        let key = (channel_type, channel_id.to_string());
+
+        if !inner.chats.contains_key(&key) && inner.chats.len() >= self.max_chats {
+            if let Some(lru) = inner
+                .chats
+                .iter()
+                .min_by_key(|(_, chat)| chat.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                inner.chats.remove(&lru);
+            }
+        }
+
+        let chat = inner.chats.entry(key).or_default();
+        chat.last_used = tick;
+        chat.exchanges.push_back(Exchange {
+            user: user.to_string(),
+            assistant: assistant.to_string(),
+        });
+        while chat.exchanges.len() > self.max_turns {
+            chat.exchanges.pop_front();
+        }
+    }
+
+    /// Forget the history of a channel id (on every channel type); returns the exchanges dropped
+    pub fn reset(&self, channel_id: &str) -> usize {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut dropped = 0;
+        inner.chats.retain(|(_, id), chat| {
+            if id == channel_id {
+                dropped += chat.exchanges.len();
+                false
+            } else {
+                true
+            }
+        });
+        dropped
+    }
+}
+
+fn message(role: MessageRole, content: &str) -> AgentMessage {
+    AgentMessage {
+        role,
+        content: content.to_string(),
+        images: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_keeps_last_turns_per_chat() {
+        let history = ConversationHistory::new(2, 10);
+        history.record(ChannelType::Telegram, "1", "a", "A");
+        history.record(ChannelType::Telegram, "1", "b", "B");
+        history.record(ChannelType::Telegram, "1", "c", "C");
+        history.record(ChannelType::Discord, "2", "x", "X");
+
+        let messages = history.messages(ChannelType::Telegram, "1");
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["b", "B", "c", "C"]);
+        assert!(matches!(messages[0].role, MessageRole::User));
+        assert!(matches!(messages[1].role, MessageRole::Assistant));
+        assert_eq!(history.messages(ChannelType::Discord, "2").len(), 2);
+    }
+
+    #[test]
+    fn test_reset_clears_only_that_channel() {
+        let history = ConversationHistory::new(5, 10);
+        history.record(ChannelType::Telegram, "1", "a", "A");
+        history.record(ChannelType::Telegram, "2", "b", "B");
+
+        assert_eq!(history.reset("1"), 1);
+        assert!(history.messages(ChannelType::Telegram, "1").is_empty());
+        assert_eq!(history.messages(ChannelType::Telegram, "2").len(), 2);
+        assert_eq!(history.reset("1"), 0);
+    }
+
+    #[test]
+    fn test_least_recently_active_chat_is_forgotten() {
+        let history = ConversationHistory::new(5, 2);
+        history.record(ChannelType::Telegram, "1", "a", "A");
+        history.record(ChannelType::Telegram, "2", "b", "B");
+        history.record(ChannelType::Telegram, "1", "c", "C");
+        history.record(ChannelType::Telegram, "3", "d", "D");
+
+        assert!(history.messages(ChannelType::Telegram, "2").is_empty());
+        assert_eq!(history.messages(ChannelType::Telegram, "1").len(), 4);
+        assert_eq!(history.messages(ChannelType::Telegram, "3").len(), 2);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(ConversationHistory::from_config(&ProcessingConfig::default()).is_none());
+    }
+}
@@ -31,18 +31,29 @@
 //! }
 //! ```
 
+pub mod audit;
 pub mod broadcast;
+pub mod budget;
+pub mod cache;
+pub mod codec;
+pub mod dedup;
+pub mod faq;
 pub mod handlers;
+pub mod history;
 pub mod middleware;
+pub mod pool;
+pub mod postprocess;
 pub mod processor;
+pub mod router;
 pub mod server;
 pub mod state;
+pub mod streaming;
 pub mod types;
 
 // Re-export commonly used types
 pub use server::GatewayServer;
 pub use state::AppState;
 pub use types::{
-    ApiError, ConnectionId, ConnectionState, HealthResponse,
+    ApiError, ConnectionId, ConnectionState, HealthResponse, NoticeLevel,
     WsClientMessage, WsServerMessage,
 };
@@ -1,9 +1,13 @@
+use crate::state::AppState;
+use crate::types::ApiError;
 use axum::{
     body::Body,
+    extract::State,
     http::{header, HeaderMap, HeaderValue, Method, StatusCode, Version},
+    response::IntoResponse,
 };
 use std::time::{Duration, Instant};
-use tracing::{debug, warn};
+use tracing::{debug, error, warn};
 
 /// CORS configuration
 pub fn cors_layer() -> tower_http::cors::CorsLayer {
@@ -31,8 +35,7 @@ pub fn add_security_headers(headers: &mut HeaderMap) {
 
 /// Security headers middleware
 pub async fn security_headers_middleware(
-    headers: HeaderMap,
-    mut request: axum::http::Request<Body>,
+    request: axum::http::Request<Body>,
     next: axum::middleware::Next,
 ) -> axum::response::Response {
     let mut response = next.run(request).await;
@@ -40,6 +43,58 @@ pub async fn security_headers_middleware(
     response
 }
 
+/// Admin endpoint guard: requires `Authorization: Bearer <server.admin_token>`.
+/// Admin endpoints answer 403 while no admin token is configured.
+pub async fn require_admin_token(
+    State(state): State<AppState>,
+    request: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let config = state.config();
+    let Some(expected) = config.server.admin_token.as_deref() else {
+        return ApiError::new(
+            "ADMIN_DISABLED",
+            "Admin endpoints are disabled; set OPENCLAW_ADMIN_TOKEN to enable them",
+            StatusCode::FORBIDDEN,
+        )
+        .into_response();
+    };
+
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !provided.is_some_and(|token| tokens_match(token, expected)) {
+        warn!("Rejected admin request to {}", request.uri().path());
+        return ApiError::unauthorized("Missing or invalid admin token").into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Compare tokens without short-circuiting on the first differing byte
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    let (a, b) = (provided.as_bytes(), expected.as_bytes());
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Request timing middleware
+pub async fn request_timing_middleware(
+    request: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let version = request.version();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    log_request_timing(&method, &path, &version, response.status(), start.elapsed());
+
+    response
+}
+
 /// Log request timing
 fn log_request_timing(
     method: &Method,
@@ -59,18 +114,24 @@ fn log_request_timing(
         format!("{}s", duration.as_secs_f32())
     };
 
-    if status.is_success() {
-        debug!("{} {} {:?} {} {}", method, path, version, status_code, duration_str);
+    if status.is_server_error() {
+        error!(%method, path, ?version, status = status_code, duration = %duration_str, "request failed");
     } else if status.is_client_error() {
-        warn!("{} {} {:?} {} {}", method, path, version, status_code, duration_str);
+        warn!(%method, path, ?version, status = status_code, duration = %duration_str, "request rejected");
     } else {
-        warn!("{} {} {:?} {} {}", method, path, version, status_code, duration_str);
+        debug!(%method, path, ?version, status = status_code, duration = %duration_str, "request completed");
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{routing::get, Router};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use tracing::field::{Field, Visit};
+    use tracing::Level;
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
 
     #[test]
     fn test_cors_layer_creation() {
@@ -87,4 +148,62 @@ mod tests {
         assert!(headers.contains_key(header::X_FRAME_OPTIONS));
         assert!(headers.contains_key(header::X_XSS_PROTECTION));
     }
+
+    /// Captured event: level plus formatted fields
+    type Captured = Arc<Mutex<Vec<(Level, String)>>>;
+
+    struct CaptureLayer(Captured);
+
+    impl<S: tracing::Subscriber> Layer<S> for CaptureLayer {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            struct Fields(String);
+            impl Visit for Fields {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    self.0.push_str(&format!("{}={:?} ", field.name(), value));
+                }
+            }
+
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push((*event.metadata().level(), fields.0));
+        }
+    }
+
+    async fn request_log(path: &str) -> Vec<(Level, String)> {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::registry().with(CaptureLayer(captured.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/boom", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(axum::middleware::from_fn(request_timing_middleware));
+        let request = axum::http::Request::builder().uri(path).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let events = captured.lock().unwrap().clone();
+        events
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_request_timing_logged_at_debug_on_success() {
+        let events = request_log("/ok").await;
+
+        assert_eq!(events.len(), 1);
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::DEBUG);
+        assert!(fields.contains("method=GET"));
+        assert!(fields.contains("path=\"/ok\""));
+        assert!(fields.contains("status=200"));
+        assert!(fields.contains("duration="));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_request_timing_logged_at_error_on_server_error() {
+        let events = request_log("/boom").await;
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, Level::ERROR);
+        assert!(events[0].1.contains("status=500"));
+    }
 }
@@ -0,0 +1,88 @@
+//! Round-robin pool of primary agents.
+//!
+//! With `[[agents]]` configured, direct replies rotate across `[agent]` and the
+//! extra agents so load (and per-key rate limits) is spread over several keys.
+
+use clanker_agent::Agent;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Agents taking turns serving direct replies
+pub struct AgentPool {
+    agents: Vec<Arc<dyn Agent + Send + Sync>>,
+    next: AtomicUsize,
+}
+
+impl AgentPool {
+    /// Create a pool; `None` when there are fewer than two agents to rotate across
+    pub fn new(agents: Vec<Arc<dyn Agent + Send + Sync>>) -> Option<Self> {
+        (agents.len() > 1).then(|| Self {
+            agents,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Agents in pool order (index 0 is `[agent]`)
+    pub fn agents(&self) -> &[Arc<dyn Agent + Send + Sync>] {
+        &self.agents
+    }
+
+    /// Number of agents in the pool
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Check if the pool has no agents
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Agents in the order to try for the next request: the agent whose turn
+    /// it is first, then the rest for failover. Each entry carries its index.
+    pub fn rotation(&self) -> Vec<(usize, Arc<dyn Agent + Send + Sync>)> {
+        let len = self.agents.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+        (0..len)
+            .map(|offset| {
+                let index = (start + offset) % len;
+                (index, self.agents[index].clone())
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for AgentPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentPool")
+            .field("agents", &self.agents.len())
+            .field("next", &self.next.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_agent::PlaceholderAgent;
+    use clanker_config::AgentConfig;
+
+    fn agent() -> Arc<dyn Agent + Send + Sync> {
+        Arc::new(PlaceholderAgent::new(AgentConfig::default()))
+    }
+
+    #[test]
+    fn test_single_agent_is_not_a_pool() {
+        assert!(AgentPool::new(vec![agent()]).is_none());
+    }
+
+    #[test]
+    fn test_rotation_starts_at_next_agent() {
+        let pool = AgentPool::new(vec![agent(), agent(), agent()]).unwrap();
+
+        let order = |pool: &AgentPool| pool.rotation().into_iter().map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(order(&pool), vec![0, 1, 2]);
+        assert_eq!(order(&pool), vec![1, 2, 0]);
+        assert_eq!(order(&pool), vec![2, 0, 1]);
+        assert_eq!(order(&pool), vec![0, 1, 2]);
+    }
+}
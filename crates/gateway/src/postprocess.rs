@@ -0,0 +1,111 @@
+//! Cleanup of agent responses before they are sent to a channel.
+//!
+//! Some providers wrap the whole answer in a markdown code fence or pad it
+//! with blank lines. Each transformation is opt-in via
+//! `[processing.post_process]`; with everything disabled the text passes
+//! through unchanged.
+
+use clanker_config::PostProcessConfig;
+
+/// Apply the enabled transformations to a response
+pub fn apply(config: &PostProcessConfig, content: String) -> String {
+    let mut content = content;
+    if config.strip_code_fence {
+        content = strip_code_fence(&content);
+    }
+    if config.collapse_blank_lines {
+        content = collapse_blank_lines(&content);
+    }
+    if config.trim_whitespace {
+        content = content.trim().to_string();
+    }
+    content
+}
+
+/// Unwrap a message that is a single fenced code block (```lang ... ```).
+/// Messages with other fences or text outside the block are left alone.
+pub fn strip_code_fence(content: &str) -> String {
+    let trimmed = content.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return content.to_string();
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return content.to_string();
+    };
+    // The opening line may carry a language tag; the body starts after it
+    let Some((_, body)) = body.split_once('\n') else {
+        return content.to_string();
+    };
+    if body.contains("```") {
+        return content.to_string();
+    }
+    body.trim_end_matches(['\n', '\r']).to_string()
+}
+
+/// Collapse runs of blank (or whitespace-only) lines into a single blank line
+pub fn collapse_blank_lines(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut previous_blank = false;
+    for line in content.lines() {
+        let blank = line.trim().is_empty();
+        if blank && previous_blank {
+            continue;
+        }
+        lines.push(if blank { "" } else { line });
+        previous_blank = blank;
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_enabled() -> PostProcessConfig {
+        PostProcessConfig {
+            trim_whitespace: true,
+            strip_code_fence: true,
+            collapse_blank_lines: true,
+        }
+    }
+
+    #[test]
+    fn test_disabled_is_a_no_op() {
+        let content = "  ```\ncode\n```\n\n\n\nmore  \n".to_string();
+        assert_eq!(apply(&PostProcessConfig::default(), content.clone()), content);
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        let config = PostProcessConfig {
+            trim_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(apply(&config, "\n  Hello there.  \n\n".to_string()), "Hello there.");
+    }
+
+    #[test]
+    fn test_strip_code_fence_around_whole_message() {
+        assert_eq!(strip_code_fence("```markdown\n# Title\n\nBody\n```"), "# Title\n\nBody");
+        assert_eq!(strip_code_fence("\n```\nplain\n```\n"), "plain");
+
+        // Fences that don't wrap the whole message are kept
+        let mixed = "Here you go:\n```rust\nfn main() {}\n```";
+        assert_eq!(strip_code_fence(mixed), mixed);
+        let two_blocks = "```\na\n```\ntext\n```\nb\n```";
+        assert_eq!(strip_code_fence(two_blocks), two_blocks);
+        assert_eq!(strip_code_fence("```inline```"), "```inline```");
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        assert_eq!(collapse_blank_lines("one\n\n\n\ntwo\n \n\t\nthree"), "one\n\ntwo\n\nthree");
+        assert_eq!(collapse_blank_lines("one\n\ntwo"), "one\n\ntwo");
+    }
+
+    #[test]
+    fn test_all_enabled() {
+        let content = "\n```text\nAnswer:\n\n\n\nDone.\n```\n".to_string();
+        assert_eq!(apply(&all_enabled(), content), "Answer:\n\nDone.");
+    }
+}
@@ -1,20 +1,188 @@
-//! Message processing: core::Message → Agent → core::Message
+//! Message processing: core::Message → Router → Agent → core::Message
 //!
-//! When orchestration is enabled, uses Master_Clanker which may delegate to Worker_Clankers.
+//! The router chain picks a static reply, the agent, or Master_Clanker orchestration
+//! (which may delegate to Worker_Clankers).
 
+use crate::audit::AuditedAnswer;
+use crate::budget::estimated_cost;
+use crate::postprocess;
+use crate::router::Route;
 use crate::state::AppState;
-use clanker_agent::{Agent, AgentFactory, AgentMessage, MessageRole, MASTER_SYSTEM_PROMPT};
-use clanker_core::Message;
+use crate::streaming::{self, EditThrottle, StreamTarget, StreamedReply};
+use crate::types::WsServerMessage;
+use clanker_agent::{
+    Agent, AgentError, AgentFactory, AgentMessage, AgentResponse, ChatOptions, Delegation, ImageInput, MasterClanker,
+    MessageRole, ProgressCallback, Usage, WorkerProgress, WorkerResult, DELEGATION_LIMIT_PROMPT, DIRECT_ANSWER_PROMPT,
+    MASTER_SYSTEM_PROMPT, system_prompts,
+};
+use clanker_config::InboundLimitMode;
+use clanker_core::{ChannelType, Message};
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Notice sent when the provider filtered a response and returned no text
+pub const DEFAULT_REFUSAL_MESSAGE: &str = "This response was withheld by the provider's content filter.";
+
+/// Reply to chat users whose message exceeds `processing.max_inbound_chars` in reject mode
+pub const INBOUND_TOO_LONG_MESSAGE: &str =
+    "Sorry, that message is too long for me to handle. Please send a shorter one.";
+
+/// Reply to chat users who send `/reset`
+pub const CONVERSATION_RESET_MESSAGE: &str = "Conversation history cleared.";
+
+/// Processed AI response for an incoming message
+#[derive(Debug, Clone)]
+pub struct ProcessedResponse {
+    /// Response message addressed to the originating channel
+    pub message: Message,
+    /// Provider refused or content-filtered the response
+    pub filtered: bool,
+    /// Reply is already in the chat (streamed into a placeholder); don't send it again
+    pub delivered: bool,
+    /// Provider of the agent that wrote the reply (None when the gateway answered
+    /// itself, e.g. a static route or `/reset`)
+    pub provider: Option<String>,
+}
+
+/// Per-request processing options
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// Skip the fallback agent and return the primary's error instead
+    pub no_fallback: bool,
+    /// Ask the agent for a JSON object response (direct call; bypasses orchestration and the cache)
+    pub json_mode: bool,
+    /// Stream direct answers into a placeholder reply on this channel (bypasses the cache)
+    pub stream_to: Option<StreamTarget>,
+    /// Client-picked provider for this request (an `agent.model_overrides` entry)
+    pub provider: Option<String>,
+    /// Client-picked model for this request (an `agent.model_overrides` entry)
+    pub model: Option<String>,
+}
 
 /// Process incoming message through agent (or orchestrator) and return AI response
-pub async fn process_message(state: &AppState, incoming: &Message) -> Result<Message, String> {
+pub async fn process_message(state: &AppState, incoming: &Message) -> Result<ProcessedResponse, String> {
+    process_message_with(state, incoming, &ProcessOptions::default()).await
+}
+
+/// Process a message received from a channel listener.
+/// Returns `None` for redeliveries already seen within `processing.dedup_window_secs`.
+pub async fn process_channel_message(
+    state: &AppState,
+    incoming: &Message,
+) -> Option<Result<ProcessedResponse, String>> {
+    if state.is_duplicate(incoming) {
+        debug!(
+            "Skipping duplicate message {} from {} ({})",
+            incoming.id, incoming.sender, incoming.channel_type
+        );
+        return None;
+    }
+    if !chat_allowed(state, incoming) {
+        debug!("Ignoring message from chat {} ({}): not in allowed_chats", incoming.channel_id, incoming.channel_type);
+        return None;
+    }
+    let bot_username = state.channel_for(incoming.channel_type).and_then(|c| c.bot_username());
+    if state.conversation_history().is_some() && is_reset_command(&incoming.text, bot_username.as_deref()) {
+        let dropped = state.reset_conversation(&incoming.channel_id);
+        debug!("Reset conversation in {} ({} exchanges dropped)", incoming.channel_id, dropped);
+        return Some(Ok(ProcessedResponse {
+            message: response_message(incoming, CONVERSATION_RESET_MESSAGE.to_string()),
+            filtered: false,
+            delivered: false,
+            provider: None,
+        }));
+    }
+    // Chat users get a polite reply rather than silence
+    if let Err(e) = limit_inbound(state, incoming) {
+        warn!("Rejecting channel message from {} ({}): {}", incoming.sender, incoming.channel_type, e);
+        return Some(Ok(ProcessedResponse {
+            message: response_message(incoming, INBOUND_TOO_LONG_MESSAGE.to_string()),
+            filtered: false,
+            delivered: false,
+            provider: None,
+        }));
+    }
+    let options = ProcessOptions {
+        stream_to: streaming::target_for(state, incoming),
+        ..Default::default()
+    };
+    Some(process_message_with(state, incoming, &options).await)
+}
+
+/// Check `channels.telegram.allowed_chats` (read per message, so reloads apply);
+/// other channels have no allowlist
+fn chat_allowed(state: &AppState, incoming: &Message) -> bool {
+    if incoming.channel_type != ChannelType::Telegram {
+        return true;
+    }
+    let config = state.config();
+    match config.channels.telegram.as_ref().and_then(|t| t.allowed_chats.as_ref()) {
+        Some(chats) => chats.iter().any(|chat| chat == &incoming.channel_id),
+        None => true,
+    }
+}
+
+/// Check for the `/reset` chat command, including Telegram's `/reset@botname` form
+/// when it names this bot (`bot_username`); commands for other bots are not ours
+fn is_reset_command(text: &str, bot_username: Option<&str>) -> bool {
+    match text.trim().split_once('@') {
+        Some((command, addressee)) => {
+            command.eq_ignore_ascii_case("/reset")
+                && bot_username.is_some_and(|name| name.eq_ignore_ascii_case(addressee))
+        }
+        None => text.trim().eq_ignore_ascii_case("/reset"),
+    }
+}
+
+/// Apply `processing.max_inbound_chars`: a truncated copy of the message in
+/// truncate mode, an error in reject mode, `None` when it is within the limit
+fn limit_inbound(state: &AppState, incoming: &Message) -> Result<Option<Message>, String> {
+    let processing = &state.config().processing;
+    let Some(max) = processing.max_inbound_chars else {
+        return Ok(None);
+    };
+    let len = incoming.text.chars().count();
+    if len <= max {
+        return Ok(None);
+    }
+
+    match processing.inbound_limit_mode {
+        InboundLimitMode::Reject => Err(format!("Message too long: {} characters (limit {})", len, max)),
+        InboundLimitMode::Truncate => {
+            debug!("Truncating inbound message from {} to {} characters", len, max);
+            let mut truncated = incoming.clone();
+            truncated.text = incoming.text.chars().take(max).collect();
+            Ok(Some(truncated))
+        }
+    }
+}
+
+/// Process incoming message with per-request options
+pub async fn process_message_with(
+    state: &AppState,
+    incoming: &Message,
+    options: &ProcessOptions,
+) -> Result<ProcessedResponse, String> {
+    let truncated = limit_inbound(state, incoming)?;
+    let incoming = truncated.as_ref().unwrap_or(incoming);
     let user_content = incoming.text.clone();
     if user_content.is_empty() {
         return Err("Message text cannot be empty".to_string());
     }
 
+    let override_agent = override_agent(state, options.provider.as_deref(), options.model.as_deref());
+    let override_agent = match override_agent {
+        Ok(agent) => agent,
+        Err(e) => {
+            let config = state.config();
+            let provider = options.provider.as_deref().unwrap_or(&config.agent.provider);
+            let model = options.model.as_deref().unwrap_or("*");
+            audit_failure(state, incoming, &user_content, (provider, model), Duration::ZERO, &e).await;
+            return Err(e);
+        }
+    };
+
     info!(
         "Processing message from {} ({}): {} chars",
         incoming.sender,
@@ -22,51 +190,335 @@ pub async fn process_message(state: &AppState, incoming: &Message) -> Result<Mes
         user_content.len()
     );
 
-    let fallback = state.fallback_agent();
-    let content = if state.orchestration_enabled() {
-        if let Some(orchestrator) = state.orchestrator() {
-            process_with_orchestration(state, orchestrator, fallback.as_deref(), &user_content).await?
-        } else {
-            process_direct(state.agent().as_ref(), fallback.as_deref(), &user_content).await?
+    let route = state.router_chain().route(state, incoming).await;
+    if let Route::Static(answer) = route {
+        debug!("Static route, skipping agent call");
+        return Ok(ProcessedResponse {
+            message: response_message(incoming, answer),
+            filtered: false,
+            delivered: false,
+            provider: None,
+        });
+    }
+
+    let chat_options = ChatOptions { json_mode: options.json_mode };
+    let agent = override_agent.clone().unwrap_or_else(|| state.agent());
+    if chat_options.json_mode && !agent.supports_json_mode() {
+        let error = format!("JSON mode is not supported by provider {}", agent.provider());
+        audit_failure(state, incoming, &user_content, (agent.provider(), agent.model()), Duration::ZERO, &error).await;
+        return Err(error);
+    }
+
+    if let Some(budget) = state.budget() {
+        if let Err(e) = budget.check(chrono::Utc::now()) {
+            warn!("Refusing agent request from {} ({}): {}", incoming.sender, incoming.channel_type, e);
+            let error = e.to_string();
+            audit_failure(state, incoming, &user_content, (agent.provider(), agent.model()), Duration::ZERO, &error).await;
+            return Err(error);
+        }
+    }
+
+    // Held until the reply is built, bounding concurrent provider calls
+    let _permit = state
+        .request_semaphore()
+        .acquire_owned()
+        .await
+        .map_err(|e| format!("Request limiter closed: {}", e))?;
+
+    let fallback = if options.no_fallback {
+        debug!("Fallback disabled for this request");
+        None
+    } else {
+        state.fallback_agent()
+    };
+    let mut streamed = None;
+
+    let images = image_inputs(agent.as_ref(), incoming);
+    let orchestrated = override_agent.is_none()
+        && !chat_options.json_mode
+        && matches!(route, Route::Orchestrate)
+        && state.orchestrator().is_some();
+    let started = Instant::now();
+    let response = match (route, state.orchestrator()) {
+        // A client-picked model answers itself, bypassing orchestration, the pool and the cache
+        _ if override_agent.is_some() => {
+            process_overridden(state, agent.as_ref(), fallback.as_deref(), incoming, &user_content, &images, &chat_options)
+                .await
+        }
+        // JSON must come straight from the agent, not a synthesis or a cached text answer
+        _ if chat_options.json_mode => {
+            process_direct(state, fallback.as_deref(), incoming, &user_content, &images, &chat_options).await
+        }
+        (Route::Orchestrate, Some(orchestrator)) => {
+            process_with_orchestration(state, &orchestrator, fallback.as_deref(), &user_content, &images).await
+        }
+        // The cache is keyed on text only, so messages with images always reach the agent
+        _ if !images.is_empty() => {
+            process_direct(state, fallback.as_deref(), incoming, &user_content, &images, &ChatOptions::default()).await
+        }
+        _ => match &options.stream_to {
+            Some(target) => process_streamed(state, fallback.as_deref(), incoming, &user_content, target)
+                .await
+                .map(|(response, reply)| {
+                    streamed = reply;
+                    response
+                }),
+            None => process_direct_cached(state, fallback.as_deref(), incoming, &user_content).await,
+        },
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            audit_failure(state, incoming, &user_content, (agent.provider(), agent.model()), started.elapsed(), &e).await;
+            return Err(e);
         }
+    };
+
+    if let Some(budget) = state.budget() {
+        budget.record(estimated_cost(&response.provider, &response.model, &response.usage), chrono::Utc::now()).await;
+    }
+
+    let filtered = response.is_filtered();
+    let content = if filtered {
+        warn!(
+            "Response from {} was filtered (finish_reason={})",
+            response.provider, response.finish_reason
+        );
+        filtered_content(state, response.content)
+    } else {
+        postprocess::apply(&state.config().processing.post_process, response.content)
+    };
+    let content = if !filtered && content.trim().is_empty() {
+        // Channels reject empty messages; say something instead
+        warn!(
+            "Empty response from {} (finish_reason={})",
+            response.provider, response.finish_reason
+        );
+        state.config().processing.empty_response_message.clone()
     } else {
-        process_direct(state.agent().as_ref(), fallback.as_deref(), &user_content).await?
+        content
+    };
+
+    // Orchestrated answers are built without history, so they don't join it either
+    if let Some(history) = state.conversation_history().filter(|_| !filtered && !orchestrated) {
+        history.record(incoming.channel_type, &incoming.channel_id, &user_content, &content);
+    }
+
+    if let Some(audit) = state.audit_log() {
+        let answer = AuditedAnswer {
+            provider: &response.provider,
+            model: &response.model,
+            usage: &response.usage,
+            latency: started.elapsed(),
+            filtered,
+            reply: &content,
+        };
+        audit.append(&audit.record_for(incoming, &user_content, answer)).await;
+    }
+
+    // The placeholder gets the final text; if that edit fails the reply is sent anew
+    let delivered = match &streamed {
+        Some(reply) => match reply.edit(&content).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Could not finish streamed reply: {}; sending it instead", e);
+                reply.discard().await;
+                false
+            }
+        },
+        None => false,
     };
 
-    let response_message = Message::new(
+    Ok(ProcessedResponse {
+        message: response_message(incoming, content),
+        filtered,
+        delivered,
+        provider: Some(response.provider.clone()),
+    })
+}
+
+/// Append an audit line for a request that got no answer from `agent` (provider, model)
+async fn audit_failure(
+    state: &AppState,
+    incoming: &Message,
+    prompt: &str,
+    agent: (&str, &str),
+    latency: Duration,
+    error: &str,
+) {
+    if let Some(audit) = state.audit_log() {
+        audit.append(&audit.failure_for(incoming, prompt, agent.0, agent.1, latency, error)).await;
+    }
+}
+
+/// Build the assistant reply addressed to the incoming message's channel and sender
+fn response_message(incoming: &Message, content: String) -> Message {
+    Message::new(
         incoming.channel_type,
         incoming.channel_id.clone(),
         "assistant".to_string(),
         content,
-    );
+    )
+    .set_reply_to(incoming.id.clone())
+    .set_in_reply_to_sender(incoming.sender.clone())
+}
 
-    Ok(response_message)
+/// Image attachments to send with the message, when the answering agent accepts images
+fn image_inputs(agent: &(dyn Agent + Send + Sync), incoming: &Message) -> Vec<ImageInput> {
+    let images: Vec<_> = incoming
+        .metadata
+        .attachments
+        .iter()
+        .filter_map(ImageInput::from_attachment)
+        .collect();
+    if !images.is_empty() && !agent.supports_vision() {
+        debug!(
+            "{} does not accept images, skipping {} attachment(s)",
+            agent.model(),
+            images.len()
+        );
+        return Vec::new();
+    }
+    images
 }
 
-/// Direct agent call (no orchestration). Retries with fallback agent on failure.
-async fn process_direct(
-    agent: &(dyn Agent + Send + Sync),
+/// System prompt for a channel: `agent.system_prompts` override or the built-in prompt for its type
+fn channel_system_prompt(state: &AppState, channel_type: ChannelType) -> String {
+    state
+        .config()
+        .agent
+        .system_prompts
+        .get(channel_type.as_str())
+        .cloned()
+        .unwrap_or_else(|| system_prompts::for_channel(channel_type).content)
+}
+
+/// Content to send for a filtered response: configured message, provider text, or a generic notice
+fn filtered_content(state: &AppState, provider_content: String) -> String {
+    if let Some(message) = &state.config().processing.refusal_message {
+        return message.clone();
+    }
+    if provider_content.trim().is_empty() {
+        return DEFAULT_REFUSAL_MESSAGE.to_string();
+    }
+    provider_content
+}
+
+/// Direct agent call through the response cache (when enabled).
+/// Only unfiltered answers from the primary agent are cached; with conversation
+/// history the answer depends on earlier turns, so the cache is skipped.
+async fn process_direct_cached(
+    state: &AppState,
     fallback: Option<&(dyn Agent + Send + Sync)>,
+    incoming: &Message,
     user_content: &str,
-) -> Result<String, String> {
-    let agent_messages = vec![AgentMessage {
-        role: MessageRole::User,
-        content: user_content.to_string(),
-    }];
+) -> Result<AgentResponse, String> {
+    let Some(cache) = state.response_cache().filter(|_| state.conversation_history().is_none()) else {
+        return process_direct(state, fallback, incoming, user_content, &[], &ChatOptions::default()).await;
+    };
 
-    let result = agent.chat(agent_messages.clone()).await;
-    if let Ok(response) = result {
-        debug!(
-            "Agent response: {} chars, model={}",
-            response.content.len(),
-            response.model
-        );
-        return Ok(response.content);
+    let agent = state.agent();
+    let system_prompt = channel_system_prompt(state, incoming.channel_type);
+    if let Some(content) = cache.get(agent.provider(), agent.model(), &system_prompt, user_content) {
+        debug!("Response cache hit");
+        return Ok(AgentResponse {
+            content,
+            finish_reason: "stop".to_string(),
+            usage: Default::default(),
+            model: agent.model().to_string(),
+            provider: agent.provider().to_string(),
+        });
+    }
+
+    let response = process_direct(state, fallback, incoming, user_content, &[], &ChatOptions::default()).await?;
+    if !response.is_filtered()
+        && !response.content.trim().is_empty()
+        && response.provider == agent.provider()
+        && response.model == agent.model()
+    {
+        cache.insert(agent.provider(), agent.model(), &system_prompt, user_content, response.content.clone());
+    }
+    Ok(response)
+}
+
+/// Progress callback broadcasting worker events, when enabled in config
+fn worker_progress(state: &AppState) -> Option<ProgressCallback> {
+    if !state.config().orchestration.progress_events {
+        return None;
+    }
+    let broadcaster = state.broadcaster().clone();
+    Some(Arc::new(move |progress| {
+        broadcaster.publish(match progress {
+            WorkerProgress::Started { identity } => WsServerMessage::WorkerStarted { identity },
+            WorkerProgress::Completed { identity } => WsServerMessage::WorkerCompleted { identity },
+        })
+    }))
+}
+
+/// Direct agent call (no orchestration). Retries with fallback agent on transient
+/// failures; a rejected key or request is returned as is. The chat's earlier exchanges go between the system prompt and the new message.
+async fn process_direct(
+    state: &AppState,
+    fallback: Option<&(dyn Agent + Send + Sync)>,
+    incoming: &Message,
+    user_content: &str,
+    images: &[ImageInput],
+    options: &ChatOptions,
+) -> Result<AgentResponse, String> {
+    let agent_messages = direct_messages(state, incoming, user_content, images);
+    let mut agents = state.direct_agents();
+    if options.json_mode {
+        agents.retain(|(index, agent)| {
+            let supported = agent.supports_json_mode();
+            if !supported {
+                debug!("Skipping pool agent {} ({}): JSON mode is not supported", index, agent.provider());
+            }
+            supported
+        });
+    }
+    let fallback = fallback.filter(|fb| {
+        let supported = !options.json_mode || fb.supports_json_mode();
+        if !supported {
+            debug!("Skipping fallback ({}): JSON mode is not supported", fb.provider());
+        }
+        supported
+    });
+    let pooled = agents.len() > 1;
+    let mut last_error = None;
+    for (position, (index, agent)) in agents.iter().enumerate() {
+        let result = agent.chat_cancellable(agent_messages.clone(), options, state.shutdown_token().clone()).await;
+        state.record_agent_result(&result);
+        match result {
+            Ok(response) => {
+                debug!(
+                    "Agent response: {} chars, model={}",
+                    response.content.len(),
+                    response.model
+                );
+                if pooled {
+                    info!("Request served by pool agent {} ({}/{})", index, agent.provider(), agent.model());
+                }
+                return Ok(response);
+            }
+            // Rate limits are per key: try the next agent in the pool
+            Err(AgentError::RateLimited(_)) if position + 1 < agents.len() => {
+                warn!("Pool agent {} ({}) is rate limited, trying the next agent", index, agent.provider());
+            }
+            Err(e) => {
+                last_error = Some(e);
+                break;
+            }
+        }
+    }
+    if let Some(e) = last_error.as_ref().filter(|e| !e.is_transient()) {
+        warn!("Primary agent rejected the request, not retrying with the fallback: {}", e);
+        return Err(e.to_string());
     }
+    let error = last_error.map(|e| e.to_string()).unwrap_or_default();
 
     if let Some(fb) = fallback {
         error!("Primary agent failed, retrying with fallback ({})", fb.provider());
-        let response = fb.chat(agent_messages).await.map_err(|e| {
+        let response = fb.chat_cancellable(agent_messages, options, state.shutdown_token().clone()).await.map_err(|e| {
             error!("Fallback agent error: {}", e);
             e.to_string()
         })?;
@@ -75,44 +527,169 @@ async fn process_direct(
             response.content.len(),
             response.model
         );
-        return Ok(response.content);
+        return Ok(response);
     }
 
-    Err(result.unwrap_err().to_string())
+    Err(error)
+}
+
+/// Direct call to a client-picked agent, retrying with the fallback agent on transient failures.
+/// Its results are not recorded as the primary agent's health.
+async fn process_overridden(
+    state: &AppState,
+    agent: &(dyn Agent + Send + Sync),
+    fallback: Option<&(dyn Agent + Send + Sync)>,
+    incoming: &Message,
+    user_content: &str,
+    images: &[ImageInput],
+    options: &ChatOptions,
+) -> Result<AgentResponse, String> {
+    info!("Request served by override agent ({}/{})", agent.provider(), agent.model());
+    let agent_messages = direct_messages(state, incoming, user_content, images);
+    let error = match agent.chat_cancellable(agent_messages.clone(), options, state.shutdown_token().clone()).await {
+        Ok(response) => return Ok(response),
+        Err(e) if !e.is_transient() => return Err(e.to_string()),
+        Err(e) => e.to_string(),
+    };
+
+    let Some(fb) = fallback else {
+        return Err(error);
+    };
+    error!("Override agent failed ({}), retrying with fallback ({})", error, fb.provider());
+    fb.chat_cancellable(agent_messages, options, state.shutdown_token().clone())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Direct agent call streamed into a placeholder reply on `target`.
+/// Agents that cannot stream, and streams that fail part way, fall back to
+/// [`process_direct`]; the reply is then returned as `None` and sent normally.
+async fn process_streamed(
+    state: &AppState,
+    fallback: Option<&(dyn Agent + Send + Sync)>,
+    incoming: &Message,
+    user_content: &str,
+    target: &StreamTarget,
+) -> Result<(AgentResponse, Option<StreamedReply>), String> {
+    // Streams come from the next agent in the pool rotation, like direct replies
+    let Some((_, agent)) = state.direct_agents().into_iter().next() else {
+        return Err("No agent available".to_string());
+    };
+    let messages = direct_messages(state, incoming, user_content, &[]);
+    let chunks = match agent.chat_stream(messages).await {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            debug!("Not streaming from {}: {}", agent.provider(), e);
+            return process_direct(state, fallback, incoming, user_content, &[], &ChatOptions::default())
+                .await
+                .map(|response| (response, None));
+        }
+    };
+
+    let placeholder = response_message(incoming, streaming::PLACEHOLDER.to_string());
+    let reply = StreamedReply::start(target, placeholder).await.unwrap_or_else(|e| {
+        warn!("Could not send streamed reply placeholder: {}", e);
+        None
+    });
+    let throttle = EditThrottle::new(streaming::EDIT_INTERVAL);
+    let result = tokio::select! {
+        result = streaming::stream_into(reply.as_ref(), chunks, throttle) => result,
+        // Shutting down: no retry, which would start another provider call
+        _ = state.shutdown_token().cancelled() => {
+            if let Some(reply) = &reply {
+                reply.discard().await;
+            }
+            return Err(format!("{} request cancelled: shutting down", agent.provider()));
+        }
+    };
+    state.record_agent_result(&result);
+
+    match result {
+        Ok(collected) => {
+            debug!("Streamed response: {} chars, model={}", collected.content.len(), agent.model());
+            let response = AgentResponse {
+                content: collected.content,
+                finish_reason: collected.finish_reason.unwrap_or_else(|| "stop".to_string()),
+                usage: collected.usage,
+                model: agent.model().to_string(),
+                provider: agent.provider().to_string(),
+            };
+            Ok((response, reply))
+        }
+        Err(e) => {
+            warn!("Streaming from {} failed: {}; retrying without streaming", agent.provider(), e);
+            if let Some(reply) = &reply {
+                reply.discard().await;
+            }
+            process_direct(state, fallback, incoming, user_content, &[], &ChatOptions::default())
+                .await
+                .map(|response| (response, None))
+        }
+    }
+}
+
+/// Agent messages for a direct call: system prompt, the chat's history, then the new message
+fn direct_messages(state: &AppState, incoming: &Message, user_content: &str, images: &[ImageInput]) -> Vec<AgentMessage> {
+    let mut agent_messages = vec![AgentMessage {
+        role: MessageRole::System,
+        content: channel_system_prompt(state, incoming.channel_type),
+        images: Vec::new(),
+    }];
+    if let Some(history) = state.conversation_history() {
+        agent_messages.extend(history.messages(incoming.channel_type, &incoming.channel_id));
+    }
+    agent_messages.push(AgentMessage {
+        role: MessageRole::User,
+        content: user_content.to_string(),
+        images: images.to_vec(),
+    });
+    agent_messages
 }
 
 /// Orchestration flow: Master_Clanker may delegate to Worker_Clankers.
-/// Retries with fallback agent when master fails.
+/// Retries with fallback agent when master fails. The response's usage totals
+/// every master and worker call, so budgets and the audit log see all of it.
 async fn process_with_orchestration(
     state: &AppState,
     orchestrator: &clanker_agent::MasterClanker,
     fallback: Option<&(dyn Agent + Send + Sync)>,
     user_content: &str,
-) -> Result<String, String> {
+    images: &[ImageInput],
+) -> Result<AgentResponse, String> {
     let master = orchestrator.master_agent();
 
+    let config = state.config();
+    let master_prompt = config
+        .orchestration
+        .master_prompt
+        .as_deref()
+        .unwrap_or(MASTER_SYSTEM_PROMPT);
+
     // First call: Master decides whether to delegate or respond directly
     let mut messages = vec![
         AgentMessage {
             role: MessageRole::System,
-            content: MASTER_SYSTEM_PROMPT.to_string(),
+            content: master_prompt.to_string(),
+            images: Vec::new(),
         },
         AgentMessage {
             role: MessageRole::User,
             content: user_content.to_string(),
+            images: images.to_vec(),
         },
     ];
 
-    let response = match master.chat(messages.clone()).await {
+    let result = master.chat_cancellable(messages.clone(), &ChatOptions::default(), state.shutdown_token().clone()).await;
+    state.record_agent_result(&result);
+    let response = match result {
         Ok(r) => r,
         Err(e) => {
             error!("Master_Clanker error: {}", e);
             if let Some(fb) = fallback {
                 error!("Retrying with fallback ({})", fb.provider());
                 return fb
-                    .chat(messages)
+                    .chat_cancellable(messages, &ChatOptions::default(), state.shutdown_token().clone())
                     .await
-                    .map(|r| r.content)
                     .map_err(|e2| {
                         error!("Fallback agent error: {}", e2);
                         e2.to_string()
@@ -121,20 +698,60 @@ async fn process_with_orchestration(
             return Err(e.to_string());
         }
     };
+    let mut spent = response.usage.clone();
+
+    if let Delegation::Malformed(reason) = MasterClanker::parse_delegation_block(response.content.trim()) {
+        error!("Malformed delegation from Master_Clanker ({}), asking for a direct answer", reason);
+        return charged(retry_direct_answer(state, master.as_ref(), fallback, user_content, images).await, &spent);
+    }
 
-    let master_response = response.content.trim();
+    // Each round runs the workers the master asked for, then asks it to synthesize
+    // their results; a synthesis may delegate again until max_delegation_depth
+    let max_depth = config.orchestration.max_delegation_depth;
+    let mut response = response;
+    let mut depth = 0;
+    loop {
+        let master_response = response.content.trim().to_string();
+        let Some(worker_tasks) = MasterClanker::parse_delegation(&master_response) else {
+            // No delegation - Master's response is final
+            return Ok(AgentResponse {
+                content: master_response,
+                usage: spent,
+                ..response
+            });
+        };
 
-    // Check for delegation
-    if let Some(worker_tasks) = clanker_agent::MasterClanker::parse_delegation(master_response) {
+        if depth >= max_depth {
+            warn!(
+                "Master_Clanker delegated {} task(s) past max_delegation_depth {}; not spawning more workers",
+                worker_tasks.len(),
+                max_depth
+            );
+            if depth == 0 {
+                return charged(retry_direct_answer(state, master.as_ref(), fallback, user_content, images).await, &spent);
+            }
+            return charged(
+                answer_at_delegation_limit(state, master.as_ref(), fallback, messages, master_response).await,
+                &spent,
+            );
+        }
+        if !orchestrator.workers_available() {
+            warn!("Master_Clanker delegated but no worker API key is configured; asking for a direct answer");
+            return charged(retry_direct_answer(state, master.as_ref(), fallback, user_content, images).await, &spent);
+        }
         let n = worker_tasks.len().min(state.worker_max());
 
         if n == 0 {
-            return Ok(master_response.to_string());
+            return Ok(AgentResponse {
+                content: master_response,
+                usage: spent,
+                ..response
+            });
         }
 
         // Acquire semaphore permits before spawning
         let semaphore = state.worker_semaphore();
-        let _permit = semaphore
+        let permit = semaphore
             .acquire_many_owned(n as u32)
             .await
             .map_err(|e| {
@@ -142,68 +759,251 @@ async fn process_with_orchestration(
                 "Worker limit unavailable".to_string()
             })?;
 
-        state.increment_worker_count(n);
+        let active = state.track_workers(n);
 
         let worker_tasks: Vec<_> = worker_tasks.into_iter().take(n).collect();
-        let results = orchestrator.delegate(worker_tasks).await;
+        let results = orchestrator
+            .delegate_with_progress(worker_tasks, worker_progress(state))
+            .await;
 
-        state.decrement_worker_count(n);
+        drop(active);
+        drop(permit);
+        depth += 1;
+        for result in &results {
+            spent += &result.usage;
+        }
 
-        // Second call: Master synthesizes worker results
-        let results_text = results
-            .iter()
-            .map(|r| format!("[{}] Task: {}\nResult: {}", r.identity, r.task, r.content))
-            .collect::<Vec<_>>()
-            .join("\n\n");
+        // Next call: Master synthesizes worker results
+        let results_block = WorkerResult::to_synthesis_block(&results);
 
         messages.push(AgentMessage {
             role: MessageRole::Assistant,
-            content: master_response.to_string(),
+            content: master_response.clone(),
+            images: Vec::new(),
         });
         messages.push(AgentMessage {
             role: MessageRole::User,
             content: format!(
-                "Worker_Clanker results:\n\n{}\n\nSynthesize these results into a coherent response for the user.",
-                results_text
+                "Worker_Clanker results (JSON array of identity, task and content, or error for workers that failed):\n\n{}\n\nSynthesize these results into a coherent response for the user.",
+                results_block
             ),
+            images: Vec::new(),
         });
 
-        let synthesis = match master.chat(messages.clone()).await {
+        response = match master.chat_cancellable(messages.clone(), &ChatOptions::default(), state.shutdown_token().clone()).await {
             Ok(r) => r,
             Err(e) => {
                 error!("Master_Clanker synthesis error: {}", e);
                 if let Some(fb) = fallback {
                     error!("Retrying synthesis with fallback ({})", fb.provider());
-                    return fb
-                        .chat(messages)
+                    let response = fb
+                        .chat_cancellable(messages, &ChatOptions::default(), state.shutdown_token().clone())
                         .await
-                        .map(|r| r.content)
                         .map_err(|e2| {
                             error!("Fallback agent error: {}", e2);
                             e2.to_string()
                         });
+                    return charged(response, &spent);
                 }
                 return Err(e.to_string());
             }
         };
+        spent += &response.usage;
+    }
+}
 
-        Ok(synthesis.content)
-    } else {
-        // No delegation - Master's response is final
-        Ok(master_response.to_string())
+/// Add the usage of the orchestration calls made before `response`
+fn charged(response: Result<AgentResponse, String>, spent: &Usage) -> Result<AgentResponse, String> {
+    response.map(|mut response| {
+        response.usage += spent;
+        response
+    })
+}
+
+/// Ask Master_Clanker to answer from the worker results it already has,
+/// after it delegated again at `orchestration.max_delegation_depth`
+async fn answer_at_delegation_limit(
+    state: &AppState,
+    master: &(dyn Agent + Send + Sync),
+    fallback: Option<&(dyn Agent + Send + Sync)>,
+    mut messages: Vec<AgentMessage>,
+    delegation: String,
+) -> Result<AgentResponse, String> {
+    messages.push(AgentMessage {
+        role: MessageRole::Assistant,
+        content: delegation,
+        images: Vec::new(),
+    });
+    messages.push(AgentMessage {
+        role: MessageRole::User,
+        content: DELEGATION_LIMIT_PROMPT.to_string(),
+        images: Vec::new(),
+    });
+
+    let response = match master.chat_cancellable(messages.clone(), &ChatOptions::default(), state.shutdown_token().clone()).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Master_Clanker answer at delegation limit failed: {}", e);
+            match fallback {
+                Some(fb) => fb.chat_cancellable(messages, &ChatOptions::default(), state.shutdown_token().clone()).await.map_err(|e2| {
+                    error!("Fallback agent error: {}", e2);
+                    e2.to_string()
+                })?,
+                None => return Err(e.to_string()),
+            }
+        }
+    };
+
+    if response.content.trim_start().starts_with("[DELEGATE]") {
+        return Err("Master_Clanker kept delegating past max_delegation_depth".to_string());
+    }
+    Ok(response)
+}
+
+/// Re-ask Master_Clanker without the delegation instruction
+async fn retry_direct_answer(
+    state: &AppState,
+    master: &(dyn Agent + Send + Sync),
+    fallback: Option<&(dyn Agent + Send + Sync)>,
+    user_content: &str,
+    images: &[ImageInput],
+) -> Result<AgentResponse, String> {
+    let messages = vec![
+        AgentMessage {
+            role: MessageRole::System,
+            content: DIRECT_ANSWER_PROMPT.to_string(),
+            images: Vec::new(),
+        },
+        AgentMessage {
+            role: MessageRole::User,
+            content: user_content.to_string(),
+            images: images.to_vec(),
+        },
+    ];
+
+    let response = match master.chat_cancellable(messages.clone(), &ChatOptions::default(), state.shutdown_token().clone()).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Master_Clanker direct retry error: {}", e);
+            match fallback {
+                Some(fb) => fb.chat_cancellable(messages, &ChatOptions::default(), state.shutdown_token().clone()).await.map_err(|e2| {
+                    error!("Fallback agent error: {}", e2);
+                    e2.to_string()
+                })?,
+                None => return Err(e.to_string()),
+            }
+        }
+    };
+
+    if response.content.trim_start().starts_with("[DELEGATE]") {
+        return Err("Master_Clanker returned a malformed delegation".to_string());
+    }
+    Ok(response)
+}
+
+/// One-off agent for a client-picked provider and/or model; `None` when the request
+/// names neither (or in dry-run mode). Only `agent.model_overrides` entries are
+/// accepted; a provider alone picks its first entry, a model alone the agent's provider.
+/// Same-provider overrides keep the agent's settings; cross-provider ones start from the
+/// provider defaults, so `api_base_url` and `extra_headers` are not carried over.
+pub fn override_agent(
+    state: &AppState,
+    provider: Option<&str>,
+    model: Option<&str>,
+) -> Result<Option<Arc<dyn Agent + Send + Sync>>, String> {
+    if provider.is_none() && model.is_none() {
+        return Ok(None);
     }
+    let config = state.config();
+    let provider = provider.unwrap_or(&config.agent.provider);
+    let entry = config
+        .agent
+        .model_overrides
+        .iter()
+        .find(|entry| entry.provider.eq_ignore_ascii_case(provider) && model.is_none_or(|model| entry.model == model))
+        .ok_or_else(|| format!("Model override {}/{} is not allowed", provider, model.unwrap_or("*")))?;
+    if config.server.dry_run {
+        return Ok(None);
+    }
+
+    let agent_config = if entry.provider == config.agent.provider {
+        clanker_config::AgentConfig {
+            model: entry.model.clone(),
+            api_key: entry.api_key.clone().or_else(|| config.agent.api_key.clone()),
+            worker: None,
+            fallback: None,
+            model_overrides: Vec::new(),
+            ..config.agent.clone()
+        }
+    } else {
+        clanker_config::AgentConfig {
+            provider: entry.provider.clone(),
+            model: entry.model.clone(),
+            api_key_env: entry.api_key_env.clone().unwrap_or_default(),
+            api_key: entry.api_key.clone(),
+            max_tokens: config.agent.max_tokens,
+            request_timeout_secs: config.agent.request_timeout_secs,
+            stop_sequences: config.agent.stop_sequences.clone(),
+            ..Default::default()
+        }
+    };
+    Ok(Some(AgentFactory::create_arc_from_config(agent_config)))
 }
 
-/// Create agent from config
+/// Create agent from config (the echoing placeholder agent in dry-run mode)
 pub fn create_agent(config: &clanker_config::Config) -> Arc<dyn Agent + Send + Sync> {
+    if config.server.dry_run {
+        warn!("DRY RUN: replies come from the placeholder agent; no provider API calls will be made");
+        return Arc::new(clanker_agent::PlaceholderAgent::new(clanker_config::AgentConfig {
+            provider: "placeholder".to_string(),
+            model: "dry-run".to_string(),
+            ..Default::default()
+        }));
+    }
+    let mut agent_config = config.agent.clone();
+    agent_config.worker = None;
+    agent_config.fallback = None;
+    AgentFactory::create_arc_from_config(agent_config)
+}
+
+/// Create the agent Master_Clanker answers with. It never runs in JSON mode, since its
+/// replies are parsed for `[DELEGATE]` blocks; otherwise it is the primary agent.
+pub fn create_master_agent(
+    config: &clanker_config::Config,
+    agent: &Arc<dyn Agent + Send + Sync>,
+) -> Arc<dyn Agent + Send + Sync> {
+    if config.server.dry_run || !config.agent.json_mode {
+        return agent.clone();
+    }
     let mut agent_config = config.agent.clone();
+    agent_config.json_mode = false;
     agent_config.worker = None;
     agent_config.fallback = None;
     AgentFactory::create_arc_from_config(agent_config)
 }
 
+/// Create the extra `[[agents]]` sharing direct replies with the primary agent
+pub fn create_pool_agents(config: &clanker_config::Config) -> Vec<Arc<dyn Agent + Send + Sync>> {
+    if config.server.dry_run {
+        return Vec::new();
+    }
+    config
+        .agents
+        .iter()
+        .map(|agent| {
+            let mut agent_config = agent.clone();
+            agent_config.worker = None;
+            agent_config.fallback = None;
+            AgentFactory::create_arc_from_config(agent_config)
+        })
+        .collect()
+}
+
 /// Create fallback agent from config when configured and API key is present
 pub fn create_fallback_agent(config: &clanker_config::Config) -> Option<Arc<dyn Agent + Send + Sync>> {
+    if config.server.dry_run {
+        return None;
+    }
     let fallback = config.agent.fallback.as_ref()?;
     let api_key = fallback.api_key.as_ref().filter(|k| !k.is_empty())?;
     let agent_config = clanker_config::AgentConfig {
@@ -212,9 +1012,7 @@ pub fn create_fallback_agent(config: &clanker_config::Config) -> Option<Arc<dyn
         api_key_env: fallback.api_key_env.clone(),
         api_key: Some(api_key.clone()),
         max_tokens: 4096,
-        api_base_url: None,
-        worker: None,
-        fallback: None,
+        ..Default::default()
     };
     Some(AgentFactory::create_arc_from_config(agent_config))
 }
@@ -222,31 +1020,1393 @@ pub fn create_fallback_agent(config: &clanker_config::Config) -> Option<Arc<dyn
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clanker_agent::AgentFactory;
+    use clanker_agent::{AgentError, StreamChunk};
     use clanker_config::Config;
     use clanker_core::ChannelType;
+    use futures_util::Stream;
+    use std::time::Duration;
     use tokio_util::sync::CancellationToken;
 
-    fn create_test_config_no_orchestration() -> Config {
-        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
-        config.orchestration.enabled = false;
-        config
+    /// Agent that always answers with a fixed content and finish reason
+    struct StubAgent {
+        content: String,
+        finish_reason: String,
     }
 
-    #[tokio::test]
-    async fn test_process_message_empty_fails() {
-        let config = create_test_config_no_orchestration();
-        let shutdown = CancellationToken::new();
-        let state = AppState::new(config, shutdown);
+    #[async_trait::async_trait]
+    impl Agent for StubAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            Ok(AgentResponse {
+                content: self.content.clone(),
+                finish_reason: self.finish_reason.clone(),
+                usage: Usage {
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                },
+                model: "stub".to_string(),
+                provider: "stub".to_string(),
+            })
+        }
 
-        let msg = Message::new(
-            ChannelType::Telegram,
-            "123".to_string(),
-            "user".to_string(),
-            "".to_string(),
-        );
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::Unknown("streaming not supported".to_string()))
+        }
 
-        let result = process_message(&state, &msg).await;
-        assert!(result.is_err());
+        fn provider(&self) -> &str {
+            "stub"
+        }
+
+        fn model(&self) -> &str {
+            "stub"
+        }
+    }
+
+    /// Agent whose every call fails
+    struct FailingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for FailingAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            Err(AgentError::ProviderError("primary unavailable".to_string()))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::ProviderError("primary unavailable".to_string()))
+        }
+
+        fn provider(&self) -> &str {
+            "failing"
+        }
+
+        fn model(&self) -> &str {
+            "failing"
+        }
+    }
+
+    struct RateLimitedAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for RateLimitedAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            Err(AgentError::RateLimited(None))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::RateLimited(None))
+        }
+
+        fn provider(&self) -> &str {
+            "limited"
+        }
+
+        fn model(&self) -> &str {
+            "limited"
+        }
+    }
+
+    /// Agent whose key the provider rejects
+    struct RejectedKeyAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for RejectedKeyAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            Err(AgentError::AuthenticationFailed)
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::AuthenticationFailed)
+        }
+
+        fn provider(&self) -> &str {
+            "rejected"
+        }
+
+        fn model(&self) -> &str {
+            "rejected"
+        }
+    }
+
+    /// Agent whose calls block until the test hands out a permit on `gate`
+    struct GatedAgent {
+        gate: tokio::sync::Semaphore,
+        started: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for GatedAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            self.started.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.gate.acquire().await.unwrap().forget();
+            Ok(AgentResponse {
+                content: "done".to_string(),
+                finish_reason: "stop".to_string(),
+                usage: Usage {
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                },
+                model: "gated".to_string(),
+                provider: "gated".to_string(),
+            })
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::Unknown("streaming not supported".to_string()))
+        }
+
+        fn provider(&self) -> &str {
+            "gated"
+        }
+
+        fn model(&self) -> &str {
+            "gated"
+        }
+    }
+
+    fn failing_state_with_fallback() -> AppState {
+        let mut config = create_test_config_no_orchestration();
+        config.agent.fallback = Some(clanker_config::FallbackAgentConfig {
+            provider: "placeholder".to_string(),
+            model: "placeholder".to_string(),
+            api_key_env: "UNUSED".to_string(),
+            api_key: Some("test-key".to_string()),
+        });
+        AppState::with_agent(config, CancellationToken::new(), Arc::new(FailingAgent))
+    }
+
+    /// Agent that records the messages of every call and replies from a script
+    /// ("Direct answer" once the script runs out)
+    struct RecordingAgent {
+        calls: std::sync::Mutex<Vec<Vec<AgentMessage>>>,
+        replies: std::sync::Mutex<std::collections::VecDeque<String>>,
+        vision: bool,
+        json: bool,
+    }
+
+    impl RecordingAgent {
+        fn new(replies: &[&str]) -> Self {
+            Self {
+                calls: std::sync::Mutex::new(Vec::new()),
+                replies: std::sync::Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+                vision: false,
+                json: false,
+            }
+        }
+
+        fn with_vision(mut self) -> Self {
+            self.vision = true;
+            self
+        }
+
+        fn with_json_mode(mut self) -> Self {
+            self.json = true;
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for RecordingAgent {
+        async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            self.calls.lock().unwrap().push(messages);
+            let content = self
+                .replies
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| "Direct answer".to_string());
+            Ok(AgentResponse {
+                content,
+                finish_reason: "stop".to_string(),
+                usage: Usage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    total_tokens: 15,
+                },
+                model: "recording".to_string(),
+                provider: "recording".to_string(),
+            })
+        }
+
+        async fn chat_with_options(
+            &self,
+            messages: Vec<AgentMessage>,
+            options: &ChatOptions,
+        ) -> Result<AgentResponse, AgentError> {
+            if options.json_mode && !self.json {
+                return Err(AgentError::InvalidRequest("JSON mode is not supported by recording".to_string()));
+            }
+            self.chat(messages).await
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Err(AgentError::Unknown("streaming not supported".to_string()))
+        }
+
+        fn provider(&self) -> &str {
+            "recording"
+        }
+
+        fn model(&self) -> &str {
+            "recording"
+        }
+
+        fn supports_vision(&self) -> bool {
+            self.vision
+        }
+
+        fn supports_json_mode(&self) -> bool {
+            self.json
+        }
+    }
+
+    fn stub_state(config: Config, content: &str, finish_reason: &str) -> AppState {
+        let agent = Arc::new(StubAgent {
+            content: content.to_string(),
+            finish_reason: finish_reason.to_string(),
+        });
+        AppState::with_agent(config, CancellationToken::new(), agent)
+    }
+
+    fn user_message(text: &str) -> Message {
+        Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "user".to_string(),
+            text.to_string(),
+        )
+    }
+
+    fn create_test_config_no_orchestration() -> Config {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = false;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_process_message_empty_fails() {
+        let config = create_test_config_no_orchestration();
+        let shutdown = CancellationToken::new();
+        let state = AppState::new(config, shutdown);
+
+        let msg = Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "user".to_string(),
+            "".to_string(),
+        );
+
+        let result = process_message(&state, &msg).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_process_message_faq_match_skips_agent() {
+        let mut config = create_test_config_no_orchestration();
+        config.faq.entries.push(clanker_config::FaqEntry {
+            pattern: "/start".to_string(),
+            response: "Welcome to Open Clanker!".to_string(),
+            regex: false,
+        });
+        let state = AppState::new(config, CancellationToken::new());
+
+        let msg = Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "user".to_string(),
+            "/start".to_string(),
+        );
+
+        // The test config has no API key, so reaching the agent would fail
+        let response = process_message(&state, &msg).await.unwrap();
+        assert_eq!(response.message.text, "Welcome to Open Clanker!");
+        assert_eq!(response.message.channel_id, "123");
+        assert!(!response.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_process_message_normal_response_not_filtered() {
+        let state = stub_state(create_test_config_no_orchestration(), "Hello!", "stop");
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "Hello!");
+        assert!(!response.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_agent_pool_rotates_across_agents() {
+        let first = Arc::new(RecordingAgent::new(&["from first", "from first again"]));
+        let second = Arc::new(RecordingAgent::new(&["from second"]));
+        let state = AppState::with_agents(
+            create_test_config_no_orchestration(),
+            CancellationToken::new(),
+            first.clone(),
+            vec![second.clone()],
+        );
+
+        let mut replies = Vec::new();
+        for text in ["one", "two", "three"] {
+            replies.push(process_message(&state, &user_message(text)).await.unwrap().message.text);
+        }
+
+        assert_eq!(replies, vec!["from first", "from second", "from first again"]);
+        assert_eq!(first.calls.lock().unwrap().len(), 2);
+        assert_eq!(second.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_agent_pool_fails_over_on_rate_limit() {
+        let backup = Arc::new(RecordingAgent::new(&["from backup"]));
+        let state = AppState::with_agents(
+            create_test_config_no_orchestration(),
+            CancellationToken::new(),
+            Arc::new(RateLimitedAgent),
+            vec![backup.clone()],
+        );
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "from backup");
+        assert_eq!(backup.calls.lock().unwrap().len(), 1);
+    }
+
+    /// Agent streaming `content` and then `finish_reason`
+    struct StreamingAgent {
+        content: &'static str,
+        finish_reason: &'static str,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for StreamingAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            Err(AgentError::Unknown("only streams".to_string()))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            let chunk = |content: &str, finish_reason: Option<&str>, done| StreamChunk {
+                content: content.to_string(),
+                done,
+                usage: None,
+                finish_reason: finish_reason.map(str::to_string),
+            };
+            let chunks = vec![
+                Ok(chunk(self.content, None, false)),
+                Ok(chunk("", Some(self.finish_reason), false)),
+                Ok(chunk("", None, true)),
+            ];
+            Ok(Box::new(futures_util::stream::iter(chunks)))
+        }
+
+        fn provider(&self) -> &str {
+            "streaming"
+        }
+
+        fn model(&self) -> &str {
+            "streaming"
+        }
+    }
+
+    /// Agent whose stream never yields, counting plain `chat` calls
+    #[derive(Default)]
+    struct StalledStreamAgent {
+        chats: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for StalledStreamAgent {
+        async fn chat(&self, _messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            self.chats.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(AgentError::Unknown("not expected".to_string()))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            Ok(Box::new(futures_util::stream::pending()))
+        }
+
+        fn provider(&self) -> &str {
+            "stalled"
+        }
+
+        fn model(&self) -> &str {
+            "stalled"
+        }
+    }
+
+    /// Telegram channel accepting every send without a message id
+    struct NullChannel;
+
+    #[async_trait::async_trait]
+    impl clanker_channels::Channel for NullChannel {
+        async fn send(&self, _message: Message) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(&self) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        async fn listen_with_tx(&self, _tx: tokio::sync::mpsc::Sender<Message>) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        fn channel_type(&self) -> ChannelType {
+            ChannelType::Telegram
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_replies_rotate_pool_and_keep_finish_reason() {
+        let state = AppState::with_agents(
+            create_test_config_no_orchestration(),
+            CancellationToken::new(),
+            Arc::new(StreamingAgent {
+                content: "from first",
+                finish_reason: "stop",
+            }),
+            vec![Arc::new(StreamingAgent {
+                content: "from second",
+                finish_reason: "content_filter",
+            })],
+        );
+        let options = ProcessOptions {
+            stream_to: Some(StreamTarget(Arc::new(NullChannel))),
+            ..Default::default()
+        };
+
+        let first = process_message_with(&state, &user_message("one"), &options).await.unwrap();
+        assert_eq!(first.message.text, "from first");
+        assert!(!first.filtered);
+
+        let second = process_message_with(&state, &user_message("two"), &options).await.unwrap();
+        assert_eq!(second.message.text, "from second");
+        assert!(second.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_during_stream_is_not_retried() {
+        let agent = Arc::new(StalledStreamAgent::default());
+        let shutdown = CancellationToken::new();
+        let state = AppState::with_agent(create_test_config_no_orchestration(), shutdown.clone(), agent.clone());
+        let options = ProcessOptions {
+            stream_to: Some(StreamTarget(Arc::new(NullChannel))),
+            ..Default::default()
+        };
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            shutdown.cancel();
+        });
+        let err = process_message_with(&state, &user_message("hi"), &options).await.unwrap_err();
+        canceller.await.unwrap();
+
+        assert!(err.contains("cancelled"), "{}", err);
+        assert_eq!(agent.chats.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_response_replies_to_incoming_message() {
+        let state = stub_state(create_test_config_no_orchestration(), "Hello!", "stop");
+        let incoming = Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "alice".to_string(),
+            "hi".to_string(),
+        );
+
+        let response = process_message(&state, &incoming).await.unwrap();
+        assert_eq!(response.message.sender, "assistant");
+        assert_eq!(response.message.metadata.reply_to.as_deref(), Some(incoming.id.as_str()));
+        assert_eq!(response.message.metadata.in_reply_to_sender.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_process_message_refusal_uses_configured_message() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.refusal_message = Some("Sorry, I can't help with that.".to_string());
+        let state = stub_state(config, "I refuse.", "refusal");
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "Sorry, I can't help with that.");
+        assert!(response.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_process_message_empty_filtered_response_gets_notice() {
+        let state = stub_state(create_test_config_no_orchestration(), "", "content_filter");
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, DEFAULT_REFUSAL_MESSAGE);
+        assert!(response.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_empty_response_replaced_with_fallback_message() {
+        let mut config = create_test_config_no_orchestration();
+        let agent = Arc::new(clanker_agent::PlaceholderAgent::scripted(vec![
+            String::new(),
+            "  \n ".to_string(),
+        ]));
+        let state = AppState::with_agent(config.clone(), CancellationToken::new(), agent.clone());
+
+        let processed = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(processed.message.text, "I couldn't generate a response.");
+        assert!(!processed.filtered);
+
+        config.processing.empty_response_message = "Nothing to say.".to_string();
+        let state = AppState::with_agent(config, CancellationToken::new(), agent);
+        let processed = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(processed.message.text, "Nothing to say.");
+    }
+
+    #[tokio::test]
+    async fn test_interaction_appends_one_audit_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let mut config = create_test_config_no_orchestration();
+        config.audit.path = Some(path.to_string_lossy().into_owned());
+        let state = stub_state(config, "Hello!", "stop");
+
+        process_message(&state, &user_message("Hi there")).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(contents.ends_with('\n'));
+        let record: crate::audit::AuditRecord = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record.channel_type, ChannelType::Telegram);
+        assert_eq!((record.channel_id.as_str(), record.sender.as_str()), ("123", "user"));
+        assert_eq!(record.prompt_chars, 8);
+        assert_eq!(record.provider, "stub");
+        assert!(!record.filtered);
+        // Content stays out of the log unless include_content is set
+        assert!(record.prompt.is_none() && record.reply.is_none());
+        assert!(!lines[0].contains("Hi there") && !lines[0].contains("Hello!"));
+        assert!(record.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_are_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let mut config = create_test_config_no_orchestration();
+        config.audit.path = Some(path.to_string_lossy().into_owned());
+        config.budget.daily_usd = Some(0.5);
+        let state = AppState::with_agent(config, CancellationToken::new(), Arc::new(FailingAgent));
+
+        process_message(&state, &user_message("hi")).await.unwrap_err();
+        let options = ProcessOptions {
+            provider: Some("groq".to_string()),
+            ..Default::default()
+        };
+        process_message_with(&state, &user_message("hi"), &options).await.unwrap_err();
+        let options = ProcessOptions {
+            json_mode: true,
+            ..Default::default()
+        };
+        process_message_with(&state, &user_message("hi"), &options).await.unwrap_err();
+        state.budget().unwrap().record(0.5, chrono::Utc::now()).await;
+        process_message(&state, &user_message("hi")).await.unwrap_err();
+
+        let records: Vec<crate::audit::AuditRecord> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].provider, "failing");
+        assert!(records[0].error.as_deref().unwrap().contains("primary unavailable"));
+        assert_eq!((records[1].provider.as_str(), records[1].model.as_str()), ("groq", "*"));
+        assert!(records[1].error.as_deref().unwrap().contains("not allowed"));
+        assert!(records[2].error.as_deref().unwrap().starts_with("JSON mode is not supported"));
+        assert!(records[3].error.as_deref().unwrap().starts_with("Daily spend budget"));
+    }
+
+    #[tokio::test]
+    async fn test_spent_budget_refuses_agent_requests() {
+        let mut config = create_test_config_no_orchestration();
+        config.budget.daily_usd = Some(0.5);
+        let agent = Arc::new(RecordingAgent::new(&["first", "second"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("hi")).await.unwrap();
+        state.budget().unwrap().record(0.5, chrono::Utc::now()).await;
+
+        let err = process_message(&state, &user_message("hi again")).await.unwrap_err();
+        assert!(err.starts_with("Daily spend budget of $0.50 reached"), "{}", err);
+        assert_eq!(agent.calls.lock().unwrap().len(), 1);
+    }
+
+    fn config_with_model_overrides() -> Config {
+        let mut config = create_test_config_no_orchestration();
+        config.agent.api_key = Some("anthropic-key".to_string());
+        config.agent.model_overrides = vec![
+            clanker_config::ModelOverrideConfig {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-haiku-20241022".to_string(),
+                api_key_env: None,
+                api_key: None,
+            },
+            clanker_config::ModelOverrideConfig {
+                provider: "groq".to_string(),
+                model: "llama-3.1-8b-instant".to_string(),
+                api_key_env: Some("OPENCLAW_GROQ_API_KEY".to_string()),
+                api_key: Some("groq-key".to_string()),
+            },
+        ];
+        config
+    }
+
+    #[test]
+    fn test_allowed_model_override_routes_to_provider() {
+        let state = stub_state(config_with_model_overrides(), "primary", "stop");
+        assert!(override_agent(&state, None, None).unwrap().is_none());
+
+        let agent = override_agent(&state, Some("groq"), None).unwrap().unwrap();
+        assert_eq!((agent.provider(), agent.model()), ("groq", "llama-3.1-8b-instant"));
+        let agent = override_agent(&state, Some("groq"), Some("llama-3.1-8b-instant")).unwrap().unwrap();
+        assert_eq!(agent.provider(), "groq");
+
+        // A model alone keeps the agent's provider (and its key)
+        let agent = override_agent(&state, None, Some("claude-3-5-haiku-20241022")).unwrap().unwrap();
+        assert_eq!((agent.provider(), agent.model()), ("anthropic", "claude-3-5-haiku-20241022"));
+    }
+
+    #[tokio::test]
+    async fn test_model_override_answers_the_request() {
+        use wiremock::matchers::{body_partial_json, method, path};
+
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"model": "gpt-4o-mini"})))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "1",
+                "model": "gpt-4o-mini",
+                "choices": [{"message": {"role": "assistant", "content": "override answer"}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 2, "total_tokens": 3},
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = create_test_config_no_orchestration();
+        config.agent.provider = "openai".to_string();
+        config.agent.model = "gpt-4o".to_string();
+        config.agent.api_key = Some("sk-test".to_string());
+        config.agent.api_base_url = Some(server.uri());
+        config.agent.model_overrides = vec![clanker_config::ModelOverrideConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: None,
+            api_key: None,
+        }];
+        let agent = Arc::new(RecordingAgent::new(&["primary"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+        let options = ProcessOptions {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o-mini".to_string()),
+            ..Default::default()
+        };
+
+        let processed = process_message_with(&state, &user_message("hi"), &options).await.unwrap();
+        assert_eq!(processed.message.text, "override answer");
+        assert_eq!(processed.provider.as_deref(), Some("openai"));
+        assert!(agent.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_model_override_rejected() {
+        let agent = Arc::new(RecordingAgent::new(&["primary"]));
+        let state = AppState::with_agent(config_with_model_overrides(), CancellationToken::new(), agent.clone());
+        let options = ProcessOptions {
+            provider: Some("groq".to_string()),
+            model: Some("llama-3.3-70b-versatile".to_string()),
+            ..Default::default()
+        };
+
+        let err = process_message_with(&state, &user_message("hi"), &options).await.unwrap_err();
+        assert_eq!(err, "Model override groq/llama-3.3-70b-versatile is not allowed");
+        let options = ProcessOptions {
+            provider: Some("openai".to_string()),
+            ..Default::default()
+        };
+        assert!(process_message_with(&state, &user_message("hi"), &options).await.is_err());
+        assert!(agent.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_json_mode_rejected_for_unsupported_agent() {
+        let state = stub_state(create_test_config_no_orchestration(), "{}", "stop");
+        let options = ProcessOptions {
+            json_mode: true,
+            ..Default::default()
+        };
+
+        let err = process_message_with(&state, &user_message("colors?"), &options).await.unwrap_err();
+        assert_eq!(err, "JSON mode is not supported by provider stub");
+    }
+
+    #[tokio::test]
+    async fn test_json_mode_skips_unsupported_pool_agents() {
+        let primary = Arc::new(RecordingAgent::new(&["{}", "{}"]).with_json_mode());
+        let plain = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agents(
+            create_test_config_no_orchestration(),
+            CancellationToken::new(),
+            primary.clone(),
+            vec![plain.clone()],
+        );
+        let options = ProcessOptions {
+            json_mode: true,
+            ..Default::default()
+        };
+
+        for text in ["one", "two"] {
+            process_message_with(&state, &user_message(text), &options).await.unwrap();
+        }
+        assert_eq!(primary.calls.lock().unwrap().len(), 2);
+        assert!(plain.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_post_processing_applied_to_reply() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.post_process.strip_code_fence = true;
+        let state = stub_state(config, "```markdown\n**Hi!**\n```", "stop");
+
+        let processed = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(processed.message.text, "**Hi!**");
+    }
+
+    #[tokio::test]
+    async fn test_process_message_refusal_keeps_provider_text() {
+        let state = stub_state(create_test_config_no_orchestration(), "I can't do that.", "refusal");
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "I can't do that.");
+        assert!(response.filtered);
+    }
+
+    #[tokio::test]
+    async fn test_process_message_uses_fallback_by_default() {
+        let state = failing_state_with_fallback();
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert!(response.message.text.starts_with("Placeholder response"));
+        assert_eq!(response.provider.as_deref(), Some("placeholder"));
+        assert!(state.is_fallback_provider("placeholder"));
+        assert!(!state.is_fallback_provider("failing"));
+    }
+
+    #[tokio::test]
+    async fn test_process_message_no_fallback_returns_primary_error() {
+        let state = failing_state_with_fallback();
+        let options = ProcessOptions {
+            no_fallback: true,
+            ..Default::default()
+        };
+
+        let err = process_message_with(&state, &user_message("hi"), &options)
+            .await
+            .unwrap_err();
+        assert!(err.contains("primary unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_is_not_retried_with_fallback() {
+        let mut config = create_test_config_no_orchestration();
+        config.agent.fallback = Some(clanker_config::FallbackAgentConfig {
+            provider: "placeholder".to_string(),
+            model: "placeholder".to_string(),
+            api_key_env: "UNUSED".to_string(),
+            api_key: Some("test-key".to_string()),
+        });
+        let state = AppState::with_agent(config, CancellationToken::new(), Arc::new(RejectedKeyAgent));
+
+        let err = process_message(&state, &user_message("hi")).await.unwrap_err();
+        assert_eq!(err, AgentError::AuthenticationFailed.to_string());
+    }
+
+    /// Agent failing like [`FailingAgent`] while `down` is set, answering otherwise
+    struct FlakyAgent {
+        down: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for FlakyAgent {
+        async fn chat(&self, messages: Vec<AgentMessage>) -> Result<AgentResponse, AgentError> {
+            if self.down.load(std::sync::atomic::Ordering::SeqCst) {
+                return FailingAgent.chat(messages).await;
+            }
+            let primary = StubAgent {
+                content: "Primary answer".to_string(),
+                finish_reason: "stop".to_string(),
+            };
+            primary.chat(messages).await
+        }
+
+        async fn chat_stream(
+            &self,
+            messages: Vec<AgentMessage>,
+        ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send + Unpin>, AgentError> {
+            FailingAgent.chat_stream(messages).await
+        }
+
+        fn provider(&self) -> &str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            "flaky"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_primary_failures_mark_state_degraded() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.degraded_after_failures = 2;
+        config.agent.fallback = Some(clanker_config::FallbackAgentConfig {
+            provider: "placeholder".to_string(),
+            model: "placeholder".to_string(),
+            api_key_env: "UNUSED".to_string(),
+            api_key: Some("test-key".to_string()),
+        });
+        let agent = Arc::new(FlakyAgent {
+            down: std::sync::atomic::AtomicBool::new(true),
+        });
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("one")).await.unwrap();
+        assert_eq!(state.consecutive_primary_failures(), 1);
+        assert_eq!(state.degraded_provider(), None);
+
+        process_message(&state, &user_message("two")).await.unwrap();
+        assert_eq!(state.degraded_provider().as_deref(), Some("placeholder"));
+
+        // One primary success clears it
+        agent.down.store(false, std::sync::atomic::Ordering::SeqCst);
+        process_message(&state, &user_message("three")).await.unwrap();
+        assert_eq!(state.consecutive_primary_failures(), 0);
+        assert_eq!(state.degraded_provider(), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_in_flight_request() {
+        let agent = Arc::new(
+            clanker_agent::PlaceholderAgent::new(clanker_config::AgentConfig {
+                provider: "placeholder".to_string(),
+                ..Default::default()
+            })
+            .with_delay(std::time::Duration::from_secs(30)),
+        );
+        let shutdown = CancellationToken::new();
+        let state = AppState::with_agent(create_test_config_no_orchestration(), shutdown.clone(), agent);
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            shutdown.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let err = process_message(&state, &user_message("hi")).await.unwrap_err();
+        canceller.await.unwrap();
+
+        assert!(err.contains("cancelled"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_orchestration_uses_custom_master_prompt() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        config.orchestration.master_prompt = Some("Prefer answering directly.".to_string());
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "Direct answer");
+
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(matches!(calls[0][0].role, MessageRole::System));
+        assert_eq!(calls[0][0].content, "Prefer answering directly.");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_delegation_retries_without_marker() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&[r#"[DELEGATE][{"identity":"A","task":"#]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let response = process_message(&state, &user_message("hi")).await.unwrap();
+        assert_eq!(response.message.text, "Direct answer");
+
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1][0].content, DIRECT_ANSWER_PROMPT);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_malformed_delegation_is_not_leaked() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&["[DELEGATE]{}", "[DELEGATE]{}"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent);
+
+        assert!(process_message(&state, &user_message("hi")).await.is_err());
+    }
+
+    const TWO_WORKER_DELEGATION: &str = r#"[DELEGATE][{"identity":"Researcher","task":"Find facts"},{"identity":"Summarizer","task":"Summarize"}]"#;
+
+    #[tokio::test]
+    async fn test_worker_progress_events_broadcast() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        config.orchestration.progress_events = true;
+        let agent = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, "Synthesized"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent)
+            .with_worker_agent(Arc::new(clanker_agent::PlaceholderAgent::new(Default::default())));
+        let mut rx = state.broadcaster().subscribe();
+
+        let response = process_message(&state, &user_message("research this")).await.unwrap();
+        assert_eq!(response.message.text, "Synthesized");
+
+        let mut started = Vec::new();
+        let mut completed = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                WsServerMessage::WorkerStarted { identity } => started.push(identity),
+                WsServerMessage::WorkerCompleted { identity } => completed.push(identity),
+                other => panic!("unexpected broadcast: {:?}", other),
+            }
+        }
+        assert_eq!(started, vec!["Researcher", "Summarizer"]);
+        completed.sort();
+        assert_eq!(completed, vec!["Researcher", "Summarizer"]);
+    }
+
+    #[tokio::test]
+    async fn test_synthesis_delegation_past_depth_limit_spawns_no_workers() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        assert_eq!(config.orchestration.max_delegation_depth, 1);
+        let master = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, TWO_WORKER_DELEGATION, "Final answer"]));
+        let worker = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), master.clone()).with_worker_agent(worker.clone());
+
+        let response = process_message(&state, &user_message("research this")).await.unwrap();
+        assert_eq!(response.message.text, "Final answer");
+
+        // Only the first round's two workers ran; the re-delegation got the limit notice
+        assert_eq!(worker.calls.lock().unwrap().len(), 2);
+        let calls = master.calls.lock().unwrap();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[2].last().unwrap().content, DELEGATION_LIMIT_PROMPT);
+        assert_eq!(state.worker_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_delegation_restores_worker_count() {
+        use std::sync::atomic::Ordering;
+
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        let master = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION]));
+        let worker = Arc::new(GatedAgent {
+            gate: tokio::sync::Semaphore::new(0),
+            started: Default::default(),
+        });
+        let state = AppState::with_agent(config, CancellationToken::new(), master).with_worker_agent(worker.clone());
+
+        let processing = {
+            let state = state.clone();
+            tokio::spawn(async move { process_message(&state, &user_message("research this")).await })
+        };
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while worker.started.load(Ordering::SeqCst) < 2 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(state.worker_count(), 2);
+
+        // The request dies mid-delegation; its workers no longer count as active
+        processing.abort();
+        assert!(processing.await.unwrap_err().is_cancelled());
+        assert_eq!(state.worker_count(), 0);
+        assert_eq!(state.worker_semaphore().available_permits(), state.worker_max());
+    }
+
+    #[tokio::test]
+    async fn test_orchestration_audits_every_call_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        config.audit.path = Some(path.to_string_lossy().into_owned());
+        let master = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, "Synthesized"]));
+        let worker = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), master).with_worker_agent(worker);
+
+        process_message(&state, &user_message("research this")).await.unwrap();
+
+        // Decision, two workers and the synthesis at 10 + 5 tokens each
+        let record: crate::audit::AuditRecord =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!((record.prompt_tokens, record.completion_tokens), (40, 20));
+    }
+
+    #[tokio::test]
+    async fn test_delegation_depth_allows_further_rounds() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        config.orchestration.max_delegation_depth = 2;
+        let master = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, TWO_WORKER_DELEGATION, "Synthesized"]));
+        let worker = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), master.clone()).with_worker_agent(worker.clone());
+
+        let response = process_message(&state, &user_message("research this")).await.unwrap();
+        assert_eq!(response.message.text, "Synthesized");
+        assert_eq!(worker.calls.lock().unwrap().len(), 4);
+        assert_eq!(master.calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_delegation_without_worker_key_answers_directly() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        assert!(config.agent.worker.as_ref().unwrap().api_key.is_none());
+        let agent = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, "Direct answer"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let response = process_message(&state, &user_message("research this")).await.unwrap();
+        assert_eq!(response.message.text, "Direct answer");
+        assert!(!response.message.text.contains("API key not configured"));
+
+        // The second call re-asks for a direct answer instead of synthesizing worker errors
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1][0].content, DIRECT_ANSWER_PROMPT);
+        assert!(calls[1].iter().all(|m| !m.content.contains("not configured")));
+    }
+
+    #[tokio::test]
+    async fn test_worker_progress_events_off_by_default() {
+        let mut config: Config = toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap();
+        config.orchestration.enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&[TWO_WORKER_DELEGATION, "Synthesized"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent)
+            .with_worker_agent(Arc::new(clanker_agent::PlaceholderAgent::new(Default::default())));
+        let mut rx = state.broadcaster().subscribe();
+
+        process_message(&state, &user_message("research this")).await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_skips_agent_call() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.cache_enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&["First answer", "Second answer"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let first = process_message(&state, &user_message("What is Rust?")).await.unwrap();
+        let second = process_message(&state, &user_message("what is  rust?")).await.unwrap();
+
+        assert_eq!(first.message.text, "First answer");
+        assert_eq!(second.message.text, "First answer");
+        assert_eq!(agent.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_not_shared_across_channel_prompts() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.cache_enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&["Telegram answer", "Discord answer"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let telegram = process_message(&state, &user_message("What is Rust?")).await.unwrap();
+        let discord = Message::new(
+            ChannelType::Discord,
+            "123456789".to_string(),
+            "user".to_string(),
+            "What is Rust?".to_string(),
+        );
+        let discord = process_message(&state, &discord).await.unwrap();
+
+        assert_eq!(telegram.message.text, "Telegram answer");
+        assert_eq!(discord.message.text, "Discord answer");
+        assert_eq!(agent.calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_bypassed_for_orchestration() {
+        let mut config = create_test_config_no_orchestration();
+        config.orchestration.enabled = true;
+        config.processing.cache_enabled = true;
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("hi")).await.unwrap();
+        process_message(&state, &user_message("hi")).await.unwrap();
+
+        assert_eq!(agent.calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_channel_message_processed_once() {
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(create_test_config_no_orchestration(), CancellationToken::new(), agent.clone());
+        let msg = user_message("hello");
+
+        let first = process_channel_message(&state, &msg).await;
+        assert!(matches!(first, Some(Ok(_))));
+        // Redelivery of the same update
+        assert!(process_channel_message(&state, &msg.clone()).await.is_none());
+        assert_eq!(agent.calls.lock().unwrap().len(), 1);
+
+        // A different message in the same chat is still processed
+        assert!(process_channel_message(&state, &user_message("hello again")).await.is_some());
+        assert_eq!(agent.calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_disabled_with_zero_window() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.dedup_window_secs = 0;
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+        let msg = user_message("hello");
+
+        assert!(process_channel_message(&state, &msg).await.is_some());
+        assert!(process_channel_message(&state, &msg).await.is_some());
+        assert_eq!(agent.calls.lock().unwrap().len(), 2);
+    }
+
+    fn image_message() -> Message {
+        let mut msg = user_message("What is this?");
+        msg.metadata.attachments = vec![
+            clanker_core::Attachment::new("https://example.com/cat.png".to_string(), "image/png".to_string(), 10),
+            clanker_core::Attachment::new("https://example.com/doc.pdf".to_string(), "application/pdf".to_string(), 10),
+        ];
+        msg
+    }
+
+    #[tokio::test]
+    async fn test_image_attachments_sent_to_vision_agent() {
+        let agent = Arc::new(RecordingAgent::new(&[]).with_vision());
+        let state = AppState::with_agent(create_test_config_no_orchestration(), CancellationToken::new(), agent.clone());
+
+        process_message(&state, &image_message()).await.unwrap();
+
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(
+            calls[0][1].images,
+            vec![ImageInput::Url { url: "https://example.com/cat.png".to_string() }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_image_attachments_skipped_for_text_only_agent() {
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(create_test_config_no_orchestration(), CancellationToken::new(), agent.clone());
+
+        let response = process_message(&state, &image_message()).await.unwrap();
+        assert_eq!(response.message.text, "Direct answer");
+        assert!(agent.calls.lock().unwrap()[0][1].images.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_direct_call_uses_channel_system_prompt() {
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(create_test_config_no_orchestration(), CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("hi")).await.unwrap();
+
+        let calls = agent.calls.lock().unwrap();
+        assert!(matches!(calls[0][0].role, MessageRole::System));
+        assert_eq!(calls[0][0].content, system_prompts::for_channel(ChannelType::Telegram).content);
+        assert_eq!(calls[0][1].content, "hi");
+    }
+
+    #[tokio::test]
+    async fn test_channel_system_prompt_override() {
+        let mut config = create_test_config_no_orchestration();
+        config
+            .agent
+            .system_prompts
+            .insert("telegram".to_string(), "Answer in one sentence.".to_string());
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("hi")).await.unwrap();
+        let mut discord = user_message("hi");
+        discord.channel_type = ChannelType::Discord;
+        process_message(&state, &discord).await.unwrap();
+
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(calls[0][0].content, "Answer in one sentence.");
+        // Channels without an override keep the built-in prompt
+        assert_eq!(calls[1][0].content, system_prompts::for_channel(ChannelType::Discord).content);
+    }
+
+    #[tokio::test]
+    async fn test_reset_command_clears_conversation_history() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.history_turns = 5;
+        let agent = Arc::new(RecordingAgent::new(&[]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_channel_message(&state, &user_message("first")).await.unwrap().unwrap();
+        process_channel_message(&state, &user_message("second")).await.unwrap().unwrap();
+        let reset = process_channel_message(&state, &user_message("/reset")).await.unwrap().unwrap();
+        assert_eq!(reset.message.text, CONVERSATION_RESET_MESSAGE);
+        process_channel_message(&state, &user_message("third")).await.unwrap().unwrap();
+
+        let calls = agent.calls.lock().unwrap();
+        // /reset is answered by the gateway, not the agent
+        assert_eq!(calls.len(), 3);
+        let contents = |call: &[AgentMessage]| call[1..].iter().map(|m| m.content.clone()).collect::<Vec<_>>();
+        assert_eq!(contents(&calls[1]), vec!["first", "Direct answer", "second"]);
+        // After the reset only the system prompt and the new message are sent
+        assert_eq!(calls[2].len(), 2);
+        assert!(matches!(calls[2][0].role, MessageRole::System));
+        assert_eq!(calls[2][1].content, "third");
+    }
+
+    #[tokio::test]
+    async fn test_allowed_chats_follow_reloads() {
+        let mut config = create_test_config_no_orchestration();
+        config.channels.telegram.as_mut().unwrap().allowed_chats = Some(vec!["999".to_string()]);
+        let state = stub_state(config.clone(), "Hello!", "stop");
+
+        assert!(process_channel_message(&state, &user_message("hi")).await.is_none());
+
+        config.channels.telegram.as_mut().unwrap().allowed_chats = Some(vec!["123".to_string()]);
+        state.reload_config(config);
+        let reply = process_channel_message(&state, &user_message("hi again")).await.unwrap().unwrap();
+        assert_eq!(reply.message.text, "Hello!");
+    }
+
+    #[tokio::test]
+    async fn test_reset_command_needs_history() {
+        let agent = Arc::new(RecordingAgent::new(&["agent reply"]));
+        let state = AppState::with_agent(create_test_config_no_orchestration(), CancellationToken::new(), agent.clone());
+
+        let reply = process_channel_message(&state, &user_message("/reset")).await.unwrap().unwrap();
+        assert_eq!(reply.message.text, "agent reply");
+        assert_eq!(agent.calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_is_reset_command() {
+        assert!(is_reset_command(" /RESET ", None));
+        assert!(is_reset_command("/reset@clanker_bot", Some("Clanker_Bot")));
+        assert!(!is_reset_command("/reset@otherbot", Some("clanker_bot")));
+        assert!(!is_reset_command("/reset@clanker_bot", None));
+        assert!(!is_reset_command("/resetall", None));
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_limit_wait_for_a_permit() {
+        use std::sync::atomic::Ordering;
+
+        let mut config = create_test_config_no_orchestration();
+        config.processing.max_concurrent_requests = 1;
+        let agent = Arc::new(GatedAgent {
+            gate: tokio::sync::Semaphore::new(0),
+            started: Default::default(),
+        });
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        let spawn = |text: &'static str| {
+            let state = state.clone();
+            tokio::spawn(async move { process_message(&state, &user_message(text)).await })
+        };
+        let first = spawn("one");
+        let second = spawn("two");
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while agent.started.load(Ordering::SeqCst) == 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // The second request is parked on the limiter, not at the provider
+        assert_eq!(agent.started.load(Ordering::SeqCst), 1);
+        assert_eq!(state.request_semaphore().available_permits(), 0);
+
+        agent.gate.add_permits(2);
+        assert_eq!(first.await.unwrap().unwrap().message.text, "done");
+        assert_eq!(second.await.unwrap().unwrap().message.text, "done");
+        assert_eq!(agent.started.load(Ordering::SeqCst), 2);
+        assert_eq!(state.request_semaphore().available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_limit_rejects_over_limit_messages() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.max_inbound_chars = Some(5);
+        let agent = Arc::new(RecordingAgent::new(&["ok", "ok"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        // Exactly at the limit (multi-byte characters count once)
+        let processed = process_message(&state, &user_message("héllo")).await.unwrap();
+        assert_eq!(processed.message.text, "ok");
+
+        let err = process_message(&state, &user_message("hello!")).await.unwrap_err();
+        assert_eq!(err, "Message too long: 6 characters (limit 5)");
+
+        // Channel users get a notice instead of an error
+        let reply = process_channel_message(&state, &user_message("hello!")).await.unwrap().unwrap();
+        assert_eq!(reply.message.text, INBOUND_TOO_LONG_MESSAGE);
+        assert_eq!(agent.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_limit_truncates_over_limit_messages() {
+        let mut config = create_test_config_no_orchestration();
+        config.processing.max_inbound_chars = Some(5);
+        config.processing.inbound_limit_mode = InboundLimitMode::Truncate;
+        let agent = Arc::new(RecordingAgent::new(&["ok", "ok"]));
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+
+        process_message(&state, &user_message("hello")).await.unwrap();
+        process_message(&state, &user_message("hello world")).await.unwrap();
+
+        let calls = agent.calls.lock().unwrap();
+        assert_eq!(calls[0].last().unwrap().content, "hello");
+        assert_eq!(calls[1].last().unwrap().content, "hello");
     }
 }
@@ -0,0 +1,190 @@
+//! Message routing: decide how an inbound message is handled before any agent call.
+//!
+//! The processor consults a [`RouterChain`]; the first router that returns a
+//! [`Route`] wins. Routers that don't recognize a message return `None` so the
+//! next one can look at it.
+
+use crate::faq::FaqMatcher;
+use crate::state::AppState;
+use async_trait::async_trait;
+use clanker_config::Config;
+use clanker_core::Message;
+
+/// Handling path for an inbound message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// Reply with fixed text (FAQ answers, built-in commands) without calling the agent
+    Static(String),
+    /// Send to the primary agent directly
+    Agent,
+    /// Send to Master_Clanker, which may delegate to Worker_Clankers
+    Orchestrate,
+}
+
+/// Inspects an inbound message and optionally decides its route
+#[async_trait]
+pub trait Router: Send + Sync {
+    /// Router name (for logging)
+    fn name(&self) -> &str;
+
+    /// Return a route, or `None` to defer to the next router in the chain
+    async fn route(&self, state: &AppState, message: &Message) -> Option<Route>;
+}
+
+/// Ordered list of routers; falls back to [`Route::Agent`] when none match
+pub struct RouterChain {
+    routers: Vec<Box<dyn Router>>,
+}
+
+impl RouterChain {
+    /// Create chain from routers, consulted in order
+    pub fn new(routers: Vec<Box<dyn Router>>) -> Self {
+        Self { routers }
+    }
+
+    /// Default chain: FAQ answers, then agent or orchestration
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(vec![
+            Box::new(FaqRouter::new(FaqMatcher::from_config(&config.faq))),
+            Box::new(DefaultRouter),
+        ])
+    }
+
+    /// Find the route for a message
+    pub async fn route(&self, state: &AppState, message: &Message) -> Route {
+        for router in &self.routers {
+            if let Some(route) = router.route(state, message).await {
+                tracing::debug!("Router {} chose {:?}", router.name(), route);
+                return route;
+            }
+        }
+        Route::Agent
+    }
+
+    /// Router names in order
+    pub fn names(&self) -> Vec<&str> {
+        self.routers.iter().map(|r| r.name()).collect()
+    }
+}
+
+impl std::fmt::Debug for RouterChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouterChain")
+            .field("routers", &self.names())
+            .finish()
+    }
+}
+
+/// Answers configured FAQ inputs with their static response
+#[derive(Debug)]
+pub struct FaqRouter {
+    matcher: FaqMatcher,
+}
+
+impl FaqRouter {
+    /// Create FAQ router from a compiled matcher
+    pub fn new(matcher: FaqMatcher) -> Self {
+        Self { matcher }
+    }
+}
+
+#[async_trait]
+impl Router for FaqRouter {
+    fn name(&self) -> &str {
+        "faq"
+    }
+
+    async fn route(&self, _state: &AppState, message: &Message) -> Option<Route> {
+        self.matcher
+            .lookup(&message.text)
+            .map(|answer| Route::Static(answer.to_string()))
+    }
+}
+
+/// Sends everything to orchestration when enabled, otherwise to the agent
+#[derive(Debug, Default)]
+pub struct DefaultRouter;
+
+#[async_trait]
+impl Router for DefaultRouter {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    async fn route(&self, state: &AppState, _message: &Message) -> Option<Route> {
+        if state.orchestration_enabled() && state.orchestrator().is_some() {
+            Some(Route::Orchestrate)
+        } else {
+            Some(Route::Agent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clanker_config::FaqEntry;
+    use clanker_core::ChannelType;
+    use tokio_util::sync::CancellationToken;
+
+    fn create_test_config() -> Config {
+        toml::from_str(include_str!("../../../config-examples/config.toml")).unwrap()
+    }
+
+    fn message(text: &str) -> Message {
+        Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "user".to_string(),
+            text.to_string(),
+        )
+    }
+
+    /// Routes one exact input to a static reply
+    struct CommandRouter;
+
+    #[async_trait]
+    impl Router for CommandRouter {
+        fn name(&self) -> &str {
+            "command"
+        }
+
+        async fn route(&self, _state: &AppState, message: &Message) -> Option<Route> {
+            (message.text == "/ping").then(|| Route::Static("pong".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_chain_routes() {
+        let mut config = create_test_config();
+        config.faq.entries.push(FaqEntry {
+            pattern: "hello".to_string(),
+            response: "Hi there!".to_string(),
+            regex: false,
+        });
+        let chain = RouterChain::from_config(&config);
+        assert_eq!(chain.names(), vec!["faq", "default"]);
+
+        // Example config enables orchestration
+        let state = AppState::new(config.clone(), CancellationToken::new());
+        assert_eq!(chain.route(&state, &message("Hello")).await, Route::Static("Hi there!".to_string()));
+        assert_eq!(chain.route(&state, &message("what is rust?")).await, Route::Orchestrate);
+
+        config.orchestration.enabled = false;
+        let state = AppState::new(config, CancellationToken::new());
+        assert_eq!(chain.route(&state, &message("what is rust?")).await, Route::Agent);
+    }
+
+    #[tokio::test]
+    async fn test_chain_order_and_fallthrough() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+
+        let chain = RouterChain::new(vec![Box::new(CommandRouter)]);
+        assert_eq!(chain.route(&state, &message("/ping")).await, Route::Static("pong".to_string()));
+        // No router matched
+        assert_eq!(chain.route(&state, &message("hi")).await, Route::Agent);
+
+        let chain = RouterChain::new(vec![Box::new(DefaultRouter), Box::new(CommandRouter)]);
+        assert_eq!(chain.route(&state, &message("/ping")).await, Route::Orchestrate);
+    }
+}
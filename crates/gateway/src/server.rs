@@ -1,20 +1,203 @@
-use crate::handlers::{health_check, root, websocket_handler};
-use crate::middleware::{cors_layer, security_headers_middleware};
+use crate::handlers::{
+    broadcast_notice, health_check, health_detailed, list_channels, list_connections, metrics, root, send_message, stats, version, websocket_handler,
+};
+use crate::middleware::{cors_layer, request_timing_middleware, require_admin_token, security_headers_middleware};
 use crate::processor;
 use crate::state::AppState;
-use axum::{routing::{any, get, Router}};
+use axum::{routing::{any, get, post, Router}};
 use clanker_config::Config;
-use clanker_core::Message;
+use clanker_core::{mask_secret, Message};
+use clanker_channels::Channel;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
+
+/// First wait before restarting a failed channel listener
+const LISTENER_BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// Longest wait between listener restarts
+const LISTENER_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// A listener that ran this long before failing starts the backoff over
+const LISTENER_STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// How often the typing indicator is renewed while a channel message is processed
+/// (Telegram clears it after about 5 seconds)
+const TYPING_REFRESH: Duration = Duration::from_secs(4);
+
+/// Loads the configuration again from where it came from (file, environment,
+/// CLI overrides); called on SIGHUP
+pub type ConfigLoader = Arc<dyn Fn() -> anyhow::Result<Config> + Send + Sync>;
+
+/// Reload and validate the configuration, then swap it into `state`.
+/// On failure the running configuration stays in place.
+fn reload_config(state: &AppState, loader: &ConfigLoader) {
+    let config = match loader() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Config reload failed, keeping the running configuration: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = config.validate() {
+        error!("Reloaded config is invalid, keeping the running configuration: {}", e);
+        return;
+    }
+
+    let restart_required = state.reload_config(config);
+    info!("Configuration reloaded");
+    if !restart_required.is_empty() {
+        warn!(
+            "Changed settings take effect after a restart: {}",
+            restart_required.join(", ")
+        );
+    }
+}
+
+/// Reconnect policy for channel listeners: 1s, doubling per consecutive failure up to 60s
+#[derive(Debug, Default)]
+struct ListenerBackoff {
+    failures: u32,
+}
+
+impl ListenerBackoff {
+    /// Record a failure after the listener ran for `uptime`; returns the wait before reconnecting
+    fn next_delay(&mut self, uptime: Duration) -> Duration {
+        if uptime >= LISTENER_STABLE_AFTER {
+            self.failures = 0;
+        }
+        let factor = 1u32 << self.failures.min(6);
+        self.failures = self.failures.saturating_add(1);
+        (LISTENER_BACKOFF_BASE * factor).min(LISTENER_BACKOFF_MAX)
+    }
+
+    /// Consecutive failures so far
+    fn attempts(&self) -> u32 {
+        self.failures
+    }
+}
+
+/// Run a channel listener, reconnecting with backoff when it fails.
+/// Returns when the listener stops cleanly, the processing loop is gone, or on shutdown.
+async fn run_listener(
+    ch: Arc<dyn Channel + Send + Sync>,
+    tx: mpsc::Sender<Message>,
+    shutdown: CancellationToken,
+) {
+    let channel_type = ch.channel_type();
+    let mut backoff = ListenerBackoff::default();
+
+    loop {
+        let started = Instant::now();
+        let result = tokio::select! {
+            result = ch.listen_with_tx(tx.clone()) => result,
+            _ = shutdown.cancelled() => return,
+        };
+
+        let e = match result {
+            Ok(()) => {
+                info!("Channel {} listener stopped", channel_type);
+                return;
+            }
+            Err(e) => e,
+        };
+        if tx.is_closed() {
+            error!("Channel {} listener error: {}; processing loop is gone, not reconnecting", channel_type, e);
+            return;
+        }
+
+        let delay = backoff.next_delay(started.elapsed());
+        warn!(
+            "Channel {} listener error: {}; reconnecting in {:?} (attempt {})",
+            channel_type,
+            e,
+            delay,
+            backoff.attempts()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.cancelled() => return,
+        }
+        info!("Reconnecting channel {} listener", channel_type);
+    }
+}
+
+/// Open connections to the primary and fallback providers (`server.warmup_on_start`).
+/// Returns how long the warmup took, or `None` when it is disabled.
+async fn warmup_providers(state: &AppState) -> Option<Duration> {
+    if !state.config().server.warmup_on_start {
+        return None;
+    }
+    let started = Instant::now();
+    let agents: Vec<_> = std::iter::once(state.agent()).chain(state.fallback_agent()).collect();
+    let results = futures_util::future::join_all(agents.iter().map(|agent| agent.warmup())).await;
+    for (agent, result) in agents.iter().zip(results) {
+        if let Err(e) = result {
+            warn!("Warmup of {} failed: {}", agent.provider(), e);
+        }
+    }
+    let elapsed = started.elapsed();
+    info!("Provider warmup took {:?} ({} agents)", elapsed, agents.len());
+    Some(elapsed)
+}
+
+/// Await `work` while keeping the chat's typing indicator up
+async fn with_typing<F: std::future::Future>(
+    channel: Option<Arc<dyn Channel + Send + Sync>>,
+    channel_id: &str,
+    work: F,
+) -> F::Output {
+    let Some(channel) = channel else {
+        return work.await;
+    };
+    tokio::pin!(work);
+    let mut refresh = tokio::time::interval(TYPING_REFRESH);
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            _ = refresh.tick() => {
+                if let Err(e) = channel.send_typing(channel_id).await {
+                    debug!("Typing indicator for {} failed: {}", channel.channel_type(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Serve `app` on every listener until shutdown. All listeners share the
+/// router (and so the `AppState`); if one fails the others are shut down too.
+async fn serve_all(listeners: Vec<TcpListener>, app: Router, shutdown: CancellationToken) -> std::io::Result<()> {
+    let mut servers = tokio::task::JoinSet::new();
+    for listener in listeners {
+        let app = app.clone();
+        let shutdown = shutdown.clone();
+        servers.spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                .await
+        });
+    }
+
+    while let Some(joined) = servers.join_next().await {
+        let result = joined.unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        if let Err(e) = result {
+            error!("Listener failed: {}; shutting down", e);
+            shutdown.cancel();
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
 /// Gateway Server
 pub struct GatewayServer {
     config: Config,
     state: AppState,
     shutdown_token: CancellationToken,
+    config_loader: Option<ConfigLoader>,
 }
 
 impl GatewayServer {
@@ -26,25 +209,63 @@ impl GatewayServer {
         info!("  Port: {}", config.server.port);
         info!("  Provider: {}", config.agent.provider);
         info!("  Model: {}", config.agent.model);
+        match config.agent.api_key.as_deref() {
+            Some(key) if !key.is_empty() => {
+                info!("  API key: {} (from {})", mask_secret(key), config.agent.api_key_env)
+            }
+            _ => info!("  API key: not set ({})", config.agent.api_key_env),
+        }
 
         Self {
             config,
             state,
             shutdown_token,
+            config_loader: None,
         }
     }
 
+    /// Reload the configuration with `loader` on SIGHUP
+    pub fn with_config_loader(mut self, loader: ConfigLoader) -> Self {
+        self.config_loader = Some(loader);
+        self
+    }
+
     pub async fn start(self) -> Result<(), Box<dyn std::error::Error>> {
-        let addr = format!("{}:{}", self.config.server.host, self.config.server.port);
-        let listener = TcpListener::bind(&addr).await?;
+        // Starting over from zero would let spending run past the caps
+        if let Some(e) = self.state.budget().and_then(|budget| budget.load_error()) {
+            return Err(e.into());
+        }
+
+        // Bind everything up front so a bad address fails startup before anything is served
+        let mut listeners = Vec::new();
+        for addr in self.addresses() {
+            let listener = TcpListener::bind(&addr).await?;
 
-        info!("Gateway server listening on {}", addr);
-        info!("  WebSocket: ws://{}/ws", addr);
-        info!("  Health: http://{}/health", addr);
-        info!("  API: http://{}/", addr);
+            info!("Gateway server listening on {}", addr);
+            info!("  WebSocket: ws://{}/ws", addr);
+            info!("  Health: http://{}/health", addr);
+            info!("  API: http://{}/", addr);
+            listeners.push(listener);
+        }
 
         let app = self.build_router();
         self.setup_graceful_shutdown();
+        self.setup_config_reload();
+
+        // Surface misconfigured keys at boot rather than on the first user message;
+        // the warmup runs alongside so neither adds to startup time on its own
+        let agent = self.state.agent();
+        let (_, health) = tokio::join!(warmup_providers(&self.state), agent.health());
+        self.state.record_agent_result(&health);
+        match health {
+            Ok(()) => info!("Agent health check passed ({} / {})", agent.provider(), agent.model()),
+            Err(e) => {
+                warn!("==========================================================");
+                warn!("Agent health check FAILED ({} / {}): {}", agent.provider(), agent.model(), e);
+                warn!("Messages will fail until the agent configuration is fixed");
+                warn!("==========================================================");
+            }
+        }
 
         // Spawn channel listeners and processing loop when channels are configured
         let state = self.state.clone();
@@ -54,13 +275,7 @@ impl GatewayServer {
 
             // Spawn channel listeners
             for ch in state.channels() {
-                let ch = ch.clone();
-                let tx = tx.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = ch.listen_with_tx(tx).await {
-                        error!("Channel {} listener error: {}", ch.channel_type(), e);
-                    }
-                });
+                tokio::spawn(run_listener(ch.clone(), tx.clone(), shutdown.clone()));
             }
 
             // Spawn processing loop
@@ -69,17 +284,21 @@ impl GatewayServer {
                 loop {
                     tokio::select! {
                         Some(incoming) = rx.recv() => {
-                            match processor::process_message(&state_clone, &incoming).await {
-                                Ok(response) => {
+                            let processing = processor::process_channel_message(&state_clone, &incoming);
+                            let channel = state_clone.channel_for(incoming.channel_type);
+                            match with_typing(channel, &incoming.channel_id, processing).await {
+                                None => {}
+                                Some(Ok(processed)) if processed.delivered => {}
+                                Some(Ok(processed)) => {
                                     if let Some(ch) = state.channel_for(incoming.channel_type) {
-                                        if let Err(e) = ch.send(response).await {
+                                        if let Err(e) = ch.send(processed.message).await {
                                             error!("Failed to send to {}: {}", incoming.channel_type, e);
                                         }
                                     } else {
                                         warn!("No channel for type {:?}", incoming.channel_type);
                                     }
                                 }
-                                Err(e) => error!("Processor error: {}", e),
+                                Some(Err(e)) => error!("Processor error: {}", e),
                             }
                         }
                         _ = shutdown.cancelled() => break,
@@ -88,11 +307,7 @@ impl GatewayServer {
             });
         }
 
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async move {
-                self.shutdown_token.cancelled().await;
-            })
-            .await?;
+        serve_all(listeners, app, self.shutdown_token.clone()).await?;
 
         info!("Gateway server shutdown complete");
         Ok(())
@@ -102,10 +317,37 @@ impl GatewayServer {
         Router::new()
             .route("/", get(root))
             .route("/health", get(health_check))
+            .route("/health/detailed", get(health_detailed))
+            .route("/stats", get(stats))
+            .route("/metrics", get(metrics))
+            .route("/version", get(version))
+            .route("/send", post(send_message))
+            .route(
+                "/channels",
+                get(list_channels).layer(axum::middleware::from_fn_with_state(
+                    self.state.clone(),
+                    require_admin_token,
+                )),
+            )
+            .route(
+                "/connections",
+                get(list_connections).layer(axum::middleware::from_fn_with_state(
+                    self.state.clone(),
+                    require_admin_token,
+                )),
+            )
+            .route(
+                "/admin/broadcast",
+                post(broadcast_notice).layer(axum::middleware::from_fn_with_state(
+                    self.state.clone(),
+                    require_admin_token,
+                )),
+            )
             .route("/ws", any(websocket_handler))
             .with_state(self.state.clone())
             .layer(cors_layer())
             .route_layer(axum::middleware::from_fn(security_headers_middleware))
+            .layer(axum::middleware::from_fn(request_timing_middleware))
     }
 
     fn setup_graceful_shutdown(&self) {
@@ -130,10 +372,40 @@ impl GatewayServer {
         });
     }
 
+    fn setup_config_reload(&self) {
+        let Some(loader) = self.config_loader.clone() else {
+            return;
+        };
+        let state = self.state.clone();
+        let shutdown_token = self.shutdown_token.clone();
+        tokio::spawn(async move {
+            let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .expect("Failed to setup SIGHUP handler");
+            loop {
+                tokio::select! {
+                    Some(()) = hangup.recv() => {
+                        info!("SIGHUP received, reloading configuration...");
+                        reload_config(&state, &loader);
+                    }
+                    _ = shutdown_token.cancelled() => break,
+                }
+            }
+        });
+    }
+
     pub fn address(&self) -> String {
         format!("{}:{}", self.config.server.host, self.config.server.port)
     }
 
+    /// Addresses the server binds: `server.listen`, or `host:port` when that is empty
+    pub fn addresses(&self) -> Vec<String> {
+        if self.config.server.listen.is_empty() {
+            vec![self.address()]
+        } else {
+            self.config.server.listen.clone()
+        }
+    }
+
     pub fn state(&self) -> &AppState {
         &self.state
     }
@@ -159,6 +431,32 @@ mod tests {
         assert_eq!(server.state().version(), env!("CARGO_PKG_VERSION"));
     }
 
+    #[test]
+    fn test_listener_backoff_doubles_and_caps() {
+        let mut backoff = ListenerBackoff::default();
+        let quick = Duration::from_secs(1);
+
+        assert_eq!(backoff.next_delay(quick), Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(quick), Duration::from_secs(2));
+        assert_eq!(backoff.next_delay(quick), Duration::from_secs(4));
+        assert_eq!(backoff.attempts(), 3);
+
+        for _ in 0..10 {
+            backoff.next_delay(quick);
+        }
+        assert_eq!(backoff.next_delay(quick), LISTENER_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn test_listener_backoff_resets_after_stable_run() {
+        let mut backoff = ListenerBackoff::default();
+        for _ in 0..4 {
+            backoff.next_delay(Duration::ZERO);
+        }
+        assert_eq!(backoff.next_delay(LISTENER_STABLE_AFTER), LISTENER_BACKOFF_BASE);
+        assert_eq!(backoff.attempts(), 1);
+    }
+
     #[tokio::test]
     async fn test_router_creation() {
         let config = create_test_config();
@@ -167,4 +465,156 @@ mod tests {
         let server = GatewayServer::new(config, shutdown_token);
         let _router = server.build_router();
     }
+
+    #[tokio::test]
+    async fn test_channels_requires_admin_token() {
+        use tower::ServiceExt;
+
+        let get_channels = |token: Option<&str>| {
+            let request = axum::http::Request::get("/channels");
+            let request = match token {
+                Some(token) => request.header("authorization", format!("Bearer {}", token)),
+                None => request,
+            };
+            request.body(axum::body::Body::empty()).unwrap()
+        };
+
+        let mut config = create_test_config();
+        config.server.admin_token = Some("secret".to_string());
+        let router = GatewayServer::new(config, CancellationToken::new()).build_router();
+
+        let response = router.clone().oneshot(get_channels(None)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        let response = router.oneshot(get_channels(Some("secret"))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    #[test]
+    fn test_addresses_default_to_host_port() {
+        let mut config = create_test_config();
+        let server = GatewayServer::new(config.clone(), CancellationToken::new());
+        assert_eq!(server.addresses(), vec!["0.0.0.0:18789"]);
+
+        config.server.listen = vec!["127.0.0.1:18790".to_string(), "[::]:18789".to_string()];
+        let server = GatewayServer::new(config, CancellationToken::new());
+        assert_eq!(server.addresses(), vec!["127.0.0.1:18790", "[::]:18789"]);
+    }
+
+    async fn get_health(addr: std::net::SocketAddr) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_one_router_served_on_multiple_binds() {
+        let shutdown_token = CancellationToken::new();
+        let server = GatewayServer::new(create_test_config(), shutdown_token.clone());
+
+        let first = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let second = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addrs = [first.local_addr().unwrap(), second.local_addr().unwrap()];
+        let serving = tokio::spawn(serve_all(vec![first, second], server.build_router(), shutdown_token.clone()));
+
+        for addr in addrs {
+            let response = get_health(addr).await;
+            assert!(response.starts_with("HTTP/1.1 200"), "{}: {}", addr, response);
+            assert!(response.contains(&format!("\"version\":\"{}\"", env!("CARGO_PKG_VERSION"))));
+        }
+
+        shutdown_token.cancel();
+        serving.await.unwrap().unwrap();
+    }
+
+    /// Example config with the API key the environment would provide
+    fn valid_config() -> Config {
+        let mut config = create_test_config();
+        config.agent.api_key = Some("test-key".to_string());
+        config
+    }
+
+    #[test]
+    fn test_reload_rejects_invalid_config() {
+        let state = AppState::new(valid_config(), CancellationToken::new());
+
+        let loader: ConfigLoader = Arc::new(|| {
+            let mut config = valid_config();
+            config.processing.refusal_message = Some("Not today.".to_string());
+            config.processing.max_concurrent_requests = 0;
+            Ok(config)
+        });
+        reload_config(&state, &loader);
+        assert!(state.config().processing.refusal_message.is_none());
+
+        let loader: ConfigLoader = Arc::new(|| anyhow::bail!("config.toml: no such file"));
+        reload_config(&state, &loader);
+
+        let loader: ConfigLoader = Arc::new(|| {
+            let mut config = valid_config();
+            config.processing.refusal_message = Some("Not today.".to_string());
+            Ok(config)
+        });
+        reload_config(&state, &loader);
+        assert_eq!(state.config().processing.refusal_message.as_deref(), Some("Not today."));
+    }
+
+    /// Agent counting warmups
+    #[derive(Default)]
+    struct WarmupCounter {
+        warmups: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl clanker_agent::Agent for WarmupCounter {
+        async fn chat(
+            &self,
+            _messages: Vec<clanker_agent::AgentMessage>,
+        ) -> Result<clanker_agent::AgentResponse, clanker_agent::AgentError> {
+            Err(clanker_agent::AgentError::Unknown("not used".to_string()))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<clanker_agent::AgentMessage>,
+        ) -> Result<
+            Box<dyn futures_util::Stream<Item = Result<clanker_agent::StreamChunk, clanker_agent::AgentError>> + Send + Unpin>,
+            clanker_agent::AgentError,
+        > {
+            Err(clanker_agent::AgentError::Unknown("not used".to_string()))
+        }
+
+        async fn warmup(&self) -> Result<(), clanker_agent::AgentError> {
+            self.warmups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn provider(&self) -> &str {
+            "counter"
+        }
+
+        fn model(&self) -> &str {
+            "counter"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warmup_runs_only_when_enabled() {
+        let agent = Arc::new(WarmupCounter::default());
+        let state = AppState::with_agent(create_test_config(), CancellationToken::new(), agent.clone());
+        assert_eq!(warmup_providers(&state).await, None);
+        assert_eq!(agent.warmups.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        let mut config = create_test_config();
+        config.server.warmup_on_start = true;
+        let state = AppState::with_agent(config, CancellationToken::new(), agent.clone());
+        assert!(warmup_providers(&state).await.is_some());
+        assert_eq!(agent.warmups.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
 }
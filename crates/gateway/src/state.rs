@@ -1,17 +1,84 @@
+use crate::audit::AuditLog;
 use crate::broadcast::MessageBroadcaster;
+use crate::budget::SpendBudget;
+use crate::cache::ResponseCache;
+use crate::dedup::MessageDeduplicator;
+use crate::history::ConversationHistory;
+use crate::pool::AgentPool;
 use crate::processor;
-use crate::types::{ConnectionId, ConnectionState};
-use clanker_config::Config;
-use clanker_core::ChannelType;
+use crate::router::RouterChain;
+use crate::types::{ApiError, ConnectionId, ConnectionState, HealthResponse};
+use arc_swap::ArcSwap;
+use clanker_channels::discord::{DiscordChannel, MessageTrigger};
+use clanker_config::{Config, LagPolicy};
+use clanker_core::{ChannelType, Message};
 use std::collections::HashMap;
 use std::fmt;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, Semaphore};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Connection or subscription rejected by a configured limit
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitError {
+    #[error("Connection limit reached ({0})")]
+    TooManyConnections(usize),
+
+    #[error("Subscription limit reached ({0} per connection)")]
+    TooManySubscriptions(usize),
+
+    #[error("Unknown connection")]
+    UnknownConnection,
+}
+
+impl LimitError {
+    /// Error code sent to WebSocket clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitError::TooManyConnections(_) => "CONNECTION_LIMIT",
+            LimitError::TooManySubscriptions(_) => "SUBSCRIPTION_LIMIT",
+            LimitError::UnknownConnection => "UNKNOWN_CONNECTION",
+        }
+    }
+}
+
+/// Channel section found in the config, and why it was not instantiated (if it wasn't)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfiguredChannel {
+    pub channel_type: ChannelType,
+    pub skipped_reason: Option<String>,
+}
+
+impl ConfiguredChannel {
+    fn from_result<T>(channel_type: ChannelType, created: &std::result::Result<T, String>) -> Self {
+        Self {
+            channel_type,
+            skipped_reason: created.as_ref().err().cloned(),
+        }
+    }
+}
+
+impl From<LimitError> for ApiError {
+    fn from(e: LimitError) -> Self {
+        let status = match e {
+            LimitError::UnknownConnection => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            _ => axum::http::StatusCode::TOO_MANY_REQUESTS,
+        };
+        ApiError::new(e.code(), e.to_string(), status)
+    }
+}
+
+/// Outcome of the most recent primary agent call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentOutcome {
+    Success(chrono::DateTime<chrono::Utc>),
+    Failure(chrono::DateTime<chrono::Utc>, String),
+}
+
 /// Shared application state
 #[derive(Debug, Clone)]
 pub struct AppState {
@@ -22,21 +89,81 @@ pub struct AppState {
 impl AppState {
     /// Create new application state
     pub fn new(config: Config, shutdown_token: CancellationToken) -> Self {
-        let inner = Arc::new(AppStateInner::new(config, shutdown_token));
+        let agent = processor::create_agent(&config);
+        let pool = processor::create_pool_agents(&config);
+        Self::with_agents(config, shutdown_token, agent, pool)
+    }
+
+    /// Create application state around an existing agent instead of building one from config
+    pub fn with_agent(
+        config: Config,
+        shutdown_token: CancellationToken,
+        agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
+    ) -> Self {
+        Self::with_agents(config, shutdown_token, agent, Vec::new())
+    }
+
+    /// Create application state whose direct replies rotate across `agent` and `pool`
+    pub fn with_agents(
+        config: Config,
+        shutdown_token: CancellationToken,
+        agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
+        pool: Vec<Arc<dyn clanker_agent::Agent + Send + Sync>>,
+    ) -> Self {
+        let inner = Arc::new(AppStateInner::new(config, shutdown_token, agent, pool));
 
         info!("Application state created");
 
         Self { inner }
     }
 
+    /// Run Worker_Clankers on `worker` instead of the configured worker provider.
+    /// Call before the state is cloned; panics on a shared state.
+    pub fn with_worker_agent(mut self, worker: Arc<dyn clanker_agent::Agent + Send + Sync>) -> Self {
+        let inner = Arc::get_mut(&mut self.inner).expect("with_worker_agent called on a shared AppState");
+        let agents = inner.agents.load_full();
+        let orchestrator = build_orchestrator(&inner.config.load(), &agents.agent, Some(&worker));
+        inner.agents.store(Arc::new(Agents {
+            agent: agents.agent.clone(),
+            agent_pool: agents.agent_pool.clone(),
+            fallback_agent: agents.fallback_agent.clone(),
+            orchestrator,
+        }));
+        inner.worker_agent = Some(worker);
+        self
+    }
+
     /// Get broadcaster
     pub fn broadcaster(&self) -> &MessageBroadcaster {
         &self.inner.broadcaster
     }
 
-    /// Get configuration
-    pub fn config(&self) -> &Config {
-        &self.inner.config
+    /// Get the current configuration (replaced by [`AppState::reload_config`])
+    pub fn config(&self) -> Arc<Config> {
+        self.inner.config.load_full()
+    }
+
+    /// Swap in a reloaded configuration. Settings that only take effect at
+    /// startup keep their running values; their names are returned so the
+    /// caller can report that they need a restart. Changed `[agent]` or
+    /// `[[agents]]` settings rebuild the agents (replacing injected ones too).
+    pub fn reload_config(&self, mut config: Config) -> Vec<&'static str> {
+        let current = self.config();
+        let restart_required = keep_startup_settings(&current, &mut config);
+        if agents_changed(&current, &config) {
+            info!("Agent settings changed, rebuilding the agents");
+            let agent = processor::create_agent(&config);
+            let pool = processor::create_pool_agents(&config);
+            let agents = Agents::new(&config, agent, pool, self.inner.worker_agent.as_ref());
+            self.inner.agents.store(Arc::new(agents));
+        }
+        self.inner.config.store(Arc::new(config));
+        restart_required
+    }
+
+    /// Check if replies come from the placeholder agent (`server.dry_run`)
+    pub fn is_dry_run(&self) -> bool {
+        self.config().server.dry_run
     }
 
     /// Get shutdown token
@@ -44,12 +171,52 @@ impl AppState {
         &self.inner.shutdown_token
     }
 
-    /// Add connection
-    pub async fn add_connection(&self, id: ConnectionId, state: ConnectionState) {
+    /// Add connection (rejected once `server.max_connections` is reached)
+    pub async fn add_connection(&self, id: ConnectionId, state: ConnectionState) -> Result<(), LimitError> {
+        let max = self.config().server.max_connections;
         let mut connections = self.inner.connections.write().await;
+        if connections.len() >= max {
+            warn!("Connection {} rejected: limit of {} reached", id, max);
+            return Err(LimitError::TooManyConnections(max));
+        }
         connections.insert(id, state);
+        self.inner.peak_connections.fetch_max(connections.len(), Ordering::Relaxed);
 
         debug!("Connection {} added. Total connections: {}", id, connections.len());
+        Ok(())
+    }
+
+    /// Subscribe a connection to a channel (rejected once `server.max_subscriptions_per_connection` is reached)
+    pub async fn subscribe(
+        &self,
+        id: &ConnectionId,
+        channel_id: String,
+        channel_type: ChannelType,
+    ) -> Result<(), LimitError> {
+        let max = self.config().server.max_subscriptions_per_connection;
+        let mut connections = self.inner.connections.write().await;
+        let conn = connections.get_mut(id).ok_or(LimitError::UnknownConnection)?;
+        if !conn.is_subscribed(&channel_id) && conn.subscription_count() >= max {
+            return Err(LimitError::TooManySubscriptions(max));
+        }
+        conn.subscribe(channel_id, channel_type);
+        Ok(())
+    }
+
+    /// Unsubscribe a connection from a channel
+    pub async fn unsubscribe(&self, id: &ConnectionId, channel_id: &str) {
+        let mut connections = self.inner.connections.write().await;
+        if let Some(conn) = connections.get_mut(id) {
+            conn.unsubscribe(channel_id);
+        }
+    }
+
+    /// Turn a connection's all-channels wildcard on or off
+    pub async fn set_subscribe_all(&self, id: &ConnectionId, enabled: bool) {
+        let mut connections = self.inner.connections.write().await;
+        if let Some(conn) = connections.get_mut(id) {
+            conn.set_subscribe_all(enabled);
+        }
     }
 
     /// Remove connection
@@ -78,6 +245,49 @@ impl AppState {
         connections.len()
     }
 
+    /// Record a client lagging the broadcast channel by `skipped` messages.
+    /// Returns true when the client should stay connected under the configured lag policy.
+    pub fn record_broadcast_lag(&self, skipped: u64) -> bool {
+        self.inner.broadcast_lag_events.fetch_add(1, Ordering::Relaxed);
+        self.inner.broadcast_lagged_messages.fetch_add(skipped, Ordering::Relaxed);
+        match self.config().server.broadcast_lag_policy {
+            LagPolicy::SkipToLatest => true,
+            LagPolicy::Disconnect => {
+                self.inner.broadcast_lag_disconnects.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// Get broadcast lag counters: (lag events, messages dropped, clients disconnected)
+    pub fn broadcast_lag_counts(&self) -> (u64, u64, u64) {
+        (
+            self.inner.broadcast_lag_events.load(Ordering::Relaxed),
+            self.inner.broadcast_lagged_messages.load(Ordering::Relaxed),
+            self.inner.broadcast_lag_disconnects.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Last `/health` response, when computed within `server.health_cache_ms`
+    pub fn cached_health(&self) -> Option<HealthResponse> {
+        let max_age = Duration::from_millis(self.config().server.health_cache_ms);
+        let cached = self.inner.health_cache.read().unwrap_or_else(|e| e.into_inner());
+        cached
+            .as_ref()
+            .filter(|(computed_at, _)| computed_at.elapsed() < max_age)
+            .map(|(_, health)| health.clone())
+    }
+
+    /// Remember a freshly computed `/health` response
+    pub fn store_health(&self, health: HealthResponse) {
+        *self.inner.health_cache.write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), health));
+    }
+
+    /// Get peak concurrent connections since startup
+    pub fn peak_connection_count(&self) -> usize {
+        self.inner.peak_connections.load(Ordering::Relaxed)
+    }
+
     /// Increment message count
     pub fn increment_message_count(&self) {
         self.inner.total_messages.fetch_add(1, Ordering::Relaxed);
@@ -122,22 +332,139 @@ impl AppState {
 
     /// Get agent for message processing
     pub fn agent(&self) -> Arc<dyn clanker_agent::Agent + Send + Sync> {
-        self.inner.agent.clone()
+        self.inner.agents.load().agent.clone()
+    }
+
+    /// Agents to try, in order, for the next direct reply: the pool's rotation
+    /// when `[[agents]]` is configured, otherwise just the primary agent.
+    /// Each entry carries the agent's pool index (0 = `[agent]`).
+    pub fn direct_agents(&self) -> Vec<(usize, Arc<dyn clanker_agent::Agent + Send + Sync>)> {
+        let agents = self.inner.agents.load();
+        match &agents.agent_pool {
+            Some(pool) => pool.rotation(),
+            None => vec![(0, agents.agent.clone())],
+        }
     }
 
     /// Get fallback agent when primary fails (e.g. Z.ai when Claude fails)
     pub fn fallback_agent(&self) -> Option<Arc<dyn clanker_agent::Agent + Send + Sync>> {
-        self.inner.fallback_agent.clone()
+        self.inner.agents.load().fallback_agent.clone()
+    }
+
+    /// Record the outcome of a primary agent call (used by `/health/detailed`)
+    pub fn record_agent_result<T, E: fmt::Display>(&self, result: &std::result::Result<T, E>) {
+        let mut outcome = self
+            .inner
+            .agent_outcome
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *outcome = Some(match result {
+            Ok(_) => AgentOutcome::Success(chrono::Utc::now()),
+            Err(e) => AgentOutcome::Failure(chrono::Utc::now(), e.to_string()),
+        });
+        match result {
+            Ok(_) => self.inner.primary_failures.store(0, Ordering::Relaxed),
+            Err(_) => {
+                self.inner.primary_failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Primary agent calls that failed in a row since the last success
+    pub fn consecutive_primary_failures(&self) -> u32 {
+        self.inner.primary_failures.load(Ordering::Relaxed)
+    }
+
+    /// Provider answering in place of the primary, once it has failed
+    /// `processing.degraded_after_failures` times in a row and a fallback is configured
+    pub fn degraded_provider(&self) -> Option<String> {
+        let threshold = self.config().processing.degraded_after_failures;
+        if self.consecutive_primary_failures() < threshold {
+            return None;
+        }
+        self.fallback_agent().map(|fallback| fallback.provider().to_string())
+    }
+
+    /// Whether `provider` is the fallback's and none of the primary agents', i.e. a
+    /// reply from it was written in place of the primary
+    pub fn is_fallback_provider(&self, provider: &str) -> bool {
+        let agents = self.inner.agents.load();
+        let primary = match &agents.agent_pool {
+            Some(pool) => pool.agents().iter().any(|agent| agent.provider() == provider),
+            None => agents.agent.provider() == provider,
+        };
+        !primary && agents.fallback_agent.as_ref().is_some_and(|fallback| fallback.provider() == provider)
+    }
+
+    /// Get the outcome of the most recent primary agent call
+    pub fn last_agent_outcome(&self) -> Option<AgentOutcome> {
+        self.inner
+            .agent_outcome
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Get response cache when caching is enabled
+    pub fn response_cache(&self) -> Option<&ResponseCache> {
+        self.inner.response_cache.as_ref()
+    }
+
+    /// Get the audit log when `audit.path` is set
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.inner.audit_log.as_ref()
+    }
+
+    /// Get the spend budget when `[budget]` caps are set
+    pub fn budget(&self) -> Option<&SpendBudget> {
+        self.inner.budget.as_ref()
+    }
+
+    /// Get conversation history when `processing.history_turns` is set
+    pub fn conversation_history(&self) -> Option<&ConversationHistory> {
+        self.inner.conversation_history.as_ref()
+    }
+
+    /// Clear the stored conversation history of a channel; returns the exchanges dropped
+    pub fn reset_conversation(&self, channel_id: &str) -> usize {
+        self.conversation_history()
+            .map_or(0, |history| history.reset(channel_id))
+    }
+
+    /// Record an inbound channel message; `true` if it is a recent duplicate
+    pub fn is_duplicate(&self, message: &Message) -> bool {
+        self.inner
+            .deduplicator
+            .as_ref()
+            .is_some_and(|dedup| dedup.check_and_record(message))
+    }
+
+    /// Get router chain consulted before the agent
+    pub fn router_chain(&self) -> Arc<RouterChain> {
+        self.inner
+            .router_chain
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the router chain (e.g. to add custom routers)
+    pub fn set_router_chain(&self, chain: RouterChain) {
+        *self
+            .inner
+            .router_chain
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Arc::new(chain);
     }
 
     /// Get orchestrator when orchestration is enabled
-    pub fn orchestrator(&self) -> Option<&clanker_agent::MasterClanker> {
-        self.inner.orchestrator.as_ref()
+    pub fn orchestrator(&self) -> Option<Arc<clanker_agent::MasterClanker>> {
+        self.inner.agents.load().orchestrator.clone()
     }
 
     /// Check if orchestration is enabled
     pub fn orchestration_enabled(&self) -> bool {
-        self.inner.config.orchestration.enabled
+        self.config().orchestration.enabled && !self.is_dry_run()
     }
 
     /// Get channel by type (for sending responses)
@@ -150,6 +477,11 @@ impl AppState {
         &self.inner.channels
     }
 
+    /// Get the channels configured at startup, including skipped ones
+    pub fn configured_channels(&self) -> &[ConfiguredChannel] {
+        &self.inner.configured_channels
+    }
+
     /// Get current number of active Worker_Clankers
     pub fn worker_count(&self) -> usize {
         self.inner.active_workers.load(Ordering::Relaxed)
@@ -165,14 +497,33 @@ impl AppState {
         self.inner.worker_semaphore.clone()
     }
 
-    /// Increment active worker count (called when spawning)
-    pub fn increment_worker_count(&self, n: usize) {
+    /// Permits bounding in-flight agent requests (`processing.max_concurrent_requests`)
+    pub fn request_semaphore(&self) -> Arc<Semaphore> {
+        self.inner.request_semaphore.clone()
+    }
+
+    /// Count `n` Worker_Clankers as active until the returned guard is dropped,
+    /// so early returns and cancelled requests can't leave the count behind
+    pub fn track_workers(&self, n: usize) -> WorkerCountGuard {
         self.inner.active_workers.fetch_add(n, Ordering::Relaxed);
+        WorkerCountGuard {
+            state: self.clone(),
+            count: n,
+        }
     }
+}
+
+/// Active Worker_Clankers registered with [`AppState::track_workers`]
+#[derive(Debug)]
+#[must_use = "the workers stop counting as active when the guard is dropped"]
+pub struct WorkerCountGuard {
+    state: AppState,
+    count: usize,
+}
 
-    /// Decrement active worker count (called when workers complete)
-    pub fn decrement_worker_count(&self, n: usize) {
-        self.inner.active_workers.fetch_sub(n, Ordering::Relaxed);
+impl Drop for WorkerCountGuard {
+    fn drop(&mut self) {
+        self.state.inner.active_workers.fetch_sub(self.count, Ordering::Relaxed);
     }
 }
 
@@ -180,18 +531,44 @@ impl AppState {
 struct AppStateInner {
     /// Message broadcaster
     broadcaster: MessageBroadcaster,
-    /// Application configuration
-    config: Config,
-    /// AI agent for message processing (Master_Clanker when orchestration enabled)
-    agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
-    /// Fallback agent when primary fails (e.g. Z.ai when Claude fails)
-    fallback_agent: Option<Arc<dyn clanker_agent::Agent + Send + Sync>>,
-    /// Orchestrator when orchestration is enabled
-    orchestrator: Option<clanker_agent::MasterClanker>,
+    /// Application configuration, swapped on reload
+    config: ArcSwap<Config>,
+    /// Agents answering messages, swapped when a reload changes their settings
+    agents: ArcSwap<Agents>,
+    /// Worker agent replacing the configured worker provider (see [`AppState::with_worker_agent`])
+    worker_agent: Option<Arc<dyn clanker_agent::Agent + Send + Sync>>,
+    /// Cached direct agent responses (opt-in)
+    response_cache: Option<ResponseCache>,
+    /// Recent exchanges per chat (None when history is disabled)
+    conversation_history: Option<ConversationHistory>,
+    /// Recently seen channel messages (None when deduplication is disabled)
+    deduplicator: Option<MessageDeduplicator>,
+    /// Audit trail of answered messages (None without `audit.path`)
+    audit_log: Option<AuditLog>,
+    /// Spend against `[budget]` caps (None without caps)
+    budget: Option<SpendBudget>,
+    /// Routers deciding how each message is handled
+    router_chain: std::sync::RwLock<Arc<RouterChain>>,
+    /// Outcome of the most recent primary agent call
+    agent_outcome: Mutex<Option<AgentOutcome>>,
+    /// Consecutive failed primary agent calls (reset by a success)
+    primary_failures: AtomicU32,
+    /// Most recent `/health` response and when it was computed
+    health_cache: std::sync::RwLock<Option<(Instant, HealthResponse)>>,
     /// Channel instances for sending responses
     channels: Vec<Arc<dyn clanker_channels::Channel + Send + Sync>>,
+    /// Every configured channel, instantiated or skipped
+    configured_channels: Vec<ConfiguredChannel>,
     /// Active connections (connection_id -> connection_state)
     connections: RwLock<HashMap<ConnectionId, ConnectionState>>,
+    /// Highest concurrent connection count seen (high-water mark)
+    peak_connections: AtomicUsize,
+    /// Times a client lagged the broadcast channel
+    broadcast_lag_events: AtomicU64,
+    /// Broadcast messages dropped for lagging clients
+    broadcast_lagged_messages: AtomicU64,
+    /// Clients disconnected for lagging
+    broadcast_lag_disconnects: AtomicU64,
     /// Total messages processed
     total_messages: AtomicU64,
     /// Server start time
@@ -206,14 +583,16 @@ struct AppStateInner {
     max_workers: usize,
     /// Semaphore to limit concurrent workers globally
     worker_semaphore: Arc<Semaphore>,
+    /// Semaphore to limit concurrent agent requests globally
+    request_semaphore: Arc<Semaphore>,
 }
 
 impl fmt::Debug for AppStateInner {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AppStateInner")
             .field("broadcaster", &self.broadcaster)
-            .field("config", &self.config)
-            .field("agent", &format!("<agent: {}>", self.agent.provider()))
+            .field("config", &self.config.load())
+            .field("agent", &format!("<agent: {}>", self.agents.load().agent.provider()))
             .field("connections", &"...")
             .field("total_messages", &self.total_messages.load(Ordering::Relaxed))
             .field("start_time", &self.start_time)
@@ -222,36 +601,98 @@ impl fmt::Debug for AppStateInner {
     }
 }
 
+/// The primary agent and the agents built around it, replaced together on reload
+struct Agents {
+    /// AI agent for message processing (Master_Clanker when orchestration enabled)
+    agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
+    /// Primary agent plus `[[agents]]`, rotated for direct replies (None without extra agents)
+    agent_pool: Option<Arc<AgentPool>>,
+    /// Fallback agent when primary fails (e.g. Z.ai when Claude fails)
+    fallback_agent: Option<Arc<dyn clanker_agent::Agent + Send + Sync>>,
+    /// Orchestrator when orchestration is enabled
+    orchestrator: Option<Arc<clanker_agent::MasterClanker>>,
+}
+
+impl Agents {
+    fn new(
+        config: &Config,
+        agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
+        pool: Vec<Arc<dyn clanker_agent::Agent + Send + Sync>>,
+        worker: Option<&Arc<dyn clanker_agent::Agent + Send + Sync>>,
+    ) -> Self {
+        Self {
+            agent_pool: AgentPool::new(std::iter::once(agent.clone()).chain(pool).collect()).map(Arc::new),
+            fallback_agent: processor::create_fallback_agent(config),
+            orchestrator: build_orchestrator(config, &agent, worker),
+            agent,
+        }
+    }
+}
+
+/// Master_Clanker around `agent` when orchestration is enabled, its workers on `worker`
+/// when given. Workers would call their provider even in dry-run, so the placeholder
+/// answers alone there.
+fn build_orchestrator(
+    config: &Config,
+    agent: &Arc<dyn clanker_agent::Agent + Send + Sync>,
+    worker: Option<&Arc<dyn clanker_agent::Agent + Send + Sync>>,
+) -> Option<Arc<clanker_agent::MasterClanker>> {
+    if !config.orchestration.enabled || config.server.dry_run {
+        return None;
+    }
+    let worker_config = config.agent.worker.clone().unwrap_or_default();
+    let orchestrator = clanker_agent::MasterClanker::new(
+        processor::create_master_agent(config, agent),
+        worker_config,
+        config.orchestration.max_workers,
+    );
+    Some(Arc::new(match worker {
+        Some(worker) => orchestrator.with_worker_agent(worker.clone()),
+        None => orchestrator,
+    }))
+}
+
 impl AppStateInner {
     /// Create new inner state
-    fn new(config: Config, shutdown_token: CancellationToken) -> Self {
-        let agent = processor::create_agent(&config);
-        let fallback_agent = processor::create_fallback_agent(&config);
-        let channels = Self::create_channels_from_config(&config);
+    fn new(
+        config: Config,
+        shutdown_token: CancellationToken,
+        agent: Arc<dyn clanker_agent::Agent + Send + Sync>,
+        pool: Vec<Arc<dyn clanker_agent::Agent + Send + Sync>>,
+    ) -> Self {
+        let agents = Agents::new(&config, agent, pool, None);
+        let (channels, configured_channels) = Self::create_channels_from_config(&config);
+        let router_chain = RouterChain::from_config(&config);
+        let response_cache = ResponseCache::from_config(&config.processing);
+        let conversation_history = ConversationHistory::from_config(&config.processing);
+        let deduplicator = MessageDeduplicator::from_config(&config.processing);
+        let audit_log = AuditLog::from_config(&config.audit);
+        let budget = SpendBudget::from_config(&config.budget);
         let max_workers = config.orchestration.max_workers;
-
-        let orchestrator = if config.orchestration.enabled {
-            let worker_config = config
-                .agent
-                .worker
-                .clone()
-                .unwrap_or_else(clanker_config::WorkerAgentConfig::default);
-            Some(clanker_agent::MasterClanker::new(
-                agent.clone(),
-                worker_config,
-                max_workers,
-            ))
-        } else {
-            None
-        };
+        let max_requests = config.processing.max_concurrent_requests;
 
         Self {
-            broadcaster: MessageBroadcaster::new(shutdown_token.clone()),
-            config,
-            agent,
-            fallback_agent,
+            broadcaster: MessageBroadcaster::new(shutdown_token.clone(), config.server.broadcast_capacity)
+                .with_summary_threshold(config.server.broadcast_summary_bytes),
+            config: ArcSwap::from_pointee(config),
+            agents: ArcSwap::from_pointee(agents),
+            worker_agent: None,
+            response_cache,
+            conversation_history,
+            deduplicator,
+            audit_log,
+            budget,
+            router_chain: std::sync::RwLock::new(Arc::new(router_chain)),
+            agent_outcome: Mutex::new(None),
+            primary_failures: AtomicU32::new(0),
+            health_cache: std::sync::RwLock::new(None),
             channels,
+            configured_channels,
             connections: RwLock::new(HashMap::new()),
+            peak_connections: AtomicUsize::new(0),
+            broadcast_lag_events: AtomicU64::new(0),
+            broadcast_lagged_messages: AtomicU64::new(0),
+            broadcast_lag_disconnects: AtomicU64::new(0),
             total_messages: AtomicU64::new(0),
             start_time: chrono::Utc::now(),
             shutdown_token,
@@ -259,27 +700,175 @@ impl AppStateInner {
             active_workers: AtomicUsize::new(0),
             max_workers,
             worker_semaphore: Arc::new(Semaphore::new(max_workers)),
-            orchestrator,
+            request_semaphore: Arc::new(Semaphore::new(max_requests)),
         }
     }
 
-    /// Create channel instances from config (only when token is non-empty)
-    fn create_channels_from_config(config: &Config) -> Vec<Arc<dyn clanker_channels::Channel + Send + Sync>> {
+    /// Create channel instances from config (only when a real token is set)
+    fn create_channels_from_config(
+        config: &Config,
+    ) -> (Vec<Arc<dyn clanker_channels::Channel + Send + Sync>>, Vec<ConfiguredChannel>) {
         let mut channels = Vec::new();
+        let mut configured = Vec::new();
 
         if let Some(ref tg) = config.channels.telegram {
-            if !tg.bot_token.is_empty() && tg.bot_token != "your-telegram-bot-token" {
-                match clanker_channels::ChannelFactory::create_arc_telegram(tg.bot_token.clone()) {
-                    Ok(ch) => {
-                        channels.push(ch);
-                        info!("Telegram channel created");
-                    }
-                    Err(e) => tracing::warn!("Failed to create Telegram channel: {}", e),
-                }
-            }
+            let created = if is_placeholder_token(&tg.bot_token) {
+                Err("placeholder token".to_string())
+            } else {
+                clanker_channels::ChannelFactory::create_arc_telegram_with_attempts(
+                    tg.bot_token.clone(),
+                    tg.send_attempts,
+                )
+                .map_err(|e| {
+                    warn!("Failed to create Telegram channel: {}", e);
+                    e.to_string()
+                })
+            };
+            configured.push(ConfiguredChannel::from_result(ChannelType::Telegram, &created));
+            channels.extend(created);
+        }
+
+        if let Some(ref dc) = config.channels.discord {
+            let created = if is_placeholder_token(&dc.bot_token) {
+                Err("placeholder token".to_string())
+            } else {
+                discord_channel(dc)
+                    .map(|ch| Arc::new(ch) as Arc<dyn clanker_channels::Channel + Send + Sync>)
+                    .map_err(|e| {
+                        warn!("Failed to create Discord channel: {}", e);
+                        e.to_string()
+                    })
+            };
+            configured.push(ConfiguredChannel::from_result(ChannelType::Discord, &created));
+            channels.extend(created);
         }
 
-        channels
+        if let Some(ref hook) = config.channels.webhook {
+            let created = clanker_channels::ChannelFactory::create_arc_webhook(
+                hook.url.clone(),
+                hook.bearer_token.clone(),
+            )
+            .map_err(|e| {
+                warn!("Failed to create webhook channel: {}", e);
+                e.to_string()
+            });
+            configured.push(ConfiguredChannel::from_result(ChannelType::Webhook, &created));
+            channels.extend(created);
+        }
+
+        let active: Vec<&str> = channels.iter().map(|ch| ch.channel_type().as_str()).collect();
+        let skipped: Vec<String> = configured
+            .iter()
+            .filter_map(|ch| Some(format!("{} ({})", ch.channel_type, ch.skipped_reason.as_ref()?)))
+            .collect();
+        if active.is_empty() {
+            warn!(
+                "No channels active (skipped: {}); only WebSocket clients will be served",
+                if skipped.is_empty() { "none configured".to_string() } else { skipped.join(", ") }
+            );
+        } else {
+            info!(
+                "Channels active: {}; skipped: {}",
+                active.join(", "),
+                if skipped.is_empty() { "none".to_string() } else { skipped.join(", ") }
+            );
+        }
+
+        (channels, configured)
+    }
+}
+
+/// Discord channel for `[channels.discord]`: its trigger and shard count
+fn discord_channel(config: &clanker_config::DiscordConfig) -> clanker_channels::Result<DiscordChannel> {
+    let trigger = MessageTrigger {
+        command_prefix: config.command_prefix.clone(),
+        respond_on_mention: config.respond_on_mention,
+    };
+    Ok(DiscordChannel::new(config.bot_token.clone())?
+        .with_trigger(trigger)
+        .with_shard_count(config.shard_count))
+}
+
+/// Check for an unset or example (`your-...`) bot token
+fn is_placeholder_token(token: &str) -> bool {
+    let token = token.trim();
+    token.is_empty() || token.starts_with("your-")
+}
+
+/// Restore settings in `new` that are only read at startup (bind address,
+/// channels, agents, caches, ...) to their running values, returning the
+/// names of those that differed
+fn keep_startup_settings(current: &Config, new: &mut Config) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    keep("server.host", &current.server.host, &mut new.server.host, &mut changed);
+    keep("server.port", &current.server.port, &mut new.server.port, &mut changed);
+    keep("server.listen", &current.server.listen, &mut new.server.listen, &mut changed);
+    keep("server.tls", &current.server.tls, &mut new.server.tls, &mut changed);
+    keep(
+        "server.broadcast_capacity",
+        &current.server.broadcast_capacity,
+        &mut new.server.broadcast_capacity,
+        &mut changed,
+    );
+    keep(
+        "server.broadcast_summary_bytes",
+        &current.server.broadcast_summary_bytes,
+        &mut new.server.broadcast_summary_bytes,
+        &mut changed,
+    );
+    // Telegram's allowlist is checked per message; the rest builds the channels
+    let mut running_channels = current.channels.clone();
+    if let (Some(running), Some(reloaded)) = (running_channels.telegram.as_mut(), new.channels.telegram.as_ref()) {
+        running.allowed_chats = reloaded.allowed_chats.clone();
+    }
+    keep("channels", &running_channels, &mut new.channels, &mut changed);
+    // [agent] and [[agents]] are rebuilt on reload (see `agents_changed`)
+    keep("orchestration", &current.orchestration, &mut new.orchestration, &mut changed);
+    keep("logging", &current.logging, &mut new.logging, &mut changed);
+    keep("faq", &current.faq, &mut new.faq, &mut changed);
+    keep("audit", &current.audit, &mut new.audit, &mut changed);
+    keep("budget", &current.budget, &mut new.budget, &mut changed);
+    let (running, reloaded) = (&current.processing, &mut new.processing);
+    keep("processing.cache_enabled", &running.cache_enabled, &mut reloaded.cache_enabled, &mut changed);
+    keep("processing.cache_ttl_secs", &running.cache_ttl_secs, &mut reloaded.cache_ttl_secs, &mut changed);
+    keep("processing.cache_capacity", &running.cache_capacity, &mut reloaded.cache_capacity, &mut changed);
+    keep(
+        "processing.dedup_window_secs",
+        &running.dedup_window_secs,
+        &mut reloaded.dedup_window_secs,
+        &mut changed,
+    );
+    keep(
+        "processing.max_concurrent_requests",
+        &running.max_concurrent_requests,
+        &mut reloaded.max_concurrent_requests,
+        &mut changed,
+    );
+    keep("processing.history_turns", &running.history_turns, &mut reloaded.history_turns, &mut changed);
+    keep(
+        "processing.history_max_chats",
+        &running.history_max_chats,
+        &mut reloaded.history_max_chats,
+        &mut changed,
+    );
+    changed
+}
+
+/// Whether `[agent]` or `[[agents]]` differ in anything but the per-channel
+/// system prompts (which are read per request)
+fn agents_changed(current: &Config, new: &Config) -> bool {
+    let mut running_agent = current.agent.clone();
+    running_agent.system_prompts = new.agent.system_prompts.clone();
+    serde_json::to_value(&running_agent).ok() != serde_json::to_value(&new.agent).ok()
+        || serde_json::to_value(&current.agents).ok() != serde_json::to_value(&new.agents).ok()
+}
+
+/// Put back the running value of one startup-only setting, noting `name` if it changed
+fn keep<T: serde::Serialize + Clone>(name: &'static str, running: &T, new: &mut T, changed: &mut Vec<&'static str>) {
+    // Config sections are compared through their serialized form (they don't implement PartialEq)
+    if serde_json::to_value(running).ok() != serde_json::to_value(&*new).ok() {
+        changed.push(name);
+        *new = running.clone();
     }
 }
 
@@ -320,8 +909,8 @@ mod tests {
         let conn1 = ConnectionState::new(addr1);
         let conn2 = ConnectionState::new(addr2);
 
-        state.add_connection(id1, conn1.clone()).await;
-        state.add_connection(id2, conn2.clone()).await;
+        state.add_connection(id1, conn1.clone()).await.unwrap();
+        state.add_connection(id2, conn2.clone()).await.unwrap();
 
         assert_eq!(state.connection_count().await, 2);
 
@@ -336,6 +925,227 @@ mod tests {
         assert!(state.get_connection(&id2).await.is_some());
     }
 
+    #[tokio::test]
+    async fn test_peak_connections_high_water_mark() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+
+        let id1 = Uuid::new_v4();
+        let id2 = Uuid::new_v4();
+        state.add_connection(id1, ConnectionState::new(addr)).await.unwrap();
+        state.add_connection(id2, ConnectionState::new(addr)).await.unwrap();
+        assert_eq!(state.peak_connection_count(), 2);
+
+        state.remove_connection(&id1).await;
+        state.remove_connection(&id2).await;
+        assert_eq!(state.connection_count().await, 0);
+        assert_eq!(state.peak_connection_count(), 2);
+
+        state.add_connection(Uuid::new_v4(), ConnectionState::new(addr)).await.unwrap();
+        assert_eq!(state.peak_connection_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_connection_cap_enforced() {
+        let mut config = create_test_config();
+        config.server.max_connections = 2;
+        let state = AppState::new(config, CancellationToken::new());
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+
+        let id1 = Uuid::new_v4();
+        state.add_connection(id1, ConnectionState::new(addr)).await.unwrap();
+        state.add_connection(Uuid::new_v4(), ConnectionState::new(addr)).await.unwrap();
+
+        let err = state
+            .add_connection(Uuid::new_v4(), ConnectionState::new(addr))
+            .await
+            .unwrap_err();
+        assert_eq!(err, LimitError::TooManyConnections(2));
+        assert_eq!(state.connection_count().await, 2);
+
+        // A slot frees up once a connection closes
+        state.remove_connection(&id1).await;
+        state.add_connection(Uuid::new_v4(), ConnectionState::new(addr)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscription_cap_enforced() {
+        let mut config = create_test_config();
+        config.server.max_subscriptions_per_connection = 2;
+        let state = AppState::new(config, CancellationToken::new());
+        let id = Uuid::new_v4();
+        state
+            .add_connection(id, ConnectionState::new("127.0.0.1:8080".parse().unwrap()))
+            .await
+            .unwrap();
+
+        state.subscribe(&id, "a".to_string(), ChannelType::Telegram).await.unwrap();
+        state.subscribe(&id, "b".to_string(), ChannelType::Telegram).await.unwrap();
+        // Re-subscribing to an existing channel does not count against the cap
+        state.subscribe(&id, "a".to_string(), ChannelType::Telegram).await.unwrap();
+
+        let err = state
+            .subscribe(&id, "c".to_string(), ChannelType::Telegram)
+            .await
+            .unwrap_err();
+        assert_eq!(err, LimitError::TooManySubscriptions(2));
+
+        state.unsubscribe(&id, "a").await;
+        state.subscribe(&id, "c".to_string(), ChannelType::Telegram).await.unwrap();
+        assert_eq!(state.get_connection(&id).await.unwrap().subscription_count(), 2);
+
+        let unknown = state
+            .subscribe(&Uuid::new_v4(), "a".to_string(), ChannelType::Telegram)
+            .await
+            .unwrap_err();
+        assert_eq!(unknown, LimitError::UnknownConnection);
+    }
+
+    #[tokio::test]
+    async fn test_agent_outcome_tracking() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        assert!(state.last_agent_outcome().is_none());
+
+        state.record_agent_result::<(), _>(&Err("timeout"));
+        assert!(matches!(
+            state.last_agent_outcome(),
+            Some(AgentOutcome::Failure(_, ref e)) if e == "timeout"
+        ));
+
+        state.record_agent_result::<_, String>(&Ok(()));
+        assert!(matches!(state.last_agent_outcome(), Some(AgentOutcome::Success(_))));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_lag_policy() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        assert!(!state.record_broadcast_lag(12));
+        assert_eq!(state.broadcast_lag_counts(), (1, 12, 1));
+
+        let mut config = create_test_config();
+        config.server.broadcast_lag_policy = LagPolicy::SkipToLatest;
+        let state = AppState::new(config, CancellationToken::new());
+        assert!(state.record_broadcast_lag(3));
+        assert!(state.record_broadcast_lag(4));
+        assert_eq!(state.broadcast_lag_counts(), (2, 7, 0));
+    }
+
+    #[tokio::test]
+    async fn test_worker_semaphore_caps_at_max_workers() {
+        let mut config = create_test_config();
+        config.orchestration.max_workers = 3;
+        let state = AppState::new(config, CancellationToken::new());
+
+        assert!(state.orchestration_enabled());
+        assert!(state.orchestrator().is_some());
+        assert_eq!(state.worker_max(), 3);
+
+        let semaphore = state.worker_semaphore();
+        assert!(semaphore.clone().try_acquire_many_owned(4).is_err());
+
+        let permits = semaphore.clone().try_acquire_many_owned(3).unwrap();
+        assert!(semaphore.try_acquire().is_err());
+        drop(permits);
+        assert_eq!(state.worker_semaphore().available_permits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_orchestration_disabled_has_no_orchestrator() {
+        let mut config = create_test_config();
+        config.orchestration.enabled = false;
+        let state = AppState::new(config, CancellationToken::new());
+
+        assert!(!state.orchestration_enabled());
+        assert!(state.orchestrator().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_master_gets_own_agent_in_json_mode() {
+        let mut config = create_test_config();
+        let state = AppState::new(config.clone(), CancellationToken::new());
+        let master = state.orchestrator().unwrap().master_agent();
+        assert!(Arc::ptr_eq(&master, &state.agent()));
+
+        // [DELEGATE] blocks cannot be parsed out of a JSON reply
+        config.agent.provider = "openai".to_string();
+        config.agent.model = "gpt-4o-mini".to_string();
+        config.agent.json_mode = true;
+        let state = AppState::new(config, CancellationToken::new());
+        let master = state.orchestrator().unwrap().master_agent();
+        assert!(!Arc::ptr_eq(&master, &state.agent()));
+        assert_eq!(master.provider(), "openai");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_uses_placeholder_agent() {
+        let mut config = create_test_config();
+        assert_eq!(config.agent.provider, "anthropic");
+        config.server.dry_run = true;
+        let state = AppState::new(config, CancellationToken::new());
+
+        assert!(state.is_dry_run());
+        assert_eq!(state.agent().provider(), "placeholder");
+        assert!(state.fallback_agent().is_none());
+        assert!(!state.orchestration_enabled());
+        assert!(state.orchestrator().is_none());
+    }
+
+    #[test]
+    fn test_placeholder_tokens_skip_channels() {
+        assert!(is_placeholder_token(""));
+        assert!(is_placeholder_token("your-telegram-bot-token"));
+        assert!(!is_placeholder_token("123456:ABC-DEF"));
+
+        // Example config only has placeholder tokens
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        assert!(state.channels().is_empty());
+        assert!(state.channel_for(ChannelType::Telegram).is_none());
+    }
+
+    #[test]
+    fn test_webhook_channel_from_config() {
+        let mut config = create_test_config();
+        config.channels.webhook = Some(clanker_config::WebhookConfig {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            bearer_token: None,
+        });
+
+        let state = AppState::new(config, CancellationToken::new());
+        assert_eq!(state.channels().len(), 1);
+        assert!(state.channel_for(ChannelType::Webhook).is_some());
+    }
+
+    #[test]
+    fn test_discord_channel_from_config() {
+        let mut config = create_test_config();
+        config.channels.discord = Some(clanker_config::DiscordConfig {
+            bot_token: "discord-token".to_string(),
+            command_prefix: Some("!ask".to_string()),
+            ..Default::default()
+        });
+
+        let state = AppState::new(config, CancellationToken::new());
+        assert_eq!(state.channels().len(), 1);
+        assert!(state.channel_for(ChannelType::Discord).is_some());
+    }
+
+    #[test]
+    fn test_discord_config_reaches_the_client() {
+        let config = clanker_config::DiscordConfig {
+            bot_token: "discord-token".to_string(),
+            command_prefix: Some("!ask".to_string()),
+            shard_count: Some(3),
+            ..Default::default()
+        };
+        let channel = discord_channel(&config).unwrap();
+        assert_eq!(channel.shard_strategy(), clanker_channels::discord::ShardStrategy::Fixed(3));
+
+        let message =
+            |text: &str| Message::new(ChannelType::Discord, "1".to_string(), "user".to_string(), text.to_string());
+        assert_eq!(channel.filter_inbound(message("!ask hi")).unwrap().text, "hi");
+        assert!(channel.filter_inbound(message("hi")).is_none());
+    }
+
     #[tokio::test]
     async fn test_message_counting() {
         let config = create_test_config();
@@ -378,7 +1188,7 @@ mod tests {
         let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
         let conn = ConnectionState::new(addr);
 
-        state.add_connection(id1, conn.clone()).await;
+        state.add_connection(id1, conn.clone()).await.unwrap();
 
         let all = state.get_all_connections().await;
 
@@ -401,4 +1211,96 @@ mod tests {
         let server_id2 = state.server_id();
         assert_eq!(server_id, server_id2);
     }
+
+    #[test]
+    fn test_reload_config_swaps_live_settings() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        let before = state.config();
+
+        let mut reloaded = create_test_config();
+        reloaded.processing.refusal_message = Some("Not today.".to_string());
+        reloaded.server.admin_token = Some("new-token".to_string());
+        assert!(state.reload_config(reloaded).is_empty());
+
+        let config = state.config();
+        assert_eq!(config.processing.refusal_message.as_deref(), Some("Not today."));
+        assert_eq!(config.server.admin_token.as_deref(), Some("new-token"));
+        // Readers holding the previous config keep a consistent snapshot
+        assert!(before.processing.refusal_message.is_none());
+    }
+
+    #[test]
+    fn test_reload_config_keeps_startup_settings() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        let port = state.config().server.port;
+
+        let mut reloaded = create_test_config();
+        reloaded.server.port = port + 1;
+        reloaded.agent.model = "another-model".to_string();
+        reloaded
+            .agent
+            .system_prompts
+            .insert("telegram".to_string(), "Be brief.".to_string());
+
+        reloaded.channels.telegram.as_mut().unwrap().allowed_chats = Some(vec!["42".to_string()]);
+        reloaded.channels.telegram.as_mut().unwrap().send_attempts = 7;
+        reloaded.budget.daily_usd = Some(1.0);
+
+        assert_eq!(state.reload_config(reloaded), vec!["server.port", "channels", "budget"]);
+        let config = state.config();
+        assert_eq!(config.server.port, port);
+        assert_eq!(config.agent.system_prompts["telegram"], "Be brief.");
+        let telegram = config.channels.telegram.as_ref().unwrap();
+        assert_eq!(telegram.allowed_chats, Some(vec!["42".to_string()]));
+        assert_ne!(telegram.send_attempts, 7);
+        assert!(config.budget.daily_usd.is_none());
+    }
+
+    #[test]
+    fn test_reload_config_rebuilds_agents() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+        let before = state.agent();
+
+        // Prompts alone are read per request and keep the running agent
+        let mut reloaded = create_test_config();
+        reloaded
+            .agent
+            .system_prompts
+            .insert("telegram".to_string(), "Be brief.".to_string());
+        state.reload_config(reloaded.clone());
+        assert!(Arc::ptr_eq(&before, &state.agent()));
+
+        reloaded.agent.model = "claude-3-5-haiku-20241022".to_string();
+        assert!(state.reload_config(reloaded).is_empty());
+        assert_eq!(state.agent().model(), "claude-3-5-haiku-20241022");
+        let master = state.orchestrator().unwrap().master_agent();
+        assert_eq!(master.model(), "claude-3-5-haiku-20241022");
+    }
+
+    #[test]
+    fn test_worker_count_guard_restores_count_on_early_return() {
+        let state = AppState::new(create_test_config(), CancellationToken::new());
+
+        let delegate = |fail: bool| -> Result<usize, String> {
+            let _workers = state.track_workers(3);
+            if fail {
+                return Err("worker failed".to_string());
+            }
+            Ok(state.worker_count())
+        };
+        let concurrent = state.track_workers(2);
+        assert_eq!(delegate(false), Ok(5));
+        assert!(delegate(true).is_err());
+        assert_eq!(state.worker_count(), 2);
+
+        let panicking = state.clone();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _workers = panicking.track_workers(1);
+            panic!("worker panicked");
+        }));
+        assert!(result.is_err());
+
+        drop(concurrent);
+        assert_eq!(state.worker_count(), 0);
+    }
 }
@@ -0,0 +1,288 @@
+//! Progressive channel replies: type the agent's answer out in the chat.
+//!
+//! With `channels.telegram.stream_to_channel`, a placeholder reply is sent as
+//! soon as the agent starts streaming and is edited as chunks arrive. Edits are
+//! throttled to [`EDIT_INTERVAL`] to stay within Telegram's rate limits; the
+//! processor finishes the reply with one last edit carrying the final text.
+
+use crate::state::AppState;
+use clanker_agent::{AgentError, StreamChunk, Usage};
+use clanker_channels::Channel;
+use clanker_core::{ChannelType, Message};
+use futures_util::{Stream, StreamExt};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Shortest gap between two edits of a streamed reply
+pub const EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Text of a streamed reply before the first edit
+pub const PLACEHOLDER: &str = "…";
+
+/// Channel a reply is streamed to
+#[derive(Clone)]
+pub struct StreamTarget(pub Arc<dyn Channel + Send + Sync>);
+
+impl fmt::Debug for StreamTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StreamTarget").field(&self.0.channel_type()).finish()
+    }
+}
+
+/// Streaming target for a channel message, when its channel streams replies
+pub fn target_for(state: &AppState, incoming: &Message) -> Option<StreamTarget> {
+    let enabled = match incoming.channel_type {
+        ChannelType::Telegram => state
+            .config()
+            .channels
+            .telegram
+            .as_ref()
+            .is_some_and(|telegram| telegram.stream_to_channel),
+        _ => false,
+    };
+    if !enabled {
+        return None;
+    }
+    state.channel_for(incoming.channel_type).map(StreamTarget)
+}
+
+/// Allows one edit per interval; the placeholder send counts as the first
+#[derive(Debug)]
+pub struct EditThrottle {
+    interval: Duration,
+    last: Instant,
+}
+
+impl EditThrottle {
+    /// Start throttling now, as the placeholder is sent
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    /// Whether an edit may be made now; if so it is counted
+    pub fn try_acquire(&mut self) -> bool {
+        let now = Instant::now();
+        if now.duration_since(self.last) < self.interval {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+}
+
+/// Placeholder reply sent to a channel, edited as the answer streams in
+#[derive(Debug, Clone)]
+pub struct StreamedReply {
+    target: StreamTarget,
+    channel_id: String,
+    message_id: String,
+}
+
+impl StreamedReply {
+    /// Send `placeholder`; `None` when the channel reports no id to edit it by
+    pub async fn start(target: &StreamTarget, placeholder: Message) -> clanker_channels::Result<Option<Self>> {
+        let channel_id = placeholder.channel_id.clone();
+        let message_id = target.0.send_with_id(placeholder).await?;
+        Ok(message_id.map(|message_id| Self {
+            target: target.clone(),
+            channel_id,
+            message_id,
+        }))
+    }
+
+    /// Replace the reply's text
+    pub async fn edit(&self, text: &str) -> clanker_channels::Result<()> {
+        self.target.0.edit(&self.channel_id, &self.message_id, text).await
+    }
+
+    /// Remove the reply (best effort), e.g. when the stream failed part way
+    pub async fn discard(&self) {
+        if let Err(e) = self.target.0.delete(&self.channel_id, &self.message_id).await {
+            debug!("Could not delete streamed reply {}: {}", self.message_id, e);
+        }
+    }
+}
+
+/// What a chunk stream added up to
+#[derive(Debug, Default)]
+pub struct Collected {
+    pub content: String,
+    pub usage: Usage,
+    /// The provider's finish reason, when a chunk reported one
+    pub finish_reason: Option<String>,
+}
+
+/// Collect a chunk stream, editing `reply` with the text so far whenever
+/// `throttle` allows. Failed edits are skipped; the final edit is the caller's.
+pub async fn stream_into<S>(
+    reply: Option<&StreamedReply>,
+    mut chunks: S,
+    mut throttle: EditThrottle,
+) -> Result<Collected, AgentError>
+where
+    S: Stream<Item = Result<StreamChunk, AgentError>> + Unpin,
+{
+    let mut collected = Collected::default();
+    let mut edited_len = 0;
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        collected.content.push_str(&chunk.content);
+        if let Some(usage) = chunk.usage {
+            collected.usage = usage;
+        }
+        if chunk.finish_reason.is_some() {
+            collected.finish_reason = chunk.finish_reason;
+        }
+        if chunk.done {
+            break;
+        }
+
+        let Some(reply) = reply else { continue };
+        let content = &collected.content;
+        if content.len() == edited_len || content.trim().is_empty() || !throttle.try_acquire() {
+            continue;
+        }
+        match reply.edit(content).await {
+            Ok(()) => edited_len = content.len(),
+            Err(e) => debug!("Streamed reply edit failed: {}", e),
+        }
+    }
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Channel recording edits with the (paused) time they were made
+    #[derive(Default)]
+    struct EditRecorder {
+        started: Mutex<Option<Instant>>,
+        edits: Mutex<Vec<(Duration, String)>>,
+    }
+
+    #[async_trait]
+    impl Channel for EditRecorder {
+        async fn send(&self, _message: Message) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        async fn send_with_id(&self, _message: Message) -> clanker_channels::Result<Option<String>> {
+            *self.started.lock().unwrap() = Some(Instant::now());
+            Ok(Some("1".to_string()))
+        }
+
+        async fn edit(&self, _channel_id: &str, _message_id: &str, new_text: &str) -> clanker_channels::Result<()> {
+            let at = self.started.lock().unwrap().unwrap().elapsed();
+            self.edits.lock().unwrap().push((at, new_text.to_string()));
+            Ok(())
+        }
+
+        async fn listen(&self) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        async fn listen_with_tx(&self, _tx: tokio::sync::mpsc::Sender<Message>) -> clanker_channels::Result<()> {
+            Ok(())
+        }
+
+        fn channel_type(&self) -> ChannelType {
+            ChannelType::Telegram
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    fn chunk(content: &str, done: bool) -> Result<StreamChunk, AgentError> {
+        Ok(StreamChunk {
+            content: content.to_string(),
+            done,
+            usage: None,
+            finish_reason: None,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_edit_throttle_allows_one_edit_per_interval() {
+        let mut throttle = EditThrottle::new(Duration::from_millis(750));
+        assert!(!throttle.try_acquire());
+
+        tokio::time::advance(Duration::from_millis(749)).await;
+        assert!(!throttle.try_acquire());
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert!(throttle.try_acquire());
+        assert!(!throttle.try_acquire());
+
+        tokio::time::advance(Duration::from_millis(750)).await;
+        assert!(throttle.try_acquire());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_edits_are_throttled() {
+        let recorder = Arc::new(EditRecorder::default());
+        let target = StreamTarget(recorder.clone());
+        let placeholder = Message::new(
+            ChannelType::Telegram,
+            "123".to_string(),
+            "assistant".to_string(),
+            PLACEHOLDER.to_string(),
+        );
+        let reply = StreamedReply::start(&target, placeholder).await.unwrap().unwrap();
+
+        // A chunk every 300ms, a pause, then two more and the end of the stream
+        let delays = [300, 300, 300, 300, 300, 900, 100, 0];
+        let texts = ["a", "b", "c", "d", "e", "f", "g", ""];
+        let chunks = futures_util::stream::iter(delays.into_iter().zip(texts).enumerate())
+            .then(|(i, (delay, text))| async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                chunk(text, i == texts.len() - 1)
+            })
+            .boxed();
+
+        let collected = stream_into(Some(&reply), chunks, EditThrottle::new(EDIT_INTERVAL))
+            .await
+            .unwrap();
+        assert_eq!(collected.content, "abcdefg");
+
+        let edits = recorder.edits.lock().unwrap();
+        let texts: Vec<_> = edits.iter().map(|(_, text)| text.as_str()).collect();
+        // 1.2s: first chunk a second after the placeholder; 2.4s: after the pause
+        assert_eq!(texts, vec!["abcd", "abcdef"]);
+        assert_eq!(edits[0].0, Duration::from_millis(1200));
+        assert_eq!(edits[1].0, Duration::from_millis(2400));
+    }
+
+    #[tokio::test]
+    async fn test_stream_keeps_finish_reason() {
+        let filtered = Ok(StreamChunk {
+            content: String::new(),
+            done: false,
+            usage: None,
+            finish_reason: Some("content_filter".to_string()),
+        });
+        let chunks = futures_util::stream::iter(vec![chunk("I can", false), filtered, chunk("", true)]);
+        let collected = stream_into(None, chunks, EditThrottle::new(EDIT_INTERVAL)).await.unwrap();
+        assert_eq!(collected.content, "I can");
+        assert_eq!(collected.finish_reason.as_deref(), Some("content_filter"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_returned() {
+        let chunks = futures_util::stream::iter(vec![
+            chunk("partial", false),
+            Err(AgentError::InvalidResponse("Stream ended before [DONE]".to_string())),
+        ]);
+        let result = stream_into(None, chunks, EditThrottle::new(EDIT_INTERVAL)).await;
+        assert!(matches!(result, Err(AgentError::InvalidResponse(_))));
+    }
+}
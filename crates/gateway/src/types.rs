@@ -1,12 +1,17 @@
-use clanker_core::{ChannelType, Message};
+use clanker_core::{ChannelType, HealthStatus, Message};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Unique connection identifier
 pub type ConnectionId = Uuid;
 
+/// Channel id reported in the confirmations of `subscribe_all` and `unsubscribe_all`
+pub const WILDCARD_CHANNEL: &str = "*";
+
 /// WebSocket message from client
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data", rename_all = "snake_case")]
@@ -20,11 +25,31 @@ pub enum WsClientMessage {
     Unsubscribe {
         channel_id: String,
     },
+    /// Receive messages from every channel, subscribed or not
+    SubscribeAll,
+    /// Stop receiving messages from channels not explicitly subscribed to
+    UnsubscribeAll,
     /// Send message to channel
     SendMessage {
         channel_id: String,
         channel_type: ChannelType,
         message: String,
+        /// Return the primary agent's error instead of retrying with the fallback
+        #[serde(default)]
+        no_fallback: bool,
+        /// Ask for a JSON object response (OpenAI, Groq and Z.ai agents only)
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        json_mode: bool,
+        /// Answer with this provider instead of the agent's (must be in `agent.model_overrides`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+        /// Answer with this model instead of the agent's (must be in `agent.model_overrides`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+    },
+    /// Clear the channel's stored conversation history
+    ResetConversation {
+        channel_id: String,
     },
     /// Ping to keep connection alive
     Ping { timestamp: u64 },
@@ -45,6 +70,12 @@ pub enum WsServerMessage {
     Unsubscribed {
         channel_id: String,
     },
+    /// Conversation history of the channel was cleared
+    ConversationReset {
+        channel_id: String,
+    },
+    /// Message accepted and handed to the agent; its `SendResponse` follows
+    Processing { message_id: String },
     /// Response to sent message (includes AI content when available)
     SendResponse {
         success: bool,
@@ -52,7 +83,20 @@ pub enum WsServerMessage {
         error: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         content: Option<String>,
+        /// Provider refused or content-filtered the response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        filtered: bool,
+        /// Primary agent is failing; the reply came from the fallback
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        degraded: bool,
+        /// Provider answering while degraded
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
     },
+    /// Worker_Clanker spawned during orchestration (when progress events are enabled)
+    WorkerStarted { identity: String },
+    /// Worker_Clanker finished during orchestration (when progress events are enabled)
+    WorkerCompleted { identity: String },
     /// Health check response
     Health {
         status: String,
@@ -65,6 +109,18 @@ pub enum WsServerMessage {
         code: String,
         message: String,
     },
+    /// Operator announcement sent to every connection via `POST /admin/broadcast`
+    Notice { level: NoticeLevel, message: String },
+}
+
+/// Severity of an operator notice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoticeLevel {
+    #[default]
+    Info,
+    Warning,
+    Critical,
 }
 
 impl WsServerMessage {
@@ -88,10 +144,79 @@ impl WsServerMessage {
             message_id,
             error,
             content,
+            filtered: false,
+            degraded: false,
+            provider: None,
+        }
+    }
+
+    /// Mark a send response as filtered by the provider
+    pub fn filtered(mut self) -> Self {
+        if let Self::SendResponse { filtered, .. } = &mut self {
+            *filtered = true;
         }
+        self
+    }
+
+    /// Mark a send response as served by `provider` while the primary is failing
+    pub fn degraded(mut self, active_provider: impl Into<String>) -> Self {
+        if let Self::SendResponse { degraded, provider, .. } = &mut self {
+            *degraded = true;
+            *provider = Some(active_provider.into());
+        }
+        self
     }
 }
 
+/// Request body for `POST /send` (HTTP equivalent of `WsClientMessage::SendMessage`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendRequest {
+    pub channel_id: String,
+    pub channel_type: ChannelType,
+    pub message: String,
+    /// Return the primary agent's error instead of retrying with the fallback
+    #[serde(default)]
+    pub no_fallback: bool,
+    /// Ask for a JSON object response (OpenAI, Groq and Z.ai agents only)
+    #[serde(default)]
+    pub json_mode: bool,
+}
+
+/// Response body for `POST /send`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendResult {
+    pub message_id: String,
+    pub content: String,
+    /// Provider refused or content-filtered the response
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub filtered: bool,
+    /// Set when no channel instance backs `channel_type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_error: Option<String>,
+    /// Primary agent is failing; the reply came from the fallback
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// Provider answering while degraded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+/// Request body for `POST /admin/broadcast`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastRequest {
+    /// Notice severity (`code` is accepted as an alias)
+    #[serde(default, alias = "code")]
+    pub level: NoticeLevel,
+    pub message: String,
+}
+
+/// Response body for `POST /admin/broadcast`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastResult {
+    /// Subscribers the notice was handed to
+    pub recipients: usize,
+}
+
 /// WebSocket connection state
 #[derive(Debug, Clone)]
 pub struct ConnectionState {
@@ -103,6 +228,41 @@ pub struct ConnectionState {
     pub connected_at: chrono::DateTime<chrono::Utc>,
     /// Subscribed channels (channel_id -> channel_type)
     pub subscriptions: HashMap<String, ChannelType>,
+    /// Receives messages from every channel (`subscribe_all`)
+    pub subscribe_all: bool,
+    /// `User-Agent` header sent with the WebSocket upgrade
+    pub user_agent: Option<String>,
+    /// Data frames exchanged with the client (shared across clones)
+    pub counters: Arc<MessageCounters>,
+}
+
+/// Data frames sent to and received from a WebSocket client
+#[derive(Debug, Default)]
+pub struct MessageCounters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl MessageCounters {
+    /// Count a frame sent to the client
+    pub fn record_sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a frame received from the client
+    pub fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Frames sent to the client
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Frames received from the client
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
 }
 
 impl ConnectionState {
@@ -113,9 +273,18 @@ impl ConnectionState {
             addr,
             connected_at: chrono::Utc::now(),
             subscriptions: HashMap::new(),
+            subscribe_all: false,
+            user_agent: None,
+            counters: Arc::default(),
         }
     }
 
+    /// Set the client's user agent
+    pub fn with_user_agent(mut self, user_agent: Option<String>) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Add subscription
     pub fn subscribe(&mut self, channel_id: String, channel_type: ChannelType) {
         self.subscriptions.insert(channel_id, channel_type);
@@ -131,6 +300,16 @@ impl ConnectionState {
         self.subscriptions.contains_key(channel_id)
     }
 
+    /// Turn the all-channels wildcard on or off; explicit subscriptions are kept
+    pub fn set_subscribe_all(&mut self, enabled: bool) {
+        self.subscribe_all = enabled;
+    }
+
+    /// Check if messages from a channel are delivered, by subscription or wildcard
+    pub fn receives_channel(&self, channel_id: &str) -> bool {
+        self.subscribe_all || self.is_subscribed(channel_id)
+    }
+
     /// Get subscription count
     pub fn subscription_count(&self) -> usize {
         self.subscriptions.len()
@@ -142,6 +321,31 @@ impl ConnectionState {
     }
 }
 
+/// Build metadata returned by `GET /version`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionResponse {
+    /// Crate version
+    pub version: String,
+    /// Git commit the binary was built from ("unknown" outside a checkout)
+    pub git_sha: String,
+    /// Build time (UTC, RFC 3339)
+    pub build_timestamp: String,
+    /// `rustc --version` of the compiler used
+    pub rustc_version: String,
+}
+
+impl VersionResponse {
+    /// Metadata embedded by the gateway's build script
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("CLANKER_GIT_SHA").to_string(),
+            build_timestamp: env!("CLANKER_BUILD_TIMESTAMP").to_string(),
+            rustc_version: env!("CLANKER_RUSTC_VERSION").to_string(),
+        }
+    }
+}
+
 /// Health check response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthResponse {
@@ -188,6 +392,158 @@ impl HealthResponse {
     }
 }
 
+/// Per-component health served by `/health/detailed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailedHealthResponse {
+    /// Aggregate of all component statuses
+    pub overall: HealthStatus,
+    /// Status per component (e.g. "agent", "broadcaster", "channel:telegram")
+    pub components: BTreeMap<String, HealthStatus>,
+    /// Server timestamp
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl DetailedHealthResponse {
+    /// Build response from component statuses, computing the overall status
+    pub fn from_components(components: BTreeMap<String, HealthStatus>) -> Self {
+        Self {
+            overall: HealthStatus::aggregate(components.values()),
+            components,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+/// One WebSocket connection as listed by `GET /connections`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub id: ConnectionId,
+    /// Client address
+    pub addr: SocketAddr,
+    pub connected_at: chrono::DateTime<chrono::Utc>,
+    /// Seconds since the connection was established
+    pub uptime_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Data frames sent to the client
+    pub messages_sent: u64,
+    /// Data frames received from the client
+    pub messages_received: u64,
+    /// Subscribed channels, sorted by channel id
+    pub subscriptions: Vec<SubscriptionInfo>,
+    /// Receives messages from every channel
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub subscribe_all: bool,
+}
+
+/// Channel subscription of a connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubscriptionInfo {
+    pub channel_id: String,
+    pub channel_type: ChannelType,
+}
+
+impl ConnectionInfo {
+    /// Snapshot a connection at `now`
+    pub fn from_state(state: &ConnectionState, now: chrono::DateTime<chrono::Utc>) -> Self {
+        let mut subscriptions: Vec<SubscriptionInfo> = state
+            .subscriptions
+            .iter()
+            .map(|(channel_id, channel_type)| SubscriptionInfo {
+                channel_id: channel_id.clone(),
+                channel_type: *channel_type,
+            })
+            .collect();
+        subscriptions.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+        Self {
+            id: state.id,
+            addr: state.addr,
+            connected_at: state.connected_at,
+            uptime_seconds: (now - state.connected_at).num_seconds().max(0) as u64,
+            user_agent: state.user_agent.clone(),
+            messages_sent: state.counters.sent(),
+            messages_received: state.counters.received(),
+            subscriptions,
+            subscribe_all: state.subscribe_all,
+        }
+    }
+}
+
+/// Response of `GET /connections`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionsResponse {
+    pub count: usize,
+    pub connections: Vec<ConnectionInfo>,
+}
+
+/// Configured channel listed by `GET /channels`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChannelStatus {
+    pub channel_type: ChannelType,
+    /// The gateway created the channel at startup
+    pub instantiated: bool,
+    /// The channel reports a live connection
+    pub connected: bool,
+    /// Why the channel was not instantiated (placeholder token, creation error, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_reason: Option<String>,
+}
+
+/// Response of `GET /channels`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelsResponse {
+    pub channels: Vec<ChannelStatus>,
+}
+
+/// Runtime statistics served by `/stats` and `/metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    /// Uptime in seconds
+    pub uptime_seconds: u64,
+    /// Active WebSocket connections
+    pub active_connections: usize,
+    /// Peak concurrent WebSocket connections since startup
+    pub peak_connections: usize,
+    /// Total messages processed
+    pub total_messages: u64,
+    /// Active Worker_Clankers spawned by Master_Clanker
+    pub active_workers: usize,
+    /// Maximum Worker_Clankers allowed
+    pub max_workers: usize,
+    /// Times a client lagged the broadcast channel
+    pub broadcast_lag_events: u64,
+    /// Broadcast messages dropped for lagging clients
+    pub broadcast_lagged_messages: u64,
+    /// Clients disconnected for lagging
+    pub broadcast_lag_disconnects: u64,
+    /// Server timestamp
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl StatsResponse {
+    /// Render stats in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, f64); 9] = [
+            ("clanker_uptime_seconds", "gauge", "Gateway uptime in seconds", self.uptime_seconds as f64),
+            ("clanker_active_connections", "gauge", "Active WebSocket connections", self.active_connections as f64),
+            ("clanker_peak_connections", "gauge", "Peak concurrent WebSocket connections since startup", self.peak_connections as f64),
+            ("clanker_messages_total", "counter", "Total messages processed", self.total_messages as f64),
+            ("clanker_active_workers", "gauge", "Active Worker_Clankers", self.active_workers as f64),
+            ("clanker_max_workers", "gauge", "Maximum Worker_Clankers allowed", self.max_workers as f64),
+            ("clanker_broadcast_lag_events_total", "counter", "Times a client lagged the broadcast channel", self.broadcast_lag_events as f64),
+            ("clanker_broadcast_lagged_messages_total", "counter", "Broadcast messages dropped for lagging clients", self.broadcast_lagged_messages as f64),
+            ("clanker_broadcast_lag_disconnects_total", "counter", "Clients disconnected for lagging", self.broadcast_lag_disconnects as f64),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+        }
+        out
+    }
+}
+
 /// API error response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiError {
@@ -229,6 +585,39 @@ impl ApiError {
     pub fn internal(message: impl Into<String>) -> Self {
         Self::new("INTERNAL_ERROR", message, axum::http::StatusCode::INTERNAL_SERVER_ERROR)
     }
+
+    /// WebSocket close code for this error: 1011 for server errors, 1008 (policy violation) otherwise
+    pub fn close_code(&self) -> u16 {
+        if self.status.is_server_error() {
+            axum::extract::ws::close_code::ERROR
+        } else {
+            axum::extract::ws::close_code::POLICY
+        }
+    }
+
+    /// Close frame carrying this error as `{"code": ..., "message": ...}`
+    pub fn close_frame(&self) -> axum::extract::ws::Message {
+        close_frame(self.close_code(), &self.code, &self.message)
+    }
+}
+
+/// Longest close reason in bytes (a close frame payload is at most 125 bytes, 2 of them the code)
+pub const MAX_CLOSE_REASON_BYTES: usize = 123;
+
+/// Close frame whose reason is a JSON error body; the message is shortened
+/// until the reason fits in [`MAX_CLOSE_REASON_BYTES`]
+pub fn close_frame(close_code: u16, code: &str, message: &str) -> axum::extract::ws::Message {
+    let mut message = message.to_string();
+    let reason = loop {
+        let reason = serde_json::json!({ "code": code, "message": message }).to_string();
+        if reason.len() <= MAX_CLOSE_REASON_BYTES || message.pop().is_none() {
+            break reason;
+        }
+    };
+    axum::extract::ws::Message::Close(Some(axum::extract::ws::CloseFrame {
+        code: close_code,
+        reason: reason.into(),
+    }))
 }
 
 /// Convert ApiError to axum response
@@ -267,6 +656,17 @@ mod tests {
         assert!(matches!(deserialized, WsClientMessage::Ping { .. }));
     }
 
+    #[test]
+    fn test_send_message_no_fallback_defaults_to_false() {
+        let json = r#"{"type":"send_message","data":{"channel_id":"1","channel_type":"telegram","message":"hi"}}"#;
+        let msg: WsClientMessage = serde_json::from_str(json).unwrap();
+        assert!(matches!(msg, WsClientMessage::SendMessage { no_fallback: false, .. }));
+
+        let json = r#"{"type":"send_message","data":{"channel_id":"1","channel_type":"telegram","message":"hi","no_fallback":true}}"#;
+        let msg: WsClientMessage = serde_json::from_str(json).unwrap();
+        assert!(matches!(msg, WsClientMessage::SendMessage { no_fallback: true, .. }));
+    }
+
     #[test]
     fn test_ws_server_message_error() {
         let msg = WsServerMessage::error("TEST_CODE", "Test error message");
@@ -277,6 +677,81 @@ mod tests {
         assert!(serialized.contains("Test error message"));
     }
 
+    #[test]
+    fn test_api_error_close_frame() {
+        let axum::extract::ws::Message::Close(Some(frame)) = ApiError::internal("boom").close_frame() else {
+            panic!("expected close frame");
+        };
+        assert_eq!(frame.code, 1011);
+        assert_eq!(frame.reason.as_str(), r#"{"code":"INTERNAL_ERROR","message":"boom"}"#);
+        assert_eq!(ApiError::unauthorized("no").close_code(), 1008);
+
+        // Long messages are cut so the reason stays valid JSON within the frame limit
+        let axum::extract::ws::Message::Close(Some(frame)) = close_frame(1008, "LIMIT", &"é".repeat(200)) else {
+            panic!("expected close frame");
+        };
+        assert!(frame.reason.len() <= MAX_CLOSE_REASON_BYTES);
+        assert!(serde_json::from_str::<serde_json::Value>(frame.reason.as_str()).is_ok());
+    }
+
+    #[test]
+    fn test_send_response_filtered_flag() {
+        let plain = WsServerMessage::send_response(true, None, None, Some("hi".to_string()));
+        let serialized = serde_json::to_string(&plain).unwrap();
+        assert!(!serialized.contains("filtered"));
+
+        let filtered = plain.filtered();
+        let serialized = serde_json::to_string(&filtered).unwrap();
+        assert!(serialized.contains("\"filtered\":true"));
+        assert!(!serialized.contains("degraded"));
+
+        let degraded = filtered.degraded("zai");
+        let serialized = serde_json::to_string(&degraded).unwrap();
+        assert!(serialized.contains("\"degraded\":true,\"provider\":\"zai\""), "{}", serialized);
+    }
+
+    #[test]
+    fn test_detailed_health_aggregation() {
+        let mut components = BTreeMap::new();
+        components.insert("agent".to_string(), HealthStatus::healthy());
+        components.insert("broadcaster".to_string(), HealthStatus::healthy());
+        let health = DetailedHealthResponse::from_components(components.clone());
+        assert_eq!(health.overall, HealthStatus::Healthy);
+
+        components.insert("channel:telegram".to_string(), HealthStatus::degraded("not connected"));
+        let health = DetailedHealthResponse::from_components(components.clone());
+        assert!(matches!(health.overall, HealthStatus::Degraded(_)));
+
+        components.insert("agent".to_string(), HealthStatus::unhealthy("auth failed"));
+        let health = DetailedHealthResponse::from_components(components);
+        assert!(matches!(health.overall, HealthStatus::Unhealthy(_)));
+
+        let json = serde_json::to_string(&health).unwrap();
+        assert!(json.contains(r#""overall":{"status":"unhealthy""#));
+        assert!(json.contains(r#""channel:telegram":{"status":"degraded","reason":"not connected"}"#));
+    }
+
+    #[test]
+    fn test_stats_prometheus_format() {
+        let stats = StatsResponse {
+            uptime_seconds: 42,
+            active_connections: 1,
+            peak_connections: 7,
+            total_messages: 100,
+            active_workers: 0,
+            max_workers: 5,
+            broadcast_lag_events: 2,
+            broadcast_lagged_messages: 40,
+            broadcast_lag_disconnects: 1,
+            timestamp: chrono::Utc::now(),
+        };
+
+        let text = stats.to_prometheus();
+        assert!(text.contains("clanker_broadcast_lagged_messages_total 40\n"));
+        assert!(text.contains("# TYPE clanker_peak_connections gauge\nclanker_peak_connections 7\n"));
+        assert!(text.contains("clanker_messages_total 100\n"));
+    }
+
     #[test]
     fn test_connection_state() {
         let mut state = ConnectionState::new("127.0.0.1:8080".parse().unwrap());